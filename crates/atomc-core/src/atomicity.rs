@@ -0,0 +1,230 @@
+//! Atomicity heuristics for commit plans.
+//!
+//! Semantic validation checks how each unit is worded; these analyzers
+//! check what each unit groups together:
+//! - test files bundled with source changes under a non-`test` type;
+//! - a `feat` unit that only touches documentation;
+//! - formatting-only hunks bundled with logic changes;
+//! - one very large unit in a plan whose other units are small.
+//!
+//! [`AtomicityKind::is_error`] decides which findings reject the plan
+//! under the configured [`AtomicityPolicy`]; rejected findings are fed back
+//! to the model like any other semantic error.
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::config::AtomicityPolicy;
+use crate::git::{self, DiffHunk};
+use crate::types::{CommitType, CommitUnit};
+
+/// Changed lines a unit needs before it can count as oversized.
+const LARGE_UNIT_MIN_LINES: usize = 200;
+/// How many times larger than every other unit an oversized unit is.
+const LARGE_UNIT_RATIO: usize = 5;
+/// Units a plan needs before one can stand out as oversized.
+const LARGE_UNIT_MIN_UNITS: usize = 3;
+
+const TEST_DIRS: &[&str] = &["test", "tests", "__tests__", "spec", "specs"];
+const DOC_DIRS: &[&str] = &["doc", "docs"];
+const DOC_EXTENSIONS: &[&str] = &["md", "markdown", "rst", "adoc"];
+/// Documentation files by stem, whatever their extension (`README.txt`,
+/// `LICENSE`). Other `.txt` files are often inputs, like `requirements.txt`.
+const DOC_STEMS: &[&str] = &["readme", "changelog", "changes", "license", "copying", "contributing", "authors", "notice"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AtomicityKind {
+    /// Test files and source files in one unit whose type is not `test`.
+    TestsWithSource,
+    /// A `feat` unit that only touches documentation.
+    DocsAsFeature,
+    /// Formatting-only hunks bundled with logic changes.
+    FormattingWithLogic,
+    /// A unit many times larger than every other unit in the plan.
+    OversizedUnit,
+}
+
+impl AtomicityKind {
+    /// Whether a finding of this kind rejects the plan under `policy`.
+    pub fn is_error(self, policy: AtomicityPolicy) -> bool {
+        match policy {
+            AtomicityPolicy::Off => false,
+            AtomicityPolicy::Standard => self == Self::DocsAsFeature,
+            AtomicityPolicy::Strict => true,
+        }
+    }
+}
+
+/// One unit that groups changes it probably should not.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AtomicityFinding {
+    pub unit: String,
+    pub kind: AtomicityKind,
+    /// What is wrong and how to fix it, worded for the model.
+    pub message: String,
+}
+
+/// Run every analyzer over `units` against the diff they were planned
+/// from, in unit order.
+pub fn analyze_plan(units: &[CommitUnit], diff: &str) -> Vec<AtomicityFinding> {
+    let hunks = git::diff_hunks(diff);
+    let mut findings = Vec::new();
+    for unit in units {
+        findings.extend(tests_with_source(unit));
+        findings.extend(docs_as_feature(unit));
+        findings.extend(formatting_with_logic(unit, &hunks));
+    }
+    findings.extend(oversized_unit(units, &hunks));
+    findings
+}
+
+fn tests_with_source(unit: &CommitUnit) -> Option<AtomicityFinding> {
+    if matches!(unit.type_, CommitType::Test) {
+        return None;
+    }
+    let tests: Vec<&str> = unit
+        .files
+        .iter()
+        .map(String::as_str)
+        .filter(|path| is_test_path(path))
+        .collect();
+    let has_source = unit
+        .files
+        .iter()
+        .any(|path| !is_test_path(path) && !is_docs_path(path));
+    (!tests.is_empty() && has_source).then(|| AtomicityFinding {
+        unit: unit.id.clone(),
+        kind: AtomicityKind::TestsWithSource,
+        message: format!(
+            "it mixes test files ({}) with source changes; move the tests to a unit of type test",
+            tests.join(", ")
+        ),
+    })
+}
+
+fn docs_as_feature(unit: &CommitUnit) -> Option<AtomicityFinding> {
+    let docs_only = !unit.files.is_empty() && unit.files.iter().all(|path| is_docs_path(path));
+    (matches!(unit.type_, CommitType::Feat) && docs_only).then(|| AtomicityFinding {
+        unit: unit.id.clone(),
+        kind: AtomicityKind::DocsAsFeature,
+        message: "it only changes documentation but has type feat; use type docs".to_string(),
+    })
+}
+
+fn formatting_with_logic(
+    unit: &CommitUnit,
+    hunks: &BTreeMap<String, Vec<DiffHunk>>,
+) -> Option<AtomicityFinding> {
+    if matches!(unit.type_, CommitType::Style) {
+        return None;
+    }
+    let mut formatting_files = Vec::new();
+    let mut has_logic = false;
+    for file in &unit.files {
+        let file_hunks = unit_hunks(unit, file, hunks);
+        if file_hunks.iter().any(|hunk| is_formatting_only(hunk)) {
+            formatting_files.push(file.as_str());
+        }
+        has_logic |= file_hunks.iter().any(|hunk| !is_formatting_only(hunk));
+    }
+    (!formatting_files.is_empty() && has_logic).then(|| AtomicityFinding {
+        unit: unit.id.clone(),
+        kind: AtomicityKind::FormattingWithLogic,
+        message: format!(
+            "it bundles formatting-only hunks in {} with logic changes; move them to a unit of type style",
+            formatting_files.join(", ")
+        ),
+    })
+}
+
+fn oversized_unit(units: &[CommitUnit], hunks: &BTreeMap<String, Vec<DiffHunk>>) -> Option<AtomicityFinding> {
+    if units.len() < LARGE_UNIT_MIN_UNITS {
+        return None;
+    }
+    let sizes: Vec<usize> = units.iter().map(|unit| changed_lines(unit, hunks)).collect();
+    let (largest, &size) = sizes.iter().enumerate().max_by_key(|(_, size)| **size)?;
+    let others = sizes
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != largest)
+        .map(|(_, size)| *size)
+        .max()
+        .unwrap_or(0);
+    (size >= LARGE_UNIT_MIN_LINES && size >= others.saturating_mul(LARGE_UNIT_RATIO)).then(|| {
+        AtomicityFinding {
+            unit: units[largest].id.clone(),
+            kind: AtomicityKind::OversizedUnit,
+            message: format!(
+                "it changes {size} lines while no other unit changes more than {others}; split it into smaller units"
+            ),
+        }
+    })
+}
+
+/// The hunks of `file` that `unit` commits: the ones its `hunks` name, or
+/// all of them when it names none for that file.
+pub(crate) fn unit_hunks<'a>(unit: &CommitUnit, file: &str, hunks: &'a BTreeMap<String, Vec<DiffHunk>>) -> Vec<&'a DiffHunk> {
+    let Some(file_hunks) = hunks.get(file) else {
+        return Vec::new();
+    };
+    let headers: Vec<&str> = unit
+        .hunks
+        .iter()
+        .filter(|hunk| hunk.file == file)
+        .map(|hunk| hunk.header.as_str())
+        .collect();
+    file_hunks
+        .iter()
+        .filter(|hunk| headers.is_empty() || headers.contains(&hunk.header.as_str()))
+        .collect()
+}
+
+fn changed_lines(unit: &CommitUnit, hunks: &BTreeMap<String, Vec<DiffHunk>>) -> usize {
+    unit.files
+        .iter()
+        .flat_map(|file| unit_hunks(unit, file, hunks))
+        .map(|hunk| hunk.removed.len() + hunk.added.len())
+        .sum()
+}
+
+/// A hunk whose removed and added lines differ only in whitespace.
+fn is_formatting_only(hunk: &DiffHunk) -> bool {
+    let squash = |lines: &[String]| -> String {
+        lines
+            .iter()
+            .flat_map(|line| line.chars())
+            .filter(|ch| !ch.is_whitespace())
+            .collect()
+    };
+    (!hunk.removed.is_empty() || !hunk.added.is_empty()) && squash(&hunk.removed) == squash(&hunk.added)
+}
+
+/// Paths under a test directory, or named like `test_x.py`, `x_test.go`,
+/// `x.test.ts`, `x.spec.js`, or `x_spec.rb`. Documentation never counts,
+/// so `docs/cli_spec.md` is not a test.
+pub fn is_test_path(path: &str) -> bool {
+    if is_docs_path(path) {
+        return false;
+    }
+    let lower = path.to_ascii_lowercase();
+    let (dirs, name) = lower.rsplit_once('/').unwrap_or(("", lower.as_str()));
+    if dirs.split('/').any(|dir| TEST_DIRS.contains(&dir)) {
+        return true;
+    }
+    let stem = name.split('.').next().unwrap_or(name);
+    name.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+}
+
+/// Paths under a `doc`/`docs` directory, with a documentation extension,
+/// or named like `README` or `LICENSE`.
+pub fn is_docs_path(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    let (dirs, name) = lower.rsplit_once('/').unwrap_or(("", lower.as_str()));
+    let (stem, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+    dirs.split('/').any(|dir| DOC_DIRS.contains(&dir)) || DOC_EXTENSIONS.contains(&ext) || DOC_STEMS.contains(&stem)
+}
@@ -0,0 +1,235 @@
+//! Local cache of validated commit plans.
+//!
+//! Entries are JSON files named by the digest of a [`CacheKey`], so a plan is
+//! only reused for the same diff, backends, prompt, and settings. Each entry
+//! keeps the warnings raised when the plan was accepted. Every store prunes
+//! entries older than [`MAX_AGE`] and keeps at most [`MAX_ENTRIES`] plans,
+//! newest first.
+use crate::config::ResolvedConfig;
+use crate::hash;
+use crate::llm::Prompt;
+use crate::types::{CommitPlan, Warning};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Plans kept by default; older entries are pruned past this count.
+pub const MAX_ENTRIES: usize = 500;
+
+/// Age after which a cached plan is pruned by default.
+pub const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("cache directory not available")]
+    NoDir,
+    #[error("cache io error: {path}: {source}")]
+    Io { path: PathBuf, source: std::io::Error },
+    #[error("cache entry serialize error: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// Everything a cached plan depends on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub diff_hash: String,
+    pub model: String,
+    pub runtime: String,
+    pub prompt_version: String,
+    /// Hash of the settings that shape the model's answer or how it is
+    /// checked, of the whole backend chain (a fallback may have produced
+    /// the plan), and of the full prompt, which also carries the repo
+    /// metadata.
+    pub config_fingerprint: String,
+}
+
+impl CacheKey {
+    pub fn new(config: &ResolvedConfig, prompt: &Prompt, diff: &str) -> Self {
+        let backends = serde_json::to_string(&config.backend_chain()).unwrap_or_default();
+        let fingerprint = format!(
+            "temperature={}\nmax_tokens={}\ncandidates={}\nmax_attempts={}\natomicity={:?}\n\
+             dependency_order={:?}\nbackends={}\nsystem={}\nuser={}",
+            config.temperature,
+            config.max_tokens,
+            config.plan_candidates,
+            config.llm_max_attempts,
+            config.atomicity,
+            config.dependency_order,
+            backends,
+            prompt.system,
+            prompt.user
+        );
+        Self {
+            diff_hash: hash::diff_hash(diff),
+            model: config.model.clone(),
+            runtime: config.runtime.as_str().to_string(),
+            prompt_version: prompt.version.clone(),
+            config_fingerprint: hash::diff_hash(&fingerprint),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        let joined = [
+            &self.diff_hash,
+            &self.model,
+            &self.runtime,
+            &self.prompt_version,
+            &self.config_fingerprint,
+        ]
+        .map(String::as_str)
+        .join("\n");
+        let digest = hash::diff_hash(&joined);
+        format!("{}.json", digest.trim_start_matches("sha256:"))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: CacheKey,
+    plan: CommitPlan,
+    #[serde(default)]
+    warnings: Vec<Warning>,
+}
+
+/// Plans stored under `<dir>/plans`.
+#[derive(Debug, Clone)]
+pub struct PlanCache {
+    dir: PathBuf,
+    max_entries: usize,
+    max_age: Duration,
+}
+
+impl PlanCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_entries: MAX_ENTRIES,
+            max_age: MAX_AGE,
+        }
+    }
+
+    /// Keep at most `max_entries` plans, none older than `max_age`.
+    pub fn with_limits(self, max_entries: usize, max_age: Duration) -> Self {
+        Self {
+            max_entries,
+            max_age,
+            ..self
+        }
+    }
+
+    /// The configured cache, or `None` when caching is off.
+    pub fn from_config(config: &ResolvedConfig) -> Result<Option<Self>, CacheError> {
+        if !config.plan_cache {
+            return Ok(None);
+        }
+        let dir = match &config.cache_dir {
+            Some(dir) => dir.clone(),
+            None => default_cache_dir()?,
+        };
+        Ok(Some(Self::new(dir)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The plan stored for `key` and its warnings. Missing, unreadable, and
+    /// mismatched entries are all misses.
+    pub fn get(&self, key: &CacheKey) -> Option<(CommitPlan, Vec<Warning>)> {
+        let bytes = fs::read(self.plans_dir().join(key.file_name())).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&bytes).ok()?;
+        (entry.key == *key).then_some((entry.plan, entry.warnings))
+    }
+
+    /// Store `plan` and the warnings raised accepting it for `key`,
+    /// replacing any earlier entry, then prune the cache to its limits.
+    pub fn put(&self, key: &CacheKey, plan: &CommitPlan, warnings: &[Warning]) -> Result<(), CacheError> {
+        let dir = self.plans_dir();
+        fs::create_dir_all(&dir).map_err(|source| CacheError::Io {
+            path: dir.clone(),
+            source,
+        })?;
+        let entry = CacheEntry {
+            key: key.clone(),
+            plan: plan.clone(),
+            warnings: warnings.to_vec(),
+        };
+        let path = dir.join(key.file_name());
+        // Write then rename so concurrent readers never see a partial entry.
+        let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+        let stored = fs::write(&tmp, serde_json::to_vec(&entry)?)
+            .map_err(|source| CacheError::Io {
+                path: tmp.clone(),
+                source,
+            })
+            .and_then(|()| fs::rename(&tmp, &path).map_err(|source| CacheError::Io { path, source }));
+        if stored.is_err() {
+            fs::remove_file(&tmp).ok();
+        }
+        stored?;
+        self.prune();
+        Ok(())
+    }
+
+    /// Remove entries older than the age limit, then the oldest entries past
+    /// the count limit. Pruning is best effort: entries that cannot be read
+    /// or removed are left for the next store.
+    fn prune(&self) {
+        let Ok(entries) = fs::read_dir(self.plans_dir()) else {
+            return;
+        };
+        let now = SystemTime::now();
+        let mut kept = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            if now.duration_since(modified).is_ok_and(|age| age > self.max_age) {
+                fs::remove_file(&path).ok();
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                kept.push((modified, path));
+            }
+        }
+        if kept.len() > self.max_entries {
+            kept.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+            for (_, path) in kept.split_off(self.max_entries) {
+                fs::remove_file(path).ok();
+            }
+        }
+    }
+
+    /// Remove every cached plan and return how many were removed.
+    pub fn clear(&self) -> Result<usize, CacheError> {
+        let dir = self.plans_dir();
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(source) => return Err(CacheError::Io { path: dir, source }),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry
+                .map_err(|source| CacheError::Io {
+                    path: dir.clone(),
+                    source,
+                })?
+                .path();
+            fs::remove_file(&path).map_err(|source| CacheError::Io { path, source })?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    fn plans_dir(&self) -> PathBuf {
+        self.dir.join("plans")
+    }
+}
+
+/// `atomc` under the user cache directory (`$XDG_CACHE_HOME` or
+/// `~/.cache` on Linux, `~/Library/Caches` on macOS).
+pub fn default_cache_dir() -> Result<PathBuf, CacheError> {
+    let base_dirs = directories::BaseDirs::new().ok_or(CacheError::NoDir)?;
+    Ok(base_dirs.cache_dir().join("atomc"))
+}
@@ -1,24 +1,124 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+/// Most plans one request may sample; each is a full LLM call.
+pub const MAX_PLAN_CANDIDATES: u32 = 8;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Runtime {
     #[serde(rename = "ollama")]
     Ollama,
     #[serde(rename = "llama.cpp")]
     LlamaCpp,
+    /// Serve recorded replies from the `cassette` file instead of a model.
+    #[serde(rename = "replay")]
+    Replay,
+}
+
+impl Runtime {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Runtime::Ollama => "ollama",
+            Runtime::LlamaCpp => "llama.cpp",
+            Runtime::Replay => "replay",
+        }
+    }
+}
+
+/// One entry of the `[[backends]]` fallback chain. Unset fields take the
+/// top-level `runtime`, `ollama_url`, `model`, and `llm_timeout_secs`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendConfig {
+    pub name: Option<String>,
+    pub runtime: Option<Runtime>,
+    pub url: Option<String>,
+    pub model: Option<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+/// A backend with every setting filled in.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Backend {
+    pub name: String,
+    pub runtime: Runtime,
+    pub url: String,
+    pub model: String,
+    pub timeout_secs: u64,
+}
+
+impl From<&Backend> for BackendConfig {
+    fn from(backend: &Backend) -> Self {
+        Self {
+            name: Some(backend.name.clone()),
+            runtime: Some(backend.runtime),
+            url: Some(backend.url.clone()),
+            model: Some(backend.model.clone()),
+            timeout_secs: Some(backend.timeout_secs),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum DiffMode {
     Worktree,
     Staged,
     All,
+    /// Committed changes since the merge base of a revision and HEAD.
+    /// Planning only; selected with `--base`, never from config.
+    #[serde(skip_deserializing)]
+    Base,
+    /// Committed changes between two revisions. Planning only; selected
+    /// with `--range`, never from config.
+    #[serde(skip_deserializing)]
+    Range,
+}
+
+impl DiffMode {
+    /// Modes that diff commits rather than the worktree or index, so their
+    /// plans cannot be applied.
+    pub fn is_revision(self) -> bool {
+        matches!(self, DiffMode::Base | DiffMode::Range)
+    }
+}
+
+/// Which implementation runs git operations.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitBackendKind {
+    /// Spawn the `git` binary for every operation.
+    Cli,
+    /// Run in-process through libgit2, falling back to `git` where needed.
+    Native,
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+/// How atomicity findings affect a plan.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AtomicityPolicy {
+    /// Skip the atomicity analyzers.
+    Off,
+    /// Reject docs-only `feat` units; report other findings as warnings.
+    Standard,
+    /// Reject every finding, so the plan is retried.
+    Strict,
+}
+
+/// What to do when a unit uses something a later unit introduces.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyOrder {
+    /// Leave the model's order alone.
+    Off,
+    /// Keep the model's order and warn about each late dependency.
+    Warn,
+    /// Move units after the units they depend on.
+    Reorder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct PartialConfig {
     pub model: Option<String>,
@@ -28,9 +128,31 @@ pub struct PartialConfig {
     pub temperature: Option<f32>,
     pub llm_timeout_secs: Option<u64>,
     pub max_diff_bytes: Option<u64>,
+    pub max_file_diff_bytes: Option<u64>,
     pub diff_mode: Option<DiffMode>,
     pub include_untracked: Option<bool>,
     pub log_diff: Option<bool>,
+    pub verify_command: Option<String>,
+    pub git_backend: Option<GitBackendKind>,
+    pub recurse_submodules: Option<bool>,
+    pub include: Option<Vec<String>>,
+    pub ignore: Option<Vec<String>>,
+    pub strict_diff_hash: Option<bool>,
+    pub plan_cache: Option<bool>,
+    pub cache_dir: Option<PathBuf>,
+    pub plan_candidates: Option<u32>,
+    pub llm_max_attempts: Option<u32>,
+    pub llm_retry_backoff_ms: Option<u64>,
+    pub prompt_template: Option<PathBuf>,
+    pub prompt_examples: Option<u32>,
+    pub examples_dir: Option<PathBuf>,
+    pub examples_max_tokens: Option<u32>,
+    pub cassette: Option<PathBuf>,
+    pub cassette_redact: Option<bool>,
+    pub cassette_fuzzy: Option<bool>,
+    pub backends: Option<Vec<BackendConfig>>,
+    pub atomicity: Option<AtomicityPolicy>,
+    pub dependency_order: Option<DependencyOrder>,
 }
 
 #[derive(Debug, Clone)]
@@ -42,9 +164,59 @@ pub struct ResolvedConfig {
     pub temperature: f32,
     pub llm_timeout_secs: u64,
     pub max_diff_bytes: u64,
+    /// Per-file diff size above which a file is summarized as metadata.
+    pub max_file_diff_bytes: u64,
     pub diff_mode: DiffMode,
     pub include_untracked: bool,
     pub log_diff: bool,
+    /// Shell command run against each new commit during apply.
+    pub verify_command: Option<String>,
+    pub git_backend: GitBackendKind,
+    /// Plan and commit changes inside initialized submodules.
+    pub recurse_submodules: bool,
+    /// Globs a changed path must match to be planned; empty means all paths.
+    pub include: Vec<String>,
+    /// Globs of changed paths left out of planning.
+    pub ignore: Vec<String>,
+    /// Abort apply on any diff change, not only in the planned files.
+    pub strict_diff_hash: bool,
+    /// Reuse validated plans for identical input.
+    pub plan_cache: bool,
+    /// Where cached plans live; the user cache directory when unset.
+    pub cache_dir: Option<PathBuf>,
+    /// Plans sampled per request; the best-scoring one is used. Values
+    /// below 2 take a single sample; at most [`MAX_PLAN_CANDIDATES`].
+    pub plan_candidates: u32,
+    /// LLM requests per plan, including retries. Values below 1 mean 1.
+    pub llm_max_attempts: u32,
+    /// Delay before the first retry after a runtime error; doubles on each
+    /// further runtime error.
+    pub llm_retry_backoff_ms: u64,
+    /// Prompt template file used instead of a repository's
+    /// `.atomc/prompt.toml` or the built-in templates.
+    pub prompt_template: Option<PathBuf>,
+    /// Example diff/plan pairs shown to the model per request; 0 disables
+    /// few-shot examples.
+    pub prompt_examples: u32,
+    /// Directory of `diffs/*.diff` and `plans/*.plan.json` pairs used as
+    /// examples instead of the built-in set.
+    pub examples_dir: Option<PathBuf>,
+    /// Estimated prompt tokens all examples together may use.
+    pub examples_max_tokens: u32,
+    /// Cassette that LLM exchanges are appended to, or replayed from with
+    /// the `replay` runtime.
+    pub cassette: Option<PathBuf>,
+    /// Redact diff hunk content from recorded prompts.
+    pub cassette_redact: bool,
+    /// Replay a prompt with no recorded exchange from the next unused one
+    /// instead of failing.
+    pub cassette_fuzzy: bool,
+    /// Backends tried in order; empty means the top-level runtime alone.
+    pub backends: Vec<BackendConfig>,
+    /// Which atomicity findings reject a plan.
+    pub atomicity: AtomicityPolicy,
+    /// How generated plans are ordered by the dependencies between units.
+    pub dependency_order: DependencyOrder,
 }
 
 impl ResolvedConfig {
@@ -57,11 +229,81 @@ impl ResolvedConfig {
             temperature: 0.2,
             llm_timeout_secs: 60,
             max_diff_bytes: 2_000_000,
+            max_file_diff_bytes: 200_000,
             diff_mode: DiffMode::All,
             include_untracked: true,
             log_diff: false,
+            verify_command: None,
+            git_backend: GitBackendKind::Cli,
+            recurse_submodules: false,
+            include: Vec::new(),
+            ignore: Vec::new(),
+            strict_diff_hash: false,
+            plan_cache: true,
+            cache_dir: None,
+            plan_candidates: 1,
+            llm_max_attempts: 3,
+            llm_retry_backoff_ms: 500,
+            prompt_template: None,
+            prompt_examples: 0,
+            examples_dir: None,
+            examples_max_tokens: 1024,
+            cassette: None,
+            cassette_redact: false,
+            cassette_fuzzy: false,
+            backends: Vec::new(),
+            atomicity: AtomicityPolicy::Standard,
+            dependency_order: DependencyOrder::Reorder,
         }
     }
+
+    /// The backends to try, in order. Without `backends` this is the
+    /// top-level runtime as a single backend named `default`.
+    pub fn backend_chain(&self) -> Vec<Backend> {
+        if self.backends.is_empty() {
+            return vec![Backend {
+                name: "default".to_string(),
+                runtime: self.runtime,
+                url: self.ollama_url.clone(),
+                model: self.model.clone(),
+                timeout_secs: self.llm_timeout_secs,
+            }];
+        }
+        self.backends
+            .iter()
+            .enumerate()
+            .map(|(index, backend)| Backend {
+                name: backend.name.clone().unwrap_or_else(|| format!("backend-{}", index + 1)),
+                runtime: backend.runtime.unwrap_or(self.runtime),
+                url: backend.url.clone().unwrap_or_else(|| self.ollama_url.clone()),
+                model: backend.model.clone().unwrap_or_else(|| self.model.clone()),
+                timeout_secs: backend.timeout_secs.unwrap_or(self.llm_timeout_secs),
+            })
+            .collect()
+    }
+
+    /// This config with `backend` as the runtime, URL, model, and timeout.
+    pub fn with_backend(&self, backend: &Backend) -> Self {
+        Self {
+            runtime: backend.runtime,
+            ollama_url: backend.url.clone(),
+            model: backend.model.clone(),
+            llm_timeout_secs: backend.timeout_secs,
+            ..self.clone()
+        }
+    }
+
+    /// This config moved to the backend after the one named `used`, with
+    /// the chain cut to start there; `None` when `used` is the last.
+    pub fn escalate(&self, used: &str) -> Option<Self> {
+        let chain = self.backend_chain();
+        let position = chain.iter().position(|backend| backend.name == used)?;
+        let remaining = chain.get(position + 1..).filter(|rest| !rest.is_empty())?;
+        Some(Self {
+            backends: remaining.iter().map(BackendConfig::from).collect(),
+            ..self.with_backend(&remaining[0])
+        })
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -76,12 +318,36 @@ pub enum ConfigError {
     Path(String),
     #[error("invalid env var {key}={value}")]
     InvalidEnv { key: String, value: String },
+    #[error("{key} = {value} is above the maximum of {max}")]
+    OutOfRange { key: String, value: u64, max: u64 },
+}
+
+/// One source of settings and the keys it set.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigLayer {
+    /// `defaults`, `file`, `env`, or `cli`, lowest precedence first.
+    pub name: &'static str,
+    /// The config file path, for the `file` layer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    /// Whether the layer was found; false for a missing optional file.
+    pub loaded: bool,
+    pub keys: Vec<String>,
 }
 
 pub fn resolve_config(
     cli_path: Option<PathBuf>,
     overrides: PartialConfig,
 ) -> Result<ResolvedConfig, ConfigError> {
+    resolve_config_layers(cli_path, overrides).map(|(resolved, _)| resolved)
+}
+
+/// Resolve config as [`resolve_config`] does and also report which keys
+/// each layer set.
+pub fn resolve_config_layers(
+    cli_path: Option<PathBuf>,
+    overrides: PartialConfig,
+) -> Result<(ResolvedConfig, Vec<ConfigLayer>), ConfigError> {
     let env_path = config_path_from_env();
     let required = cli_path.is_some() || env_path.is_some();
     let path = match cli_path.clone().or(env_path.clone()) {
@@ -91,14 +357,47 @@ pub fn resolve_config(
 
     let file_config = load_config_file(&path, required)?;
     let env_config = load_env_config()?;
+    let layers = vec![
+        ConfigLayer {
+            name: "defaults",
+            source: None,
+            loaded: true,
+            keys: Vec::new(),
+        },
+        ConfigLayer {
+            name: "file",
+            loaded: path.exists(),
+            source: Some(path),
+            keys: file_config.keys(),
+        },
+        ConfigLayer {
+            name: "env",
+            source: None,
+            loaded: true,
+            keys: env_config.keys(),
+        },
+        ConfigLayer {
+            name: "cli",
+            source: None,
+            loaded: true,
+            keys: overrides.keys(),
+        },
+    ];
 
     let mut resolved = ResolvedConfig::defaults();
     // Precedence: defaults < config file < env vars < CLI overrides.
     file_config.apply_to(&mut resolved);
     env_config.apply_to(&mut resolved);
     overrides.apply_to(&mut resolved);
+    if resolved.plan_candidates > MAX_PLAN_CANDIDATES {
+        return Err(ConfigError::OutOfRange {
+            key: "plan_candidates".to_string(),
+            value: resolved.plan_candidates.into(),
+            max: MAX_PLAN_CANDIDATES.into(),
+        });
+    }
 
-    Ok(resolved)
+    Ok((resolved, layers))
 }
 
 fn load_config_file(path: &Path, required: bool) -> Result<PartialConfig, ConfigError> {
@@ -148,6 +447,9 @@ fn load_env_config() -> Result<PartialConfig, ConfigError> {
     if let Some(value) = env("LOCAL_COMMIT_MAX_DIFF_BYTES") {
         config.max_diff_bytes = Some(parse_u64("LOCAL_COMMIT_MAX_DIFF_BYTES", &value)?);
     }
+    if let Some(value) = env("LOCAL_COMMIT_MAX_FILE_DIFF_BYTES") {
+        config.max_file_diff_bytes = Some(parse_u64("LOCAL_COMMIT_MAX_FILE_DIFF_BYTES", &value)?);
+    }
     if let Some(value) = env("LOCAL_COMMIT_DIFF_MODE") {
         config.diff_mode = Some(parse_diff_mode("LOCAL_COMMIT_DIFF_MODE", &value)?);
     }
@@ -157,6 +459,66 @@ fn load_env_config() -> Result<PartialConfig, ConfigError> {
     if let Some(value) = env("LOCAL_COMMIT_LOG_DIFF") {
         config.log_diff = Some(parse_bool("LOCAL_COMMIT_LOG_DIFF", &value)?);
     }
+    if let Some(value) = env("LOCAL_COMMIT_VERIFY_COMMAND") {
+        config.verify_command = Some(value);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_GIT_BACKEND") {
+        config.git_backend = Some(parse_git_backend("LOCAL_COMMIT_GIT_BACKEND", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_RECURSE_SUBMODULES") {
+        config.recurse_submodules = Some(parse_bool("LOCAL_COMMIT_RECURSE_SUBMODULES", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_INCLUDE") {
+        config.include = Some(parse_list(&value));
+    }
+    if let Some(value) = env("LOCAL_COMMIT_IGNORE") {
+        config.ignore = Some(parse_list(&value));
+    }
+    if let Some(value) = env("LOCAL_COMMIT_STRICT_DIFF_HASH") {
+        config.strict_diff_hash = Some(parse_bool("LOCAL_COMMIT_STRICT_DIFF_HASH", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_PLAN_CACHE") {
+        config.plan_cache = Some(parse_bool("LOCAL_COMMIT_PLAN_CACHE", &value)?);
+    }
+    if let Some(value) = env_os("LOCAL_COMMIT_CACHE_DIR") {
+        config.cache_dir = Some(PathBuf::from(value));
+    }
+    if let Some(value) = env("LOCAL_COMMIT_PLAN_CANDIDATES") {
+        config.plan_candidates = Some(parse_plan_candidates("LOCAL_COMMIT_PLAN_CANDIDATES", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_LLM_MAX_ATTEMPTS") {
+        config.llm_max_attempts = Some(parse_u32("LOCAL_COMMIT_LLM_MAX_ATTEMPTS", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_LLM_RETRY_BACKOFF_MS") {
+        config.llm_retry_backoff_ms = Some(parse_u64("LOCAL_COMMIT_LLM_RETRY_BACKOFF_MS", &value)?);
+    }
+    if let Some(value) = env_os("LOCAL_COMMIT_PROMPT_TEMPLATE") {
+        config.prompt_template = Some(PathBuf::from(value));
+    }
+    if let Some(value) = env("LOCAL_COMMIT_PROMPT_EXAMPLES") {
+        config.prompt_examples = Some(parse_u32("LOCAL_COMMIT_PROMPT_EXAMPLES", &value)?);
+    }
+    if let Some(value) = env_os("LOCAL_COMMIT_EXAMPLES_DIR") {
+        config.examples_dir = Some(PathBuf::from(value));
+    }
+    if let Some(value) = env("LOCAL_COMMIT_EXAMPLES_MAX_TOKENS") {
+        config.examples_max_tokens = Some(parse_u32("LOCAL_COMMIT_EXAMPLES_MAX_TOKENS", &value)?);
+    }
+    if let Some(value) = env_os("LOCAL_COMMIT_CASSETTE") {
+        config.cassette = Some(PathBuf::from(value));
+    }
+    if let Some(value) = env("LOCAL_COMMIT_CASSETTE_REDACT") {
+        config.cassette_redact = Some(parse_bool("LOCAL_COMMIT_CASSETTE_REDACT", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_CASSETTE_FUZZY") {
+        config.cassette_fuzzy = Some(parse_bool("LOCAL_COMMIT_CASSETTE_FUZZY", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_ATOMICITY") {
+        config.atomicity = Some(parse_atomicity("LOCAL_COMMIT_ATOMICITY", &value)?);
+    }
+    if let Some(value) = env("LOCAL_COMMIT_DEPENDENCY_ORDER") {
+        config.dependency_order = Some(parse_dependency_order("LOCAL_COMMIT_DEPENDENCY_ORDER", &value)?);
+    }
 
     Ok(config)
 }
@@ -190,6 +552,7 @@ fn parse_runtime(key: &str, value: &str) -> Result<Runtime, ConfigError> {
     match value {
         "ollama" => Ok(Runtime::Ollama),
         "llama.cpp" | "llama_cpp" | "llamacpp" => Ok(Runtime::LlamaCpp),
+        "replay" => Ok(Runtime::Replay),
         _ => Err(ConfigError::InvalidEnv {
             key: key.to_string(),
             value: value.to_string(),
@@ -209,6 +572,41 @@ fn parse_diff_mode(key: &str, value: &str) -> Result<DiffMode, ConfigError> {
     }
 }
 
+fn parse_git_backend(key: &str, value: &str) -> Result<GitBackendKind, ConfigError> {
+    match value {
+        "cli" => Ok(GitBackendKind::Cli),
+        "native" => Ok(GitBackendKind::Native),
+        _ => Err(ConfigError::InvalidEnv {
+            key: key.to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
+fn parse_atomicity(key: &str, value: &str) -> Result<AtomicityPolicy, ConfigError> {
+    match value {
+        "off" => Ok(AtomicityPolicy::Off),
+        "standard" => Ok(AtomicityPolicy::Standard),
+        "strict" => Ok(AtomicityPolicy::Strict),
+        _ => Err(ConfigError::InvalidEnv {
+            key: key.to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
+fn parse_dependency_order(key: &str, value: &str) -> Result<DependencyOrder, ConfigError> {
+    match value {
+        "off" => Ok(DependencyOrder::Off),
+        "warn" => Ok(DependencyOrder::Warn),
+        "reorder" => Ok(DependencyOrder::Reorder),
+        _ => Err(ConfigError::InvalidEnv {
+            key: key.to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
 fn parse_bool(key: &str, value: &str) -> Result<bool, ConfigError> {
     match value.to_ascii_lowercase().as_str() {
         "true" | "1" | "yes" | "y" => Ok(true),
@@ -220,6 +618,16 @@ fn parse_bool(key: &str, value: &str) -> Result<bool, ConfigError> {
     }
 }
 
+/// Comma-separated values, trimmed, with empty entries dropped.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 fn parse_u32(key: &str, value: &str) -> Result<u32, ConfigError> {
     value.parse().map_err(|_| ConfigError::InvalidEnv {
         key: key.to_string(),
@@ -227,6 +635,16 @@ fn parse_u32(key: &str, value: &str) -> Result<u32, ConfigError> {
     })
 }
 
+fn parse_plan_candidates(key: &str, value: &str) -> Result<u32, ConfigError> {
+    match parse_u32(key, value)? {
+        count if count <= MAX_PLAN_CANDIDATES => Ok(count),
+        _ => Err(ConfigError::InvalidEnv {
+            key: key.to_string(),
+            value: value.to_string(),
+        }),
+    }
+}
+
 fn parse_u64(key: &str, value: &str) -> Result<u64, ConfigError> {
     value.parse().map_err(|_| ConfigError::InvalidEnv {
         key: key.to_string(),
@@ -242,6 +660,18 @@ fn parse_f32(key: &str, value: &str) -> Result<f32, ConfigError> {
 }
 
 impl PartialConfig {
+    /// Names of the settings this layer sets, sorted.
+    pub fn keys(&self) -> Vec<String> {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(fields)) => fields
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, _)| key)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
     fn apply_to(self, resolved: &mut ResolvedConfig) {
         if let Some(value) = self.model {
             resolved.model = value;
@@ -264,6 +694,9 @@ impl PartialConfig {
         if let Some(value) = self.max_diff_bytes {
             resolved.max_diff_bytes = value;
         }
+        if let Some(value) = self.max_file_diff_bytes {
+            resolved.max_file_diff_bytes = value;
+        }
         if let Some(value) = self.diff_mode {
             resolved.diff_mode = value;
         }
@@ -273,5 +706,68 @@ impl PartialConfig {
         if let Some(value) = self.log_diff {
             resolved.log_diff = value;
         }
+        if let Some(value) = self.verify_command {
+            resolved.verify_command = Some(value);
+        }
+        if let Some(value) = self.git_backend {
+            resolved.git_backend = value;
+        }
+        if let Some(value) = self.recurse_submodules {
+            resolved.recurse_submodules = value;
+        }
+        if let Some(value) = self.include {
+            resolved.include = value;
+        }
+        if let Some(value) = self.ignore {
+            resolved.ignore = value;
+        }
+        if let Some(value) = self.strict_diff_hash {
+            resolved.strict_diff_hash = value;
+        }
+        if let Some(value) = self.plan_cache {
+            resolved.plan_cache = value;
+        }
+        if let Some(value) = self.cache_dir {
+            resolved.cache_dir = Some(value);
+        }
+        if let Some(value) = self.plan_candidates {
+            resolved.plan_candidates = value;
+        }
+        if let Some(value) = self.llm_max_attempts {
+            resolved.llm_max_attempts = value;
+        }
+        if let Some(value) = self.llm_retry_backoff_ms {
+            resolved.llm_retry_backoff_ms = value;
+        }
+        if let Some(value) = self.prompt_template {
+            resolved.prompt_template = Some(value);
+        }
+        if let Some(value) = self.prompt_examples {
+            resolved.prompt_examples = value;
+        }
+        if let Some(value) = self.examples_dir {
+            resolved.examples_dir = Some(value);
+        }
+        if let Some(value) = self.examples_max_tokens {
+            resolved.examples_max_tokens = value;
+        }
+        if let Some(value) = self.cassette {
+            resolved.cassette = Some(value);
+        }
+        if let Some(value) = self.cassette_redact {
+            resolved.cassette_redact = value;
+        }
+        if let Some(value) = self.cassette_fuzzy {
+            resolved.cassette_fuzzy = value;
+        }
+        if let Some(value) = self.backends {
+            resolved.backends = value;
+        }
+        if let Some(value) = self.atomicity {
+            resolved.atomicity = value;
+        }
+        if let Some(value) = self.dependency_order {
+            resolved.dependency_order = value;
+        }
     }
 }
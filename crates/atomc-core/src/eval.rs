@@ -0,0 +1,257 @@
+//! Model evaluation over a fixture corpus.
+//!
+//! A corpus is a directory of `diffs/<name>.diff` files with optional
+//! expected plans in `plans/<name>.plan.json` (the `tests/fixtures` layout).
+//! Each diff is planned once per model, without retries or the plan cache,
+//! so results reflect the model alone. Plans are scored after mechanical
+//! repairs, as `atomc plan` would accept them.
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::config::{AtomicityPolicy, ResolvedConfig};
+use crate::llm::{LlmError, Prompt, PromptContext, PromptTemplates};
+use crate::scoring;
+use crate::semantic::ScopePolicy;
+use crate::types::CommitPlan;
+use crate::SCHEMA_VERSION;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("eval fixtures read error: {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("expected plan parse error: {path}: {source}")]
+    Parse { path: PathBuf, source: serde_json::Error },
+    #[error("no diffs found in {0}")]
+    Empty(PathBuf),
+}
+
+/// One diff to plan, with the plan it should produce when known.
+#[derive(Debug, Clone)]
+pub struct EvalCase {
+    pub name: String,
+    pub diff: String,
+    pub expected: Option<CommitPlan>,
+}
+
+/// How one model did on one case.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub case: String,
+    pub model: String,
+    /// The response parsed as JSON matching the commit plan schema.
+    pub schema_valid: bool,
+    /// The plan also passed semantic validation.
+    pub semantic_valid: bool,
+    /// Share of the diff's files some unit lists; 0.0 without a plan.
+    pub coverage: f64,
+    pub units: Option<usize>,
+    pub expected_units: Option<usize>,
+    /// Whether `units` equals `expected_units`; unset without an expected
+    /// plan.
+    pub units_match: Option<bool>,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Totals for one model across every case.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSummary {
+    pub model: String,
+    pub cases: usize,
+    pub schema_valid: usize,
+    pub semantic_valid: usize,
+    /// Mean coverage over all cases.
+    pub coverage: f64,
+    /// Share of cases with an expected plan whose unit count matched.
+    pub unit_agreement: Option<f64>,
+    pub mean_latency_ms: u64,
+    pub max_latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub schema_version: String,
+    pub fixtures: String,
+    pub models: Vec<ModelSummary>,
+    pub cases: Vec<CaseResult>,
+}
+
+/// Every `diffs/*.diff` under `dir`, sorted by name, with its expected plan
+/// when `plans/<name>.plan.json` exists.
+pub fn load_cases(dir: &Path) -> Result<Vec<EvalCase>, EvalError> {
+    let diffs = dir.join("diffs");
+    let read_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| EvalError::Read { path, source }
+    };
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&diffs).map_err(read_error(&diffs))? {
+        let path = entry.map_err(read_error(&diffs))?.path();
+        if path.extension().is_some_and(|ext| ext == "diff") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut cases = Vec::new();
+    for path in paths {
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let diff = fs::read_to_string(&path).map_err(read_error(&path))?;
+        let plan_path = dir.join("plans").join(format!("{name}.plan.json"));
+        let expected = if plan_path.is_file() {
+            let contents = fs::read_to_string(&plan_path).map_err(read_error(&plan_path))?;
+            let plan = serde_json::from_str(&contents).map_err(|source| EvalError::Parse {
+                path: plan_path.clone(),
+                source,
+            })?;
+            Some(plan)
+        } else {
+            None
+        };
+        cases.push(EvalCase {
+            name: name.to_string(),
+            diff,
+            expected,
+        });
+    }
+    if cases.is_empty() {
+        return Err(EvalError::Empty(diffs));
+    }
+    Ok(cases)
+}
+
+/// Score one model response for `case`.
+pub fn score_case(
+    case: &EvalCase,
+    model: &str,
+    response: &Result<CommitPlan, LlmError>,
+    latency: Duration,
+    atomicity: AtomicityPolicy,
+) -> CaseResult {
+    let expected_units = case.expected.as_ref().map(|plan| plan.plan.len());
+    let mut result = CaseResult {
+        case: case.name.clone(),
+        model: model.to_string(),
+        schema_valid: false,
+        semantic_valid: false,
+        coverage: 0.0,
+        units: None,
+        expected_units,
+        units_match: expected_units.map(|_| false),
+        latency_ms: latency.as_millis() as u64,
+        error: None,
+    };
+    match response {
+        Ok(plan) => {
+            let score = scoring::score_plan(&plan.plan, &case.diff, &[], ScopePolicy::Warn, atomicity);
+            result.schema_valid = true;
+            result.semantic_valid = score.is_valid();
+            result.coverage = score.coverage;
+            result.units = Some(plan.plan.len());
+            result.units_match = expected_units.map(|expected| expected == plan.plan.len());
+        }
+        Err(err) => result.error = Some(err.to_string()),
+    }
+    result
+}
+
+/// Per-model totals, in the order models first appear in `results`.
+pub fn summarize(results: &[CaseResult]) -> Vec<ModelSummary> {
+    let mut models: Vec<&str> = Vec::new();
+    for result in results {
+        if !models.contains(&result.model.as_str()) {
+            models.push(&result.model);
+        }
+    }
+    models
+        .into_iter()
+        .map(|model| {
+            let results: Vec<&CaseResult> = results.iter().filter(|result| result.model == model).collect();
+            let cases = results.len();
+            let with_expected: Vec<bool> = results.iter().filter_map(|result| result.units_match).collect();
+            let total_latency: u64 = results.iter().map(|result| result.latency_ms).sum();
+            ModelSummary {
+                model: model.to_string(),
+                cases,
+                schema_valid: results.iter().filter(|result| result.schema_valid).count(),
+                semantic_valid: results.iter().filter(|result| result.semantic_valid).count(),
+                coverage: results.iter().map(|result| result.coverage).sum::<f64>() / cases.max(1) as f64,
+                unit_agreement: (!with_expected.is_empty()).then(|| {
+                    with_expected.iter().filter(|matched| **matched).count() as f64 / with_expected.len() as f64
+                }),
+                mean_latency_ms: total_latency / cases.max(1) as u64,
+                max_latency_ms: results.iter().map(|result| result.latency_ms).max().unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// Plan every case with every model, one request at a time so latencies
+/// are comparable.
+///
+/// `request` receives the config (with `model` set and no `backends`) and
+/// the prompt. Few-shot examples whose diff is the case's own are left out
+/// of its prompt.
+pub async fn run_eval<F, Fut>(
+    config: &ResolvedConfig,
+    templates: &PromptTemplates,
+    fixtures: &Path,
+    cases: &[EvalCase],
+    models: &[String],
+    mut request: F,
+) -> EvalReport
+where
+    F: FnMut(ResolvedConfig, Prompt) -> Fut,
+    Fut: Future<Output = Result<CommitPlan, LlmError>>,
+{
+    let mut results = Vec::new();
+    for model in models {
+        // The model under test answers alone, without fallback backends.
+        let model_config = ResolvedConfig {
+            model: model.clone(),
+            backends: Vec::new(),
+            ..config.clone()
+        };
+        for case in cases {
+            let mut templates = templates.clone();
+            if let Some(few_shot) = templates.few_shot.as_mut() {
+                few_shot.examples.examples.retain(|example| example.diff != case.diff);
+            }
+            let prompt = templates.build_prompt(PromptContext {
+                repo_path: None,
+                diff_mode: None,
+                include_untracked: None,
+                git_status: None,
+                workspace: None,
+                diff: &case.diff,
+            });
+            let started = Instant::now();
+            let response = request(model_config.clone(), prompt).await;
+            let result = score_case(case, model, &response, started.elapsed(), config.atomicity);
+            info!(
+                model = %model,
+                case = %case.name,
+                schema_valid = result.schema_valid,
+                semantic_valid = result.semantic_valid,
+                latency_ms = result.latency_ms,
+                "eval case complete"
+            );
+            results.push(result);
+        }
+    }
+
+    EvalReport {
+        schema_version: SCHEMA_VERSION.to_string(),
+        fixtures: fixtures.display().to_string(),
+        models: summarize(&results),
+        cases: results,
+    }
+}
@@ -1,10 +1,33 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::config::DiffMode;
+use ulid::Ulid;
+
+use crate::config::{DiffMode, GitBackendKind, ResolvedConfig};
 use crate::hash;
-use crate::types::{ApplyResult, ApplyStatus, CommitType, CommitUnit};
+
+mod backend;
+mod condense;
+mod filter;
+#[cfg(feature = "native-git")]
+mod native;
+mod parse;
+mod submodule;
+mod workspace;
+
+pub use backend::{backend_for, CliBackend, GitBackend};
+pub use condense::condense_diff;
+pub use filter::{filter_diff, FilteredDiff, PathFilter};
+pub use parse::{diff_hunks, diff_paths, file_hashes, parse_diff_files, DiffHunk};
+pub use submodule::{plan_submodule_units, submodule_changes, SubmoduleChange};
+pub use workspace::{discover_repos, Discovery};
+#[cfg(feature = "native-git")]
+pub use native::NativeBackend;
+use crate::types::{
+    ApplyResult, ApplyStatus, CommitType, CommitUnit, DiffFile, ErrorDetail, FileStatus, HookStatus,
+    VerifyOutcome,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum GitError {
@@ -12,10 +35,17 @@ pub enum GitError {
     CommandFailed { cmd: String, stderr: String },
     #[error("git command io error: {cmd}: {source}")]
     CommandIo { cmd: String, source: std::io::Error },
+    #[error("cannot read {path}: {source}")]
+    Io { path: String, source: std::io::Error },
     #[error("git output was not utf-8")]
     OutputNotUtf8,
     #[error("diff hash mismatch: expected {expected}, actual {actual}")]
-    DiffHashMismatch { expected: String, actual: String },
+    DiffHashMismatch {
+        expected: String,
+        actual: String,
+        /// Files whose diff changed since planning, when known.
+        files: Vec<String>,
+    },
     #[error("plan includes unsupported hunks for commit {id}")]
     HunksNotSupported { id: String },
     #[error("plan file not found in diff for commit {id}: {file}")]
@@ -26,139 +56,1054 @@ pub enum GitError {
         expected: Vec<String>,
         actual: Vec<String>,
     },
+    #[error("commit {id} must include both sides of the rename {from} -> {to}")]
+    RenameSplit { id: String, from: String, to: String },
     #[error("staged diff is empty for commit {id}")]
     StagedDiffEmpty { id: String },
+    #[error("invalid revision range {spec}: {reason}")]
+    InvalidRange { spec: String, reason: String },
+    #[error("target ref already exists: {name}")]
+    RefExists { name: String },
+    #[error("split tree mismatch: expected {expected}, actual {actual}")]
+    SplitTreeMismatch { expected: String, actual: String },
+    #[error("worktree drifted from snapshot: expected tree {expected}, actual {actual}")]
+    TreeDrift {
+        expected: String,
+        actual: String,
+        files: Vec<String>,
+    },
+    #[error("commit {id} mixes files inside submodule {submodule} with files outside it")]
+    SubmoduleMixed { id: String, submodule: String },
+    #[error("invalid path pattern {pattern}: {message}")]
+    InvalidPattern { pattern: String, message: String },
+    #[error("diff mode {mode} is read-only; plans from it cannot be applied")]
+    ReadOnlyDiffMode { mode: String },
+    #[error("rollback on verify failure requires a verify command")]
+    RollbackWithoutVerify,
+    #[error("native git error: {op}: {message}")]
+    Native { op: String, message: String },
+    #[error("commit hooks failed for commit {id}: {}", hooks.join(", "))]
+    HookFailed {
+        id: String,
+        hooks: Vec<String>,
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+}
+
+/// How `apply_plan` treats repository commit hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookPolicy {
+    /// Run hooks; a unit rejected by a hook is reported as failed and the
+    /// remaining units are still applied.
+    Run,
+    /// Pass `--no-verify` so pre-commit and commit-msg hooks do not run.
+    Skip,
+    /// Run hooks and stop at the first unit a hook rejects.
+    #[default]
+    FailFast,
+}
+
+/// Hooks that can make `git commit` fail. post-commit runs after the commit
+/// exists and its exit status is ignored.
+const COMMIT_HOOKS: [&str; 3] = ["pre-commit", "prepare-commit-msg", "commit-msg"];
+
+/// What to diff and how much of each file to show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub mode: DiffMode,
+    pub include_untracked: bool,
+    /// Per-file diff size above which the file is summarized as metadata.
+    pub max_file_bytes: u64,
+    /// Append the diffs of initialized submodules, with paths prefixed by
+    /// the submodule path.
+    pub recurse_submodules: bool,
+    /// Paths left out of the diff.
+    pub filter: PathFilter,
+    /// Git pathspecs, relative to the repository root, that limit the diff;
+    /// empty means the whole tree.
+    pub pathspec: Vec<String>,
+    /// Commits diffed in the `base` and `range` modes.
+    pub range: Option<RevisionRange>,
+}
+
+impl DiffOptions {
+    pub fn new(mode: DiffMode, include_untracked: bool) -> Self {
+        Self {
+            mode,
+            include_untracked,
+            max_file_bytes: ResolvedConfig::defaults().max_file_diff_bytes,
+            recurse_submodules: false,
+            filter: PathFilter::default(),
+            pathspec: Vec::new(),
+            range: None,
+        }
+    }
+
+    pub fn from_config(config: &ResolvedConfig) -> Self {
+        Self {
+            mode: config.diff_mode,
+            include_untracked: config.include_untracked,
+            max_file_bytes: config.max_file_diff_bytes,
+            recurse_submodules: config.recurse_submodules,
+            filter: PathFilter::from_config(config),
+            pathspec: Vec::new(),
+            range: None,
+        }
+    }
 }
 
 pub struct ApplyRequest<'a> {
     pub repo: &'a Path,
+    pub backend: GitBackendKind,
     pub plan: &'a [CommitUnit],
     pub diff: &'a str,
     pub diff_mode: DiffMode,
     pub include_untracked: bool,
+    pub max_file_diff_bytes: u64,
+    /// Plan files inside submodules are committed in the submodule.
+    pub recurse_submodules: bool,
+    /// Path filter the diff was computed with.
+    pub filter: PathFilter,
+    /// Pathspec the diff was computed with.
+    pub pathspec: Vec<String>,
     pub expected_diff_hash: Option<String>,
+    /// Per-file hashes from planning (see [`file_hashes`]); computed from
+    /// `diff` when absent.
+    pub expected_file_hashes: Option<BTreeMap<String, String>>,
+    /// Require the whole diff to be unchanged, not just the planned files.
+    pub strict_diff_hash: bool,
     pub cleanup_on_error: bool,
     pub assisted_by: Option<&'a str>,
+    pub hooks: HookPolicy,
+    pub sign: bool,
+    pub signing_key: Option<&'a str>,
+    /// Shell command run in a temporary worktree checked out at each new commit.
+    pub verify_command: Option<&'a str>,
+    /// Undo every commit made by this apply when the verify command fails.
+    pub rollback_on_verify_failure: bool,
+}
+
+/// Bytes of verify command output kept in `ApplyResult`.
+const VERIFY_OUTPUT_LIMIT: usize = 4096;
+
+/// A resolved `<base>..<head>` commit range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevisionRange {
+    pub base: String,
+    pub head: String,
+}
+
+pub struct SplitRequest<'a> {
+    pub repo: &'a Path,
+    pub range: &'a RevisionRange,
+    pub plan: &'a [CommitUnit],
+    pub diff: &'a str,
+    /// Changed paths left out of `diff` by path filters. They are committed
+    /// after the plan in a unit of their own (see [`carried_unit`]), so the
+    /// rebuilt head keeps the original tree.
+    pub excluded: &'a [String],
+    pub target_ref: &'a str,
+    pub assisted_by: Option<&'a str>,
 }
 
 pub fn compute_diff(repo: &Path, mode: DiffMode, include_untracked: bool) -> Result<String, GitError> {
+    compute_diff_with(repo, &DiffOptions::new(mode, include_untracked))
+}
+
+/// Diff through the `git` binary, with binary, LFS, and oversized files
+/// reduced to metadata (see [`condense_diff`]) and filtered paths removed
+/// (see [`filter_diff`]).
+pub fn compute_diff_with(repo: &Path, options: &DiffOptions) -> Result<String, GitError> {
     let mut parts = Vec::new();
+    push_if_non_empty(&mut parts, raw_diff(repo, options, "", &options.pathspec)?);
+    if options.recurse_submodules && !options.mode.is_revision() {
+        parts.extend(submodule::submodule_diffs(
+            repo,
+            &options.pathspec,
+            &|path, prefix, pathspec| raw_diff(path, options, prefix, pathspec),
+        )?);
+    }
 
-    match mode {
+    let diff = condense_diff(repo, &parts.join("\n"), options.max_file_bytes);
+    Ok(filter_diff(&diff, &options.filter)?.diff)
+}
+
+/// Uncondensed diff of `repo`, limited to `pathspec`, with every path
+/// prefixed by `prefix`.
+fn raw_diff(
+    repo: &Path,
+    options: &DiffOptions,
+    prefix: &str,
+    pathspec: &[String],
+) -> Result<String, GitError> {
+    let src_prefix = format!("--src-prefix=a/{prefix}");
+    let dst_prefix = format!("--dst-prefix=b/{prefix}");
+    let prefixed = |args: &[&'static str]| {
+        let mut all: Vec<&str> = args.to_vec();
+        if !prefix.is_empty() {
+            all.push(&src_prefix);
+            all.push(&dst_prefix);
+        }
+        all
+    };
+    let diff_args = |args: &[&'static str]| {
+        let mut all = prefixed(args);
+        if !pathspec.is_empty() {
+            all.push("--");
+            all.extend(pathspec.iter().map(String::as_str));
+        }
+        all
+    };
+    let mut parts = Vec::new();
+
+    match options.mode {
         DiffMode::Worktree => {
-            let diff = run_git_diff(repo, &["diff"], &[])?;
+            let diff = run_git_diff(repo, &diff_args(&["diff"]), &[])?;
             push_if_non_empty(&mut parts, diff);
         }
         DiffMode::Staged => {
-            let diff = run_git_diff(repo, &["diff", "--staged"], &[])?;
+            let diff = run_git_diff(repo, &diff_args(&["diff", "--staged"]), &[])?;
             push_if_non_empty(&mut parts, diff);
         }
         DiffMode::All => {
-            let diff = run_git_diff(repo, &["diff"], &[])?;
-            let staged = run_git_diff(repo, &["diff", "--staged"], &[])?;
+            let diff = run_git_diff(repo, &diff_args(&["diff"]), &[])?;
+            let staged = run_git_diff(repo, &diff_args(&["diff", "--staged"]), &[])?;
             push_if_non_empty(&mut parts, diff);
             push_if_non_empty(&mut parts, staged);
         }
+        DiffMode::Base | DiffMode::Range => {
+            // Revision diffs never see untracked files or the worktree.
+            let range = revision_range(options)?;
+            let mut args = diff_args(&["diff"]);
+            args.insert(1, &range.base);
+            args.insert(2, &range.head);
+            return run_git_diff(repo, &args, &[]);
+        }
     }
 
-    if include_untracked {
-        let untracked = list_untracked_files(repo)?;
-        for path in untracked {
-            let diff = run_git_diff(repo, &["diff", "--no-index", "--", "/dev/null"], &[path])?;
+    if options.include_untracked {
+        let mut nested = Vec::new();
+        for path in list_untracked_files(repo, pathspec)? {
+            let name = path.to_string_lossy();
+            if submodule::is_nested_repo(&name) {
+                nested.extend(submodule::nested_repo_diff(repo, &name, prefix));
+                continue;
+            }
+            // Oversized untracked files are never read, only described.
+            if let Some(summary) = condense::untracked_summary(repo, &path, prefix, options.max_file_bytes) {
+                parts.push(summary);
+                continue;
+            }
+            let args = prefixed(&["diff", "--no-index"]);
+            let args: Vec<&str> = args.into_iter().chain(["--", "/dev/null"]).collect();
+            let diff = run_git_diff(repo, &args, &[path])?;
             push_if_non_empty(&mut parts, diff);
         }
+        parts.extend(nested);
     }
 
     Ok(parts.join("\n"))
 }
 
-pub fn apply_plan(request: ApplyRequest<'_>) -> Result<Vec<ApplyResult>, GitError> {
+/// Check that `request` can be applied: its diff mode must read the worktree,
+/// rollback needs a verify command, and the diff must not have drifted since
+/// planning (see `strict_diff_hash`).
+/// Nothing is staged or committed.
+pub fn check_apply_request(request: &ApplyRequest<'_>) -> Result<(), GitError> {
+    if request.diff_mode.is_revision() {
+        return Err(GitError::ReadOnlyDiffMode {
+            mode: diff_mode_str(request.diff_mode).to_string(),
+        });
+    }
+    if request.rollback_on_verify_failure && request.verify_command.is_none() {
+        return Err(GitError::RollbackWithoutVerify);
+    }
+
     let expected_hash = request
         .expected_diff_hash
+        .clone()
         .unwrap_or_else(|| hash::diff_hash(request.diff));
-    let diff_files = diff_files(request.diff);
-
+    let expected_files = request
+        .expected_file_hashes
+        .clone()
+        .unwrap_or_else(|| file_hashes(request.diff));
+    let diff_options = DiffOptions {
+        mode: request.diff_mode,
+        include_untracked: request.include_untracked,
+        max_file_bytes: request.max_file_diff_bytes,
+        recurse_submodules: request.recurse_submodules,
+        filter: request.filter.clone(),
+        pathspec: request.pathspec.clone(),
+        range: None,
+    };
+    let checked: Option<HashSet<&str>> = (!request.strict_diff_hash).then(|| {
+        request
+            .plan
+            .iter()
+            .flat_map(|unit| unit.files.iter().map(String::as_str))
+            .collect()
+    });
     verify_diff_hash(
+        backend_for(request.backend).as_ref(),
         request.repo,
-        request.diff_mode,
-        request.include_untracked,
+        &diff_options,
         &expected_hash,
-    )?;
+        &expected_files,
+        checked.as_ref(),
+    )
+}
+
+pub fn apply_plan(request: ApplyRequest<'_>) -> Result<Vec<ApplyResult>, GitError> {
+    check_apply_request(&request)?;
+    let parsed_files = parse_diff_files(request.diff);
+    let diff_files: HashSet<&str> = diff_paths(&parsed_files).collect();
+
+    let backend = backend_for(request.backend);
+    let submodules = if request.recurse_submodules {
+        submodule::list_submodules(request.repo)?
+    } else {
+        Vec::new()
+    };
+    let repos = submodule::repo_paths(request.repo, &submodules);
+    // Committing a unit changes the diff but never the tree of HEAD plus the
+    // uncommitted leftovers, so that tree is what later units verify against.
+    // Commits inside submodules move their gitlinks, so when recursing each
+    // repository is snapshotted on its own, without gitlinks.
+    let snapshots = repos
+        .iter()
+        .map(|repo| snapshot_tree_with(repo, request.recurse_submodules))
+        .collect::<Result<Vec<_>, _>>()?;
+    let verify_snapshots = || -> Result<(), GitError> {
+        for (repo, snapshot) in repos.iter().zip(&snapshots) {
+            verify_snapshot_tree(repo, snapshot, request.recurse_submodules)?;
+        }
+        Ok(())
+    };
+    let target_repo = |target: &Option<String>| match target {
+        Some(path) => request.repo.join(path),
+        None => request.repo.to_path_buf(),
+    };
+    // A unit only commits its own files, so before each unit only the files
+    // of the units still to come are compared with the snapshot. The whole
+    // tree is compared once more at the end.
+    let verify_pending = |pending: &[CommitUnit]| -> Result<(), GitError> {
+        let targets: Vec<(PathBuf, Vec<String>)> = pending
+            .iter()
+            .filter_map(|unit| submodule::unit_target(unit, &submodules).ok())
+            .map(|(target, files)| (target_repo(&target), files))
+            .collect();
+        for (repo, snapshot) in repos.iter().zip(&snapshots) {
+            let paths: Vec<String> = targets
+                .iter()
+                .filter(|(target, _)| target == repo)
+                .flat_map(|(_, files)| files.iter())
+                .filter(|file| !submodules.contains(file))
+                .cloned()
+                .collect();
+            if !paths.is_empty() && !backend.changed_paths(repo, snapshot, &paths)?.is_empty() {
+                verify_snapshot_tree(repo, snapshot, request.recurse_submodules)?;
+            }
+        }
+        Ok(())
+    };
+
+    let no_verify = request.hooks == HookPolicy::Skip;
+    let sign = request.sign || request.signing_key.is_some();
+    let commit_options = |repo: &Path| -> Result<CommitOptions<'_>, GitError> {
+        Ok(CommitOptions {
+            assisted_by: request.assisted_by,
+            installed_hooks: installed_hooks(repo)?,
+            no_verify,
+            sign,
+            signing_key: request.signing_key,
+        })
+    };
+    let rollback = if request.verify_command.is_some() && request.rollback_on_verify_failure {
+        Some(
+            repos
+                .iter()
+                .map(|repo| RollbackPoint::capture(repo))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    } else {
+        None
+    };
 
     let mut results = Vec::new();
-    for unit in request.plan {
-        verify_diff_hash(
-            request.repo,
-            request.diff_mode,
-            request.include_untracked,
-            &expected_hash,
-        )?;
+    for (index, unit) in request.plan.iter().enumerate() {
+        verify_pending(&request.plan[index..])?;
         if !unit.hunks.is_empty() {
             return Err(GitError::HunksNotSupported { id: unit.id.clone() });
         }
 
         for file in &unit.files {
-            if !diff_files.contains(file) {
+            if !diff_files.contains(file.as_str()) {
                 return Err(GitError::PlanFileMissing {
                     id: unit.id.clone(),
                     file: file.clone(),
                 });
             }
         }
+        check_renames(unit, &parsed_files)?;
 
-        let file_paths: Vec<PathBuf> = unit.files.iter().map(|file| request.repo.join(file)).collect();
-        if let Err(error) = stage_files(request.repo, &file_paths)
-            .and_then(|_| verify_staged_files(request.repo, unit))
-            .and_then(|_| commit_unit(request.repo, unit, request.assisted_by))
-            .and_then(|hash| {
+        let (target, files) = submodule::unit_target(unit, &submodules)?;
+        let repo = target_repo(&target);
+        let options = commit_options(&repo)?;
+        let hook_status = options.hook_status();
+        let file_paths: Vec<PathBuf> = files.iter().map(|file| repo.join(file)).collect();
+        let commit = backend
+            .stage_files(&repo, &files)
+            .and_then(|_| verify_staged_files(backend.as_ref(), &repo, &unit.id, &files))
+            .and_then(|_| backend.commit(&repo, unit, &options));
+        match commit {
+            Ok(hash) => {
+                let signed = commit_is_signed(&repo, &hash)?;
+                let verify = match request.verify_command {
+                    Some(command) => Some(verify_commit(&repo, &hash, command)?),
+                    None => None,
+                };
+                if verify.as_ref().is_none_or(|outcome| outcome.passed) {
+                    results.push(ApplyResult {
+                        id: unit.id.clone(),
+                        status: ApplyStatus::Applied,
+                        commit_hash: Some(hash),
+                        error: None,
+                        hooks: Some(hook_status),
+                        signed: Some(signed),
+                        verify,
+                    });
+                    continue;
+                }
+
+                let rolled_back = match &rollback {
+                    Some(points) => {
+                        for (repo, point) in repos.iter().zip(points) {
+                            point.restore(repo)?;
+                        }
+                        // The rolled-back commits are no longer on the branch.
+                        for result in &mut results {
+                            result.status = ApplyStatus::Skipped;
+                            result.commit_hash = None;
+                        }
+                        true
+                    }
+                    None => false,
+                };
                 results.push(ApplyResult {
                     id: unit.id.clone(),
-                    status: ApplyStatus::Applied,
-                    commit_hash: Some(hash),
+                    status: ApplyStatus::Failed,
+                    commit_hash: (!rolled_back).then(|| hash.clone()),
+                    error: Some(ErrorDetail {
+                        code: "verify_failed".to_string(),
+                        message: format!("verify command failed on commit {}", unit.id),
+                        details: Some(serde_json::json!({
+                            "commit_hash": hash,
+                            "rolled_back": rolled_back,
+                            "hint": format!(
+                                "commit {} does not build on its own; re-plan so it includes the changes it depends on",
+                                unit.id
+                            ),
+                        })),
+                    }),
+                    hooks: Some(hook_status),
+                    signed: Some(signed),
+                    verify,
+                });
+                results.extend(request.plan[index + 1..].iter().map(|unit| ApplyResult {
+                    id: unit.id.clone(),
+                    status: ApplyStatus::Skipped,
+                    commit_hash: None,
                     error: None,
+                    hooks: None,
+                    signed: None,
+                    verify: None,
+                }));
+                break;
+            }
+            Err(GitError::HookFailed {
+                id,
+                hooks,
+                exit_code,
+                stdout,
+                stderr,
+            }) if request.hooks == HookPolicy::Run => {
+                // The hook rejected this unit only; unstage it so the next
+                // unit starts from a clean index.
+                reset_files(&repo, &file_paths)?;
+                results.push(ApplyResult {
+                    id,
+                    status: ApplyStatus::Failed,
+                    commit_hash: None,
+                    error: Some(ErrorDetail {
+                        code: "hook_failed".to_string(),
+                        message: format!("commit hooks failed: {}", hooks.join(", ")),
+                        details: Some(serde_json::json!({
+                            "hooks": hooks,
+                            "exit_code": exit_code,
+                            "stdout": stdout,
+                            "stderr": stderr,
+                        })),
+                    }),
+                    hooks: Some(HookStatus::Failed),
+                    signed: None,
+                    verify: None,
                 });
-                Ok(())
-            })
-        {
-            if request.cleanup_on_error {
-                let _ = reset_files(request.repo, &file_paths);
             }
-            return Err(error);
+            Err(error) => {
+                if request.cleanup_on_error {
+                    let _ = reset_files(&repo, &file_paths);
+                }
+                return Err(error);
+            }
         }
     }
 
+    verify_snapshots()?;
     Ok(results)
 }
 
-fn list_untracked_files(repo: &Path) -> Result<Vec<PathBuf>, GitError> {
-    let output = run_git(repo, &["status", "--porcelain=v1", "-z"])?;
+/// Where a repository stands before planning or applying.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoState {
+    pub root: PathBuf,
+    /// Checked-out branch; `None` when HEAD is detached.
+    pub branch: Option<String>,
+    /// HEAD has no commit yet.
+    pub unborn: bool,
+    /// An unfinished `merge`, `rebase`, `cherry-pick`, or `revert`.
+    pub operation: Option<&'static str>,
+}
+
+/// Version reported by the `git` binary, e.g. `2.43.0`.
+pub fn git_version() -> Result<String, GitError> {
+    let mut cmd = Command::new("git");
+    cmd.arg("--version");
+    let output = run_command(cmd, "git --version".to_string(), false)?;
+    let output = output.trim();
+    Ok(output.strip_prefix("git version ").unwrap_or(output).to_string())
+}
+
+pub fn repo_state(repo: &Path) -> Result<RepoState, GitError> {
+    let root = PathBuf::from(run_git(repo, &["rev-parse", "--show-toplevel"])?.trim());
+    let branch = run_git(repo, &["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .ok()
+        .map(|branch| branch.trim().to_string());
+    let unborn = run_git(repo, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_err();
+    let git_dir = PathBuf::from(run_git(repo, &["rev-parse", "--absolute-git-dir"])?.trim());
+    let operation = [
+        ("MERGE_HEAD", "merge"),
+        ("rebase-merge", "rebase"),
+        ("rebase-apply", "rebase"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| git_dir.join(marker).exists())
+    .map(|(_, operation)| operation);
+    Ok(RepoState {
+        root,
+        branch,
+        unborn,
+        operation,
+    })
+}
+
+/// Tree id of HEAD plus every uncommitted change, untracked files included.
+///
+/// The tree is written through a copy of the index, so the real index is
+/// left untouched. Ignored files are not part of the snapshot.
+pub fn snapshot_tree(repo: &Path) -> Result<String, GitError> {
+    snapshot_tree_with(repo, false)
+}
+
+/// [`snapshot_tree`], optionally leaving out gitlinks.
+fn snapshot_tree_with(repo: &Path, without_gitlinks: bool) -> Result<String, GitError> {
+    let index = TempIndex::new();
+    let real_index = index_path(repo)?;
+    if real_index.exists() {
+        copy_file(&real_index, &index.path)?;
+    } else {
+        run_git_with_index(repo, &index.path, &["read-tree", "--empty"])?;
+    }
+    run_git_with_index(repo, &index.path, &["add", "-A"])?;
+    if without_gitlinks {
+        let entries = run_git_with_index(repo, &index.path, &["ls-files", "--stage", "-z"])?;
+        for entry in entries.split('\0') {
+            if let Some(("160000", path)) = entry
+                .split_once('\t')
+                .map(|(meta, path)| (meta.split(' ').next().unwrap_or_default(), path))
+            {
+                run_git_with_index(repo, &index.path, &["rm", "--cached", "-q", "--", path])?;
+            }
+        }
+    }
+    Ok(run_git_with_index(repo, &index.path, &["write-tree"])?
+        .trim()
+        .to_string())
+}
+
+/// Which of `paths` differ between `tree` and the worktree.
+///
+/// Only `paths` are hashed: they are written over `tree` in a temporary
+/// index, which is then compared with `tree`.
+fn changed_paths(repo: &Path, tree: &str, paths: &[String]) -> Result<Vec<String>, GitError> {
+    let index = TempIndex::new();
+    run_git_with_index(repo, &index.path, &["read-tree", tree])?;
+    // Missing paths are dropped from the index, or ignored when absent.
+    let args: Vec<&str> = ["update-index", "--add", "--remove", "--"]
+        .into_iter()
+        .chain(paths.iter().map(String::as_str))
+        .collect();
+    run_git_with_index(repo, &index.path, &args)?;
+    let args: Vec<&str> = ["--literal-pathspecs", "diff-index", "--cached", "--name-only", "-z", tree, "--"]
+        .into_iter()
+        .chain(paths.iter().map(String::as_str))
+        .collect();
+    let changed = run_git_with_index(repo, &index.path, &args)?;
+    Ok(changed
+        .split('\0')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.to_string())
+        .collect())
+}
+
+fn index_path(repo: &Path) -> Result<PathBuf, GitError> {
+    let path = run_git(repo, &["rev-parse", "--git-path", "index"])?;
+    Ok(repo.join(path.trim()))
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<(), GitError> {
+    std::fs::copy(from, to)
+        .map(|_| ())
+        .map_err(|source| GitError::CommandIo {
+            cmd: format!("copy {} {}", from.display(), to.display()),
+            source,
+        })
+}
+
+/// HEAD and index as they were before apply, for undoing its commits.
+pub struct RollbackPoint {
+    head: Option<String>,
+    index: Option<TempIndex>,
+}
+
+impl RollbackPoint {
+    pub fn capture(repo: &Path) -> Result<Self, GitError> {
+        let head = rev_parse(repo, "HEAD").ok();
+        let real_index = index_path(repo)?;
+        let index = if real_index.exists() {
+            let backup = TempIndex::new();
+            copy_file(&real_index, &backup.path)?;
+            Some(backup)
+        } else {
+            None
+        };
+        Ok(Self { head, index })
+    }
+
+    /// Move the branch back and restore the index; the worktree is untouched.
+    pub fn restore(&self, repo: &Path) -> Result<(), GitError> {
+        match &self.head {
+            Some(head) => run_git(repo, &["reset", "-q", "--soft", head])?,
+            None => run_git(repo, &["update-ref", "-d", "HEAD"])?,
+        };
+        let real_index = index_path(repo)?;
+        match &self.index {
+            Some(backup) => copy_file(&backup.path, &real_index),
+            None => std::fs::remove_file(&real_index).map_err(|source| GitError::CommandIo {
+                cmd: format!("remove {}", real_index.display()),
+                source,
+            }),
+        }
+    }
+}
+
+/// Run `command` through `sh -c` in a detached temporary worktree at `commit`.
+fn verify_commit(repo: &Path, commit: &str, command: &str) -> Result<VerifyOutcome, GitError> {
+    let worktree = TempWorktree::add(repo, commit)?;
+    let output = Command::new("sh")
+        .current_dir(&worktree.path)
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|source| GitError::CommandIo {
+            cmd: format!("sh -c {command}"),
+            source,
+        })?;
+
+    let mut combined = output.stdout;
+    combined.extend_from_slice(&output.stderr);
+    let start = combined.len().saturating_sub(VERIFY_OUTPUT_LIMIT);
+    Ok(VerifyOutcome {
+        command: command.to_string(),
+        passed: output.status.success(),
+        exit_code: output.status.code(),
+        output: String::from_utf8_lossy(&combined[start..]).to_string(),
+    })
+}
+
+/// A detached `git worktree` that is removed when dropped.
+struct TempWorktree {
+    repo: PathBuf,
+    path: PathBuf,
+}
+
+impl TempWorktree {
+    fn add(repo: &Path, commit: &str) -> Result<Self, GitError> {
+        let path = std::env::temp_dir().join(format!("atomc-verify-{}", Ulid::new()));
+        let path_arg = path.to_string_lossy().to_string();
+        run_git(repo, &["worktree", "add", "--detach", "-q", &path_arg, commit])?;
+        Ok(Self {
+            repo: repo.to_path_buf(),
+            path,
+        })
+    }
+}
+
+impl Drop for TempWorktree {
+    fn drop(&mut self) {
+        let path = self.path.to_string_lossy().to_string();
+        let _ = run_git(&self.repo, &["worktree", "remove", "--force", &path]);
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+fn verify_snapshot_tree(repo: &Path, expected: &str, without_gitlinks: bool) -> Result<(), GitError> {
+    let actual = snapshot_tree_with(repo, without_gitlinks)?;
+    if actual == expected {
+        return Ok(());
+    }
+    let changed = run_git(repo, &["diff-tree", "-r", "--name-only", "-z", expected, &actual])?;
+    let files = changed
+        .split('\0')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.to_string())
+        .collect();
+    Err(GitError::TreeDrift {
+        expected: expected.to_string(),
+        actual,
+        files,
+    })
+}
+
+/// Resolve `<base>..<head>` (or `<base>`, meaning `<base>..HEAD`) to commit ids.
+///
+/// The base must be an ancestor of the head so the range can be rebuilt linearly.
+pub fn resolve_range(repo: &Path, spec: &str) -> Result<RevisionRange, GitError> {
+    let (base, head) = match spec.split_once("..") {
+        Some((base, head)) => (base, if head.is_empty() { "HEAD" } else { head }),
+        None => (spec, "HEAD"),
+    };
+    if base.is_empty() || head.starts_with('.') {
+        return Err(GitError::InvalidRange {
+            spec: spec.to_string(),
+            reason: "expected <base>..<head> or <base>".to_string(),
+        });
+    }
+
+    let base = resolve_commit(repo, spec, base)?;
+    let head = resolve_commit(repo, spec, head)?;
+    if base == head {
+        return Err(GitError::InvalidRange {
+            spec: spec.to_string(),
+            reason: "range is empty".to_string(),
+        });
+    }
+
+    let ancestor = Command::new("git")
+        .current_dir(repo)
+        .args(["merge-base", "--is-ancestor", &base, &head])
+        .status()
+        .map_err(|source| GitError::CommandIo {
+            cmd: "git merge-base --is-ancestor".to_string(),
+            source,
+        })?;
+    if !ancestor.success() {
+        return Err(GitError::InvalidRange {
+            spec: spec.to_string(),
+            reason: "base is not an ancestor of head".to_string(),
+        });
+    }
+
+    Ok(RevisionRange { base, head })
+}
+
+/// Resolve `rev` against HEAD for the `base` diff mode: the range runs from
+/// their merge base to HEAD, so only the current branch's commits are diffed.
+pub fn resolve_base(repo: &Path, rev: &str) -> Result<RevisionRange, GitError> {
+    let head = resolve_commit(repo, rev, "HEAD")?;
+    let other = resolve_commit(repo, rev, rev)?;
+    let base = run_git(repo, &["merge-base", &other, &head])
+        .map_err(|_| GitError::InvalidRange {
+            spec: rev.to_string(),
+            reason: "no common ancestor with HEAD".to_string(),
+        })?
+        .trim()
+        .to_string();
+    if base == head {
+        return Err(GitError::InvalidRange {
+            spec: rev.to_string(),
+            reason: "range is empty".to_string(),
+        });
+    }
+    Ok(RevisionRange { base, head })
+}
+
+/// The commits a revision diff mode needs, which callers resolve up front.
+fn revision_range(options: &DiffOptions) -> Result<&RevisionRange, GitError> {
+    options.range.as_ref().ok_or_else(|| GitError::InvalidRange {
+        spec: diff_mode_str(options.mode).to_string(),
+        reason: "no revisions given".to_string(),
+    })
+}
+
+fn diff_mode_str(mode: DiffMode) -> &'static str {
+    match mode {
+        DiffMode::Worktree => "worktree",
+        DiffMode::Staged => "staged",
+        DiffMode::All => "all",
+        DiffMode::Base => "base",
+        DiffMode::Range => "range",
+    }
+}
+
+/// Diff the committed changes between the range base and head.
+pub fn compute_range_diff(repo: &Path, range: &RevisionRange) -> Result<String, GitError> {
+    run_git_diff(repo, &["diff", &range.base, &range.head], &[])
+}
+
+/// The `chore` unit that carries paths path filters left out of a split, so
+/// no planned commit contains changes its message does not describe.
+pub fn carried_unit(excluded: &[String]) -> Option<CommitUnit> {
+    (!excluded.is_empty()).then(|| CommitUnit {
+        id: "carried-paths".to_string(),
+        type_: CommitType::Chore,
+        scope: None,
+        summary: "carry over changes that path filters kept out of the plan".to_string(),
+        body: excluded.iter().map(|path| format!("Carry {path}")).collect(),
+        files: excluded.to_vec(),
+        hunks: Vec::new(),
+    })
+}
+
+/// Distinct `Name <email>` authors of the commits in `range`, oldest first.
+/// Split commits are authored by the current identity instead.
+pub fn range_authors(repo: &Path, range: &RevisionRange) -> Result<Vec<String>, GitError> {
+    let spec = format!("{}..{}", range.base, range.head);
+    let log = run_git(repo, &["log", "--reverse", "--format=%an <%ae>", &spec])?;
+    let mut authors: Vec<String> = Vec::new();
+    for author in log.lines() {
+        if !authors.iter().any(|known| known == author) {
+            authors.push(author.to_string());
+        }
+    }
+    Ok(authors)
+}
+
+/// Rebuild a commit range as one commit per plan unit on a new branch,
+/// followed by the [`carried_unit`] for `excluded` paths, if any.
+///
+/// Commits are written through a temporary index so neither the worktree nor
+/// the current branch is touched. They are authored and dated by the current
+/// identity, not the original commits. The branch is only created when the
+/// final tree is identical to the tree of the original head.
+pub fn split_range(request: SplitRequest<'_>) -> Result<Vec<ApplyResult>, GitError> {
+    let target_ref = branch_ref(request.target_ref);
+    if run_git(request.repo, &["rev-parse", "--verify", "--quiet", &target_ref]).is_ok() {
+        return Err(GitError::RefExists { name: target_ref });
+    }
+
+    let parsed_files = parse_diff_files(request.diff);
+    let diff_files: HashSet<&str> = diff_paths(&parsed_files).collect();
+    for unit in request.plan {
+        if !unit.hunks.is_empty() {
+            return Err(GitError::HunksNotSupported { id: unit.id.clone() });
+        }
+        for file in &unit.files {
+            if !diff_files.contains(file.as_str()) {
+                return Err(GitError::PlanFileMissing {
+                    id: unit.id.clone(),
+                    file: file.clone(),
+                });
+            }
+        }
+        check_renames(unit, &parsed_files)?;
+    }
+
+    let index = TempIndex::new();
+    run_git_with_index(request.repo, &index.path, &["read-tree", &request.range.base])?;
+
+    let mut parent = request.range.base.clone();
+    let mut parent_tree = rev_parse(request.repo, &format!("{parent}^{{tree}}"))?;
+    let mut results = Vec::new();
+    let carried = carried_unit(request.excluded);
+    for unit in request.plan.iter().chain(&carried) {
+        for file in &unit.files {
+            stage_file_from_commit(request.repo, &index.path, &request.range.head, file)?;
+        }
+        let tree = run_git_with_index(request.repo, &index.path, &["write-tree"])?
+            .trim()
+            .to_string();
+        if tree == parent_tree {
+            return Err(GitError::StagedDiffEmpty { id: unit.id.clone() });
+        }
+
+        let mut args = vec!["commit-tree".to_string(), tree.clone(), "-p".to_string(), parent];
+        for message in commit_message_parts(unit, request.assisted_by) {
+            args.push("-m".to_string());
+            args.push(message);
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let commit = run_git(request.repo, &args)?.trim().to_string();
+
+        results.push(ApplyResult {
+            id: unit.id.clone(),
+            status: ApplyStatus::Applied,
+            commit_hash: Some(commit.clone()),
+            error: None,
+            hooks: None,
+            signed: Some(false),
+            verify: None,
+        });
+        parent = commit;
+        parent_tree = tree;
+    }
+
+    let expected = rev_parse(request.repo, &format!("{}^{{tree}}", request.range.head))?;
+    if parent_tree != expected {
+        return Err(GitError::SplitTreeMismatch {
+            expected,
+            actual: parent_tree,
+        });
+    }
+
+    // An empty old value makes update-ref refuse to overwrite an existing ref.
+    run_git(request.repo, &["update-ref", &target_ref, &parent, ""])?;
+    Ok(results)
+}
+
+fn resolve_commit(repo: &Path, spec: &str, rev: &str) -> Result<String, GitError> {
+    rev_parse(repo, &format!("{rev}^{{commit}}")).map_err(|_| GitError::InvalidRange {
+        spec: spec.to_string(),
+        reason: format!("unknown revision {rev}"),
+    })
+}
+
+fn rev_parse(repo: &Path, rev: &str) -> Result<String, GitError> {
+    Ok(run_git(repo, &["rev-parse", "--verify", "--quiet", rev])?
+        .trim()
+        .to_string())
+}
+
+fn branch_ref(name: &str) -> String {
+    if name.starts_with("refs/") {
+        name.to_string()
+    } else {
+        format!("refs/heads/{name}")
+    }
+}
+
+/// Copy a path's entry from `commit` into the index, or drop it if absent there.
+fn stage_file_from_commit(
+    repo: &Path,
+    index: &Path,
+    commit: &str,
+    file: &str,
+) -> Result<(), GitError> {
+    let entry = run_git(repo, &["ls-tree", "-z", commit, "--", file])?;
+    let entry = entry.trim_end_matches('\0');
+    if entry.is_empty() {
+        run_git_with_index(repo, index, &["update-index", "--force-remove", "--", file])?;
+        return Ok(());
+    }
+
+    // ls-tree entries are "<mode> <type> <object>\t<path>".
+    let meta = entry.split('\t').next().unwrap_or_default();
+    let mut parts = meta.split_whitespace();
+    let mode = parts.next().unwrap_or_default();
+    let _kind = parts.next();
+    let object = parts.next().unwrap_or_default();
+    let cacheinfo = format!("{mode},{object},{file}");
+    run_git_with_index(repo, index, &["update-index", "--add", "--cacheinfo", &cacheinfo])?;
+    Ok(())
+}
+
+/// A scratch index file that is removed when dropped.
+struct TempIndex {
+    path: PathBuf,
+}
+
+impl TempIndex {
+    fn new() -> Self {
+        Self {
+            path: std::env::temp_dir().join(format!("atomc-index-{}", Ulid::new())),
+        }
+    }
+}
+
+impl Drop for TempIndex {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Untracked files relative to the repo root, one entry per file.
+fn list_untracked_files(repo: &Path, pathspec: &[String]) -> Result<Vec<PathBuf>, GitError> {
+    let mut args = vec!["status", "--porcelain=v1", "-z", "--untracked-files=all"];
+    if !pathspec.is_empty() {
+        args.push("--");
+        args.extend(pathspec.iter().map(String::as_str));
+    }
+    let output = run_git(repo, &args)?;
     let mut paths = Vec::new();
     for entry in output.split('\0') {
         if entry.is_empty() {
             continue;
         }
         if let Some(rest) = entry.strip_prefix("?? ") {
-            paths.push(repo.join(rest));
+            paths.push(PathBuf::from(rest));
         }
     }
     Ok(paths)
 }
 
+/// Compare the current diff with the planned one. With `checked`, only
+/// drift in those files fails; otherwise any change to the diff does.
 fn verify_diff_hash(
+    backend: &dyn GitBackend,
     repo: &Path,
-    diff_mode: DiffMode,
-    include_untracked: bool,
+    options: &DiffOptions,
     expected: &str,
+    expected_files: &BTreeMap<String, String>,
+    checked: Option<&HashSet<&str>>,
 ) -> Result<(), GitError> {
-    let current = compute_diff(repo, diff_mode, include_untracked)?;
+    let current = backend.diff(repo, options)?;
     let actual = hash::diff_hash(&current);
-    if actual != expected {
-        return Err(GitError::DiffHashMismatch {
-            expected: expected.to_string(),
-            actual,
-        });
+    if actual == expected {
+        return Ok(());
     }
-    Ok(())
+
+    let actual_files = file_hashes(&current);
+    let drifted: Vec<String> = expected_files
+        .keys()
+        .chain(actual_files.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|path| expected_files.get(*path) != actual_files.get(*path))
+        .filter(|path| checked.is_none_or(|checked| checked.contains(path.as_str())))
+        .cloned()
+        .collect();
+    if checked.is_some() && drifted.is_empty() {
+        return Ok(());
+    }
+    Err(GitError::DiffHashMismatch {
+        expected: expected.to_string(),
+        actual,
+        files: drifted,
+    })
 }
 
 fn stage_files(repo: &Path, files: &[PathBuf]) -> Result<(), GitError> {
@@ -166,7 +1111,22 @@ fn stage_files(repo: &Path, files: &[PathBuf]) -> Result<(), GitError> {
         return Ok(());
     }
     run_git_with_extra_paths(repo, &["reset", "-q", "--"], files, false)?;
-    run_git_with_extra_paths(repo, &["add", "--"], files, false)?;
+    // Deleted files and the old side of renames are gone from the worktree.
+    let (present, removed): (Vec<PathBuf>, Vec<PathBuf>) = files
+        .iter()
+        .cloned()
+        .partition(|path| path.symlink_metadata().is_ok());
+    if !present.is_empty() {
+        run_git_with_extra_paths(repo, &["add", "--"], &present, false)?;
+    }
+    if !removed.is_empty() {
+        run_git_with_extra_paths(
+            repo,
+            &["rm", "--cached", "-q", "--ignore-unmatch", "--"],
+            &removed,
+            false,
+        )?;
+    }
     Ok(())
 }
 
@@ -178,18 +1138,23 @@ fn reset_files(repo: &Path, files: &[PathBuf]) -> Result<(), GitError> {
     Ok(())
 }
 
-fn verify_staged_files(repo: &Path, unit: &CommitUnit) -> Result<(), GitError> {
-    let staged = list_staged_files(repo)?;
+fn verify_staged_files(
+    backend: &dyn GitBackend,
+    repo: &Path,
+    id: &str,
+    files: &[String],
+) -> Result<(), GitError> {
+    let staged = backend.staged_files(repo)?;
     if staged.is_empty() {
-        return Err(GitError::StagedDiffEmpty { id: unit.id.clone() });
+        return Err(GitError::StagedDiffEmpty { id: id.to_string() });
     }
 
-    let expected: HashSet<String> = unit.files.iter().cloned().collect();
+    let expected: HashSet<String> = files.iter().cloned().collect();
     let actual: HashSet<String> = staged.iter().cloned().collect();
     if !actual.is_subset(&expected) || actual.is_empty() {
         return Err(GitError::StagedFilesMismatch {
-            id: unit.id.clone(),
-            expected: unit.files.clone(),
+            id: id.to_string(),
+            expected: files.to_vec(),
             actual: staged,
         });
     }
@@ -197,7 +1162,13 @@ fn verify_staged_files(repo: &Path, unit: &CommitUnit) -> Result<(), GitError> {
 }
 
 fn list_staged_files(repo: &Path) -> Result<Vec<String>, GitError> {
-    let output = run_git_with_extra_paths(repo, &["diff", "--staged", "--name-only", "-z"], &[], true)?;
+    // Without rename detection both sides of a staged rename are listed.
+    let output = run_git_with_extra_paths(
+        repo,
+        &["diff", "--staged", "--name-only", "--no-renames", "-z"],
+        &[],
+        true,
+    )?;
     let mut files = Vec::new();
     for entry in output.split('\0') {
         if !entry.is_empty() {
@@ -207,30 +1178,104 @@ fn list_staged_files(repo: &Path) -> Result<Vec<String>, GitError> {
     Ok(files)
 }
 
-fn commit_unit(
+/// How a unit is committed: attribution, hooks, and signing.
+pub struct CommitOptions<'a> {
+    pub assisted_by: Option<&'a str>,
+    pub installed_hooks: Vec<String>,
+    pub no_verify: bool,
+    pub sign: bool,
+    pub signing_key: Option<&'a str>,
+}
+
+impl CommitOptions<'_> {
+    /// Whether `hook` runs for the commit. `--no-verify` skips pre-commit
+    /// and commit-msg but, as in git, not prepare-commit-msg.
+    fn runs_hook(&self, hook: &str) -> bool {
+        self.installed_hooks.iter().any(|installed| installed == hook)
+            && (!self.no_verify || hook == "prepare-commit-msg")
+    }
+
+    /// What the hooks did for a commit that was created.
+    fn hook_status(&self) -> HookStatus {
+        if self.no_verify {
+            HookStatus::Skipped
+        } else if COMMIT_HOOKS.iter().any(|hook| self.runs_hook(hook)) {
+            HookStatus::Passed
+        } else {
+            HookStatus::NotInstalled
+        }
+    }
+}
+
+fn commit_unit(repo: &Path, unit: &CommitUnit, options: &CommitOptions<'_>) -> Result<String, GitError> {
+    let hooks_dir = hooks_dir(repo)?;
+    let toplevel = PathBuf::from(run_git(repo, &["rev-parse", "--show-toplevel"])?.trim());
+    let message = repo.join(run_git(repo, &["rev-parse", "--git-path", "atomc-commit.msg"])?.trim());
+    let mut text = commit_message_parts(unit, options.assisted_by).join("\n\n");
+    text.push('\n');
+    std::fs::write(&message, text).map_err(|source| GitError::CommandIo {
+        cmd: "write commit message".to_string(),
+        source,
+    })?;
+    let committed = run_hooks_and_commit(repo, unit, options, &hooks_dir, &toplevel, &message);
+    std::fs::remove_file(&message).ok();
+    let hash = committed?;
+
+    // git commit ran with hooks disabled, so post-commit is run here. Its
+    // exit status is ignored, as git ignores it.
+    if is_executable(&hooks_dir.join("post-commit")) {
+        let mut cmd = hook_command(&hooks_dir, "post-commit", &toplevel);
+        cmd.output().ok();
+    }
+    Ok(hash)
+}
+
+/// Run the commit hooks for `unit` once, on the real index, then commit
+/// the (possibly hook-edited) `message` with git's own hooks disabled. A
+/// failure is blamed on a hook only when that hook exited non-zero.
+fn run_hooks_and_commit(
     repo: &Path,
     unit: &CommitUnit,
-    assisted_by: Option<&str>,
+    options: &CommitOptions<'_>,
+    hooks_dir: &Path,
+    toplevel: &Path,
+    message: &Path,
 ) -> Result<String, GitError> {
-    let header = commit_header(unit);
-    let cmd_string = format!("git commit -m {}", header);
-    let mut cmd = Command::new("git");
-    cmd.current_dir(repo).arg("commit").arg("-m").arg(&header);
-    for line in &unit.body {
-        cmd.arg("-m").arg(line);
-    }
-    if let Some(assisted_by) = assisted_by {
-        cmd.arg("-m").arg(format!("Assisted by: {assisted_by}"));
+    for hook in COMMIT_HOOKS.iter().filter(|hook| options.runs_hook(hook)) {
+        let mut cmd = hook_command(hooks_dir, hook, toplevel);
+        match *hook {
+            "prepare-commit-msg" => {
+                cmd.arg(message).arg("message");
+            }
+            "commit-msg" => {
+                cmd.arg(message);
+            }
+            _ => {}
+        }
+        let output = cmd.output().map_err(|source| GitError::CommandIo {
+            cmd: format!("{hook} hook"),
+            source,
+        })?;
+        if !output.status.success() {
+            return Err(GitError::HookFailed {
+                id: unit.id.clone(),
+                hooks: vec![hook.to_string()],
+                exit_code: output.status.code(),
+                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
     }
+
+    let (mut cmd, cmd_string) = commit_command(repo, unit, options, message);
     let output = cmd.output().map_err(|source| GitError::CommandIo {
         cmd: cmd_string.clone(),
         source,
     })?;
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         return Err(GitError::CommandFailed {
             cmd: cmd_string,
-            stderr,
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
         });
     }
 
@@ -238,6 +1283,88 @@ fn commit_unit(
     Ok(hash.trim().to_string())
 }
 
+/// `hook` from `hooks_dir`, invoked the way `git commit` would: from the
+/// top of the worktree, with no stdin and no editor.
+fn hook_command(hooks_dir: &Path, hook: &str, toplevel: &Path) -> Command {
+    let mut cmd = Command::new(hooks_dir.join(hook));
+    cmd.current_dir(toplevel)
+        .env("GIT_EDITOR", ":")
+        .stdin(std::process::Stdio::null());
+    cmd
+}
+
+/// Whether commit `hash` carries a signature header, whichever of `-S`,
+/// `--gpg-sign`, or `commit.gpgsign` put it there.
+fn commit_is_signed(repo: &Path, hash: &str) -> Result<bool, GitError> {
+    let commit = run_git(repo, &["cat-file", "commit", hash])?;
+    Ok(commit
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .any(|line| line.starts_with("gpgsig ") || line.starts_with("gpgsig-sha256 ")))
+}
+
+/// `git commit` for `unit` with `options`, and its display form. The
+/// message is read from `message`, and hooks are disabled because
+/// [`commit_unit`] has already run them.
+fn commit_command(repo: &Path, unit: &CommitUnit, options: &CommitOptions<'_>, message: &Path) -> (Command, String) {
+    let header = commit_header(unit);
+    let mut cmd = Command::new("git");
+    cmd.current_dir(repo);
+    cmd.args(["-c", "core.hooksPath=/dev/null", "commit", "--no-verify"]);
+    let mut flags = Vec::new();
+    match (options.sign, options.signing_key) {
+        (_, Some(key)) => flags.push(format!("--gpg-sign={key}")),
+        (true, None) => flags.push("-S".to_string()),
+        (false, None) => {}
+    }
+    let cmd_string = if flags.is_empty() {
+        format!("git commit --no-verify -m {}", header)
+    } else {
+        format!("git commit --no-verify {} -m {}", flags.join(" "), header)
+    };
+    cmd.args(&flags);
+    cmd.arg("-F").arg(message);
+    (cmd, cmd_string)
+}
+
+fn hooks_dir(repo: &Path) -> Result<PathBuf, GitError> {
+    let hooks_dir = run_git(repo, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(repo.join(hooks_dir.trim()))
+}
+
+/// Commit hooks present and executable in the repository's hooks directory.
+fn installed_hooks(repo: &Path) -> Result<Vec<String>, GitError> {
+    let hooks_dir = hooks_dir(repo)?;
+    Ok(COMMIT_HOOKS
+        .iter()
+        .filter(|hook| is_executable(&hooks_dir.join(hook)))
+        .map(|hook| hook.to_string())
+        .collect())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Message paragraphs for a unit: header, body lines, then attribution.
+fn commit_message_parts(unit: &CommitUnit, assisted_by: Option<&str>) -> Vec<String> {
+    let mut parts = vec![commit_header(unit)];
+    parts.extend(unit.body.iter().cloned());
+    if let Some(assisted_by) = assisted_by {
+        parts.push(format!("Assisted by: {assisted_by}"));
+    }
+    parts
+}
+
 fn commit_header(unit: &CommitUnit) -> String {
     let type_str = commit_type_str(&unit.type_);
     match unit.scope.as_deref() {
@@ -261,32 +1388,23 @@ fn commit_type_str(commit_type: &CommitType) -> &'static str {
     }
 }
 
-fn diff_files(diff: &str) -> HashSet<String> {
-    let mut files = HashSet::new();
-    for line in diff.lines() {
-        if let Some(rest) = line.strip_prefix("diff --git ") {
-            let mut parts = rest.split_whitespace();
-            let a_path = parts.next();
-            let b_path = parts.next();
-            if let Some(path) = normalize_diff_path(a_path, b_path) {
-                files.insert(path);
-            }
+/// A rename must be committed as a whole: both paths in the same unit.
+fn check_renames(unit: &CommitUnit, files: &[DiffFile]) -> Result<(), GitError> {
+    for file in files {
+        let (FileStatus::Renamed, Some(old_path)) = (file.status, file.old_path.as_ref()) else {
+            continue;
+        };
+        let has_new = unit.files.contains(&file.path);
+        let has_old = unit.files.contains(old_path);
+        if has_new != has_old {
+            return Err(GitError::RenameSplit {
+                id: unit.id.clone(),
+                from: old_path.clone(),
+                to: file.path.clone(),
+            });
         }
     }
-    files
-}
-
-fn normalize_diff_path(a_path: Option<&str>, b_path: Option<&str>) -> Option<String> {
-    let candidate = b_path.or(a_path)?;
-    let stripped = candidate
-        .strip_prefix("b/")
-        .or_else(|| candidate.strip_prefix("a/"))
-        .unwrap_or(candidate);
-    if stripped == "/dev/null" || stripped.is_empty() {
-        None
-    } else {
-        Some(stripped.to_string())
-    }
+    Ok(())
 }
 
 fn run_git_with_extra_paths(
@@ -315,6 +1433,16 @@ fn run_git_with_extra_paths(
         }
     );
 
+    run_command(cmd, cmd_string, allow_exit_1)
+}
+
+fn run_git_with_index(repo: &Path, index: &Path, args: &[&str]) -> Result<String, GitError> {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(repo).env("GIT_INDEX_FILE", index).args(args);
+    run_command(cmd, format!("git {}", args.join(" ")), false)
+}
+
+fn run_command(mut cmd: Command, cmd_string: String, allow_exit_1: bool) -> Result<String, GitError> {
     let output = cmd.output().map_err(|source| GitError::CommandIo {
         cmd: cmd_string.clone(),
         source,
@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::GitBackendKind;
+use crate::types::CommitUnit;
+
+use super::{CommitOptions, DiffOptions, GitError};
+
+/// Git operations used to plan and apply commits.
+///
+/// Paths are relative to the repository root.
+pub trait GitBackend {
+    fn name(&self) -> &'static str;
+
+    /// Diff for `options.mode`, followed by untracked files as additions when
+    /// requested. Binary, LFS, and oversized files are reduced to metadata.
+    fn diff(&self, repo: &Path, options: &DiffOptions) -> Result<String, GitError>;
+
+    /// Untracked files that are not ignored.
+    fn untracked_files(&self, repo: &Path) -> Result<Vec<String>, GitError>;
+
+    /// Files whose index entry differs from HEAD.
+    fn staged_files(&self, repo: &Path) -> Result<Vec<String>, GitError>;
+
+    /// Set the index entries for `files` to their worktree state.
+    fn stage_files(&self, repo: &Path, files: &[String]) -> Result<(), GitError>;
+
+    /// Commit the index for `unit` and return the new commit id.
+    fn commit(
+        &self,
+        repo: &Path,
+        unit: &CommitUnit,
+        options: &CommitOptions<'_>,
+    ) -> Result<String, GitError>;
+
+    /// Which of `paths` differ between `tree` and the worktree.
+    fn changed_paths(&self, repo: &Path, tree: &str, paths: &[String]) -> Result<Vec<String>, GitError>;
+}
+
+/// Backend that spawns the `git` binary.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CliBackend;
+
+impl GitBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "cli"
+    }
+
+    fn diff(&self, repo: &Path, options: &DiffOptions) -> Result<String, GitError> {
+        super::compute_diff_with(repo, options)
+    }
+
+    fn untracked_files(&self, repo: &Path) -> Result<Vec<String>, GitError> {
+        Ok(super::list_untracked_files(repo, &[])?
+            .into_iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect())
+    }
+
+    fn staged_files(&self, repo: &Path) -> Result<Vec<String>, GitError> {
+        super::list_staged_files(repo)
+    }
+
+    fn stage_files(&self, repo: &Path, files: &[String]) -> Result<(), GitError> {
+        let paths: Vec<PathBuf> = files.iter().map(|file| repo.join(file)).collect();
+        super::stage_files(repo, &paths)
+    }
+
+    fn commit(
+        &self,
+        repo: &Path,
+        unit: &CommitUnit,
+        options: &CommitOptions<'_>,
+    ) -> Result<String, GitError> {
+        super::commit_unit(repo, unit, options)
+    }
+
+    fn changed_paths(&self, repo: &Path, tree: &str, paths: &[String]) -> Result<Vec<String>, GitError> {
+        super::changed_paths(repo, tree, paths)
+    }
+}
+
+/// Backend for `kind`; `Native` falls back to the CLI when built without
+/// the `native-git` feature.
+pub fn backend_for(kind: GitBackendKind) -> Box<dyn GitBackend> {
+    match kind {
+        GitBackendKind::Cli => Box::new(CliBackend),
+        #[cfg(feature = "native-git")]
+        GitBackendKind::Native => Box::new(super::native::NativeBackend),
+        #[cfg(not(feature = "native-git"))]
+        GitBackendKind::Native => Box::new(CliBackend),
+    }
+}
@@ -0,0 +1,230 @@
+use std::path::Path;
+
+use crate::types::FileKind;
+
+use super::parse_diff_files;
+
+/// Prefix of the line that stands in for an omitted file body.
+pub(crate) const OMITTED_MARKER: &str = "atomc-omitted:";
+
+const LFS_VERSION_LINE: &str = "version https://git-lfs.github.com/spec/v1";
+
+/// Replace the body of binary, Git LFS pointer, and oversized file blocks
+/// with an `atomc-omitted: kind=<kind> size=<bytes>` line.
+///
+/// The extended header lines (`new file mode`, `rename from`, ...) are kept
+/// so the file's path and status still parse; `index` lines are dropped so
+/// every backend produces the same summary. Unchanged gitlink `index` lines
+/// are dropped for the same reason. Sizes come from the worktree, or
+/// from the pointer for LFS files, and are left out when unknown.
+pub fn condense_diff(repo: &Path, diff: &str, max_file_bytes: u64) -> String {
+    let mut out = String::with_capacity(diff.len());
+    for block in split_blocks(diff) {
+        // Blocks that already carry a marker are re-rendered so the output
+        // does not depend on how they were produced.
+        if let Some((kind, size)) = block.lines().find_map(parse_marker) {
+            out.push_str(&summarize(block, kind, size));
+            continue;
+        }
+        match classify(block, max_file_bytes) {
+            Some(FileKind::LfsPointer) => {
+                out.push_str(&summarize(block, FileKind::LfsPointer, lfs_size(block)));
+            }
+            Some(kind) => out.push_str(&summarize(block, kind, worktree_size(repo, block))),
+            None => out.extend(
+                block
+                    .split_inclusive('\n')
+                    .filter(|line| !is_unchanged_gitlink_index(line)),
+            ),
+        }
+    }
+    out
+}
+
+/// `index abc..abc 160000`: libgit2 writes it for a submodule that is only
+/// dirty, git does not.
+fn is_unchanged_gitlink_index(line: &str) -> bool {
+    let Some(rest) = line.trim_end_matches('\n').strip_prefix("index ") else {
+        return false;
+    };
+    match rest.split_once(' ') {
+        Some((range, "160000")) => range.split_once("..").is_some_and(|(old, new)| old == new),
+        _ => false,
+    }
+}
+
+/// Kind and size from an `atomc-omitted` line.
+pub(crate) fn parse_marker(line: &str) -> Option<(FileKind, Option<u64>)> {
+    let rest = line.strip_prefix(OMITTED_MARKER)?;
+    let mut kind = None;
+    let mut size = None;
+    for field in rest.split_whitespace() {
+        match field.split_once('=') {
+            Some(("kind", "binary")) => kind = Some(FileKind::Binary),
+            Some(("kind", "lfs_pointer")) => kind = Some(FileKind::LfsPointer),
+            Some(("kind", "large")) => kind = Some(FileKind::Large),
+            Some(("size", value)) => size = value.parse().ok(),
+            _ => {}
+        }
+    }
+    kind.map(|kind| (kind, size))
+}
+
+/// Summary block for an untracked file over `max_file_bytes`, so its
+/// content is never read. `None` when the file is within the limit.
+pub(crate) fn untracked_summary(
+    repo: &Path,
+    path: &Path,
+    prefix: &str,
+    max_file_bytes: u64,
+) -> Option<String> {
+    let metadata = repo.join(path).metadata().ok()?;
+    if !metadata.is_file() || metadata.len() <= max_file_bytes {
+        return None;
+    }
+    let path = path.to_string_lossy();
+    let (old, new) = (
+        quote_path(&format!("a/{prefix}{path}")),
+        quote_path(&format!("b/{prefix}{path}")),
+    );
+    Some(format!(
+        "diff --git {old} {new}\nnew file mode {}\n{} kind={} size={}\n",
+        file_mode(&metadata),
+        OMITTED_MARKER,
+        kind_name(FileKind::Large),
+        metadata.len()
+    ))
+}
+
+/// Split a diff into per-file blocks, each ending with its newline. Text
+/// before the first `diff --git` header is returned as its own block.
+pub(super) fn split_blocks(diff: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in diff.split_inclusive('\n') {
+        if line.starts_with("diff --git ") && offset > start {
+            blocks.push(&diff[start..offset]);
+            start = offset;
+        }
+        offset += line.len();
+    }
+    if offset > start {
+        blocks.push(&diff[start..offset]);
+    }
+    blocks
+}
+
+fn classify(block: &str, max_file_bytes: u64) -> Option<FileKind> {
+    if !block.starts_with("diff --git ") {
+        return None;
+    }
+    let mut in_hunks = false;
+    for line in block.lines() {
+        if !in_hunks && (line.starts_with("Binary files ") || line == "GIT binary patch") {
+            return Some(FileKind::Binary);
+        }
+        if line.starts_with("@@") {
+            in_hunks = true;
+        } else if in_hunks && is_lfs_version(line) {
+            return Some(FileKind::LfsPointer);
+        }
+    }
+    (block.len() as u64 > max_file_bytes).then_some(FileKind::Large)
+}
+
+fn summarize(block: &str, kind: FileKind, size: Option<u64>) -> String {
+    let mut out = String::new();
+    for line in block.split_inclusive('\n') {
+        let bare = line.trim_end_matches('\n');
+        if bare.starts_with("--- ")
+            || bare.starts_with("@@")
+            || bare.starts_with("Binary files ")
+            || bare == "GIT binary patch"
+        {
+            break;
+        }
+        if !bare.starts_with("index ") && !bare.starts_with(OMITTED_MARKER) && !bare.is_empty() {
+            out.push_str(bare);
+            out.push('\n');
+        }
+    }
+
+    out.push_str(&format!("{OMITTED_MARKER} kind={}", kind_name(kind)));
+    if let Some(size) = size {
+        out.push_str(&format!(" size={size}"));
+    }
+    out.push('\n');
+    out
+}
+
+fn is_lfs_version(line: &str) -> bool {
+    matches!(line.strip_prefix(['+', '-', ' ']), Some(LFS_VERSION_LINE))
+}
+
+/// Object size recorded in the new side of a pointer, else the old side.
+fn lfs_size(block: &str) -> Option<u64> {
+    let size = |prefix: char| {
+        block
+            .lines()
+            .filter_map(|line| line.strip_prefix(prefix)?.strip_prefix("size "))
+            .find_map(|value| value.trim().parse().ok())
+    };
+    size('+').or_else(|| size(' ')).or_else(|| size('-'))
+}
+
+fn worktree_size(repo: &Path, block: &str) -> Option<u64> {
+    let file = parse_diff_files(block).into_iter().next()?;
+    let metadata = repo.join(&file.path).metadata().ok()?;
+    metadata.is_file().then_some(metadata.len())
+}
+
+fn kind_name(kind: FileKind) -> &'static str {
+    match kind {
+        FileKind::Text => "text",
+        FileKind::Binary => "binary",
+        FileKind::LfsPointer => "lfs_pointer",
+        FileKind::Large => "large",
+        FileKind::Submodule => "submodule",
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> &'static str {
+    use std::os::unix::fs::PermissionsExt;
+
+    if metadata.permissions().mode() & 0o111 != 0 {
+        "100755"
+    } else {
+        "100644"
+    }
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &std::fs::Metadata) -> &'static str {
+    "100644"
+}
+
+/// Quote a path the way git does when it contains special bytes.
+pub(super) fn quote_path(path: &str) -> String {
+    let needs_quotes = path
+        .bytes()
+        .any(|byte| !(0x20..0x7f).contains(&byte) || byte == b'"' || byte == b'\\');
+    if !needs_quotes {
+        return path.to_string();
+    }
+    let mut out = String::from("\"");
+    for byte in path.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\t' => out.push_str("\\t"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => out.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,111 @@
+use globset::{Glob, GlobBuilder, GlobMatcher};
+
+use crate::config::ResolvedConfig;
+
+use super::condense::split_blocks;
+use super::{parse_diff_files, GitError};
+
+/// Glob patterns that decide which changed paths are planned.
+///
+/// A path is kept when it matches an `include` pattern (or there are none)
+/// and no `exclude` pattern. A pattern matches a path, any directory above
+/// it, or, when it has no `/`, any single path component: `vendor`,
+/// `vendor/**` and `*.lock` all work as expected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl PathFilter {
+    pub fn from_config(config: &ResolvedConfig) -> Self {
+        Self {
+            include: config.include.clone(),
+            exclude: config.ignore.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+/// A diff with the blocks of filtered-out paths removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilteredDiff {
+    pub diff: String,
+    /// Paths whose changes were left out, in diff order.
+    pub excluded: Vec<String>,
+}
+
+/// Drop the blocks of paths `filter` rejects. The worktree is not touched,
+/// so excluded changes stay uncommitted.
+pub fn filter_diff(diff: &str, filter: &PathFilter) -> Result<FilteredDiff, GitError> {
+    if filter.is_empty() {
+        return Ok(FilteredDiff {
+            diff: diff.to_string(),
+            excluded: Vec::new(),
+        });
+    }
+
+    let include = compile(&filter.include)?;
+    let exclude = compile(&filter.exclude)?;
+    let mut out = String::with_capacity(diff.len());
+    let mut excluded: Vec<String> = Vec::new();
+    for block in split_blocks(diff) {
+        let Some(file) = parse_diff_files(block).into_iter().next() else {
+            out.push_str(block);
+            continue;
+        };
+        let included = include.is_empty() || include.iter().any(|pattern| pattern.matches(&file.path));
+        if included && !exclude.iter().any(|pattern| pattern.matches(&file.path)) {
+            out.push_str(block);
+        } else if !excluded.contains(&file.path) {
+            excluded.push(file.path);
+        }
+    }
+
+    Ok(FilteredDiff {
+        diff: out,
+        excluded,
+    })
+}
+
+struct Pattern {
+    matcher: GlobMatcher,
+    /// Patterns without `/` also match single path components.
+    any_component: bool,
+}
+
+impl Pattern {
+    fn matches(&self, path: &str) -> bool {
+        let mut prefix_end = Some(path.len());
+        while let Some(end) = prefix_end {
+            if self.matcher.is_match(&path[..end]) {
+                return true;
+            }
+            prefix_end = path[..end].rfind('/');
+        }
+        self.any_component && path.split('/').any(|component| self.matcher.is_match(component))
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Pattern>, GitError> {
+    patterns
+        .iter()
+        .map(|raw| {
+            let pattern = raw.trim_end_matches('/');
+            let glob: Glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| GitError::InvalidPattern {
+                    pattern: raw.clone(),
+                    message: err.kind().to_string(),
+                })?;
+            Ok(Pattern {
+                matcher: glob.compile_matcher(),
+                any_component: !pattern.contains('/'),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,312 @@
+use std::path::Path;
+
+use git2::{
+    Delta, Diff, DiffFindOptions, DiffFormat, Oid, Pathspec, PathspecFlags, Repository, Status, StatusOptions,
+};
+
+use crate::config::DiffMode;
+use crate::types::CommitUnit;
+
+use super::backend::{CliBackend, GitBackend};
+use super::{CommitOptions, DiffOptions, GitError};
+
+/// In-process backend built on libgit2.
+///
+/// Repositories libgit2 cannot open, diffing and staging in repositories
+/// with filter drivers (git-lfs and friends), and commits that must run hooks or be
+/// signed go through [`CliBackend`] instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NativeBackend;
+
+impl GitBackend for NativeBackend {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn diff(&self, repo: &Path, options: &DiffOptions) -> Result<String, GitError> {
+        // Revision diffs read only commits, which the CLI handles the same way.
+        // libgit2 would diff worktree files without their clean filters, so
+        // the hashes would not match what `git add` stores.
+        let Some(git) = open(repo)
+            .filter(|_| !options.mode.is_revision())
+            .filter(|git| !has_filter_drivers(git))
+        else {
+            return CliBackend.diff(repo, options);
+        };
+
+        let mut parts = Vec::new();
+        push_if_non_empty(&mut parts, raw_diff(&git, repo, options, "", &options.pathspec)?);
+        if options.recurse_submodules {
+            parts.extend(super::submodule::submodule_diffs(
+                repo,
+                &options.pathspec,
+                &|path, prefix, pathspec| match open(path) {
+                    Some(git) => raw_diff(&git, path, options, prefix, pathspec),
+                    None => super::raw_diff(path, options, prefix, pathspec),
+                },
+            )?);
+        }
+
+        let diff = super::condense_diff(repo, &parts.join("\n"), options.max_file_bytes);
+        Ok(super::filter_diff(&diff, &options.filter)?.diff)
+    }
+
+    fn untracked_files(&self, repo: &Path) -> Result<Vec<String>, GitError> {
+        let Some(git) = open(repo) else {
+            return CliBackend.untracked_files(repo);
+        };
+
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true);
+        let statuses = git
+            .statuses(Some(&mut options))
+            .map_err(native_error("status"))?;
+        Ok(statuses
+            .iter()
+            .filter(|entry| entry.status().contains(Status::WT_NEW))
+            .filter_map(|entry| entry.path().map(str::to_string))
+            .collect())
+    }
+
+    fn staged_files(&self, repo: &Path) -> Result<Vec<String>, GitError> {
+        let Some(git) = open(repo) else {
+            return CliBackend.staged_files(repo);
+        };
+
+        let head = head_tree(&git)?;
+        let diff = git
+            .diff_tree_to_index(head.as_ref(), None, None)
+            .map_err(native_error("diff"))?;
+        Ok(diff
+            .deltas()
+            .filter_map(|delta| {
+                delta
+                    .new_file()
+                    .path()
+                    .or_else(|| delta.old_file().path())
+                    .map(|path| path.to_string_lossy().to_string())
+            })
+            .collect())
+    }
+
+    fn stage_files(&self, repo: &Path, files: &[String]) -> Result<(), GitError> {
+        let Some(git) = open(repo) else {
+            return CliBackend.stage_files(repo, files);
+        };
+        // `index.add_path` skips clean filters, which would commit the raw
+        // file instead of what the filter stores.
+        let Some(workdir) = git.workdir().map(Path::to_path_buf).filter(|_| !has_filter_drivers(&git)) else {
+            return CliBackend.stage_files(repo, files);
+        };
+
+        let mut index = git.index().map_err(native_error("index"))?;
+        for file in files {
+            let path = Path::new(file);
+            // Deleted files are staged as removals, like `git add`.
+            if workdir.join(path).symlink_metadata().is_ok() {
+                index.add_path(path).map_err(native_error("add"))?;
+            } else {
+                index.remove_path(path).map_err(native_error("rm"))?;
+            }
+        }
+        index.write().map_err(native_error("index"))
+    }
+
+    fn commit(
+        &self,
+        repo: &Path,
+        unit: &CommitUnit,
+        options: &CommitOptions<'_>,
+    ) -> Result<String, GitError> {
+        // libgit2 neither runs hooks nor signs commits.
+        let runs_hooks = !options.no_verify && !options.installed_hooks.is_empty();
+        if runs_hooks || options.sign {
+            return CliBackend.commit(repo, unit, options);
+        }
+        let Some(git) = open(repo).filter(|git| !signs_by_default(git)) else {
+            return CliBackend.commit(repo, unit, options);
+        };
+
+        let mut index = git.index().map_err(native_error("index"))?;
+        let tree_id = index.write_tree().map_err(native_error("write-tree"))?;
+        let tree = git.find_tree(tree_id).map_err(native_error("write-tree"))?;
+        let signature = git.signature().map_err(native_error("commit"))?;
+        let parent = match git.head() {
+            Ok(head) => Some(head.peel_to_commit().map_err(native_error("commit"))?),
+            Err(_) => None,
+        };
+        let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+        let message = format!(
+            "{}\n",
+            super::commit_message_parts(unit, options.assisted_by).join("\n\n")
+        );
+        let commit = git
+            .commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)
+            .map_err(native_error("commit"))?;
+        Ok(commit.to_string())
+    }
+
+    fn changed_paths(&self, repo: &Path, tree: &str, paths: &[String]) -> Result<Vec<String>, GitError> {
+        // libgit2 would hash files without their clean filters, so every
+        // filtered file would look changed.
+        let Some(git) = open(repo).filter(|git| !has_filter_drivers(git)) else {
+            return CliBackend.changed_paths(repo, tree, paths);
+        };
+
+        let tree = Oid::from_str(tree)
+            .and_then(|id| git.find_tree(id))
+            .map_err(native_error("rev-parse"))?;
+        let mut options = git2::DiffOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_typechange(true)
+            .disable_pathspec_match(true);
+        for path in paths {
+            options.pathspec(path);
+        }
+        let diff = git
+            .diff_tree_to_workdir(Some(&tree), Some(&mut options))
+            .map_err(native_error("diff"))?;
+        Ok(diff
+            .deltas()
+            .filter_map(|delta| {
+                delta
+                    .new_file()
+                    .path()
+                    .or_else(|| delta.old_file().path())
+                    .map(|path| path.to_string_lossy().to_string())
+            })
+            .collect())
+    }
+}
+
+/// Uncondensed diff of `git`, limited to `pathspec`, with every path
+/// prefixed by `prefix`.
+fn raw_diff(
+    git: &Repository,
+    repo: &Path,
+    options: &DiffOptions,
+    prefix: &str,
+    pathspec: &[String],
+) -> Result<String, GitError> {
+    let diff_options = || {
+        let mut diff_options = git2::DiffOptions::new();
+        diff_options
+            .old_prefix(format!("a/{prefix}"))
+            .new_prefix(format!("b/{prefix}"));
+        for spec in pathspec {
+            diff_options.pathspec(spec);
+        }
+        diff_options
+    };
+    let scope = if pathspec.is_empty() {
+        None
+    } else {
+        Some(Pathspec::new(pathspec.iter()).map_err(native_error("pathspec"))?)
+    };
+    let in_scope = |path: &str| {
+        scope.as_ref().is_none_or(|scope| {
+            scope.matches_path(Path::new(path.trim_end_matches('/')), PathspecFlags::DEFAULT)
+        })
+    };
+
+    let mut parts = Vec::new();
+    if matches!(options.mode, DiffMode::Worktree | DiffMode::All) {
+        let diff = git
+            .diff_index_to_workdir(None, Some(&mut diff_options()))
+            .map_err(native_error("diff"))?;
+        push_if_non_empty(&mut parts, patch_text(&diff, |_| true)?);
+    }
+    if matches!(options.mode, DiffMode::Staged | DiffMode::All) {
+        let head = head_tree(git)?;
+        let mut diff = git
+            .diff_tree_to_index(head.as_ref(), None, Some(&mut diff_options()))
+            .map_err(native_error("diff"))?;
+        // Match `git diff`, which detects renames by default.
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))
+            .map_err(native_error("diff"))?;
+        push_if_non_empty(&mut parts, patch_text(&diff, |_| true)?);
+    }
+    if options.include_untracked {
+        let mut untracked = diff_options();
+        untracked
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        let diff = git
+            .diff_index_to_workdir(None, Some(&mut untracked))
+            .map_err(native_error("diff"))?;
+        push_if_non_empty(
+            &mut parts,
+            patch_text(&diff, |status| status == Delta::Untracked)?,
+        );
+        // libgit2 leaves nested repositories out of the patch.
+        for path in NativeBackend.untracked_files(repo)? {
+            if super::submodule::is_nested_repo(&path) && in_scope(&path) {
+                parts.extend(super::submodule::nested_repo_diff(repo, &path, prefix));
+            }
+        }
+    }
+
+    Ok(parts.join("\n"))
+}
+
+fn open(repo: &Path) -> Option<Repository> {
+    Repository::discover(repo).ok()
+}
+
+/// Whether any `filter.<driver>.clean` or `.process` command is configured.
+fn has_filter_drivers(git: &Repository) -> bool {
+    let Ok(config) = git.config() else {
+        return false;
+    };
+    let Ok(mut entries) = config.entries(Some(r"^filter\..*\.(clean|process)$")) else {
+        return false;
+    };
+    entries.next().is_some()
+}
+
+/// Whether `commit.gpgsign` asks `git commit` to sign.
+fn signs_by_default(git: &Repository) -> bool {
+    git.config()
+        .and_then(|config| config.get_bool("commit.gpgsign"))
+        .unwrap_or(false)
+}
+
+fn head_tree(git: &Repository) -> Result<Option<git2::Tree<'_>>, GitError> {
+    match git.head() {
+        Ok(head) => Ok(Some(head.peel_to_tree().map_err(native_error("rev-parse"))?)),
+        // An unborn branch has no tree yet; everything in the index is new.
+        Err(_) => Ok(None),
+    }
+}
+
+/// Render `diff` as `git diff` style patch text, keeping deltas that pass `keep`.
+fn patch_text(diff: &Diff<'_>, keep: impl Fn(Delta) -> bool) -> Result<String, GitError> {
+    let mut bytes = Vec::new();
+    diff.print(DiffFormat::Patch, |delta, _hunk, line| {
+        if keep(delta.status()) {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                bytes.push(line.origin() as u8);
+            }
+            bytes.extend_from_slice(line.content());
+        }
+        true
+    })
+    .map_err(native_error("diff"))?;
+    String::from_utf8(bytes).map_err(|_| GitError::OutputNotUtf8)
+}
+
+fn push_if_non_empty(target: &mut Vec<String>, diff: String) {
+    if !diff.trim().is_empty() {
+        target.push(diff);
+    }
+}
+
+fn native_error(op: &'static str) -> impl Fn(git2::Error) -> GitError {
+    move |error| GitError::Native {
+        op: op.to_string(),
+        message: error.message().to_string(),
+    }
+}
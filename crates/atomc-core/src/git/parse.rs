@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+
+use crate::hash;
+use crate::types::{DiffFile, FileKind, FileStatus};
+
+use super::condense::{parse_marker, split_blocks};
+
+/// Files touched by a unified git diff, in order of appearance.
+///
+/// Paths come from the `rename from`/`rename to` and `---`/`+++` lines when
+/// present, since the `diff --git` header is ambiguous for paths with spaces.
+/// C-style quoted paths are unquoted. A file that appears in more than one
+/// block (for example both staged and unstaged) is listed once. Binary
+/// patches, `atomc-omitted` markers, and gitlink modes set the file's kind
+/// and size.
+pub fn parse_diff_files(diff: &str) -> Vec<DiffFile> {
+    let mut files: Vec<DiffFile> = Vec::new();
+    let mut current: Option<Block> = None;
+
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            if let Some(block) = current.take() {
+                push_file(&mut files, block.finish());
+            }
+            current = Some(Block::from_header(rest));
+            continue;
+        }
+        let Some(block) = current.as_mut() else {
+            continue;
+        };
+        if block.in_hunks {
+            block.mark_dirty_gitlink(line);
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            block.in_hunks = true;
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            block.kind = FileKind::Binary;
+        } else if let Some((kind, size)) = parse_marker(line) {
+            block.kind = kind;
+            block.size = size;
+        } else if line.starts_with("new file mode") {
+            block.status = Some(FileStatus::Added);
+            block.mark_gitlink(line);
+        } else if line.starts_with("deleted file mode") {
+            block.status = Some(FileStatus::Deleted);
+            block.mark_gitlink(line);
+        } else if line.starts_with("index ") {
+            block.has_index = true;
+            block.mark_gitlink(line);
+        } else if line.starts_with("old mode") || line.starts_with("new mode") {
+            block.mode_changed = true;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            block.old_path = Some(unquote(path));
+            block.status = Some(FileStatus::Renamed);
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            block.new_path = Some(unquote(path));
+            block.status = Some(FileStatus::Renamed);
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            block.minus = side_path(path, "a/");
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            block.plus = side_path(path, "b/");
+        }
+    }
+    if let Some(block) = current.take() {
+        push_file(&mut files, block.finish());
+    }
+
+    files
+}
+
+/// SHA-256 of each file's blocks in `diff`, keyed by path and, for renames,
+/// also by the old path. A file that appears in several blocks hashes their
+/// concatenation.
+pub fn file_hashes(diff: &str) -> BTreeMap<String, String> {
+    let mut contents: BTreeMap<String, String> = BTreeMap::new();
+    for block in split_blocks(diff) {
+        let Some(file) = parse_diff_files(block).into_iter().next() else {
+            continue;
+        };
+        for path in std::iter::once(file.path).chain(file.old_path) {
+            contents.entry(path).or_default().push_str(block);
+        }
+    }
+    contents
+        .into_iter()
+        .map(|(path, content)| (path, hash::diff_hash(&content)))
+        .collect()
+}
+
+/// One `@@` hunk of a file's diff with its changed lines, without their
+/// `-`/`+` markers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub header: String,
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+/// The hunks of every file in `diff`, keyed by path. Files without hunks,
+/// such as binary or omitted files, map to an empty list.
+pub fn diff_hunks(diff: &str) -> BTreeMap<String, Vec<DiffHunk>> {
+    let mut hunks: BTreeMap<String, Vec<DiffHunk>> = BTreeMap::new();
+    for block in split_blocks(diff) {
+        let Some(file) = parse_diff_files(block).into_iter().next() else {
+            continue;
+        };
+        let file_hunks = hunks.entry(file.path).or_default();
+        let first = file_hunks.len();
+        for line in block.lines() {
+            if line.starts_with("@@") {
+                file_hunks.push(DiffHunk {
+                    header: line.to_string(),
+                    ..DiffHunk::default()
+                });
+            } else if let Some(hunk) = file_hunks[first..].last_mut() {
+                if let Some(removed) = line.strip_prefix('-') {
+                    hunk.removed.push(removed.to_string());
+                } else if let Some(added) = line.strip_prefix('+') {
+                    hunk.added.push(added.to_string());
+                }
+            }
+        }
+    }
+    hunks
+}
+
+/// Every path a diff touches, including the old side of renames.
+pub fn diff_paths(files: &[DiffFile]) -> impl Iterator<Item = &str> {
+    files.iter().flat_map(|file| {
+        std::iter::once(file.path.as_str()).chain(file.old_path.as_deref())
+    })
+}
+
+struct Block {
+    header_old: Option<String>,
+    header_new: Option<String>,
+    old_path: Option<String>,
+    new_path: Option<String>,
+    minus: Option<String>,
+    plus: Option<String>,
+    status: Option<FileStatus>,
+    mode_changed: bool,
+    in_hunks: bool,
+    has_index: bool,
+    kind: FileKind,
+    size: Option<u64>,
+}
+
+impl Block {
+    fn from_header(rest: &str) -> Self {
+        let (header_old, header_new) = header_paths(rest);
+        Self {
+            header_old,
+            header_new,
+            old_path: None,
+            new_path: None,
+            minus: None,
+            plus: None,
+            status: None,
+            mode_changed: false,
+            in_hunks: false,
+            has_index: false,
+            kind: FileKind::Text,
+            size: None,
+        }
+    }
+
+    /// Mode 160000 marks a submodule or nested repository pointer.
+    fn mark_gitlink(&mut self, line: &str) {
+        if line.ends_with(" 160000") {
+            self.kind = FileKind::Submodule;
+        }
+    }
+
+    /// git leaves out the `index` line when a submodule is only dirty, so
+    /// the `Subproject commit` hunk is the only sign of a gitlink.
+    fn mark_dirty_gitlink(&mut self, line: &str) {
+        if !self.has_index && line[line.len().min(1)..].starts_with("Subproject commit ") {
+            self.kind = FileKind::Submodule;
+        }
+    }
+
+    fn finish(self) -> Option<DiffFile> {
+        let status = match self.status {
+            Some(status) => status,
+            None if self.mode_changed && self.minus.is_none() && self.plus.is_none() => {
+                FileStatus::ModeChanged
+            }
+            None => FileStatus::Modified,
+        };
+
+        if status == FileStatus::Renamed {
+            return Some(DiffFile {
+                path: self.new_path.or(self.plus).or(self.header_new)?,
+                old_path: self.old_path.or(self.minus).or(self.header_old),
+                status,
+                kind: self.kind,
+                size: self.size,
+            });
+        }
+        let path = match status {
+            FileStatus::Deleted => self.minus.or(self.header_old).or(self.header_new),
+            _ => self.plus.or(self.minus).or(self.header_new).or(self.header_old),
+        }?;
+
+        Some(DiffFile {
+            path,
+            old_path: None,
+            status,
+            kind: self.kind,
+            size: self.size,
+        })
+    }
+}
+
+fn push_file(files: &mut Vec<DiffFile>, file: Option<DiffFile>) {
+    let Some(file) = file else {
+        return;
+    };
+    if !files
+        .iter()
+        .any(|existing| existing.path == file.path && existing.old_path == file.old_path)
+    {
+        files.push(file);
+    }
+}
+
+/// Paths from `a/<old> b/<new>`; unquoted paths with spaces are only
+/// recoverable when both sides are equal.
+fn header_paths(rest: &str) -> (Option<String>, Option<String>) {
+    if rest.starts_with('"') {
+        let (old, remainder) = split_quoted(rest);
+        let new = remainder.trim_start();
+        let new = if new.starts_with('"') {
+            split_quoted(new).0
+        } else {
+            new.to_string()
+        };
+        return (strip_side(&old, "a/"), strip_side(&new, "b/"));
+    }
+    if let Some(index) = rest.find(" \"") {
+        let new = split_quoted(&rest[index + 1..]).0;
+        return (strip_side(&rest[..index], "a/"), strip_side(&new, "b/"));
+    }
+
+    // "a/<path> b/<path>" has odd length with the separator in the middle.
+    if rest.len() % 2 == 1 {
+        let middle = rest.len() / 2;
+        if rest.is_char_boundary(middle) && rest.as_bytes()[middle] == b' ' {
+            let (old, new) = (&rest[..middle], &rest[middle + 1..]);
+            if old.strip_prefix("a/").is_some() && old.get(2..) == new.get(2..) {
+                return (strip_side(old, "a/"), strip_side(new, "b/"));
+            }
+        }
+    }
+    let mut parts = rest.splitn(2, ' ');
+    let old = parts.next().and_then(|path| strip_side(path, "a/"));
+    let new = parts.next().and_then(|path| strip_side(path, "b/"));
+    (old, new)
+}
+
+fn side_path(raw: &str, prefix: &str) -> Option<String> {
+    // Some tools append a tab and timestamp after the path.
+    let raw = raw.split('\t').next().unwrap_or(raw);
+    if raw == "/dev/null" {
+        return None;
+    }
+    strip_side(&unquote(raw), prefix)
+}
+
+fn strip_side(path: &str, prefix: &str) -> Option<String> {
+    let path = path.strip_prefix(prefix).unwrap_or(path);
+    if path.is_empty() || path == "/dev/null" {
+        None
+    } else {
+        Some(path.to_string())
+    }
+}
+
+/// Decode a path git may have wrapped in C-style quotes.
+fn unquote(raw: &str) -> String {
+    if raw.starts_with('"') {
+        split_quoted(raw).0
+    } else {
+        raw.to_string()
+    }
+}
+
+/// Decode a leading quoted string and return it with the remaining input.
+fn split_quoted(raw: &str) -> (String, &str) {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::new();
+    let mut index = 1;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'"' => {
+                return (String::from_utf8_lossy(&out).to_string(), &raw[index + 1..]);
+            }
+            b'\\' if index + 1 < bytes.len() => {
+                index += 1;
+                match bytes[index] {
+                    b'n' => out.push(b'\n'),
+                    b't' => out.push(b'\t'),
+                    b'r' => out.push(b'\r'),
+                    b'a' => out.push(0x07),
+                    b'b' => out.push(0x08),
+                    b'f' => out.push(0x0c),
+                    b'v' => out.push(0x0b),
+                    digit @ b'0'..=b'7' => {
+                        let mut value = u32::from(digit - b'0');
+                        for _ in 0..2 {
+                            match bytes.get(index + 1) {
+                                Some(next @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(next - b'0');
+                                    index += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        out.push(value as u8);
+                    }
+                    other => out.push(other),
+                }
+            }
+            byte => out.push(byte),
+        }
+        index += 1;
+    }
+    (String::from_utf8_lossy(&out).to_string(), "")
+}
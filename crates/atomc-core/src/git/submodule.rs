@@ -0,0 +1,289 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::types::{CommitType, CommitUnit, FileKind};
+
+use super::condense::{quote_path, split_blocks};
+use super::{parse_diff_files, push_if_non_empty, rev_parse, run_git, GitError};
+
+const GITLINK_MODE: &str = "160000";
+const SUBPROJECT_PREFIX: &str = "Subproject commit ";
+const SUBMODULE_SCOPE: &str = "submodules";
+
+/// Diff of one repository: `(repo, path prefix, pathspec)`.
+pub(super) type RepoDiff<'a> = dyn Fn(&Path, &str, &[String]) -> Result<String, GitError> + 'a;
+
+/// A gitlink (submodule or nested repository) touched by a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmoduleChange {
+    pub path: String,
+    /// Commit recorded before the change; `None` for new gitlinks.
+    pub from: Option<String>,
+    /// Commit recorded after the change; `None` for removed gitlinks.
+    pub to: Option<String>,
+    /// The submodule worktree has uncommitted changes.
+    pub dirty: bool,
+}
+
+impl SubmoduleChange {
+    /// Whether committing the gitlink records a different commit. A
+    /// submodule that is only dirty has nothing to commit in the superproject.
+    pub fn moves_pointer(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// Gitlink changes in `diff`, one per path.
+///
+/// With `DiffMode::All` a path can appear in both the worktree and the
+/// staged diff; the first block supplies the new commit and the last block
+/// the old one, which spans HEAD to worktree.
+pub fn submodule_changes(diff: &str) -> Vec<SubmoduleChange> {
+    let mut changes: Vec<SubmoduleChange> = Vec::new();
+    for block in split_blocks(diff) {
+        let Some(file) = parse_diff_files(block).into_iter().next() else {
+            continue;
+        };
+        if file.kind != FileKind::Submodule {
+            continue;
+        }
+
+        let mut change = SubmoduleChange {
+            path: file.path,
+            from: None,
+            to: None,
+            dirty: false,
+        };
+        for line in block.lines() {
+            if let Some(commit) = line.strip_prefix('-').and_then(|rest| rest.strip_prefix(SUBPROJECT_PREFIX)) {
+                change.from = Some(commit.trim_end_matches("-dirty").to_string());
+            } else if let Some(commit) = line.strip_prefix('+').and_then(|rest| rest.strip_prefix(SUBPROJECT_PREFIX)) {
+                change.dirty = commit.ends_with("-dirty");
+                change.to = Some(commit.trim_end_matches("-dirty").to_string());
+            }
+        }
+
+        match changes.iter_mut().find(|existing| existing.path == change.path) {
+            Some(existing) => {
+                existing.from = change.from;
+                existing.dirty |= change.dirty;
+            }
+            None => changes.push(change),
+        }
+    }
+    changes
+}
+
+/// Give every gitlink whose commit moves its own unit, ahead of the others.
+///
+/// Gitlink paths are removed from the model's units (units left empty are
+/// dropped) and replaced by `chore[submodules]: bump <path> to <sha>` style
+/// units. A changed `.gitmodules` goes with the first added or removed
+/// submodule. Gitlinks that are only dirty are dropped from the plan.
+pub fn plan_submodule_units(units: &mut Vec<CommitUnit>, diff: &str) {
+    let changes = submodule_changes(diff);
+    if changes.is_empty() {
+        return;
+    }
+
+    let mut taken: HashSet<String> = changes.iter().map(|change| change.path.clone()).collect();
+    let moved: Vec<&SubmoduleChange> = changes.iter().filter(|change| change.moves_pointer()).collect();
+    let mut bumps: Vec<CommitUnit> = moved.iter().filter_map(|change| submodule_unit(change)).collect();
+
+    let gitmodules_changed = parse_diff_files(diff).iter().any(|file| file.path == ".gitmodules");
+    let registers = bumps
+        .iter_mut()
+        .zip(&moved)
+        .find(|(_, change)| change.from.is_none() || change.to.is_none());
+    if let (true, Some((unit, _))) = (gitmodules_changed, registers) {
+        unit.files.push(".gitmodules".to_string());
+        taken.insert(".gitmodules".to_string());
+    }
+
+    for unit in units.iter_mut() {
+        unit.files.retain(|file| !taken.contains(file));
+    }
+    units.retain(|unit| !unit.files.is_empty());
+
+    let mut next = 1;
+    for bump in &mut bumps {
+        while units.iter().any(|unit| unit.id == format!("submodule-{next}")) {
+            next += 1;
+        }
+        bump.id = format!("submodule-{next}");
+        next += 1;
+    }
+    units.splice(0..0, bumps);
+}
+
+fn submodule_unit(change: &SubmoduleChange) -> Option<CommitUnit> {
+    let (summary, body) = match (&change.from, &change.to) {
+        (Some(from), Some(to)) => (
+            fit_summary(&change.path, to, |path, sha| format!("bump {path} to {sha}")),
+            format!("Move {} from {} to {}", change.path, short(from), short(to)),
+        ),
+        (None, Some(to)) => (
+            fit_summary(&change.path, to, |path, sha| format!("add {path} submodule at {sha}")),
+            format!("Record {} at {}", change.path, short(to)),
+        ),
+        (Some(from), None) => (
+            fit_summary(&change.path, from, |path, sha| format!("remove {path} submodule at {sha}")),
+            format!("Stop tracking {} (was {})", change.path, short(from)),
+        ),
+        (None, None) => return None,
+    };
+
+    Some(CommitUnit {
+        id: String::new(),
+        type_: CommitType::Chore,
+        scope: Some(SUBMODULE_SCOPE.to_string()),
+        summary,
+        body: vec![body],
+        files: vec![change.path.clone()],
+        hunks: Vec::new(),
+    })
+}
+
+/// The longest form of `render(path, sha)` that fits a 72-char summary,
+/// shortening the sha to no less than 7 chars and falling back to the last
+/// path component for deep paths.
+fn fit_summary(path: &str, sha: &str, render: impl Fn(&str, &str) -> String) -> String {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    for candidate in [path, name.as_str()] {
+        for len in (7..=sha.len()).rev() {
+            let summary = render(candidate, &sha[..len]);
+            if summary.chars().count() <= 72 {
+                return summary;
+            }
+        }
+    }
+    render(&name, short(sha))
+}
+
+fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+/// Initialized submodules of `repo`, recursively, as repo-relative paths.
+pub(super) fn list_submodules(repo: &Path) -> Result<Vec<String>, GitError> {
+    let output = run_git(repo, &["ls-files", "--stage", "-z"])?;
+    let mut paths = Vec::new();
+    for entry in output.split('\0') {
+        let Some((meta, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        if !meta.starts_with(GITLINK_MODE) || !repo.join(path).join(".git").exists() {
+            continue;
+        }
+        let nested = list_submodules(&repo.join(path))?;
+        paths.push(path.to_string());
+        paths.extend(nested.into_iter().map(|inner| format!("{path}/{inner}")));
+    }
+    Ok(paths)
+}
+
+/// Diffs of every initialized submodule of `repo` that `pathspec` reaches,
+/// produced by `diff` with paths prefixed by the submodule path and the
+/// pathspec rewritten relative to the submodule.
+pub(super) fn submodule_diffs(
+    repo: &Path,
+    pathspec: &[String],
+    diff: &RepoDiff<'_>,
+) -> Result<Vec<String>, GitError> {
+    let mut parts = Vec::new();
+    for path in list_submodules(repo)? {
+        let Some(inner) = submodule_pathspec(pathspec, &path) else {
+            continue;
+        };
+        push_if_non_empty(&mut parts, diff(&repo.join(&path), &format!("{path}/"), &inner)?);
+    }
+    Ok(parts)
+}
+
+/// `pathspec` as seen from inside `submodule`: `None` when no entry reaches
+/// it, empty when one covers all of it. Entries are treated as path
+/// prefixes here; glob and magic pathspecs only apply to the superproject.
+fn submodule_pathspec(pathspec: &[String], submodule: &str) -> Option<Vec<String>> {
+    if pathspec.is_empty() {
+        return Some(Vec::new());
+    }
+    let mut inner = Vec::new();
+    for spec in pathspec {
+        let spec = spec.trim_end_matches('/');
+        if spec.is_empty() || spec == "." || spec == submodule || submodule.starts_with(&format!("{spec}/")) {
+            return Some(Vec::new());
+        }
+        if let Some(rest) = spec.strip_prefix(&format!("{submodule}/")) {
+            inner.push(rest.to_string());
+        }
+    }
+    (!inner.is_empty()).then_some(inner)
+}
+
+/// A `new file mode 160000` block for an untracked nested repository, or
+/// `None` when it has no commit to record.
+pub(super) fn nested_repo_diff(repo: &Path, path: &str, prefix: &str) -> Option<String> {
+    let path = path.trim_end_matches('/');
+    let head = rev_parse(&repo.join(path), "HEAD").ok()?;
+    let (old, new) = (
+        quote_path(&format!("a/{prefix}{path}")),
+        quote_path(&format!("b/{prefix}{path}")),
+    );
+    Some(format!(
+        "diff --git {old} {new}\nnew file mode {GITLINK_MODE}\nindex 0000000..{}\n--- /dev/null\n+++ {new}\n@@ -0,0 +1 @@\n+{SUBPROJECT_PREFIX}{head}\n",
+        short(&head)
+    ))
+}
+
+/// Untracked entries git reports as directories are nested repositories.
+pub(super) fn is_nested_repo(path: &str) -> bool {
+    path.ends_with('/')
+}
+
+/// The submodule a unit's files live in, and the files relative to it.
+///
+/// Files outside every submodule belong to the superproject (`None`). A
+/// unit may not span repositories.
+pub(super) fn unit_target(
+    unit: &CommitUnit,
+    submodules: &[String],
+) -> Result<(Option<String>, Vec<String>), GitError> {
+    let owner = |file: &str| {
+        submodules
+            .iter()
+            .filter(|submodule| file.starts_with(&format!("{submodule}/")))
+            .max_by_key(|submodule| submodule.len())
+            .cloned()
+    };
+
+    let target = unit.files.first().and_then(|file| owner(file));
+    for file in &unit.files {
+        let file_owner = owner(file);
+        if file_owner != target {
+            return Err(GitError::SubmoduleMixed {
+                id: unit.id.clone(),
+                submodule: file_owner.or(target).unwrap_or_default(),
+            });
+        }
+    }
+
+    let files = match &target {
+        Some(submodule) => unit
+            .files
+            .iter()
+            .map(|file| file[submodule.len() + 1..].to_string())
+            .collect(),
+        None => unit.files.clone(),
+    };
+    Ok((target, files))
+}
+
+/// `repo` followed by the worktree of each submodule.
+pub(super) fn repo_paths(repo: &Path, submodules: &[String]) -> Vec<PathBuf> {
+    std::iter::once(repo.to_path_buf())
+        .chain(submodules.iter().map(|submodule| repo.join(submodule)))
+        .collect()
+}
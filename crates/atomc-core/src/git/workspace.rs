@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::GitError;
+
+/// Directory levels searched below the discovery root.
+const MAX_DEPTH: usize = 4;
+
+/// Build output and dependency trees that hold vendored checkouts rather
+/// than repositories of the workspace.
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", "vendor", "build", "dist", "__pycache__"];
+
+/// What [`discover_repos`] found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Discovery {
+    /// Repositories, sorted by path.
+    pub repos: Vec<PathBuf>,
+    /// Directories below the root that could not be read, with the reason.
+    pub unreadable: Vec<(PathBuf, String)>,
+}
+
+/// Git repositories at or below `root`, at most [`MAX_DEPTH`] levels down.
+///
+/// A directory with a `.git` entry is a repository and is not searched any
+/// further, so submodules and nested repositories stay with their parent.
+/// Hidden directories and build or dependency directories (`target`,
+/// `node_modules`, `vendor`, ...) are skipped. Only an unreadable `root` is
+/// an error; unreadable directories below it are listed in the result.
+pub fn discover_repos(root: &Path) -> Result<Discovery, GitError> {
+    let mut discovery = Discovery::default();
+    let mut pending = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        if dir.join(".git").exists() {
+            discovery.repos.push(dir);
+            continue;
+        }
+        if depth == MAX_DEPTH {
+            continue;
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(source) if depth == 0 => {
+                return Err(GitError::Io {
+                    path: dir.display().to_string(),
+                    source,
+                })
+            }
+            Err(source) => {
+                discovery.unreadable.push((dir, source.to_string()));
+                continue;
+            }
+        };
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(source) => {
+                    discovery.unreadable.push((dir.clone(), source.to_string()));
+                    continue;
+                }
+            };
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let skipped = name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref());
+            if !skipped && entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                pending.push((entry.path(), depth + 1));
+            }
+        }
+    }
+    discovery.repos.sort();
+    discovery.unreadable.sort();
+    Ok(discovery)
+}
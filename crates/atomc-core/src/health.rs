@@ -0,0 +1,450 @@
+//! Environment checks for `atomc doctor` and `GET /v1/health`.
+//!
+//! Each check reports a status, what it found, and, when something is off,
+//! the fix to try. Checks never fail the caller; a broken environment is a
+//! report with failing checks.
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::config::{ConfigError, ConfigLayer, ResolvedConfig, Runtime};
+use crate::git;
+use crate::llm::Cassette;
+use crate::schema::{self, SchemaKind, SchemaValidationError};
+use crate::SCHEMA_VERSION;
+
+/// How long a runtime probe may take before the runtime counts as down.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// First Ollama release that accepts a JSON schema as `format`.
+const OLLAMA_SCHEMA_VERSION: (u64, u64, u64) = (0, 5, 0);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Skip,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Skip => "skip",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    /// `git`, `repo`, `config`, `runtime`, `model`, `schema_support`, or
+    /// `schema`.
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub message: String,
+    /// What to do about a `warn` or `fail`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+            fix: None,
+            details: None,
+        }
+    }
+
+    fn ok(name: &'static str, message: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Ok, message)
+    }
+
+    fn skip(name: &'static str, message: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Skip, message)
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Warn, message).with_fix(fix)
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Fail, message).with_fix(fix)
+    }
+
+    fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub schema_version: String,
+    /// The worst status among the checks.
+    pub status: CheckStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<HealthCheck>) -> Self {
+        let status = checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(CheckStatus::Ok);
+        Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            status,
+            checks,
+        }
+    }
+
+    /// The first failing check, if any.
+    pub fn first_failure(&self) -> Option<&HealthCheck> {
+        self.checks.iter().find(|check| check.status == CheckStatus::Fail)
+    }
+}
+
+/// Run every check. `repo` is the repository to inspect, if any; `config`
+/// is the outcome of resolving config with its layers.
+pub async fn run_checks(
+    repo: Option<&Path>,
+    config: &Result<(ResolvedConfig, Vec<ConfigLayer>), ConfigError>,
+) -> HealthReport {
+    let mut checks = vec![check_git(), check_repo(repo), check_config(config)];
+    match config {
+        Ok((config, _)) => checks.extend(check_runtime(config).await),
+        Err(_) => checks.push(HealthCheck::skip("runtime", "config did not resolve")),
+    }
+    checks.push(check_schemas());
+    HealthReport::new(checks)
+}
+
+pub fn check_git() -> HealthCheck {
+    match git::git_version() {
+        Ok(version) => HealthCheck::ok("git", format!("git {version}"))
+            .with_details(json!({ "version": version })),
+        Err(err) => HealthCheck::fail(
+            "git",
+            format!("git is not available: {err}"),
+            "install git and make sure it is on PATH",
+        ),
+    }
+}
+
+pub fn check_repo(repo: Option<&Path>) -> HealthCheck {
+    let Some(repo) = repo else {
+        return HealthCheck::skip("repo", "no repository given");
+    };
+    let state = match git::repo_state(repo) {
+        Ok(state) => state,
+        Err(err) => {
+            return HealthCheck::warn(
+                "repo",
+                format!("{} is not a git repository: {err}", repo.display()),
+                "run atomc inside a git repository or pass --repo",
+            )
+        }
+    };
+    let details = json!({
+        "root": state.root,
+        "branch": state.branch,
+        "unborn": state.unborn,
+        "operation": state.operation,
+    });
+    let head = match (&state.branch, state.unborn) {
+        (Some(branch), true) => format!("branch {branch} (no commits yet)"),
+        (Some(branch), false) => format!("branch {branch}"),
+        (None, _) => "detached HEAD".to_string(),
+    };
+    let check = match state.operation {
+        Some(operation) => HealthCheck::warn(
+            "repo",
+            format!("{} on {head} has a {operation} in progress", state.root.display()),
+            format!("finish it with `git {operation} --continue` or drop it with `git {operation} --abort`"),
+        ),
+        None => HealthCheck::ok("repo", format!("{} on {head}", state.root.display())),
+    };
+    check.with_details(details)
+}
+
+pub fn check_config(config: &Result<(ResolvedConfig, Vec<ConfigLayer>), ConfigError>) -> HealthCheck {
+    match config {
+        Ok((config, layers)) => {
+            let file = layers
+                .iter()
+                .find(|layer| layer.name == "file")
+                .and_then(|layer| layer.source.as_ref().map(|path| (path, layer.loaded)));
+            let message = match file {
+                Some((path, true)) => format!("resolved with {}", path.display()),
+                Some((path, false)) => format!("resolved without a config file ({} not found)", path.display()),
+                None => "resolved".to_string(),
+            };
+            HealthCheck::ok("config", message).with_details(json!({
+                "runtime": config.runtime,
+                "model": config.model,
+                "ollama_url": config.ollama_url,
+                "backends": config.backend_chain(),
+                "layers": layers,
+            }))
+        }
+        Err(err) => HealthCheck::fail(
+            "config",
+            err.to_string(),
+            match err {
+                ConfigError::MissingFile { .. } | ConfigError::ReadFile { .. } => {
+                    "check the --config path or LOCAL_COMMIT_CONFIG"
+                }
+                ConfigError::ParseFile { .. } => "fix the TOML in the config file",
+                ConfigError::Path(_) => "set LOCAL_COMMIT_CONFIG or pass --config",
+                ConfigError::InvalidEnv { .. } => "correct or unset the named environment variable",
+                ConfigError::OutOfRange { .. } => "lower the named setting to its maximum or below",
+            },
+        ),
+    }
+}
+
+/// Reachability of each backend's runtime, presence of its model, and
+/// whether the runtime accepts the commit plan schema.
+///
+/// With several backends, each check names its backend, and failures are
+/// only warnings while some other backend passes every check.
+pub async fn check_runtime(config: &ResolvedConfig) -> Vec<HealthCheck> {
+    let chain = config.backend_chain();
+    if chain.len() == 1 {
+        return check_backend(&config.with_backend(&chain[0])).await;
+    }
+    let mut per_backend = Vec::new();
+    for backend in &chain {
+        let checks = check_backend(&config.with_backend(backend)).await;
+        let checks: Vec<HealthCheck> = checks
+            .into_iter()
+            .map(|check| HealthCheck {
+                message: format!("{}: {}", backend.name, check.message),
+                ..check
+            })
+            .collect();
+        per_backend.push(checks);
+    }
+    let usable = per_backend
+        .iter()
+        .any(|checks| checks.iter().all(|check| check.status != CheckStatus::Fail));
+    per_backend
+        .into_iter()
+        .flatten()
+        .map(|check| match check.status {
+            CheckStatus::Fail if usable => HealthCheck {
+                status: CheckStatus::Warn,
+                ..check
+            },
+            _ => check,
+        })
+        .collect()
+}
+
+async fn check_backend(config: &ResolvedConfig) -> Vec<HealthCheck> {
+    match config.runtime {
+        Runtime::Ollama => check_ollama(config).await,
+        Runtime::LlamaCpp => check_llama_cpp(config).await,
+        Runtime::Replay => vec![check_cassette(config)],
+    }
+}
+
+/// Whether the bundled JSON schemas compile.
+pub fn check_schemas() -> HealthCheck {
+    let kinds = [
+        ("commit-plan", SchemaKind::CommitPlan),
+        ("commit-apply", SchemaKind::CommitApply),
+        ("workspace-apply", SchemaKind::WorkspaceApply),
+        ("eval-report", SchemaKind::EvalReport),
+        ("health", SchemaKind::Health),
+        ("error", SchemaKind::ErrorResponse),
+    ];
+    let mut broken = Vec::new();
+    for (name, kind) in kinds {
+        // An empty object violates every schema; only compile errors matter.
+        match schema::validate_schema(kind, &json!({})) {
+            Ok(()) | Err(SchemaValidationError::SchemaViolation(_)) => {}
+            Err(err) => broken.push(format!("{name}: {err}")),
+        }
+    }
+    if broken.is_empty() {
+        HealthCheck::ok("schema", format!("{} schemas compile", kinds.len()))
+    } else {
+        HealthCheck::fail(
+            "schema",
+            broken.join("; "),
+            "reinstall atomc; the bundled schemas are damaged",
+        )
+    }
+}
+
+async fn check_ollama(config: &ResolvedConfig) -> Vec<HealthCheck> {
+    let base = config.ollama_url.trim_end_matches('/');
+    let tags = match get_json(&format!("{base}/api/tags")).await {
+        Ok(tags) => tags,
+        Err(err) => return unreachable_runtime(config, err, "start Ollama with `ollama serve`"),
+    };
+    let mut checks = vec![HealthCheck::ok("runtime", format!("ollama reachable at {base}"))];
+
+    let names: Vec<&str> = tags
+        .get("models")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|model| model.get("name").and_then(Value::as_str))
+        .collect();
+    let present = names
+        .iter()
+        .any(|name| *name == config.model || *name == format!("{}:latest", config.model));
+    checks.push(if present {
+        HealthCheck::ok("model", format!("{} is installed", config.model))
+    } else {
+        HealthCheck::fail(
+            "model",
+            format!("{} is not installed", config.model),
+            format!("run `ollama pull {}` or set model to one of the installed models", config.model),
+        )
+        .with_details(json!({ "installed": names }))
+    });
+
+    checks.push(match get_json(&format!("{base}/api/version")).await {
+        Ok(value) => {
+            let version = value.get("version").and_then(Value::as_str).unwrap_or_default();
+            match parse_version(version) {
+                Some(parsed) if parsed >= OLLAMA_SCHEMA_VERSION => {
+                    HealthCheck::ok("schema_support", format!("ollama {version} accepts JSON schema output"))
+                }
+                Some(_) => HealthCheck::fail(
+                    "schema_support",
+                    format!("ollama {version} does not accept a JSON schema as format"),
+                    "upgrade Ollama to 0.5.0 or newer",
+                ),
+                None => HealthCheck::warn(
+                    "schema_support",
+                    format!("could not read the ollama version from {value}"),
+                    "upgrade Ollama to 0.5.0 or newer",
+                ),
+            }
+        }
+        Err(err) => HealthCheck::warn(
+            "schema_support",
+            format!("could not read the ollama version: {err}"),
+            "upgrade Ollama to 0.5.0 or newer",
+        ),
+    });
+    checks
+}
+
+async fn check_llama_cpp(config: &ResolvedConfig) -> Vec<HealthCheck> {
+    let base = config.ollama_url.trim_end_matches('/');
+    let models = match get_json(&format!("{base}/v1/models")).await {
+        Ok(models) => models,
+        Err(err) => {
+            return unreachable_runtime(config, err, "start llama-server with the model loaded")
+        }
+    };
+    let ids: Vec<&str> = models
+        .get("data")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|model| model.get("id").and_then(Value::as_str))
+        .collect();
+    // llama-server answers for whatever model it loaded, whatever the name.
+    let model = if ids.iter().any(|id| *id == config.model) {
+        HealthCheck::ok("model", format!("{} is loaded", config.model))
+    } else {
+        HealthCheck::warn(
+            "model",
+            format!("{} is not among the served models", config.model),
+            "set model to the name llama-server reports, or restart it with the intended model",
+        )
+        .with_details(json!({ "served": ids }))
+    };
+    vec![
+        HealthCheck::ok("runtime", format!("llama_cpp reachable at {base}")),
+        model,
+        HealthCheck::skip(
+            "schema_support",
+            "llama_cpp replies are checked against the schema after generation",
+        ),
+    ]
+}
+
+fn check_cassette(config: &ResolvedConfig) -> HealthCheck {
+    let Some(path) = &config.cassette else {
+        return HealthCheck::fail(
+            "runtime",
+            "replay runtime requires a cassette",
+            "set cassette or LOCAL_COMMIT_CASSETTE to a recorded cassette file",
+        );
+    };
+    match Cassette::load(path) {
+        Ok(cassette) => HealthCheck::ok(
+            "runtime",
+            format!(
+                "replaying {} exchanges from {}",
+                cassette.exchanges.len(),
+                path.display()
+            ),
+        ),
+        Err(err) => HealthCheck::fail(
+            "runtime",
+            err.to_string(),
+            "record the cassette again with `cassette` set and a live runtime",
+        ),
+    }
+}
+
+fn unreachable_runtime(config: &ResolvedConfig, err: String, fix: &str) -> Vec<HealthCheck> {
+    vec![
+        HealthCheck::fail(
+            "runtime",
+            format!(
+                "{} is not reachable at {}: {err}",
+                config.runtime.as_str(),
+                config.ollama_url
+            ),
+            format!("{fix}, or point ollama_url at the running server"),
+        ),
+        HealthCheck::skip("model", "runtime is not reachable"),
+        HealthCheck::skip("schema_support", "runtime is not reachable"),
+    ]
+}
+
+async fn get_json(url: &str) -> Result<Value, String> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {status}"));
+    }
+    response.json().await.map_err(|err| err.to_string())
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    Some((parts.next()??, parts.next().flatten().unwrap_or(0), parts.next().flatten().unwrap_or(0)))
+}
@@ -1,8 +1,16 @@
+pub mod atomicity;
+pub mod cache;
 pub mod config;
+pub mod eval;
 pub mod git;
 pub mod hash;
+pub mod health;
 pub mod llm;
+pub mod ordering;
+pub mod repair;
+pub mod retry;
 pub mod schema;
+pub mod scoring;
 pub mod semantic;
 pub mod types;
 
@@ -1,13 +1,24 @@
-use crate::config::{DiffMode, ResolvedConfig, Runtime};
+use crate::config::{Backend, DiffMode, ResolvedConfig, Runtime};
 use crate::schema::{self, SchemaKind};
-use crate::types::CommitPlan;
+use crate::semantic::{self, SemanticValidationError};
+use crate::types::{BackendMeta, CommitPlan};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tracing::debug;
+use tracing::{debug, warn};
 use std::path::Path;
 use std::time::Duration;
 
+mod cassette;
+mod examples;
+mod template;
+
+pub use cassette::{
+    redact_diffs, Cassette, CassetteError, Exchange, RecordedErrorKind, RecordedResponse, CASSETTE_VERSION,
+};
+pub use examples::{Example, ExampleError, ExampleSet, FewShot};
+pub use template::{PromptTemplates, TemplateError, PLACEHOLDERS, PROMPT_VERSION, REPO_TEMPLATE_PATH};
+
 #[derive(Debug, thiserror::Error)]
 pub enum LlmError {
     #[error("llm runtime error: {0}")]
@@ -24,6 +35,8 @@ pub enum LlmError {
 pub struct Prompt {
     pub system: String,
     pub user: String,
+    /// Version of the templates the prompt was built from.
+    pub version: String,
 }
 
 #[derive(Debug, Clone)]
@@ -32,15 +45,37 @@ pub struct PromptContext<'a> {
     pub diff_mode: Option<DiffMode>,
     pub include_untracked: Option<bool>,
     pub git_status: Option<&'a str>,
+    /// Other repositories planned in the same run.
+    pub workspace: Option<&'a WorkspaceContext>,
     pub diff: &'a str,
 }
 
+/// Shared context for planning several repositories together, so scopes and
+/// summaries stay consistent across them.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceContext {
+    /// Every repository in the workspace, in planning order.
+    pub repos: Vec<WorkspaceRepo>,
+    /// Label of the repository being planned.
+    pub current: String,
+    /// `<repo>: <header>` lines for commits planned in earlier repositories.
+    pub planned: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceRepo {
+    pub label: String,
+    pub files: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct LlmOptions {
     pub model: String,
     pub temperature: f32,
     pub max_tokens: u32,
     pub timeout: Duration,
+    /// Sampling seed, when the runtime should make the output reproducible.
+    pub seed: Option<u32>,
 }
 
 impl LlmOptions {
@@ -50,48 +85,26 @@ impl LlmOptions {
             temperature: config.temperature,
             max_tokens: config.max_tokens,
             timeout: Duration::from_secs(config.llm_timeout_secs),
+            seed: None,
         }
     }
-}
 
-pub fn build_prompt(context: PromptContext<'_>) -> Prompt {
-    Prompt {
-        system: SYSTEM_PROMPT.to_string(),
-        user: build_user_prompt(context),
+    /// Options for the `index`th of several sampled plans. The first uses
+    /// the configured temperature; each later one is a step warmer (capped
+    /// at 1.0) and gets its own seed, so the samples differ.
+    pub fn candidate(config: &ResolvedConfig, index: u32) -> Self {
+        let temperature = config.temperature + CANDIDATE_TEMPERATURE_STEP * index as f32;
+        Self {
+            temperature: temperature.min(1.0).max(config.temperature),
+            seed: Some(index),
+            ..Self::from_config(config)
+        }
     }
 }
 
-fn build_user_prompt(context: PromptContext<'_>) -> String {
-    let repo_path = context
-        .repo_path
-        .map(|path| path.display().to_string())
-        .unwrap_or_default();
-    let diff_mode = context
-        .diff_mode
-        .map(|mode| match mode {
-            DiffMode::Worktree => "worktree",
-            DiffMode::Staged => "staged",
-            DiffMode::All => "all",
-        })
-        .unwrap_or_default();
-    let include_untracked = context
-        .include_untracked
-        .map(|value| value.to_string())
-        .unwrap_or_default();
-    let git_status = context.git_status.unwrap_or_default();
-
-    format!(
-        "You will be given a git diff and optional repo metadata.\n\
-Produce an atomic commit plan as JSON only.\n\n\
-Context:\n\
-- repo_path: {repo_path}\n\
-- diff_mode: {diff_mode}\n\
-- include_untracked: {include_untracked}\n\
-- git_status: {git_status}\n\n\
-Diff:\n\
-{diff}",
-        diff = context.diff
-    )
+/// Prompt from the built-in templates.
+pub fn build_prompt(context: PromptContext<'_>) -> Prompt {
+    PromptTemplates::builtin().build_prompt(context)
 }
 
 pub struct OllamaClient {
@@ -112,6 +125,11 @@ impl OllamaClient {
         prompt: &Prompt,
         options: &LlmOptions,
     ) -> Result<CommitPlan, LlmError> {
+        parse_commit_plan(&self.complete(prompt, options).await?)
+    }
+
+    /// The model's raw reply to `prompt`.
+    pub async fn complete(&self, prompt: &Prompt, options: &LlmOptions) -> Result<String, LlmError> {
         let request = OllamaGenerateRequest {
             model: &options.model,
             prompt: &prompt.user,
@@ -121,6 +139,7 @@ impl OllamaClient {
             options: OllamaOptions {
                 temperature: options.temperature,
                 num_predict: options.max_tokens,
+                seed: options.seed,
             },
         };
         let url = format!(
@@ -156,10 +175,9 @@ impl OllamaClient {
             return Err(LlmError::Runtime(error));
         }
 
-        let response_text = payload
+        payload
             .response
-            .ok_or_else(|| LlmError::Parse("missing response".to_string()))?;
-        parse_commit_plan(&response_text)
+            .ok_or_else(|| LlmError::Parse("missing response".to_string()))
     }
 }
 
@@ -181,6 +199,11 @@ impl LlamaCppClient {
         prompt: &Prompt,
         options: &LlmOptions,
     ) -> Result<CommitPlan, LlmError> {
+        parse_commit_plan(&self.complete(prompt, options).await?)
+    }
+
+    /// The model's raw reply to `prompt`.
+    pub async fn complete(&self, prompt: &Prompt, options: &LlmOptions) -> Result<String, LlmError> {
         let url = format!(
             "{}/v1/chat/completions",
             self.base_url.trim_end_matches('/')
@@ -199,6 +222,7 @@ impl LlamaCppClient {
             ],
             temperature: options.temperature,
             max_tokens: options.max_tokens,
+            seed: options.seed,
             stream: false,
         };
 
@@ -225,31 +249,103 @@ impl LlamaCppClient {
         if let Some(error) = llama_cpp_error_message(&value) {
             return Err(LlmError::Runtime(error));
         }
-        let content = value
+        value
             .pointer("/choices/0/message/content")
             .and_then(|value| value.as_str())
             .or_else(|| value.pointer("/choices/0/text").and_then(|value| value.as_str()))
-            .ok_or_else(|| LlmError::Parse("missing chat completion content".to_string()))?;
-
-        parse_commit_plan(content)
+            .map(str::to_string)
+            .ok_or_else(|| LlmError::Parse("missing chat completion content".to_string()))
     }
 }
 
+/// Request a plan from the first backend in [`ResolvedConfig::backend_chain`]
+/// that answers, moving to the next one on a timeout or runtime error. The
+/// plan records the backend it came from.
 pub async fn generate_commit_plan(
     config: &ResolvedConfig,
     prompt: &Prompt,
 ) -> Result<CommitPlan, LlmError> {
-    let options = LlmOptions::from_config(config);
-    match config.runtime {
+    generate_with_fallback(config, prompt, LlmOptions::from_config).await
+}
+
+/// Request `count` plans concurrently, one per [`LlmOptions::candidate`].
+/// Results are returned in candidate order.
+pub async fn generate_commit_plan_candidates(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    count: u32,
+) -> Vec<Result<CommitPlan, LlmError>> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for index in 0..count {
+        let config = config.clone();
+        let prompt = prompt.clone();
+        tasks.spawn(async move {
+            let options = |config: &ResolvedConfig| LlmOptions::candidate(config, index);
+            (index, generate_with_fallback(&config, &prompt, options).await)
+        });
+    }
+
+    let mut results: Vec<(u32, Result<CommitPlan, LlmError>)> = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined.unwrap_or_else(|err| (count, Err(LlmError::Runtime(err.to_string())))));
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+async fn generate_with_fallback(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    options: impl Fn(&ResolvedConfig) -> LlmOptions,
+) -> Result<CommitPlan, LlmError> {
+    let chain = config.backend_chain();
+    let (last, fallbacks) = chain.split_last().expect("backend chain is never empty");
+    for backend in fallbacks {
+        let config = config.with_backend(backend);
+        match generate_with_options(&config, prompt, &options(&config)).await {
+            Ok(plan) => return Ok(with_backend_meta(plan, backend)),
+            Err(err @ (LlmError::Runtime(_) | LlmError::Timeout)) => {
+                warn!(backend = %backend.name, error = %err, "llm backend failed; trying the next one");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    let config = config.with_backend(last);
+    generate_with_options(&config, prompt, &options(&config))
+        .await
+        .map(|plan| with_backend_meta(plan, last))
+}
+
+fn with_backend_meta(plan: CommitPlan, backend: &Backend) -> CommitPlan {
+    CommitPlan {
+        backend: Some(BackendMeta {
+            name: backend.name.clone(),
+            runtime: backend.runtime.as_str().to_string(),
+            url: backend.url.clone(),
+            model: backend.model.clone(),
+        }),
+        ..plan
+    }
+}
+
+async fn generate_with_options(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    options: &LlmOptions,
+) -> Result<CommitPlan, LlmError> {
+    let response = match config.runtime {
         Runtime::Ollama => {
             let client = OllamaClient::new(config.ollama_url.clone());
-            client.generate_commit_plan(prompt, &options).await
+            client.complete(prompt, options).await
         }
         Runtime::LlamaCpp => {
             let client = LlamaCppClient::new(config.ollama_url.clone());
-            client.generate_commit_plan(prompt, &options).await
+            client.complete(prompt, options).await
         }
-    }
+        Runtime::Replay => return parse_commit_plan(&cassette::replay(config, prompt)?),
+    };
+    cassette::record(config, prompt, options, &response);
+    parse_commit_plan(&response?)
 }
 
 fn parse_commit_plan(payload: &str) -> Result<CommitPlan, LlmError> {
@@ -326,6 +422,8 @@ struct OllamaGenerateRequest<'a> {
 struct OllamaOptions {
     temperature: f32,
     num_predict: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -340,6 +438,8 @@ struct LlamaCppChatRequest<'a> {
     messages: Vec<LlamaCppMessage<'a>>,
     temperature: f32,
     max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u32>,
     stream: bool,
 }
 
@@ -358,43 +458,19 @@ static COMMIT_PLAN_SCHEMA_JSON: Lazy<Value> = Lazy::new(|| {
     .expect("commit plan schema json")
 });
 
-const SYSTEM_PROMPT: &str = "You are a local commit planning assistant.\n\
-Return a single JSON object that matches the CommitPlan schema.\n\
-The top-level object must include:\n\
-{\n\
-  \"schema_version\": \"v1\",\n\
-  \"plan\": [ { ...commit units... } ]\n\
-}\n\
-Do not use alternate keys like \"commits\".\n\
-Each commit unit must include:\n\
-- id: non-empty unique string (e.g., \"commit-1\")\n\
-- type: one of the allowed conventional commit types\n\
-- scope: non-empty kebab-case string (lowercase letters, digits, hyphens only; e.g. \"cli-tests\"),\n\
-  or null only for truly global changes\n\
-- summary: 50-72 characters\n\
-- body: 1-3 non-empty lines (no leading hyphens)\n\
-- files: non-empty array\n\
-- hunks: empty array (no patch text in MVP)\n\
-Do not include diff text, patch lines, or file content inside any fields.\n\
-Do not include Markdown, comments, or any extra text.\n\
-Follow atomic commit rules:\n\
-- Each commit must do exactly one thing.\n\
-- Split unrelated concerns into separate commits.\n\
-- Foundations first, integrations last.\n\
-- Avoid bundling refactors with feature changes.\n\
-Commit message rules:\n\
-- Use conventional commits: type[scope]: summary\n\
-- Scope is required unless the change is truly global.\n\
-- Summary is imperative, 50-72 chars.\n\
-- Body is 1-3 short lines (no leading hyphens).\n\
-If any required field is unknown, infer the best value.";
+/// Temperature added per extra candidate in best-of-N sampling.
+const CANDIDATE_TEMPERATURE_STEP: f32 = 0.15;
 
+/// Retry prompt for a response that could not be parsed as a plan.
+///
+/// The parse `errors` are appended to the system prompt; plans that parsed
+/// but failed semantic validation use [`build_unit_retry_prompt`].
 pub fn build_retry_prompt(base: &Prompt, errors: &[String]) -> Prompt {
     if errors.is_empty() {
         return base.clone();
     }
     let mut system = base.system.clone();
-    system.push_str("\n\nYour previous response failed semantic validation:\n");
+    system.push_str("\n\nYour previous response was not valid JSON for the schema:\n");
     for error in errors {
         system.push_str("- ");
         system.push_str(error);
@@ -404,5 +480,45 @@ pub fn build_retry_prompt(base: &Prompt, errors: &[String]) -> Prompt {
     Prompt {
         system,
         user: base.user.clone(),
+        version: base.version.clone(),
+    }
+}
+
+/// Retry prompt for a plan that parsed but failed semantic validation.
+///
+/// The previous plan is echoed back and the model is asked to fix only the
+/// units named in `errors`, keeping the rest unchanged.
+pub fn build_unit_retry_prompt(
+    base: &Prompt,
+    previous: &CommitPlan,
+    errors: &[SemanticValidationError],
+) -> Prompt {
+    if errors.is_empty() {
+        return base.clone();
+    }
+    let failing = semantic::failing_units(errors);
+    let mut system = base.system.clone();
+    system.push_str("\n\nYour previous response failed semantic validation.\n");
+    if let Ok(plan) = serde_json::to_string(&previous.plan) {
+        system.push_str("Previous plan:\n");
+        system.push_str(&plan);
+        system.push('\n');
+    }
+    if !failing.is_empty() {
+        system.push_str("Fix only these commits: ");
+        system.push_str(&failing.join(", "));
+        system.push_str(". Keep every other commit unchanged.\n");
+    }
+    system.push_str("Errors:\n");
+    for error in errors {
+        system.push_str("- ");
+        system.push_str(&error.to_string());
+        system.push('\n');
+    }
+    system.push_str("Return corrected JSON only that fully satisfies the schema and rules.");
+    Prompt {
+        system,
+        user: base.user.clone(),
+        version: base.version.clone(),
     }
 }
@@ -0,0 +1,284 @@
+//! Recorded LLM exchanges.
+//!
+//! With `cassette` set, every request to a real runtime is appended to the
+//! cassette file: the prompt, the sampling settings, and the model's raw
+//! reply (or the error). `Runtime::Replay` serves those replies back without
+//! a model, so parsing, validation, and retries run exactly as they did.
+//!
+//! A replayed request takes the first unused exchange whose prompt hash
+//! matches, then the latest exchange with a matching hash. A prompt with no
+//! matching exchange is an error unless `cassette_fuzzy` is set; then it
+//! takes the next unused exchange in recording order, so a cassette can be
+//! replayed when the prompt differs only in details such as temporary repo
+//! paths.
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::{LlmError, LlmOptions, Prompt};
+use crate::config::ResolvedConfig;
+use crate::hash;
+
+pub const CASSETTE_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CassetteError {
+    #[error("cassette read error: {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("cassette parse error: {path}: {source}")]
+    Parse { path: PathBuf, source: serde_json::Error },
+    #[error("cassette write error: {path}: {source}")]
+    Write { path: PathBuf, source: std::io::Error },
+    #[error("cassette {path} has no exchange left to replay")]
+    Exhausted { path: PathBuf },
+    #[error("cassette {path} has no exchange recorded for prompt {prompt_hash}")]
+    NoMatch { path: PathBuf, prompt_hash: String },
+    #[error("replay runtime requires a cassette")]
+    Missing,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub version: u32,
+    pub exchanges: Vec<Exchange>,
+}
+
+/// One request and what the runtime answered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub runtime: String,
+    pub model: String,
+    pub temperature: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    /// Hash of the unredacted system and user prompts; used for matching.
+    pub prompt_hash: String,
+    #[serde(default)]
+    pub prompt_version: String,
+    pub system: String,
+    pub user: String,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedResponse {
+    /// The model's raw reply, before parsing.
+    Content(String),
+    Error { kind: RecordedErrorKind, message: String },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedErrorKind {
+    Runtime,
+    Parse,
+    Timeout,
+    UnsupportedRuntime,
+}
+
+impl RecordedResponse {
+    fn from_result(result: &Result<String, LlmError>) -> Self {
+        match result {
+            Ok(content) => Self::Content(content.clone()),
+            Err(err) => {
+                let (kind, message) = match err {
+                    LlmError::Runtime(message) => (RecordedErrorKind::Runtime, message.clone()),
+                    LlmError::Parse(message) => (RecordedErrorKind::Parse, message.clone()),
+                    LlmError::Timeout => (RecordedErrorKind::Timeout, String::new()),
+                    LlmError::UnsupportedRuntime(message) => {
+                        (RecordedErrorKind::UnsupportedRuntime, message.clone())
+                    }
+                };
+                Self::Error { kind, message }
+            }
+        }
+    }
+
+    fn to_result(&self) -> Result<String, LlmError> {
+        match self {
+            Self::Content(content) => Ok(content.clone()),
+            Self::Error { kind, message } => Err(match kind {
+                RecordedErrorKind::Runtime => LlmError::Runtime(message.clone()),
+                RecordedErrorKind::Parse => LlmError::Parse(message.clone()),
+                RecordedErrorKind::Timeout => LlmError::Timeout,
+                RecordedErrorKind::UnsupportedRuntime => LlmError::UnsupportedRuntime(message.clone()),
+            }),
+        }
+    }
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self, CassetteError> {
+        let contents = fs::read_to_string(path).map_err(|source| CassetteError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        serde_json::from_str(&contents).map_err(|source| CassetteError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// The cassette at `path`, or an empty one when the file does not exist.
+    fn load_or_new(path: &Path) -> Result<Self, CassetteError> {
+        match Self::load(path) {
+            Err(CassetteError::Read { source, .. }) if source.kind() == ErrorKind::NotFound => Ok(Self {
+                version: CASSETTE_VERSION,
+                exchanges: Vec::new(),
+            }),
+            other => other,
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), CassetteError> {
+        let write_error = |source| CassetteError::Write {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(write_error)?;
+        }
+        let payload = serde_json::to_string_pretty(self).expect("cassette serializes");
+        fs::write(path, payload).map_err(write_error)
+    }
+}
+
+/// Replace the content lines of every diff hunk in `text` with a line
+/// counting them. File headers are kept so exchanges stay readable.
+pub fn redact_diffs(text: &str) -> String {
+    let mut out = Vec::new();
+    let mut in_hunk = false;
+    let mut redacted = 0usize;
+    let flush = |out: &mut Vec<String>, redacted: &mut usize| {
+        if *redacted > 0 {
+            out.push(format!("[{redacted} diff lines redacted]"));
+            *redacted = 0;
+        }
+    };
+    for line in text.lines() {
+        if line.starts_with("@@") {
+            flush(&mut out, &mut redacted);
+            in_hunk = true;
+            out.push(line.to_string());
+            continue;
+        }
+        if in_hunk && (line.is_empty() || line.starts_with(['+', '-', ' ', '\\'])) {
+            redacted += 1;
+            continue;
+        }
+        flush(&mut out, &mut redacted);
+        in_hunk = false;
+        out.push(line.to_string());
+    }
+    flush(&mut out, &mut redacted);
+    let mut redacted_text = out.join("\n");
+    if text.ends_with('\n') {
+        redacted_text.push('\n');
+    }
+    redacted_text
+}
+
+/// Append one exchange to the configured cassette. Failures are logged and
+/// never fail the request.
+pub(super) fn record(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    options: &LlmOptions,
+    result: &Result<String, LlmError>,
+) {
+    let Some(path) = &config.cassette else {
+        return;
+    };
+    let redact = |text: &str| {
+        if config.cassette_redact {
+            redact_diffs(text)
+        } else {
+            text.to_string()
+        }
+    };
+    let exchange = Exchange {
+        runtime: config.runtime.as_str().to_string(),
+        model: options.model.clone(),
+        temperature: options.temperature,
+        seed: options.seed,
+        prompt_hash: prompt_hash(prompt),
+        prompt_version: prompt.version.clone(),
+        system: redact(&prompt.system),
+        user: redact(&prompt.user),
+        response: RecordedResponse::from_result(result),
+    };
+
+    let _guard = RECORD_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let saved = Cassette::load_or_new(path).and_then(|mut cassette| {
+        cassette.exchanges.push(exchange);
+        cassette.save(path)
+    });
+    match saved {
+        Ok(()) => debug!(path = %path.display(), "llm exchange recorded"),
+        Err(err) => warn!(error = %err, "failed to record llm exchange"),
+    }
+}
+
+/// The recorded reply for `prompt` from the configured cassette.
+pub(super) fn replay(config: &ResolvedConfig, prompt: &Prompt) -> Result<String, LlmError> {
+    let path = config
+        .cassette
+        .as_ref()
+        .ok_or_else(|| LlmError::Runtime(CassetteError::Missing.to_string()))?;
+    let mut players = PLAYERS.lock().unwrap_or_else(|err| err.into_inner());
+    if !players.contains_key(path) {
+        let cassette = Cassette::load(path).map_err(|err| LlmError::Runtime(err.to_string()))?;
+        let used = vec![false; cassette.exchanges.len()];
+        players.insert(path.clone(), Player { cassette, used });
+    }
+    let player = players.get_mut(path).expect("player loaded");
+    let hash = prompt_hash(prompt);
+    let index = player.next(&hash, config.cassette_fuzzy).ok_or_else(|| {
+        let err = if config.cassette_fuzzy {
+            CassetteError::Exhausted { path: path.clone() }
+        } else {
+            CassetteError::NoMatch {
+                path: path.clone(),
+                prompt_hash: hash.clone(),
+            }
+        };
+        LlmError::Runtime(err.to_string())
+    })?;
+    debug!(path = %path.display(), exchange = index, "llm exchange replayed");
+    player.cassette.exchanges[index].response.to_result()
+}
+
+struct Player {
+    cassette: Cassette,
+    used: Vec<bool>,
+}
+
+impl Player {
+    /// The exchange to serve for `hash`. Without `fuzzy`, `None` means no
+    /// exchange matches; with it, that every exchange has been used.
+    fn next(&mut self, hash: &str, fuzzy: bool) -> Option<usize> {
+        let exchanges = &self.cassette.exchanges;
+        let matching = |index: &usize| exchanges[*index].prompt_hash == hash;
+        let unused = |index: &usize| !self.used[*index];
+        let index = (0..exchanges.len())
+            .find(|index| matching(index) && unused(index))
+            .or_else(|| (0..exchanges.len()).rev().find(matching))
+            .or_else(|| (0..exchanges.len()).find(|index| fuzzy && unused(index)))?;
+        self.used[index] = true;
+        Some(index)
+    }
+}
+
+fn prompt_hash(prompt: &Prompt) -> String {
+    hash::diff_hash(&format!("{}\n\u{0}{}", prompt.system, prompt.user))
+}
+
+static RECORD_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+static PLAYERS: Lazy<Mutex<HashMap<PathBuf, Player>>> = Lazy::new(|| Mutex::new(HashMap::new()));
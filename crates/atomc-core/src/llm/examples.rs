@@ -0,0 +1,264 @@
+//! Few-shot examples for the user prompt.
+//!
+//! An example is a diff paired with the plan it should produce. The
+//! built-in set is the gold fixture corpus under `tests/fixtures`; a
+//! directory with the same `diffs/<name>.diff` and `plans/<name>.plan.json`
+//! layout can replace it. For each request the examples most similar to the
+//! diff are chosen, up to a count and an estimated token budget.
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use tracing::debug;
+
+use crate::config::ResolvedConfig;
+use crate::git;
+use crate::types::CommitPlan;
+
+/// Fixture pairs shipped as the default examples.
+const BUILTIN: [(&str, &str, &str); 3] = [
+    (
+        "simple_feature",
+        include_str!("../../../../tests/fixtures/diffs/simple_feature.diff"),
+        include_str!("../../../../tests/fixtures/plans/simple_feature.plan.json"),
+    ),
+    (
+        "mixed_concerns",
+        include_str!("../../../../tests/fixtures/diffs/mixed_concerns.diff"),
+        include_str!("../../../../tests/fixtures/plans/mixed_concerns.plan.json"),
+    ),
+    (
+        "refactor_plus_feature",
+        include_str!("../../../../tests/fixtures/diffs/refactor_plus_feature.diff"),
+        include_str!("../../../../tests/fixtures/plans/refactor_plus_feature.plan.json"),
+    ),
+];
+
+/// Rough characters per token, used to keep examples under the budget
+/// without a tokenizer.
+const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExampleError {
+    #[error("examples read error: {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("example plan parse error: {path}: {source}")]
+    Parse { path: PathBuf, source: serde_json::Error },
+}
+
+/// One diff and the plan it should produce.
+#[derive(Debug, Clone)]
+pub struct Example {
+    pub name: String,
+    pub diff: String,
+    pub plan: CommitPlan,
+    /// The example as it appears in the prompt.
+    rendered: String,
+    features: BTreeSet<String>,
+}
+
+impl Example {
+    pub fn new(name: impl Into<String>, diff: impl Into<String>, plan: CommitPlan) -> Self {
+        let name = name.into();
+        let diff = diff.into();
+        let plan_json = json!({ "schema_version": plan.schema_version, "plan": plan.plan });
+        let rendered = format!("Example diff:\n{}\nExample plan:\n{plan_json}", diff.trim_end());
+        let features = features(&diff);
+        Self {
+            name,
+            diff,
+            plan,
+            rendered,
+            features,
+        }
+    }
+
+    /// Estimated prompt tokens this example takes.
+    pub fn tokens(&self) -> usize {
+        self.rendered.len().div_ceil(CHARS_PER_TOKEN)
+    }
+
+    /// Jaccard similarity between this example's diff and `diff`, from 0.0
+    /// to 1.0, over changed paths, path segments, extensions, and
+    /// identifiers on changed lines.
+    pub fn similarity(&self, diff: &str) -> f64 {
+        jaccard(&self.features, &features(diff))
+    }
+
+    pub fn rendered(&self) -> &str {
+        &self.rendered
+    }
+}
+
+/// The examples available to a run.
+#[derive(Debug, Clone, Default)]
+pub struct ExampleSet {
+    pub examples: Vec<Example>,
+}
+
+impl ExampleSet {
+    pub fn builtin() -> Self {
+        let examples = BUILTIN
+            .iter()
+            .map(|(name, diff, plan)| {
+                let plan = serde_json::from_str(plan).expect("built-in example plan is valid");
+                Example::new(*name, *diff, plan)
+            })
+            .collect();
+        Self { examples }
+    }
+
+    /// Pairs from `dir/diffs/<name>.diff` and `dir/plans/<name>.plan.json`,
+    /// sorted by name. Diffs without a plan are skipped.
+    pub fn from_dir(dir: &Path) -> Result<Self, ExampleError> {
+        let diffs = dir.join("diffs");
+        let entries = fs::read_dir(&diffs).map_err(|source| ExampleError::Read {
+            path: diffs.clone(),
+            source,
+        })?;
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|source| ExampleError::Read {
+                path: diffs.clone(),
+                source,
+            })?;
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "diff") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+        names.sort();
+
+        let mut examples = Vec::new();
+        for name in names {
+            let plan_path = dir.join("plans").join(format!("{name}.plan.json"));
+            if !plan_path.is_file() {
+                continue;
+            }
+            let diff_path = diffs.join(format!("{name}.diff"));
+            let diff = read(&diff_path)?;
+            let plan = serde_json::from_str(&read(&plan_path)?).map_err(|source| ExampleError::Parse {
+                path: plan_path.clone(),
+                source,
+            })?;
+            examples.push(Example::new(name, diff, plan));
+        }
+        Ok(Self { examples })
+    }
+
+    /// `examples_dir` when set, else the built-in set.
+    pub fn load(config: &ResolvedConfig) -> Result<Self, ExampleError> {
+        match &config.examples_dir {
+            Some(dir) => Self::from_dir(dir),
+            None => Ok(Self::builtin()),
+        }
+    }
+
+    /// Up to `count` examples for `diff`, most similar first, whose
+    /// estimated tokens together stay within `max_tokens`. An example that
+    /// does not fit is skipped in favor of less similar, smaller ones.
+    pub fn select(&self, diff: &str, count: usize, max_tokens: usize) -> Vec<&Example> {
+        let target = features(diff);
+        let mut ranked: Vec<(f64, &Example)> = self
+            .examples
+            .iter()
+            .map(|example| (jaccard(&example.features, &target), example))
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+
+        let mut selected = Vec::new();
+        let mut remaining = max_tokens;
+        for (_, example) in ranked {
+            if selected.len() == count {
+                break;
+            }
+            if example.tokens() <= remaining {
+                remaining -= example.tokens();
+                selected.push(example);
+            }
+        }
+        selected
+    }
+}
+
+/// Example selection settings for a run.
+#[derive(Debug, Clone)]
+pub struct FewShot {
+    pub examples: ExampleSet,
+    pub count: usize,
+    pub max_tokens: usize,
+}
+
+impl FewShot {
+    /// Few-shot settings from config, or `None` when `prompt_examples` is 0.
+    pub fn load(config: &ResolvedConfig) -> Result<Option<Self>, ExampleError> {
+        if config.prompt_examples == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            examples: ExampleSet::load(config)?,
+            count: config.prompt_examples as usize,
+            max_tokens: config.examples_max_tokens as usize,
+        }))
+    }
+
+    /// The examples chosen for `diff`, joined for the `examples`
+    /// placeholder.
+    pub fn render(&self, diff: &str) -> String {
+        let selected = self.examples.select(diff, self.count, self.max_tokens);
+        debug!(
+            examples = ?selected.iter().map(|example| example.name.as_str()).collect::<Vec<_>>(),
+            "few-shot examples selected"
+        );
+        selected
+            .iter()
+            .map(|example| example.rendered())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+fn read(path: &Path) -> Result<String, ExampleError> {
+    fs::read_to_string(path).map_err(|source| ExampleError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn features(diff: &str) -> BTreeSet<String> {
+    let mut features = BTreeSet::new();
+    for path in git::diff_paths(&git::parse_diff_files(diff)) {
+        features.insert(format!("path:{path}"));
+        for segment in path.split('/') {
+            features.insert(format!("seg:{segment}"));
+        }
+        if let Some((_, ext)) = path.rsplit_once('.') {
+            features.insert(format!("ext:{ext}"));
+        }
+    }
+    for line in diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        }
+        let Some(changed) = line.strip_prefix('+').or_else(|| line.strip_prefix('-')) else {
+            continue;
+        };
+        for word in changed.split(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_') {
+            if word.len() >= 3 {
+                features.insert(word.to_ascii_lowercase());
+            }
+        }
+    }
+    features
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
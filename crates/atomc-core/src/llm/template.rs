@@ -0,0 +1,312 @@
+//! Prompt templates with named placeholders.
+//!
+//! The built-in templates can be replaced per repository by
+//! `.atomc/prompt.toml`, or by the file named in the `prompt_template`
+//! setting. A template file may override either template and supply the
+//! scopes, conventions, and examples the placeholders render. With
+//! `prompt_examples` set, examples chosen from the few-shot set follow the
+//! file's own.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tracing::debug;
+
+use super::examples::{ExampleError, FewShot};
+use super::{Prompt, PromptContext, WorkspaceContext};
+use crate::config::{DiffMode, ResolvedConfig};
+use crate::hash;
+
+/// Version of the built-in templates; bump it when their wording changes so
+/// cached plans from older prompts are not reused.
+pub const PROMPT_VERSION: &str = "1";
+
+/// Repo-relative path of a repository's own template file.
+pub const REPO_TEMPLATE_PATH: &str = ".atomc/prompt.toml";
+
+/// Placeholders a template may use, written `{{name}}`.
+///
+/// `diff`, `repo_path`, `diff_mode`, `include_untracked`, and `status`
+/// render as bare values. `workspace`, `scopes`, `conventions`, and
+/// `examples` render as titled sections ending in a blank line, or as
+/// nothing when there is no content.
+pub const PLACEHOLDERS: [&str; 9] = [
+    "diff",
+    "repo_path",
+    "diff_mode",
+    "include_untracked",
+    "status",
+    "workspace",
+    "scopes",
+    "conventions",
+    "examples",
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("prompt template read error: {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("prompt template parse error: {path}: {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+    #[error("prompt template {path} uses unknown placeholder {{{{{name}}}}}")]
+    UnknownPlaceholder { path: PathBuf, name: String },
+    #[error(transparent)]
+    Examples(#[from] ExampleError),
+}
+
+/// Contents of a template file. Unset fields keep the built-in value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplateFile {
+    version: Option<String>,
+    system: Option<String>,
+    user: Option<String>,
+    #[serde(default)]
+    scopes: Vec<String>,
+    conventions: Option<String>,
+    examples: Option<String>,
+}
+
+/// System and user templates plus the content they can reference.
+#[derive(Debug, Clone)]
+pub struct PromptTemplates {
+    /// Recorded in plan metadata and logs; derived from the content when a
+    /// template file does not set one.
+    pub version: String,
+    pub system: String,
+    pub user: String,
+    /// Scopes the repository uses, listed for the model.
+    pub scopes: Vec<String>,
+    /// Free-form commit conventions for the repository.
+    pub conventions: Option<String>,
+    /// Hand-written example plans.
+    pub examples: Option<String>,
+    /// Example pairs chosen per diff and added after `examples`.
+    pub few_shot: Option<FewShot>,
+}
+
+impl PromptTemplates {
+    pub fn builtin() -> Self {
+        Self {
+            version: PROMPT_VERSION.to_string(),
+            system: BUILTIN_SYSTEM.to_string(),
+            user: BUILTIN_USER.to_string(),
+            scopes: Vec::new(),
+            conventions: None,
+            examples: None,
+            few_shot: None,
+        }
+    }
+
+    /// The built-in templates with the fields set in `path` replaced.
+    pub fn from_file(path: &Path) -> Result<Self, TemplateError> {
+        let contents = fs::read_to_string(path).map_err(|source| TemplateError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let file: TemplateFile = toml::from_str(&contents).map_err(|source| TemplateError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        for template in [&file.system, &file.user].into_iter().flatten() {
+            if let Some(name) = placeholders(template).find(|name| !PLACEHOLDERS.contains(name)) {
+                return Err(TemplateError::UnknownPlaceholder {
+                    path: path.to_path_buf(),
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        let builtin = Self::builtin();
+        let mut templates = Self {
+            version: String::new(),
+            system: file.system.unwrap_or(builtin.system),
+            user: file.user.unwrap_or(builtin.user),
+            scopes: file.scopes,
+            conventions: file.conventions,
+            examples: file.examples,
+            few_shot: None,
+        };
+        templates.version = file.version.unwrap_or_else(|| templates.content_version());
+        Ok(templates)
+    }
+
+    /// The templates for a run: the `prompt_template` file when set, else
+    /// the repository's `.atomc/prompt.toml` when present, else the
+    /// built-in ones, with few-shot examples when `prompt_examples` is set.
+    pub fn load(config: &ResolvedConfig, repo: Option<&Path>) -> Result<Self, TemplateError> {
+        let path = config.prompt_template.clone().or_else(|| {
+            repo.map(|repo| repo.join(REPO_TEMPLATE_PATH))
+                .filter(|path| path.is_file())
+        });
+        let mut templates = match &path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::builtin(),
+        };
+        templates.few_shot = FewShot::load(config)?;
+        debug!(prompt_version = %templates.version, path = ?path, "prompt templates loaded");
+        Ok(templates)
+    }
+
+    pub fn build_prompt(&self, context: PromptContext<'_>) -> Prompt {
+        Prompt {
+            system: self.render(&self.system, &context),
+            user: self.render(&self.user, &context),
+            version: self.version.clone(),
+        }
+    }
+
+    fn render(&self, template: &str, context: &PromptContext<'_>) -> String {
+        let mut out = String::with_capacity(template.len() + context.diff.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let name = &rest[start + 2..start + 2 + len];
+            out.push_str(&rest[..start]);
+            match self.value(name.trim(), context) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[start..start + len + 4]),
+            }
+            rest = &rest[start + len + 4..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn value(&self, name: &str, context: &PromptContext<'_>) -> Option<String> {
+        let value = match name {
+            "diff" => context.diff.to_string(),
+            "repo_path" => context
+                .repo_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            "diff_mode" => context.diff_mode.map(diff_mode_str).unwrap_or_default().to_string(),
+            "include_untracked" => context
+                .include_untracked
+                .map(|value| value.to_string())
+                .unwrap_or_default(),
+            "status" => context.git_status.unwrap_or_default().to_string(),
+            "workspace" => context.workspace.map(render_workspace).unwrap_or_default(),
+            "scopes" => section(
+                "Scopes",
+                (!self.scopes.is_empty()).then(|| format!("Prefer one of: {}", self.scopes.join(", "))),
+            ),
+            "conventions" => section("Conventions", self.conventions.clone()),
+            "examples" => {
+                let selected = self.few_shot.as_ref().map(|few_shot| few_shot.render(context.diff));
+                let examples = [self.examples.as_deref(), selected.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .map(str::trim)
+                    .filter(|examples| !examples.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                section("Examples", Some(examples))
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn content_version(&self) -> String {
+        let content = [
+            self.system.as_str(),
+            self.user.as_str(),
+            &self.scopes.join(","),
+            self.conventions.as_deref().unwrap_or_default(),
+            self.examples.as_deref().unwrap_or_default(),
+        ]
+        .join("\n\u{0}");
+        let digest = hash::diff_hash(&content);
+        let hex = digest.trim_start_matches("sha256:");
+        format!("custom-{}", &hex[..12])
+    }
+}
+
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template.split("{{").skip(1).filter_map(|part| part.split_once("}}").map(|(name, _)| name.trim()))
+}
+
+fn section(title: &str, body: Option<String>) -> String {
+    match body.map(|body| body.trim().to_string()).filter(|body| !body.is_empty()) {
+        Some(body) => format!("{title}:\n{body}\n\n"),
+        None => String::new(),
+    }
+}
+
+fn diff_mode_str(mode: DiffMode) -> &'static str {
+    match mode {
+        DiffMode::Worktree => "worktree",
+        DiffMode::Staged => "staged",
+        DiffMode::All => "all",
+        DiffMode::Base => "base",
+        DiffMode::Range => "range",
+    }
+}
+
+fn render_workspace(workspace: &WorkspaceContext) -> String {
+    let mut out = String::from(
+        "Workspace:\n\
+This repository changes together with the others below. Plan only this \
+repository's diff, but reuse the scopes and wording of related commits.\n",
+    );
+    for repo in &workspace.repos {
+        let marker = if repo.label == workspace.current { " (this repository)" } else { "" };
+        out.push_str(&format!("- {}{marker}: {}\n", repo.label, repo.files.join(", ")));
+    }
+    if !workspace.planned.is_empty() {
+        out.push_str("Already planned:\n");
+        for line in &workspace.planned {
+            out.push_str(&format!("- {line}\n"));
+        }
+    }
+    out.push('\n');
+    out
+}
+
+const BUILTIN_USER: &str = "You will be given a git diff and optional repo metadata.\n\
+Produce an atomic commit plan as JSON only.\n\n\
+Context:\n\
+- repo_path: {{repo_path}}\n\
+- diff_mode: {{diff_mode}}\n\
+- include_untracked: {{include_untracked}}\n\
+- git_status: {{status}}\n\n\
+{{conventions}}{{scopes}}{{examples}}{{workspace}}\
+Diff:\n\
+{{diff}}";
+
+const BUILTIN_SYSTEM: &str = "You are a local commit planning assistant.\n\
+Return a single JSON object that matches the CommitPlan schema.\n\
+The top-level object must include:\n\
+{\n\
+  \"schema_version\": \"v1\",\n\
+  \"plan\": [ { ...commit units... } ]\n\
+}\n\
+Do not use alternate keys like \"commits\".\n\
+Each commit unit must include:\n\
+- id: non-empty unique string (e.g., \"commit-1\")\n\
+- type: one of the allowed conventional commit types\n\
+- scope: non-empty kebab-case string (lowercase letters, digits, hyphens only; e.g. \"cli-tests\"),\n\
+  or null only for truly global changes\n\
+- summary: 50-72 characters\n\
+- body: 1-3 non-empty lines (no leading hyphens)\n\
+- files: non-empty array of repo-relative paths; a rename lists both the old and new path;\n\
+  every file in the diff belongs to exactly one commit, including files whose content\n\
+  is shown only as an \"atomc-omitted: kind=... size=...\" line (binary, LFS, or large),\n\
+  except submodule paths (\"Subproject commit\" changes), which are committed separately\n\
+- hunks: empty array (no patch text in MVP)\n\
+Do not include diff text, patch lines, or file content inside any fields.\n\
+Do not include Markdown, comments, or any extra text.\n\
+Follow atomic commit rules:\n\
+- Each commit must do exactly one thing.\n\
+- Split unrelated concerns into separate commits.\n\
+- Foundations first, integrations last.\n\
+- Avoid bundling refactors with feature changes.\n\
+Commit message rules:\n\
+- Use conventional commits: type[scope]: summary\n\
+- Scope is required unless the change is truly global.\n\
+- Summary is imperative, 50-72 chars.\n\
+- Body is 1-3 short lines (no leading hyphens).\n\
+If any required field is unknown, infer the best value.";
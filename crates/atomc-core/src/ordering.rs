@@ -0,0 +1,321 @@
+//! Dependency ordering for commit units.
+//!
+//! Foundations should land before the units that integrate them. A unit
+//! depends on another when its added lines use something the other
+//! introduces:
+//! - a module file the other adds (`mod name;`, `use crate::name`,
+//!   `import name`, `name::`);
+//! - a function the other defines (`name(` or `path::name(` call sites, or
+//!   imports; `.name(` method calls are too ambiguous to count);
+//! - a package the other adds to `Cargo.toml`, `package.json`, or
+//!   `requirements.txt`.
+//!
+//! [`order_units`] moves each unit after the units it depends on and keeps
+//! the model's order everywhere else.
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::atomicity::unit_hunks;
+use crate::git::{self, DiffHunk};
+use crate::types::{CommitUnit, FileStatus};
+
+const DEFINITION_KEYWORDS: &[&str] = &["fn", "def", "function", "func"];
+/// Function names too common to tie a call site to one definition.
+const COMMON_FUNCTIONS: &[&str] = &[
+    "new", "default", "from", "into", "main", "fmt", "drop", "clone", "hash", "cmp", "deref", "next", "init",
+    "setup", "test", "get", "set", "len", "run",
+];
+const SOURCE_EXTENSIONS: &[&str] = &["rs", "py", "js", "jsx", "mjs", "ts", "tsx"];
+/// File stems that name their parent directory's module.
+const PARENT_MODULE_STEMS: &[&str] = &["mod", "__init__", "index"];
+/// File stems that are entry points rather than importable modules.
+const ENTRY_STEMS: &[&str] = &["main", "lib", "build", "setup"];
+/// Manifest keys that are settings rather than package names.
+const MANIFEST_KEYS: &[&str] = &[
+    "name", "version", "edition", "authors", "description", "license", "readme", "repository", "homepage",
+    "keywords", "categories", "publish", "resolver", "members", "exclude", "include", "default", "features",
+    "path", "optional", "workspace", "rust-version", "main", "scripts", "private", "type", "module", "types",
+    "engines", "files",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Module,
+    Function,
+    Package,
+}
+
+/// `unit` uses `symbol`, which `depends_on` introduces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    pub unit: String,
+    pub depends_on: String,
+    pub kind: SymbolKind,
+    pub symbol: String,
+}
+
+/// What [`order_units`] did.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OrderReport {
+    pub dependencies: Vec<Dependency>,
+    /// Unit ids in the order the model returned them.
+    pub previous: Vec<String>,
+    /// Whether any unit moved.
+    pub moved: bool,
+    /// Units left in model order because their dependencies form a cycle,
+    /// along with any unit waiting on them.
+    pub cycle: Vec<String>,
+}
+
+/// Dependencies between `units` found in `diff`, at most one per pair of
+/// units, in unit order.
+pub fn unit_dependencies(units: &[CommitUnit], diff: &str) -> Vec<Dependency> {
+    let hunks = git::diff_hunks(diff);
+    let added_files: BTreeSet<String> = git::parse_diff_files(diff)
+        .into_iter()
+        .filter(|file| file.status == FileStatus::Added)
+        .map(|file| file.path)
+        .collect();
+    // A function removed elsewhere in the diff was moved or changed, not
+    // introduced.
+    let removed_functions: BTreeSet<String> = hunks
+        .values()
+        .flatten()
+        .flat_map(|hunk| hunk.removed.iter())
+        .filter_map(|line| defined_function(line))
+        .collect();
+    let added: Vec<Vec<(&str, &str)>> = units.iter().map(|unit| added_lines(unit, &hunks)).collect();
+
+    let mut provided: Vec<(SymbolKind, String, usize)> = Vec::new();
+    for (index, unit) in units.iter().enumerate() {
+        for file in unit.files.iter().filter(|file| added_files.contains(*file)) {
+            if let Some(name) = module_name(file) {
+                provided.push((SymbolKind::Module, name, index));
+            }
+        }
+        for (file, line) in &added[index] {
+            if let Some(name) = defined_function(line).filter(|name| !removed_functions.contains(name)) {
+                provided.push((SymbolKind::Function, name, index));
+            }
+            if let Some(name) = manifest_package(file, line) {
+                provided.push((SymbolKind::Package, name, index));
+            }
+        }
+    }
+
+    let mut dependencies: Vec<Dependency> = Vec::new();
+    for (index, unit) in units.iter().enumerate() {
+        for (kind, name, provider) in &provided {
+            let depends_on = &units[*provider].id;
+            let known = dependencies
+                .iter()
+                .any(|dependency| dependency.unit == unit.id && &dependency.depends_on == depends_on);
+            let own = provided
+                .iter()
+                .any(|(other_kind, other_name, other)| *other == index && other_kind == kind && other_name == name);
+            if *provider == index || known || own {
+                continue;
+            }
+            if added[index].iter().any(|(file, line)| uses(*kind, name, file, line)) {
+                dependencies.push(Dependency {
+                    unit: unit.id.clone(),
+                    depends_on: depends_on.clone(),
+                    kind: *kind,
+                    symbol: name.clone(),
+                });
+            }
+        }
+    }
+    dependencies
+}
+
+/// Dependencies whose `depends_on` unit comes after the unit using it.
+pub fn late_dependencies<'a>(units: &[CommitUnit], dependencies: &'a [Dependency]) -> Vec<&'a Dependency> {
+    let position = |id: &str| units.iter().position(|unit| unit.id == id);
+    dependencies
+        .iter()
+        .filter(|dependency| position(&dependency.depends_on) > position(&dependency.unit))
+        .collect()
+}
+
+/// Reorder `units` so each comes after the units it depends on. Among
+/// units that are free to go next, the one the model listed first wins.
+pub fn order_units(units: &mut Vec<CommitUnit>, diff: &str) -> OrderReport {
+    let dependencies = unit_dependencies(units, diff);
+    let previous: Vec<String> = units.iter().map(|unit| unit.id.clone()).collect();
+    let position = |id: &str| previous.iter().position(|other| other == id);
+    let edges: Vec<(usize, usize)> = dependencies
+        .iter()
+        .filter_map(|dependency| Some((position(&dependency.unit)?, position(&dependency.depends_on)?)))
+        .collect();
+
+    let mut placed = vec![false; units.len()];
+    let mut order = Vec::new();
+    let mut cycle = Vec::new();
+    while order.len() < units.len() {
+        let ready = (0..units.len()).find(|&index| {
+            !placed[index]
+                && edges
+                    .iter()
+                    .filter(|(unit, _)| *unit == index)
+                    .all(|(_, depends_on)| placed[*depends_on])
+        });
+        match ready {
+            Some(index) => {
+                placed[index] = true;
+                order.push(index);
+            }
+            None => {
+                let remaining: Vec<usize> = (0..units.len()).filter(|index| !placed[*index]).collect();
+                cycle = remaining.iter().map(|index| previous[*index].clone()).collect();
+                order.extend(remaining);
+            }
+        }
+    }
+
+    let moved = order.iter().enumerate().any(|(slot, index)| slot != *index);
+    if moved {
+        let mut slots: Vec<Option<CommitUnit>> = units.drain(..).map(Some).collect();
+        units.extend(order.iter().filter_map(|index| slots[*index].take()));
+    }
+    OrderReport {
+        dependencies,
+        previous,
+        moved,
+        cycle,
+    }
+}
+
+/// Added lines of the hunks `unit` commits, with their file.
+fn added_lines<'a>(unit: &'a CommitUnit, hunks: &'a BTreeMap<String, Vec<DiffHunk>>) -> Vec<(&'a str, &'a str)> {
+    unit.files
+        .iter()
+        .flat_map(|file| {
+            unit_hunks(unit, file, hunks)
+                .into_iter()
+                .flat_map(move |hunk| hunk.added.iter().map(move |line| (file.as_str(), line.as_str())))
+        })
+        .collect()
+}
+
+/// The module a new source file introduces: its stem, or its directory
+/// for `mod.rs`, `__init__.py`, and `index.*`.
+fn module_name(path: &str) -> Option<String> {
+    let (dirs, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let (stem, ext) = name.rsplit_once('.')?;
+    if !SOURCE_EXTENSIONS.contains(&ext) || ENTRY_STEMS.contains(&stem) {
+        return None;
+    }
+    let module = if PARENT_MODULE_STEMS.contains(&stem) {
+        dirs.rsplit('/').next().filter(|dir| !dir.is_empty())?
+    } else {
+        stem
+    };
+    Some(module.to_string())
+}
+
+/// The function a line defines, e.g. `pub fn name(`, `def name(`,
+/// `function name(`, or `func name(`.
+fn defined_function(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace();
+    while let Some(word) = words.next() {
+        if DEFINITION_KEYWORDS.contains(&word) {
+            let name = ident_prefix(words.next()?);
+            let common = name.len() < 3 || COMMON_FUNCTIONS.contains(&name);
+            return (!common).then(|| name.to_string());
+        }
+    }
+    None
+}
+
+/// The package an added manifest line declares.
+fn manifest_package(path: &str, line: &str) -> Option<String> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let line = line.trim();
+    let package = match name {
+        "Cargo.toml" => {
+            let (key, _) = line.split_once('=')?;
+            let key = key.trim().split('.').next()?;
+            key.replace('-', "_")
+        }
+        "package.json" => {
+            let (key, value) = line.split_once(':')?;
+            let value = value.trim().trim_start_matches('"');
+            if !value.starts_with(|ch: char| ch.is_ascii_digit() || "^~*<>=".contains(ch)) {
+                return None;
+            }
+            key.trim().trim_matches('"').to_string()
+        }
+        "requirements.txt" => {
+            let end = line
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || "_.-".contains(ch)))
+                .unwrap_or(line.len());
+            line[..end].to_ascii_lowercase().replace('-', "_")
+        }
+        _ => return None,
+    };
+    let valid = !package.is_empty()
+        && !MANIFEST_KEYS.contains(&package.as_str())
+        && package
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "_-@/.".contains(ch));
+    valid.then_some(package)
+}
+
+fn is_manifest(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    matches!(name, "Cargo.toml" | "package.json" | "requirements.txt")
+}
+
+fn uses(kind: SymbolKind, name: &str, file: &str, line: &str) -> bool {
+    if is_manifest(file) {
+        return false;
+    }
+    let line = line.trim();
+    let imported = is_import(line) && word_ends(line, name).next().is_some();
+    match kind {
+        SymbolKind::Function => {
+            // `.parse(` is far more likely some type's method than the new
+            // free function, so method calls need an import to count.
+            let called = word_ends(line, name)
+                .any(|end| line[end..].starts_with('(') && !line[..end - name.len()].ends_with('.'));
+            defined_function(line).as_deref() != Some(name) && (imported || called)
+        }
+        SymbolKind::Module | SymbolKind::Package => {
+            imported || word_ends(line, name).any(|end| line[end..].starts_with("::"))
+        }
+    }
+}
+
+fn is_import(line: &str) -> bool {
+    let line = line
+        .strip_prefix("pub(crate) ")
+        .or_else(|| line.strip_prefix("pub "))
+        .unwrap_or(line);
+    ["use ", "mod ", "import ", "from ", "#include "]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        || line.contains("require(")
+}
+
+/// End offsets of each occurrence of `name` in `line` that is not part of
+/// a longer identifier.
+fn word_ends<'a>(line: &'a str, name: &'a str) -> impl Iterator<Item = usize> + 'a {
+    line.match_indices(name).filter_map(move |(start, _)| {
+        let end = start + name.len();
+        let before = line[..start].chars().next_back().is_some_and(is_ident_char);
+        let after = line[end..].chars().next().is_some_and(is_ident_char);
+        (!before && !after).then_some(end)
+    })
+}
+
+fn ident_prefix(word: &str) -> &str {
+    let end = word.find(|ch: char| !is_ident_char(ch)).unwrap_or(word.len());
+    &word[..end]
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
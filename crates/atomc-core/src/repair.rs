@@ -0,0 +1,174 @@
+//! Deterministic repairs for model output.
+//!
+//! Runs before semantic validation and fixes only mechanical mistakes whose
+//! correction cannot change the plan's meaning: blank or repeated ids,
+//! ASCII scopes that are not kebab-case, `type(scope):` prefixes inside
+//! summaries, bullets and blank lines in the body, and stray whitespace.
+//! Anything else, such as non-ASCII scopes or summary length, is left for
+//! validation to report.
+use crate::types::CommitUnit;
+use serde::Serialize;
+use std::collections::HashSet;
+
+const COMMIT_TYPES: [&str; 10] = [
+    "feat", "fix", "refactor", "style", "docs", "test", "chore", "build", "perf", "ci",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairField {
+    Id,
+    Scope,
+    Summary,
+    Body,
+}
+
+impl RepairField {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Scope => "scope",
+            Self::Summary => "summary",
+            Self::Body => "body",
+        }
+    }
+}
+
+/// One change made to a unit. `unit` is the id after repair.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Repair {
+    pub unit: String,
+    pub field: RepairField,
+    pub before: String,
+    pub after: String,
+}
+
+/// Repair `units` in place and return every change made, in unit order.
+pub fn repair_plan(units: &mut [CommitUnit]) -> Vec<Repair> {
+    let mut repairs = Vec::new();
+    renumber_ids(units, &mut repairs);
+    for unit in units.iter_mut() {
+        repair_scope(unit, &mut repairs);
+        repair_summary(unit, &mut repairs);
+        repair_body(unit, &mut repairs);
+    }
+    repairs
+}
+
+/// Give blank and repeated ids a fresh `commit-<n>` id, keeping the first
+/// unit with each id as is.
+fn renumber_ids(units: &mut [CommitUnit], repairs: &mut Vec<Repair>) {
+    let mut taken: HashSet<String> = units.iter().map(|unit| unit.id.trim().to_string()).collect();
+    let mut seen = HashSet::new();
+    let mut next = 1;
+    for unit in units.iter_mut() {
+        let trimmed = unit.id.trim().to_string();
+        if !trimmed.is_empty() && seen.insert(trimmed.clone()) {
+            if trimmed != unit.id {
+                record(repairs, &trimmed, RepairField::Id, &unit.id, &trimmed);
+                unit.id = trimmed;
+            }
+            continue;
+        }
+        while taken.contains(&format!("commit-{next}")) {
+            next += 1;
+        }
+        let id = format!("commit-{next}");
+        taken.insert(id.clone());
+        seen.insert(id.clone());
+        record(repairs, &id, RepairField::Id, &unit.id, &id);
+        unit.id = id;
+    }
+}
+
+fn repair_scope(unit: &mut CommitUnit, repairs: &mut Vec<Repair>) {
+    // Dropping characters would change what the scope names.
+    let Some(scope) = unit.scope.clone().filter(|scope| scope.is_ascii()) else {
+        return;
+    };
+    let kebab = kebab_case(&scope);
+    if kebab == scope {
+        return;
+    }
+    record(repairs, &unit.id, RepairField::Scope, &scope, &kebab);
+    unit.scope = (!kebab.is_empty()).then_some(kebab);
+}
+
+fn repair_summary(unit: &mut CommitUnit, repairs: &mut Vec<Repair>) {
+    let trimmed = unit.summary.trim();
+    let repaired = strip_type_prefix(trimmed).unwrap_or(trimmed).to_string();
+    if repaired != unit.summary {
+        record(repairs, &unit.id, RepairField::Summary, &unit.summary, &repaired);
+        unit.summary = repaired;
+    }
+}
+
+fn repair_body(unit: &mut CommitUnit, repairs: &mut Vec<Repair>) {
+    let mut body = Vec::with_capacity(unit.body.len());
+    for line in &unit.body {
+        let trimmed = line.trim();
+        let stripped = ["- ", "* ", "• "]
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet))
+            .map_or(trimmed, str::trim_start);
+        if stripped != line || stripped.is_empty() {
+            record(repairs, &unit.id, RepairField::Body, line, stripped);
+        }
+        if !stripped.is_empty() {
+            body.push(stripped.to_string());
+        }
+    }
+    unit.body = body;
+}
+
+/// `summary` without a leading `type:`, `type(scope):`, or `type[scope]:`
+/// (with an optional `!`), when `type` is a commit type.
+fn strip_type_prefix(summary: &str) -> Option<&str> {
+    let (head, rest) = summary.split_once(':')?;
+    let head = head.trim_end_matches('!');
+    let kind = match head.find(['(', '[']) {
+        Some(open) => {
+            let close = if head[open..].starts_with('(') { ')' } else { ']' };
+            if !head.ends_with(close) {
+                return None;
+            }
+            &head[..open]
+        }
+        None => head,
+    };
+    let kind = kind.to_ascii_lowercase();
+    COMMIT_TYPES
+        .contains(&kind.as_str())
+        .then(|| rest.trim_start())
+        .filter(|rest| !rest.is_empty())
+}
+
+/// `value` in kebab-case: words split at case changes, spaces, and
+/// punctuation, lowercased, and joined with single dashes.
+fn kebab_case(value: &str) -> String {
+    let mut out = String::new();
+    let mut previous: Option<char> = None;
+    for ch in value.trim().chars() {
+        if ch.is_ascii_alphanumeric() {
+            let boundary = ch.is_ascii_uppercase()
+                && previous.is_some_and(|prev| prev.is_ascii_lowercase() || prev.is_ascii_digit());
+            if boundary && !out.ends_with('-') {
+                out.push('-');
+            }
+            out.push(ch.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+        previous = Some(ch);
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+fn record(repairs: &mut Vec<Repair>, unit: &str, field: RepairField, before: &str, after: &str) {
+    repairs.push(Repair {
+        unit: unit.to_string(),
+        field,
+        before: before.to_string(),
+        after: after.to_string(),
+    });
+}
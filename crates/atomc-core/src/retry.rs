@@ -0,0 +1,200 @@
+//! Retry engine for commit plan requests.
+//!
+//! One plan request may take several LLM calls. Each failure class gets its
+//! own strategy:
+//! - parse errors: retry with the parse error in the prompt at half the
+//!   previous temperature;
+//! - semantic errors: retry with the previous plan and only the failing
+//!   unit ids fed back, on the next backend in the chain when there is one;
+//! - runtime errors: retry the same prompt after an exponential backoff;
+//! - timeouts and unsupported runtimes: fail at once.
+//!
+//! Every call is recorded in a [`PlanAttempt`], returned with the result.
+use crate::config::ResolvedConfig;
+use crate::llm::{self, LlmError, Prompt};
+use crate::semantic::{self, SemanticValidationError};
+use crate::types::CommitPlan;
+use serde::Serialize;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Upper bound for a single backoff delay.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// LLM calls allowed, including the first.
+    pub max_attempts: u32,
+    /// Delay before the first retry after a runtime error.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &ResolvedConfig) -> Self {
+        Self {
+            max_attempts: config.llm_max_attempts.max(1),
+            backoff: Duration::from_millis(config.llm_retry_backoff_ms),
+        }
+    }
+
+    /// Delay after the `failures`th consecutive runtime error (from 1).
+    pub fn backoff_for(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    Accepted,
+    ParseError,
+    SemanticError,
+    RuntimeError,
+    Timeout,
+    UnsupportedRuntime,
+}
+
+/// One LLM call made for a plan.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanAttempt {
+    /// 1-based attempt number.
+    pub attempt: u32,
+    pub temperature: f32,
+    /// Backend that answered, when the attempt produced a plan.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    pub outcome: AttemptOutcome,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// Units the next prompt asked the model to fix.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failing_units: Vec<String>,
+    /// Wait before the next attempt, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backoff_ms: Option<u64>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RetryError {
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+    #[error("semantic validation failed")]
+    Semantic(Vec<SemanticValidationError>),
+}
+
+/// The outcome of [`plan_with_retry`] and every attempt made for it.
+#[derive(Debug)]
+pub struct PlanRun<T> {
+    pub result: Result<(CommitPlan, T), RetryError>,
+    pub attempts: Vec<PlanAttempt>,
+}
+
+/// Request a plan until `validate` accepts it or the policy's attempt
+/// budget runs out.
+///
+/// `request` receives the config (with the temperature and backend chain
+/// for this attempt), the prompt, and the 1-based attempt number. `validate`
+/// may adjust the plan in place before checking it and returns whatever the
+/// caller needs from an accepted plan, such as warnings.
+pub async fn plan_with_retry<T, F, Fut, V>(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    policy: &RetryPolicy,
+    mut request: F,
+    mut validate: V,
+) -> PlanRun<T>
+where
+    F: FnMut(ResolvedConfig, Prompt, u32) -> Fut,
+    Fut: Future<Output = Result<CommitPlan, LlmError>>,
+    V: FnMut(&mut CommitPlan) -> Result<T, Vec<SemanticValidationError>>,
+{
+    let mut attempt_config = config.clone();
+    let mut attempt_prompt = prompt.clone();
+    let mut attempts = Vec::new();
+    let mut runtime_failures = 0;
+    let mut last_error = RetryError::Llm(LlmError::Runtime("no attempts made".to_string()));
+
+    for number in 1..=policy.max_attempts {
+        let mut attempt = PlanAttempt {
+            attempt: number,
+            temperature: attempt_config.temperature,
+            backend: None,
+            outcome: AttemptOutcome::Accepted,
+            errors: Vec::new(),
+            failing_units: Vec::new(),
+            backoff_ms: None,
+        };
+        let response = request(attempt_config.clone(), attempt_prompt.clone(), number).await;
+        match response {
+            Ok(mut plan) => {
+                attempt.backend = plan.backend.as_ref().map(|backend| backend.name.clone());
+                match validate(&mut plan) {
+                    Ok(value) => {
+                        attempts.push(attempt);
+                        return PlanRun {
+                            result: Ok((plan, value)),
+                            attempts,
+                        };
+                    }
+                    Err(errors) => {
+                        attempt.outcome = AttemptOutcome::SemanticError;
+                        attempt.errors = errors.iter().map(ToString::to_string).collect();
+                        attempt.failing_units = semantic::failing_units(&errors);
+                        attempt_prompt = llm::build_unit_retry_prompt(prompt, &plan, &errors);
+                        let escalated = attempt
+                            .backend
+                            .as_deref()
+                            .and_then(|used| attempt_config.escalate(used));
+                        if let Some(next) = escalated {
+                            info!(from = ?attempt.backend, model = %next.model, "escalating to the next backend");
+                            attempt_config = next;
+                        }
+                        last_error = RetryError::Semantic(errors);
+                    }
+                }
+            }
+            Err(err @ LlmError::Parse(_)) => {
+                attempt.outcome = AttemptOutcome::ParseError;
+                attempt.errors = vec![err.to_string()];
+                attempt_prompt = llm::build_retry_prompt(prompt, &attempt.errors);
+                attempt_config.temperature /= 2.0;
+                last_error = RetryError::Llm(err);
+            }
+            Err(err @ LlmError::Runtime(_)) => {
+                runtime_failures += 1;
+                attempt.outcome = AttemptOutcome::RuntimeError;
+                attempt.errors = vec![err.to_string()];
+                if number < policy.max_attempts {
+                    attempt.backoff_ms = Some(policy.backoff_for(runtime_failures).as_millis() as u64);
+                }
+                last_error = RetryError::Llm(err);
+            }
+            Err(err) => {
+                attempt.outcome = match err {
+                    LlmError::Timeout => AttemptOutcome::Timeout,
+                    _ => AttemptOutcome::UnsupportedRuntime,
+                };
+                attempt.errors = vec![err.to_string()];
+                attempts.push(attempt);
+                return PlanRun {
+                    result: Err(RetryError::Llm(err)),
+                    attempts,
+                };
+            }
+        }
+        debug!(attempt = number, outcome = ?attempt.outcome, errors = ?attempt.errors, "plan attempt rejected");
+        let backoff = attempt.backoff_ms;
+        attempts.push(attempt);
+        if let Some(delay_ms) = backoff {
+            warn!(attempt = number, delay_ms, "llm request failed; retrying");
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
+
+    PlanRun {
+        result: Err(last_error),
+        attempts,
+    }
+}
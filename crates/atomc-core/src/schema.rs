@@ -9,6 +9,9 @@ use serde_json::Value;
 pub enum SchemaKind {
     CommitPlan,
     CommitApply,
+    WorkspaceApply,
+    EvalReport,
+    Health,
     ErrorResponse,
 }
 
@@ -39,6 +42,9 @@ fn schema_for(kind: SchemaKind) -> Result<&'static Validator, SchemaValidationEr
     match kind {
         SchemaKind::CommitPlan => COMMIT_PLAN_SCHEMA.as_ref(),
         SchemaKind::CommitApply => COMMIT_APPLY_SCHEMA.as_ref(),
+        SchemaKind::WorkspaceApply => WORKSPACE_APPLY_SCHEMA.as_ref(),
+        SchemaKind::EvalReport => EVAL_REPORT_SCHEMA.as_ref(),
+        SchemaKind::Health => HEALTH_SCHEMA.as_ref(),
         SchemaKind::ErrorResponse => ERROR_SCHEMA.as_ref(),
     }
     .map_err(|err| err.clone())
@@ -57,6 +63,12 @@ static COMMIT_PLAN_SCHEMA: Lazy<Result<Validator, SchemaValidationError>> =
     Lazy::new(|| compile_schema(COMMIT_PLAN_SCHEMA_STR));
 static COMMIT_APPLY_SCHEMA: Lazy<Result<Validator, SchemaValidationError>> =
     Lazy::new(|| compile_schema(COMMIT_APPLY_SCHEMA_STR));
+static WORKSPACE_APPLY_SCHEMA: Lazy<Result<Validator, SchemaValidationError>> =
+    Lazy::new(|| compile_schema(WORKSPACE_APPLY_SCHEMA_STR));
+static EVAL_REPORT_SCHEMA: Lazy<Result<Validator, SchemaValidationError>> =
+    Lazy::new(|| compile_schema(EVAL_REPORT_SCHEMA_STR));
+static HEALTH_SCHEMA: Lazy<Result<Validator, SchemaValidationError>> =
+    Lazy::new(|| compile_schema(HEALTH_SCHEMA_STR));
 static ERROR_SCHEMA: Lazy<Result<Validator, SchemaValidationError>> =
     Lazy::new(|| compile_schema(ERROR_SCHEMA_STR));
 
@@ -64,5 +76,11 @@ const COMMIT_PLAN_SCHEMA_STR: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../schemas/v1/commit-plan.json"));
 const COMMIT_APPLY_SCHEMA_STR: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../schemas/v1/commit-apply.json"));
+const WORKSPACE_APPLY_SCHEMA_STR: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../schemas/v1/workspace-apply.json"));
+const EVAL_REPORT_SCHEMA_STR: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../schemas/v1/eval-report.json"));
+const HEALTH_SCHEMA_STR: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../schemas/v1/health.json"));
 const ERROR_SCHEMA_STR: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../schemas/v1/error.json"));
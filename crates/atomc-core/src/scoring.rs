@@ -0,0 +1,136 @@
+//! Scoring for candidate commit plans.
+//!
+//! When several plans are sampled for the same diff, each one is scored and
+//! the highest total wins. Semantic errors dominate the total, then diff
+//! coverage, then warnings and atomicity penalties. Atomicity is judged by
+//! the same analyzers and policy as validation, so a plan validation would
+//! reject never outscores one it accepts.
+use crate::atomicity;
+use crate::config::AtomicityPolicy;
+use crate::git;
+use crate::semantic::{self, ScopePolicy};
+use crate::types::CommitUnit;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+const ERROR_WEIGHT: f64 = 100.0;
+const COVERAGE_WEIGHT: f64 = 50.0;
+const WARNING_WEIGHT: f64 = 5.0;
+const SHARED_FILE_WEIGHT: f64 = 10.0;
+const NON_ATOMIC_WEIGHT: f64 = 3.0;
+
+/// How well a plan fits its diff. Higher `total` is better.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanScore {
+    /// Semantic validation errors, including unplanned required files and
+    /// atomicity findings the policy rejects.
+    pub errors: usize,
+    /// Non-fatal semantic warnings.
+    pub warnings: usize,
+    /// Share of the diff's files that some unit lists, from 0.0 to 1.0.
+    pub coverage: f64,
+    /// Files listed by more than one unit without hunks to split them.
+    pub shared_files: usize,
+    /// Atomicity findings the policy only warns about.
+    pub non_atomic: usize,
+    pub total: f64,
+}
+
+impl PlanScore {
+    pub fn is_valid(&self) -> bool {
+        self.errors == 0
+    }
+}
+
+/// Score `units` against the diff they were planned from. Units are
+/// validated after mechanical repairs, as a plan would be.
+///
+/// `required` lists files that must be planned (see
+/// [`semantic::validate_file_coverage`]); every other path in `diff` only
+/// affects coverage.
+pub fn score_plan(
+    units: &[CommitUnit],
+    diff: &str,
+    required: &[String],
+    scope_policy: ScopePolicy,
+    atomicity: AtomicityPolicy,
+) -> PlanScore {
+    let mut repaired = units.to_vec();
+    let (mut errors, warnings) = match semantic::repair_and_validate_commit_units(&mut repaired, scope_policy) {
+        Ok(report) => (0, report.warnings.len()),
+        Err(errors) => (errors.len(), 0),
+    };
+    if let Err(missing) = semantic::validate_file_coverage(units, required.iter().map(String::as_str)) {
+        errors += missing.len();
+    }
+    let findings = match atomicity {
+        AtomicityPolicy::Off => Vec::new(),
+        _ => atomicity::analyze_plan(&repaired, diff),
+    };
+    let rejected = findings.iter().filter(|finding| finding.kind.is_error(atomicity)).count();
+    errors += rejected;
+    let non_atomic = findings.len() - rejected;
+
+    let diff_files: Vec<String> = git::parse_diff_files(diff).into_iter().map(|file| file.path).collect();
+
+    let planned: BTreeSet<&str> = units
+        .iter()
+        .flat_map(|unit| unit.files.iter().map(String::as_str))
+        .collect();
+    let coverage = if diff_files.is_empty() {
+        1.0
+    } else {
+        let covered = diff_files
+            .iter()
+            .filter(|path| planned.contains(path.as_str()))
+            .count();
+        covered as f64 / diff_files.len() as f64
+    };
+
+    let shared_files = shared_files(units);
+    let total = COVERAGE_WEIGHT * coverage
+        - ERROR_WEIGHT * errors as f64
+        - WARNING_WEIGHT * warnings as f64
+        - SHARED_FILE_WEIGHT * shared_files as f64
+        - NON_ATOMIC_WEIGHT * non_atomic as f64;
+
+    PlanScore {
+        errors,
+        warnings,
+        coverage,
+        shared_files,
+        non_atomic,
+        total,
+    }
+}
+
+/// Index of the highest-scoring entry. Ties go to the earliest, so the
+/// first sample (at the configured temperature) wins when nothing is better.
+pub fn best_score(scores: &[PlanScore]) -> Option<usize> {
+    scores
+        .iter()
+        .enumerate()
+        .fold(None, |best: Option<(usize, &PlanScore)>, (index, score)| match best {
+            Some((_, current)) if current.total >= score.total => best,
+            _ => Some((index, score)),
+        })
+        .map(|(index, _)| index)
+}
+
+fn shared_files(units: &[CommitUnit]) -> usize {
+    let mut owners: BTreeMap<&str, Vec<&CommitUnit>> = BTreeMap::new();
+    for unit in units {
+        for file in &unit.files {
+            owners.entry(file.as_str()).or_default().push(unit);
+        }
+    }
+    owners
+        .iter()
+        .filter(|(file, units)| {
+            units.len() > 1
+                && units
+                    .iter()
+                    .any(|unit| !unit.hunks.iter().any(|hunk| hunk.file == **file))
+        })
+        .count()
+}
@@ -1,4 +1,7 @@
 /// Semantic validation for commit plans beyond JSON schema checks.
+use crate::atomicity::{self, AtomicityKind};
+use crate::config::AtomicityPolicy;
+use crate::repair::{self, Repair};
 use crate::types::CommitUnit;
 
 pub type SemanticValidationErrors = Vec<SemanticValidationError>;
@@ -20,6 +23,42 @@ pub enum SemanticValidationError {
     ScopeMissing { id: String },
     #[error("commit {id} scope is not kebab-case")]
     ScopeInvalid { id: String },
+    #[error("file {path} is not assigned to any commit")]
+    FileUnplanned { path: String },
+    #[error("commit {id} is not atomic: {message}")]
+    NonAtomic {
+        id: String,
+        kind: AtomicityKind,
+        message: String,
+    },
+}
+
+impl SemanticValidationError {
+    /// Id of the commit unit at fault, when the error belongs to one.
+    pub fn unit_id(&self) -> Option<&str> {
+        match self {
+            Self::EmptyId { id }
+            | Self::SummaryLength { id, .. }
+            | Self::BodyLineCount { id, .. }
+            | Self::BodyLineEmpty { id, .. }
+            | Self::ScopeEmpty { id }
+            | Self::ScopeMissing { id }
+            | Self::ScopeInvalid { id }
+            | Self::NonAtomic { id, .. } => Some(id),
+            Self::FileUnplanned { .. } => None,
+        }
+    }
+}
+
+/// Ids of the units at fault in `errors`, in order and without repeats.
+pub fn failing_units(errors: &[SemanticValidationError]) -> Vec<String> {
+    let mut units: Vec<String> = Vec::new();
+    for id in errors.iter().filter_map(SemanticValidationError::unit_id) {
+        if !units.iter().any(|unit| unit == id) {
+            units.push(id.to_string());
+        }
+    }
+    units
 }
 
 /// How to treat missing commit scopes.
@@ -34,12 +73,20 @@ pub enum ScopePolicy {
 #[derive(Debug, Clone)]
 pub enum SemanticWarning {
     ScopeMissing { id: String },
+    NonAtomic {
+        id: String,
+        kind: AtomicityKind,
+        message: String,
+    },
 }
 
 /// Summary of semantic validation warnings.
 #[derive(Debug, Clone, Default)]
 pub struct SemanticValidationReport {
     pub warnings: SemanticValidationWarnings,
+    /// Mechanical fixes made before validating (see
+    /// [`repair_and_validate_commit_units`]).
+    pub repairs: Vec<Repair>,
 }
 
 /// Validate commit units and return any non-fatal warnings.
@@ -54,7 +101,82 @@ pub fn validate_commit_units(
     }
 
     if errors.is_empty() {
-        Ok(SemanticValidationReport { warnings })
+        Ok(SemanticValidationReport {
+            warnings,
+            repairs: Vec::new(),
+        })
+    } else {
+        Err(errors)
+    }
+}
+
+/// Repair mechanical mistakes in `units` (see [`repair::repair_plan`]),
+/// then validate them. The report lists the repairs made.
+pub fn repair_and_validate_commit_units(
+    units: &mut [CommitUnit],
+    scope_policy: ScopePolicy,
+) -> Result<SemanticValidationReport, SemanticValidationErrors> {
+    let repairs = repair::repair_plan(units);
+    let report = validate_commit_units(units, scope_policy)?;
+    Ok(SemanticValidationReport { repairs, ..report })
+}
+
+/// Require every path in `required` to appear in some unit's `files`.
+///
+/// Used for files the model only saw as metadata (binary, LFS, or
+/// oversized), which it could otherwise leave out of the plan.
+pub fn validate_file_coverage<'a>(
+    units: &[CommitUnit],
+    required: impl IntoIterator<Item = &'a str>,
+) -> Result<(), SemanticValidationErrors> {
+    let errors: SemanticValidationErrors = required
+        .into_iter()
+        .filter(|path| !units.iter().any(|unit| unit.files.iter().any(|file| file == path)))
+        .map(|path| SemanticValidationError::FileUnplanned {
+            path: path.to_string(),
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Run the atomicity analyzers over `units` and sort their findings into
+/// errors and warnings by `policy`.
+pub fn validate_atomicity(
+    units: &[CommitUnit],
+    diff: &str,
+    policy: AtomicityPolicy,
+) -> Result<SemanticValidationReport, SemanticValidationErrors> {
+    if policy == AtomicityPolicy::Off {
+        return Ok(SemanticValidationReport::default());
+    }
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for finding in atomicity::analyze_plan(units, diff) {
+        if finding.kind.is_error(policy) {
+            errors.push(SemanticValidationError::NonAtomic {
+                id: finding.unit,
+                kind: finding.kind,
+                message: finding.message,
+            });
+        } else {
+            warnings.push(SemanticWarning::NonAtomic {
+                id: finding.unit,
+                kind: finding.kind,
+                message: finding.message,
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(SemanticValidationReport {
+            warnings,
+            repairs: Vec::new(),
+        })
     } else {
         Err(errors)
     }
@@ -74,7 +196,7 @@ fn validate_commit_unit(
     }
 
     let summary_len = unit.summary.chars().count();
-    if summary_len < 50 || summary_len > 72 {
+    if !(50..=72).contains(&summary_len) {
         errors.push(SemanticValidationError::SummaryLength {
             id: id.clone(),
             len: summary_len,
@@ -82,7 +204,7 @@ fn validate_commit_unit(
     }
 
     let body_len = unit.body.len();
-    if body_len < 1 || body_len > 3 {
+    if !(1..=3).contains(&body_len) {
         errors.push(SemanticValidationError::BodyLineCount {
             id: id.clone(),
             count: body_len,
@@ -1,6 +1,7 @@
 /// Schema-aligned types used by CLI and server JSON responses.
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitPlan {
@@ -8,6 +9,9 @@ pub struct CommitPlan {
     pub request_id: Option<String>,
     pub warnings: Option<Vec<Warning>>,
     pub input: Option<InputMeta>,
+    /// The backend that generated the plan.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<BackendMeta>,
     pub plan: Vec<CommitUnit>,
 }
 
@@ -17,10 +21,34 @@ pub struct CommitApplyResponse {
     pub request_id: Option<String>,
     pub warnings: Option<Vec<Warning>>,
     pub input: Option<InputMeta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<BackendMeta>,
     pub plan: Vec<CommitUnit>,
     pub results: Vec<ApplyResult>,
 }
 
+/// Plans and results for several repositories planned and applied together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceApplyResponse {
+    pub schema_version: String,
+    pub request_id: Option<String>,
+    pub warnings: Option<Vec<Warning>>,
+    pub repos: Vec<RepoApplyResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoApplyResponse {
+    /// Repository path as given or discovered.
+    pub repo: String,
+    pub response: CommitApplyResponse,
+    /// Why this repository's plan was not applied, when it failed.
+    pub error: Option<ErrorDetail>,
+    /// Whether this repository's commits were undone because a repository
+    /// failed under `--rollback-on-verify-failure`.
+    #[serde(default)]
+    pub rolled_back: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub schema_version: String,
@@ -41,6 +69,60 @@ pub struct InputMeta {
     pub diff_mode: Option<DiffMode>,
     pub include_untracked: Option<bool>,
     pub diff_hash: Option<String>,
+    pub files: Option<Vec<DiffFile>>,
+    /// Pathspecs that limited a repo-derived diff.
+    pub pathspec: Option<Vec<String>>,
+    /// SHA-256 of each file's part of the diff, keyed by path.
+    pub file_hashes: Option<BTreeMap<String, String>>,
+    /// Commits the diff spans, for `range` sources.
+    pub revisions: Option<InputRevisions>,
+    /// Version of the prompt templates the plan was generated with.
+    pub prompt_version: Option<String>,
+}
+
+/// The LLM backend a plan came from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BackendMeta {
+    pub name: String,
+    pub runtime: String,
+    pub url: String,
+    pub model: String,
+}
+
+/// A file touched by the diff, as described by its `diff --git` block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiffFile {
+    pub path: String,
+    /// Previous path for renames.
+    pub old_path: Option<String>,
+    pub status: FileStatus,
+    #[serde(default)]
+    pub kind: FileKind,
+    /// Size in bytes, known for files summarized instead of diffed.
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    ModeChanged,
+}
+
+/// How a file's content is represented in the diff.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    #[default]
+    Text,
+    Binary,
+    LfsPointer,
+    Large,
+    /// A gitlink: a submodule or nested repository pointer.
+    Submodule,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +130,15 @@ pub struct InputMeta {
 pub enum InputSource {
     Repo,
     Diff,
+    /// Committed changes between two revisions of the repo.
+    Range,
+}
+
+/// Commits a revision diff was computed between.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InputRevisions {
+    pub base: String,
+    pub head: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +147,8 @@ pub enum DiffMode {
     Worktree,
     Staged,
     All,
+    Base,
+    Range,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +191,9 @@ pub struct ApplyResult {
     pub status: ApplyStatus,
     pub commit_hash: Option<String>,
     pub error: Option<ErrorDetail>,
+    pub hooks: Option<HookStatus>,
+    pub signed: Option<bool>,
+    pub verify: Option<VerifyOutcome>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -109,6 +205,26 @@ pub enum ApplyStatus {
     Failed,
 }
 
+/// Outcome of the commit hooks for a single applied unit.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStatus {
+    Passed,
+    Failed,
+    Skipped,
+    NotInstalled,
+}
+
+/// Result of running the verify command against a single new commit.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerifyOutcome {
+    pub command: String,
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    /// Tail of the command's combined stdout and stderr.
+    pub output: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorDetail {
     pub code: String,
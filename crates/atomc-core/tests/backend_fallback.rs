@@ -0,0 +1,171 @@
+use atomc_core::config::{BackendConfig, ResolvedConfig, Runtime};
+use atomc_core::llm::{generate_commit_plan, LlmError, Prompt};
+use axum::http::StatusCode;
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+fn prompt() -> Prompt {
+    Prompt {
+        system: "system".to_string(),
+        user: "user".to_string(),
+        version: "test".to_string(),
+    }
+}
+
+fn plan_response() -> Value {
+    let plan = json!({
+        "schema_version": "v1",
+        "plan": [{
+            "id": "commit-1",
+            "type": "docs",
+            "scope": "cli",
+            "summary": "document the order backends are tried in for plan requests",
+            "body": ["List backends in the order they are tried"],
+            "files": ["docs/02_cli_spec.md"],
+            "hunks": []
+        }]
+    });
+    json!({ "response": plan.to_string() })
+}
+
+/// An Ollama stand-in that answers `/api/generate` with `status` and `body`
+/// after `delay`, counting requests.
+async fn spawn_backend(
+    status: StatusCode,
+    body: Value,
+    delay: Duration,
+) -> (String, Arc<AtomicUsize>, oneshot::Sender<()>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let app = Router::new().route(
+        "/api/generate",
+        post(move || {
+            let counter = counter.clone();
+            let body = body.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                (status, Json(body))
+            }
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+    (format!("http://{addr}"), hits, shutdown_tx)
+}
+
+fn backend(name: &str, url: &str, model: &str, timeout_secs: u64) -> BackendConfig {
+    BackendConfig {
+        name: Some(name.to_string()),
+        runtime: Some(Runtime::Ollama),
+        url: Some(url.to_string()),
+        model: Some(model.to_string()),
+        timeout_secs: Some(timeout_secs),
+    }
+}
+
+#[tokio::test]
+async fn runtime_error_falls_back_to_next_backend() {
+    let (down, down_hits, down_shutdown) =
+        spawn_backend(StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": "model crashed" }), Duration::ZERO)
+            .await;
+    let (up, up_hits, up_shutdown) = spawn_backend(StatusCode::OK, plan_response(), Duration::ZERO).await;
+    let config = ResolvedConfig {
+        backends: vec![backend("local", &down, "small", 5), backend("lan", &up, "large", 5)],
+        ..ResolvedConfig::defaults()
+    };
+
+    let plan = generate_commit_plan(&config, &prompt()).await.unwrap();
+
+    let used = plan.backend.unwrap();
+    assert_eq!(used.name, "lan");
+    assert_eq!(used.model, "large");
+    assert_eq!(used.url, up);
+    assert_eq!(used.runtime, "ollama");
+    assert_eq!(down_hits.load(Ordering::SeqCst), 1);
+    assert_eq!(up_hits.load(Ordering::SeqCst), 1);
+
+    let _ = down_shutdown.send(());
+    let _ = up_shutdown.send(());
+}
+
+#[tokio::test]
+async fn timeout_falls_back_to_next_backend() {
+    let (slow, _, slow_shutdown) = spawn_backend(StatusCode::OK, plan_response(), Duration::from_secs(3)).await;
+    let (up, _, up_shutdown) = spawn_backend(StatusCode::OK, plan_response(), Duration::ZERO).await;
+    let config = ResolvedConfig {
+        backends: vec![backend("slow", &slow, "small", 1), backend("lan", &up, "large", 5)],
+        ..ResolvedConfig::defaults()
+    };
+
+    let plan = generate_commit_plan(&config, &prompt()).await.unwrap();
+    assert_eq!(plan.backend.unwrap().name, "lan");
+
+    let _ = slow_shutdown.send(());
+    let _ = up_shutdown.send(());
+}
+
+#[tokio::test]
+async fn parse_errors_do_not_fall_back() {
+    let (garbled, _, garbled_shutdown) =
+        spawn_backend(StatusCode::OK, json!({ "response": "not json" }), Duration::ZERO).await;
+    let (up, up_hits, up_shutdown) = spawn_backend(StatusCode::OK, plan_response(), Duration::ZERO).await;
+    let config = ResolvedConfig {
+        backends: vec![backend("local", &garbled, "small", 5), backend("lan", &up, "large", 5)],
+        ..ResolvedConfig::defaults()
+    };
+
+    let result = generate_commit_plan(&config, &prompt()).await;
+    assert!(matches!(result, Err(LlmError::Parse(_))));
+    assert_eq!(up_hits.load(Ordering::SeqCst), 0);
+
+    let _ = garbled_shutdown.send(());
+    let _ = up_shutdown.send(());
+}
+
+#[tokio::test]
+async fn last_backend_error_is_returned() {
+    let (down, _, down_shutdown) =
+        spawn_backend(StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": "model crashed" }), Duration::ZERO)
+            .await;
+    let config = ResolvedConfig {
+        backends: vec![backend("first", &down, "small", 5), backend("second", &down, "large", 5)],
+        ..ResolvedConfig::defaults()
+    };
+
+    let result = generate_commit_plan(&config, &prompt()).await;
+    assert!(matches!(result, Err(LlmError::Runtime(message)) if message.contains("model crashed")));
+
+    let _ = down_shutdown.send(());
+}
+
+#[tokio::test]
+async fn single_runtime_is_reported_as_default_backend() {
+    let (up, _, up_shutdown) = spawn_backend(StatusCode::OK, plan_response(), Duration::ZERO).await;
+    let config = ResolvedConfig {
+        ollama_url: up.clone(),
+        ..ResolvedConfig::defaults()
+    };
+
+    let plan = generate_commit_plan(&config, &prompt()).await.unwrap();
+    let used = plan.backend.unwrap();
+    assert_eq!(used.name, "default");
+    assert_eq!(used.model, config.model);
+    assert_eq!(used.url, up);
+
+    let _ = up_shutdown.send(());
+}
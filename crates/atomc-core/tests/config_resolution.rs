@@ -1,4 +1,7 @@
-use atomc_core::config::{resolve_config, PartialConfig, ResolvedConfig, Runtime};
+use atomc_core::config::{
+    resolve_config, resolve_config_layers, AtomicityPolicy, BackendConfig, ConfigError, DependencyOrder,
+    PartialConfig, ResolvedConfig, Runtime, MAX_PLAN_CANDIDATES,
+};
 use once_cell::sync::Lazy;
 use std::ffi::OsString;
 use std::fs;
@@ -105,3 +108,257 @@ fn resolve_config_reads_log_diff_env() {
     let resolved = resolve_config(None, PartialConfig::default()).unwrap();
     assert!(resolved.log_diff);
 }
+
+#[test]
+fn resolve_config_reads_verify_command_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let _env_verify = EnvVarGuard::set("LOCAL_COMMIT_VERIFY_COMMAND", "cargo check");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.verify_command.as_deref(), Some("cargo check"));
+}
+
+#[test]
+fn resolve_config_reads_max_file_diff_bytes_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let _env_limit = EnvVarGuard::set("LOCAL_COMMIT_MAX_FILE_DIFF_BYTES", "4096");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.max_file_diff_bytes, 4096);
+}
+
+#[test]
+fn resolve_config_reads_recurse_submodules_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let _env_recurse = EnvVarGuard::set("LOCAL_COMMIT_RECURSE_SUBMODULES", "true");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert!(resolved.recurse_submodules);
+}
+
+#[test]
+fn resolve_config_reads_path_filters() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let dir = temp_dir("config-filters");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    fs::write(&path, "ignore = [\"*.lock\", \"vendor/\"]\n").unwrap();
+    let _env_include = EnvVarGuard::set("LOCAL_COMMIT_INCLUDE", "src/**, docs/**,");
+
+    let resolved = resolve_config(Some(path), PartialConfig::default()).unwrap();
+    assert_eq!(resolved.ignore, vec!["*.lock", "vendor/"]);
+    assert_eq!(resolved.include, vec!["src/**", "docs/**"]);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn resolve_config_reads_plan_cache_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    assert!(ResolvedConfig::defaults().plan_cache);
+    let _env_cache = EnvVarGuard::set("LOCAL_COMMIT_PLAN_CACHE", "false");
+    let _env_dir = EnvVarGuard::set("LOCAL_COMMIT_CACHE_DIR", "/tmp/atomc-cache");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert!(!resolved.plan_cache);
+    assert_eq!(resolved.cache_dir, Some(PathBuf::from("/tmp/atomc-cache")));
+}
+
+#[test]
+fn resolve_config_reads_plan_candidates_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    assert_eq!(ResolvedConfig::defaults().plan_candidates, 1);
+    let _env_candidates = EnvVarGuard::set("LOCAL_COMMIT_PLAN_CANDIDATES", "4");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.plan_candidates, 4);
+
+    let _env_candidates = EnvVarGuard::set("LOCAL_COMMIT_PLAN_CANDIDATES", "9");
+    assert!(matches!(
+        resolve_config(None, PartialConfig::default()),
+        Err(ConfigError::InvalidEnv { .. })
+    ));
+}
+
+#[test]
+fn resolve_config_caps_plan_candidates_from_file() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let dir = temp_dir("config-candidates");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    fs::write(&path, format!("plan_candidates = {}\n", MAX_PLAN_CANDIDATES + 1)).unwrap();
+
+    let error = resolve_config(Some(path), PartialConfig::default()).unwrap_err();
+    assert!(matches!(error, ConfigError::OutOfRange { max: 8, .. }));
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn resolve_config_reads_retry_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let _env_attempts = EnvVarGuard::set("LOCAL_COMMIT_LLM_MAX_ATTEMPTS", "5");
+    let _env_backoff = EnvVarGuard::set("LOCAL_COMMIT_LLM_RETRY_BACKOFF_MS", "250");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.llm_max_attempts, 5);
+    assert_eq!(resolved.llm_retry_backoff_ms, 250);
+}
+
+#[test]
+fn resolve_config_reads_prompt_template_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let _env_template = EnvVarGuard::set("LOCAL_COMMIT_PROMPT_TEMPLATE", "prompts/qwen.toml");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.prompt_template, Some(PathBuf::from("prompts/qwen.toml")));
+}
+
+#[test]
+fn resolve_config_reads_prompt_examples_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let _env_examples = EnvVarGuard::set("LOCAL_COMMIT_PROMPT_EXAMPLES", "2");
+    let _env_dir = EnvVarGuard::set("LOCAL_COMMIT_EXAMPLES_DIR", "examples");
+    let _env_budget = EnvVarGuard::set("LOCAL_COMMIT_EXAMPLES_MAX_TOKENS", "512");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.prompt_examples, 2);
+    assert_eq!(resolved.examples_dir, Some(PathBuf::from("examples")));
+    assert_eq!(resolved.examples_max_tokens, 512);
+}
+
+#[test]
+fn resolve_config_reads_cassette_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let _env_runtime = EnvVarGuard::set("LOCAL_COMMIT_RUNTIME", "replay");
+    let _env_cassette = EnvVarGuard::set("LOCAL_COMMIT_CASSETTE", "bug.cassette.json");
+    let _env_redact = EnvVarGuard::set("LOCAL_COMMIT_CASSETTE_REDACT", "true");
+    let _env_fuzzy = EnvVarGuard::set("LOCAL_COMMIT_CASSETTE_FUZZY", "true");
+
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.runtime, Runtime::Replay);
+    assert_eq!(resolved.cassette, Some(PathBuf::from("bug.cassette.json")));
+    assert!(resolved.cassette_redact);
+    assert!(resolved.cassette_fuzzy);
+}
+
+#[test]
+fn resolve_config_reads_atomicity_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    assert_eq!(
+        resolve_config(None, PartialConfig::default()).unwrap().atomicity,
+        AtomicityPolicy::Standard
+    );
+
+    let _env_atomicity = EnvVarGuard::set("LOCAL_COMMIT_ATOMICITY", "strict");
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.atomicity, AtomicityPolicy::Strict);
+
+    let _env_atomicity = EnvVarGuard::set("LOCAL_COMMIT_ATOMICITY", "loose");
+    assert!(resolve_config(None, PartialConfig::default()).is_err());
+}
+
+#[test]
+fn resolve_config_reads_dependency_order_env() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    assert_eq!(
+        resolve_config(None, PartialConfig::default()).unwrap().dependency_order,
+        DependencyOrder::Reorder
+    );
+
+    let _env_order = EnvVarGuard::set("LOCAL_COMMIT_DEPENDENCY_ORDER", "warn");
+    let resolved = resolve_config(None, PartialConfig::default()).unwrap();
+    assert_eq!(resolved.dependency_order, DependencyOrder::Warn);
+}
+
+#[test]
+fn resolve_config_layers_report_keys_per_source() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let dir = temp_dir("config-layers");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    fs::write(&path, "model = \"file-model\"\nmax_diff_bytes = 1024\n").unwrap();
+    let _env_timeout = EnvVarGuard::set("LOCAL_COMMIT_LLM_TIMEOUT_SECS", "5");
+
+    let overrides = PartialConfig {
+        model: Some("cli-model".to_string()),
+        ..PartialConfig::default()
+    };
+    let (resolved, layers) = resolve_config_layers(Some(path.clone()), overrides).unwrap();
+    assert_eq!(resolved.model, "cli-model");
+
+    let names: Vec<&str> = layers.iter().map(|layer| layer.name).collect();
+    assert_eq!(names, ["defaults", "file", "env", "cli"]);
+    assert!(layers[0].keys.is_empty());
+    assert_eq!(layers[1].source.as_ref(), Some(&path));
+    assert!(layers[1].loaded);
+    assert_eq!(layers[1].keys, ["max_diff_bytes", "model"]);
+    assert!(layers[2].keys.contains(&"llm_timeout_secs".to_string()));
+    assert_eq!(layers[3].keys, ["model"]);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn resolve_config_reads_backend_chain() {
+    let _lock = ENV_LOCK.lock().unwrap();
+    let dir = temp_dir("config-backends");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    fs::write(
+        &path,
+        r#"
+model = "qwen2.5-coder:7b"
+llm_timeout_secs = 20
+
+[[backends]]
+name = "local"
+
+[[backends]]
+runtime = "llama.cpp"
+url = "http://gpu-box:8080"
+model = "qwen2.5-coder:32b"
+timeout_secs = 120
+"#,
+    )
+    .unwrap();
+
+    let resolved = resolve_config(Some(path), PartialConfig::default()).unwrap();
+    let chain = resolved.backend_chain();
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[0].name, "local");
+    assert_eq!(chain[0].runtime, Runtime::Ollama);
+    assert_eq!(chain[0].url, "http://localhost:11434");
+    assert_eq!(chain[0].model, "qwen2.5-coder:7b");
+    assert_eq!(chain[0].timeout_secs, 20);
+    assert_eq!(chain[1].name, "backend-2");
+    assert_eq!(chain[1].runtime, Runtime::LlamaCpp);
+    assert_eq!(chain[1].url, "http://gpu-box:8080");
+    assert_eq!(chain[1].model, "qwen2.5-coder:32b");
+    assert_eq!(chain[1].timeout_secs, 120);
+
+    let escalated = resolved.escalate("local").unwrap();
+    assert_eq!(escalated.runtime, Runtime::LlamaCpp);
+    assert_eq!(escalated.model, "qwen2.5-coder:32b");
+    assert_eq!(escalated.llm_timeout_secs, 120);
+    assert_eq!(escalated.backend_chain(), chain[1..]);
+    assert!(escalated.escalate("backend-2").is_none());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn backend_chain_defaults_to_top_level_runtime() {
+    let config = ResolvedConfig::defaults();
+    let chain = config.backend_chain();
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].name, "default");
+    assert_eq!(chain[0].model, config.model);
+    assert!(config.escalate("default").is_none());
+
+    let config = ResolvedConfig {
+        backends: vec![BackendConfig::default()],
+        ..ResolvedConfig::defaults()
+    };
+    assert_eq!(config.backend_chain()[0].name, "backend-1");
+}
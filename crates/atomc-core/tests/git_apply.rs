@@ -1,9 +1,12 @@
-use atomc_core::config::DiffMode;
-use atomc_core::git::{apply_plan, compute_diff, ApplyRequest, GitError};
+use atomc_core::config::{DiffMode, GitBackendKind};
+use atomc_core::git::{
+    apply_plan, check_apply_request, compute_diff, compute_diff_with, file_hashes, snapshot_tree, ApplyRequest,
+    DiffOptions, GitError, HookPolicy, PathFilter,
+};
 use atomc_core::hash::diff_hash;
-use atomc_core::types::{ApplyStatus, CommitType, CommitUnit};
+use atomc_core::types::{ApplyStatus, CommitType, CommitUnit, HookStatus};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -66,19 +69,33 @@ fn apply_plan_creates_commit() {
     let plan = sample_plan();
     let request = ApplyRequest {
         repo: &repo,
+        backend: GitBackendKind::Cli,
         plan: &plan,
         diff: &diff,
         diff_mode: DiffMode::Worktree,
         include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
         expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
         cleanup_on_error: false,
         assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
     };
 
     let results = apply_plan(request).unwrap();
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].status, ApplyStatus::Applied);
     assert!(results[0].commit_hash.as_ref().unwrap().len() > 6);
+    assert_eq!(results[0].hooks, Some(HookStatus::NotInstalled));
+    assert_eq!(results[0].signed, Some(false));
 
     fs::remove_dir_all(&repo).ok();
 }
@@ -90,13 +107,25 @@ fn apply_plan_appends_assisted_by_line() {
     let plan = sample_plan();
     let request = ApplyRequest {
         repo: &repo,
+        backend: GitBackendKind::Cli,
         plan: &plan,
         diff: &diff,
         diff_mode: DiffMode::Worktree,
         include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
         expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
         cleanup_on_error: false,
         assisted_by: Some("qwen2.5-coder:14b"),
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
     };
 
     let results = apply_plan(request).unwrap();
@@ -124,17 +153,29 @@ fn apply_plan_rejects_changed_diff() {
 
     let request = ApplyRequest {
         repo: &repo,
+        backend: GitBackendKind::Cli,
         plan: &plan,
         diff: &diff,
         diff_mode: DiffMode::Worktree,
         include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
         expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
         cleanup_on_error: false,
         assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
     };
 
     let error = apply_plan(request).unwrap_err();
-    assert!(matches!(error, GitError::DiffHashMismatch { .. }));
+    assert!(matches!(error, GitError::DiffHashMismatch { files, .. } if files == vec!["file.txt"]));
 
     fs::remove_dir_all(&repo).ok();
 }
@@ -147,13 +188,25 @@ fn apply_plan_rejects_diff_input_mismatch() {
 
     let request = ApplyRequest {
         repo: &repo,
+        backend: GitBackendKind::Cli,
         plan: &plan,
         diff,
         diff_mode: DiffMode::Worktree,
         include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
         expected_diff_hash: Some(diff_hash(diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
         cleanup_on_error: false,
         assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
     };
 
     let error = apply_plan(request).unwrap_err();
@@ -173,13 +226,25 @@ fn apply_plan_cleans_up_on_error() {
 
     let request = ApplyRequest {
         repo: &repo,
+        backend: GitBackendKind::Cli,
         plan: &plan,
         diff: &diff,
         diff_mode: DiffMode::Worktree,
         include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
         expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
         cleanup_on_error: true,
         assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
     };
 
     let error = apply_plan(request).unwrap_err();
@@ -192,6 +257,588 @@ fn apply_plan_cleans_up_on_error() {
     fs::remove_dir_all(&repo).ok();
 }
 
+#[test]
+fn apply_plan_commits_multiple_units_and_preserves_tree() {
+    let repo = setup_repo();
+    fs::write(repo.join("other.txt"), "other\n").unwrap();
+    run_git(&repo, &["add", "other.txt"]);
+    run_git(&repo, &["commit", "-qm", "other"]);
+    fs::write(repo.join("other.txt"), "other\nchanged\n").unwrap();
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let before = snapshot_tree(&repo).unwrap();
+    let mut plan = sample_plan();
+    let mut second = sample_plan().remove(0);
+    second.id = "commit-2".to_string();
+    second.files = vec!["other.txt".to_string()];
+    plan.push(second);
+
+    let request = ApplyRequest {
+        repo: &repo,
+        backend: GitBackendKind::Cli,
+        plan: &plan,
+        diff: &diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    };
+
+    let results = apply_plan(request).unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|result| result.status == ApplyStatus::Applied));
+    assert_eq!(snapshot_tree(&repo).unwrap(), before);
+    let head_tree = Command::new("git")
+        .current_dir(&repo)
+        .args(["rev-parse", "HEAD^{tree}"])
+        .output()
+        .expect("git rev-parse");
+    assert_eq!(String::from_utf8_lossy(&head_tree.stdout).trim(), before);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_reports_tree_drift() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = setup_repo();
+    let hook = repo.join(".git/hooks/pre-commit");
+    fs::write(&hook, "#!/bin/sh\necho drift > drift.txt\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+    let request = ApplyRequest {
+        repo: &repo,
+        backend: GitBackendKind::Cli,
+        plan: &plan,
+        diff: &diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    };
+
+    let error = apply_plan(request).unwrap_err();
+    match error {
+        GitError::TreeDrift { files, .. } => assert_eq!(files, vec!["drift.txt".to_string()]),
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_stops_before_units_whose_files_drifted() {
+    for backend in [GitBackendKind::Cli, GitBackendKind::Native] {
+        let repo = setup_repo();
+        fs::write(repo.join("other.txt"), "new\n").unwrap();
+        install_hook(&repo, "pre-commit", "#!/bin/sh\necho drift >> other.txt\n");
+
+        let diff = compute_diff(&repo, DiffMode::Worktree, true).unwrap();
+        let mut plan = sample_plan();
+        plan.push(CommitUnit {
+            id: "commit-2".to_string(),
+            files: vec!["other.txt".to_string()],
+            ..plan[0].clone()
+        });
+        let before = head(&repo);
+        let request = ApplyRequest {
+            repo: &repo,
+            backend,
+            plan: &plan,
+            diff: &diff,
+            diff_mode: DiffMode::Worktree,
+            include_untracked: true,
+            max_file_diff_bytes: 200_000,
+            recurse_submodules: false,
+            filter: PathFilter::default(),
+            pathspec: Vec::new(),
+            expected_diff_hash: Some(diff_hash(&diff)),
+            expected_file_hashes: None,
+            strict_diff_hash: false,
+            cleanup_on_error: false,
+            assisted_by: None,
+            hooks: HookPolicy::FailFast,
+            sign: false,
+            signing_key: None,
+            verify_command: None,
+            rollback_on_verify_failure: false,
+        };
+
+        match apply_plan(request).unwrap_err() {
+            GitError::TreeDrift { files, .. } => assert_eq!(files, vec!["other.txt".to_string()]),
+            other => panic!("unexpected error: {other:?}"),
+        }
+        // Only the first unit was committed.
+        let parent = Command::new("git")
+            .current_dir(&repo)
+            .args(["rev-parse", "HEAD~1"])
+            .output()
+            .expect("git rev-parse");
+        assert_eq!(String::from_utf8_lossy(&parent.stdout).trim(), before);
+
+        fs::remove_dir_all(&repo).ok();
+    }
+}
+
+#[cfg(unix)]
+fn install_hook(repo: &Path, name: &str, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let hook = repo.join(".git/hooks").join(name);
+    fs::write(&hook, script).unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(unix)]
+fn hook_request<'a>(
+    repo: &'a PathBuf,
+    plan: &'a [CommitUnit],
+    diff: &'a str,
+    hooks: HookPolicy,
+) -> ApplyRequest<'a> {
+    ApplyRequest {
+        repo,
+        backend: GitBackendKind::Cli,
+        plan,
+        diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: true,
+        assisted_by: None,
+        hooks,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_reports_failing_hook() {
+    let repo = setup_repo();
+    let before = head(&repo);
+    install_hook(&repo, "pre-commit", "#!/bin/sh\necho lint failed >&2\nexit 3\n");
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+
+    let error = apply_plan(hook_request(&repo, &plan, &diff, HookPolicy::FailFast)).unwrap_err();
+    match error {
+        GitError::HookFailed {
+            id, hooks, stderr, ..
+        } => {
+            assert_eq!(id, "commit-1");
+            assert_eq!(hooks, vec!["pre-commit".to_string()]);
+            assert!(stderr.contains("lint failed"));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    assert!(list_staged_files(&repo).is_empty());
+    assert_eq!(head(&repo), before);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_classifies_commit_msg_rejection_without_committing() {
+    let repo = setup_repo();
+    let reflog = || {
+        let output = Command::new("git")
+            .current_dir(&repo)
+            .args(["reflog"])
+            .output()
+            .expect("git reflog");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let before = reflog();
+    install_hook(&repo, "commit-msg", "#!/bin/sh\ngrep -q 'Ticket:' \"$1\" || exit 1\n");
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+
+    let error = apply_plan(hook_request(&repo, &plan, &diff, HookPolicy::FailFast)).unwrap_err();
+    match error {
+        GitError::HookFailed { hooks, .. } => assert_eq!(hooks, vec!["commit-msg".to_string()]),
+        other => panic!("unexpected error: {other:?}"),
+    }
+    assert_eq!(reflog(), before);
+    assert!(!repo.join(".git/atomc-commit.msg").exists());
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_run_hooks_records_failed_unit_and_continues() {
+    let repo = setup_repo();
+    fs::write(repo.join("other.txt"), "other\n").unwrap();
+    run_git(&repo, &["add", "other.txt"]);
+    run_git(&repo, &["commit", "-qm", "other"]);
+    fs::write(repo.join("other.txt"), "other\nchanged\n").unwrap();
+    install_hook(
+        &repo,
+        "pre-commit",
+        "#!/bin/sh\ngit diff --cached --name-only | grep -q file.txt && exit 1\nexit 0\n",
+    );
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let mut plan = sample_plan();
+    let mut second = sample_plan().remove(0);
+    second.id = "commit-2".to_string();
+    second.files = vec!["other.txt".to_string()];
+    plan.push(second);
+
+    let results = apply_plan(hook_request(&repo, &plan, &diff, HookPolicy::Run)).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Failed);
+    assert_eq!(results[0].hooks, Some(HookStatus::Failed));
+    assert_eq!(results[0].error.as_ref().unwrap().code, "hook_failed");
+    assert_eq!(results[0].signed, None);
+    assert_eq!(results[1].status, ApplyStatus::Applied);
+    assert_eq!(results[1].hooks, Some(HookStatus::Passed));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_runs_each_hook_once() {
+    let repo = setup_repo();
+    install_hook(&repo, "pre-commit", "#!/bin/sh\necho run >> .git/pre-commit.log\n");
+    install_hook(&repo, "commit-msg", "#!/bin/sh\necho 'Ticket: 42' >> \"$1\"\n");
+    install_hook(&repo, "post-commit", "#!/bin/sh\necho run >> .git/post-commit.log\n");
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+
+    let results = apply_plan(hook_request(&repo, &plan, &diff, HookPolicy::FailFast)).unwrap();
+    assert_eq!(results[0].hooks, Some(HookStatus::Passed));
+    assert_eq!(fs::read_to_string(repo.join(".git/pre-commit.log")).unwrap(), "run\n");
+    assert_eq!(fs::read_to_string(repo.join(".git/post-commit.log")).unwrap(), "run\n");
+    let output = Command::new("git")
+        .current_dir(&repo)
+        .args(["log", "-1", "--pretty=%B"])
+        .output()
+        .expect("git log");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Ticket: 42"));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_run_hooks_stops_on_failures_hooks_did_not_cause() {
+    let repo = setup_repo();
+    install_hook(&repo, "pre-commit", "#!/bin/sh\nexit 0\n");
+    install_hook(&repo, "post-commit", "#!/bin/sh\nexit 0\n");
+    run_git(&repo, &["config", "gpg.program", "false"]);
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+
+    let request = ApplyRequest {
+        sign: true,
+        ..hook_request(&repo, &plan, &diff, HookPolicy::Run)
+    };
+    let error = apply_plan(request).unwrap_err();
+    assert!(matches!(error, GitError::CommandFailed { .. }), "unexpected error: {error:?}");
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_reports_signature_of_created_commit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = setup_repo();
+    let gpg = repo.join(".git/fake-gpg");
+    fs::write(
+        &gpg,
+        "#!/bin/sh\ncat >/dev/null\necho '[GNUPG:] BEGIN_SIGNING' >&2\necho '[GNUPG:] SIG_CREATED ' >&2\n\
+         echo '-----BEGIN PGP SIGNATURE-----'\necho 'fake'\necho '-----END PGP SIGNATURE-----'\n",
+    )
+    .unwrap();
+    fs::set_permissions(&gpg, fs::Permissions::from_mode(0o755)).unwrap();
+    run_git(&repo, &["config", "gpg.program", gpg.to_str().unwrap()]);
+    run_git(&repo, &["config", "commit.gpgsign", "true"]);
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+
+    let results = apply_plan(hook_request(&repo, &plan, &diff, HookPolicy::FailFast)).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+    assert_eq!(results[0].signed, Some(true));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn apply_plan_skip_hooks_bypasses_pre_commit() {
+    let repo = setup_repo();
+    install_hook(&repo, "pre-commit", "#!/bin/sh\nexit 1\n");
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+
+    let results = apply_plan(hook_request(&repo, &plan, &diff, HookPolicy::Skip)).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+    assert_eq!(results[0].hooks, Some(HookStatus::Skipped));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+fn two_unit_repo() -> (PathBuf, Vec<CommitUnit>) {
+    let repo = setup_repo();
+    fs::write(repo.join("other.txt"), "other\n").unwrap();
+    run_git(&repo, &["add", "other.txt"]);
+    run_git(&repo, &["commit", "-qm", "other"]);
+    fs::write(repo.join("other.txt"), "other\nchanged\n").unwrap();
+    let mut plan = sample_plan();
+    let mut second = sample_plan().remove(0);
+    second.id = "commit-2".to_string();
+    second.files = vec!["other.txt".to_string()];
+    plan.push(second);
+    (repo, plan)
+}
+
+fn verify_request<'a>(
+    repo: &'a PathBuf,
+    plan: &'a [CommitUnit],
+    diff: &'a str,
+    verify_command: &'a str,
+    rollback_on_verify_failure: bool,
+) -> ApplyRequest<'a> {
+    ApplyRequest {
+        repo,
+        backend: GitBackendKind::Cli,
+        plan,
+        diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: Some(verify_command),
+        rollback_on_verify_failure,
+    }
+}
+
+fn head(repo: &PathBuf) -> String {
+    let output = Command::new("git")
+        .current_dir(repo)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .expect("git rev-parse");
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn apply_plan_runs_verify_command_per_commit() {
+    let (repo, plan) = two_unit_repo();
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+
+    let request = verify_request(&repo, &plan, &diff, "grep -q two file.txt", false);
+    let results = apply_plan(request).unwrap();
+    assert!(results.iter().all(|result| result.status == ApplyStatus::Applied));
+    let verify = results[1].verify.as_ref().unwrap();
+    assert!(verify.passed);
+    assert_eq!(verify.command, "grep -q two file.txt");
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_stops_when_verify_command_fails() {
+    let (repo, plan) = two_unit_repo();
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+
+    // The check only passes once other.txt is committed too.
+    let request = verify_request(&repo, &plan, &diff, "grep -q changed other.txt", false);
+    let results = apply_plan(request).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].status, ApplyStatus::Failed);
+    assert_eq!(results[0].error.as_ref().unwrap().code, "verify_failed");
+    assert!(!results[0].verify.as_ref().unwrap().passed);
+    assert_eq!(results[0].commit_hash.as_deref(), Some(head(&repo).as_str()));
+    assert_eq!(results[1].status, ApplyStatus::Skipped);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_rolls_back_when_verify_command_fails() {
+    let (repo, plan) = two_unit_repo();
+    let before = head(&repo);
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+
+    let request = verify_request(&repo, &plan, &diff, "! grep -q changed other.txt", true);
+    let results = apply_plan(request).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Skipped);
+    assert_eq!(results[0].commit_hash, None);
+    assert_eq!(results[1].status, ApplyStatus::Failed);
+    assert_eq!(results[1].commit_hash, None);
+    let details = results[1].error.as_ref().unwrap().details.as_ref().unwrap();
+    assert_eq!(details["rolled_back"], true);
+    assert_eq!(head(&repo), before);
+    assert!(list_staged_files(&repo).is_empty());
+    assert_eq!(compute_diff(&repo, DiffMode::Worktree, false).unwrap(), diff);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+/// Repo whose only change is a staged rename of `old.txt`.
+fn rename_repo() -> PathBuf {
+    let repo = setup_repo();
+    run_git(&repo, &["checkout", "-q", "--", "file.txt"]);
+    fs::write(repo.join("old.txt"), "keep\n").unwrap();
+    run_git(&repo, &["add", "old.txt"]);
+    run_git(&repo, &["commit", "-qm", "more"]);
+    run_git(&repo, &["mv", "old.txt", "new name.txt"]);
+    repo
+}
+
+fn staged_request<'a>(repo: &'a PathBuf, plan: &'a [CommitUnit], diff: &'a str) -> ApplyRequest<'a> {
+    ApplyRequest {
+        repo,
+        backend: GitBackendKind::Cli,
+        plan,
+        diff,
+        diff_mode: DiffMode::Staged,
+        include_untracked: false,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    }
+}
+
+fn show_name_status(repo: &PathBuf) -> String {
+    let output = Command::new("git")
+        .current_dir(repo)
+        .args(["show", "-M", "--name-status", "--pretty=", "HEAD"])
+        .output()
+        .expect("git show");
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn apply_plan_commits_renames() {
+    let repo = rename_repo();
+    let diff = compute_diff(&repo, DiffMode::Staged, false).unwrap();
+    let mut plan = sample_plan();
+    plan[0].files = vec!["old.txt".to_string(), "new name.txt".to_string()];
+
+    let results = apply_plan(staged_request(&repo, &plan, &diff)).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+    let changes = show_name_status(&repo);
+    assert!(changes.starts_with("R100\told.txt\tnew name.txt"), "{changes}");
+    assert!(list_staged_files(&repo).is_empty());
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_commits_deletions() {
+    let repo = setup_repo();
+    fs::remove_file(repo.join("file.txt")).unwrap();
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let plan = sample_plan();
+
+    let mut request = staged_request(&repo, &plan, &diff);
+    request.diff_mode = DiffMode::Worktree;
+    let results = apply_plan(request).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+    assert_eq!(show_name_status(&repo).trim(), "D\tfile.txt");
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_rejects_split_rename() {
+    let repo = rename_repo();
+    let diff = compute_diff(&repo, DiffMode::Staged, false).unwrap();
+    let mut plan = sample_plan();
+    plan[0].files = vec!["new name.txt".to_string()];
+
+    let error = apply_plan(staged_request(&repo, &plan, &diff)).unwrap_err();
+    match error {
+        GitError::RenameSplit { from, to, .. } => {
+            assert_eq!(from, "old.txt");
+            assert_eq!(to, "new name.txt");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_commits_untracked_files() {
+    let repo = setup_repo();
+    fs::write(repo.join("added.txt"), "added\n").unwrap();
+    let diff = compute_diff(&repo, DiffMode::Worktree, true).unwrap();
+    let mut plan = sample_plan();
+    plan[0].files.push("added.txt".to_string());
+
+    let mut request = staged_request(&repo, &plan, &diff);
+    request.diff_mode = DiffMode::Worktree;
+    request.include_untracked = true;
+    let results = apply_plan(request).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
 fn list_staged_files(repo: &PathBuf) -> Vec<String> {
     let output = Command::new("git")
         .current_dir(repo)
@@ -207,3 +854,216 @@ fn list_staged_files(repo: &PathBuf) -> Vec<String> {
         .map(|entry| entry.to_string())
         .collect()
 }
+
+#[test]
+fn apply_plan_leaves_excluded_paths_uncommitted() {
+    let repo = setup_repo();
+    fs::write(repo.join("Cargo.lock"), "version = 3\n").unwrap();
+    let filter = PathFilter {
+        include: Vec::new(),
+        exclude: vec!["*.lock".to_string()],
+    };
+    let options = DiffOptions {
+        filter: filter.clone(),
+        ..DiffOptions::new(DiffMode::Worktree, true)
+    };
+    let diff = compute_diff_with(&repo, &options).unwrap();
+    assert!(!diff.contains("Cargo.lock"));
+    let plan = sample_plan();
+    let request = ApplyRequest {
+        repo: &repo,
+        backend: GitBackendKind::Cli,
+        plan: &plan,
+        diff: &diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: true,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter,
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    };
+
+    let results = apply_plan(request).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+
+    let output = Command::new("git")
+        .current_dir(&repo)
+        .args(["status", "--porcelain"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "?? Cargo.lock\n");
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_ignores_changes_outside_pathspec() {
+    let repo = setup_repo();
+    let options = DiffOptions {
+        pathspec: vec!["file.txt".to_string()],
+        range: None,
+        ..DiffOptions::new(DiffMode::Worktree, true)
+    };
+    let diff = compute_diff_with(&repo, &options).unwrap();
+    // Edits elsewhere in the tree after planning do not change the scoped diff.
+    fs::write(repo.join("other.txt"), "other\n").unwrap();
+    let plan = sample_plan();
+    let request = ApplyRequest {
+        repo: &repo,
+        backend: GitBackendKind::Cli,
+        plan: &plan,
+        diff: &diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: true,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: options.pathspec.clone(),
+        expected_diff_hash: Some(diff_hash(&diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    };
+
+    let results = apply_plan(request).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+
+    let output = Command::new("git")
+        .current_dir(&repo)
+        .args(["status", "--porcelain"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "?? other.txt\n");
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+fn drift_request<'a>(
+    repo: &'a PathBuf,
+    plan: &'a [CommitUnit],
+    diff: &'a str,
+    strict_diff_hash: bool,
+) -> ApplyRequest<'a> {
+    ApplyRequest {
+        repo,
+        backend: GitBackendKind::Cli,
+        plan,
+        diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: true,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(diff)),
+        expected_file_hashes: Some(file_hashes(diff)),
+        strict_diff_hash,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    }
+}
+
+#[test]
+fn apply_plan_tolerates_changes_outside_the_plan() {
+    let repo = setup_repo();
+    fs::write(repo.join("notes.txt"), "draft\n").unwrap();
+    let diff = compute_diff(&repo, DiffMode::Worktree, true).unwrap();
+    let plan = sample_plan();
+    fs::write(repo.join("notes.txt"), "draft\nrevised\n").unwrap();
+    fs::write(repo.join("later.txt"), "later\n").unwrap();
+
+    let results = apply_plan(drift_request(&repo, &plan, &diff, false)).unwrap();
+    assert_eq!(results[0].status, ApplyStatus::Applied);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_strict_rejects_changes_outside_the_plan() {
+    let repo = setup_repo();
+    fs::write(repo.join("notes.txt"), "draft\n").unwrap();
+    let diff = compute_diff(&repo, DiffMode::Worktree, true).unwrap();
+    let plan = sample_plan();
+    fs::write(repo.join("notes.txt"), "draft\nrevised\n").unwrap();
+    fs::write(repo.join("later.txt"), "later\n").unwrap();
+
+    let error = apply_plan(drift_request(&repo, &plan, &diff, true)).unwrap_err();
+    assert!(matches!(
+        error,
+        GitError::DiffHashMismatch { files, .. } if files == vec!["later.txt", "notes.txt"]
+    ));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn apply_plan_rejects_revision_diff_modes() {
+    let repo = setup_repo();
+    fs::write(repo.join("notes.txt"), "draft\n").unwrap();
+    let diff = compute_diff(&repo, DiffMode::Worktree, true).unwrap();
+    let plan = sample_plan();
+
+    let request = ApplyRequest {
+        diff_mode: DiffMode::Base,
+        ..drift_request(&repo, &plan, &diff, false)
+    };
+    let error = apply_plan(request).unwrap_err();
+    assert!(matches!(error, GitError::ReadOnlyDiffMode { ref mode } if mode == "base"));
+    assert_eq!(compute_diff(&repo, DiffMode::Worktree, true).unwrap(), diff);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn check_apply_request_requires_verify_command_for_rollback() {
+    let (repo, plan) = two_unit_repo();
+    let diff = compute_diff(&repo, DiffMode::Worktree, false).unwrap();
+    let request = ApplyRequest {
+        verify_command: None,
+        ..verify_request(&repo, &plan, &diff, "true", true)
+    };
+
+    let error = check_apply_request(&request).unwrap_err();
+    assert!(matches!(error, GitError::RollbackWithoutVerify));
+    assert!(matches!(apply_plan(request).unwrap_err(), GitError::RollbackWithoutVerify));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn check_apply_request_reports_drift_without_committing() {
+    let repo = setup_repo();
+    fs::write(repo.join("notes.txt"), "draft\n").unwrap();
+    let diff = compute_diff(&repo, DiffMode::Worktree, true).unwrap();
+    let plan = sample_plan();
+
+    check_apply_request(&drift_request(&repo, &plan, &diff, false)).unwrap();
+    fs::write(repo.join("notes.txt"), "draft\nrevised\n").unwrap();
+    check_apply_request(&drift_request(&repo, &plan, &diff, false)).unwrap();
+    let error = check_apply_request(&drift_request(&repo, &plan, &diff, true)).unwrap_err();
+    assert!(matches!(error, GitError::DiffHashMismatch { .. }));
+    assert!(compute_diff(&repo, DiffMode::Worktree, true).unwrap().contains("revised"));
+
+    fs::remove_dir_all(&repo).ok();
+}
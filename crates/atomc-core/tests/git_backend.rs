@@ -0,0 +1,261 @@
+#![cfg(feature = "native-git")]
+
+mod support;
+
+use atomc_core::config::{DiffMode, GitBackendKind};
+use atomc_core::git::{
+    apply_plan, backend_for, ApplyRequest, CliBackend, CommitOptions, DiffOptions, GitBackend, GitError,
+    HookPolicy, NativeBackend, PathFilter,
+};
+use atomc_core::hash::diff_hash;
+use atomc_core::types::{ApplyStatus, CommitType, CommitUnit};
+use std::fs;
+use std::path::PathBuf;
+use support::{init_repo, run_git, temp_dir};
+
+/// Repo with a modified file, a staged file, a deletion, and an untracked file.
+fn setup_repo() -> PathBuf {
+    let dir = temp_dir("repo");
+    init_repo(&dir);
+
+    fs::write(dir.join("file.txt"), "one\n").unwrap();
+    fs::write(dir.join("staged.txt"), "staged\n").unwrap();
+    fs::write(dir.join("gone.txt"), "gone\n").unwrap();
+    run_git(&dir, &["add", "."]);
+    run_git(&dir, &["commit", "-qm", "init"]);
+
+    fs::write(dir.join("file.txt"), "one\ntwo\n").unwrap();
+    fs::write(dir.join("staged.txt"), "staged\nmore\n").unwrap();
+    run_git(&dir, &["add", "staged.txt"]);
+    fs::remove_file(dir.join("gone.txt")).unwrap();
+    fs::write(dir.join("new.txt"), "new\n").unwrap();
+
+    dir
+}
+
+fn unit(id: &str, files: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_: CommitType::Fix,
+        scope: Some("core".to_string()),
+        summary: "commit changes through the in-process git backend".to_string(),
+        body: vec!["Exercise native staging".to_string()],
+        files: files.iter().map(|file| file.to_string()).collect(),
+        hunks: Vec::new(),
+    }
+}
+
+fn request<'a>(repo: &'a PathBuf, plan: &'a [CommitUnit], diff: &'a str) -> ApplyRequest<'a> {
+    ApplyRequest {
+        repo,
+        backend: GitBackendKind::Native,
+        plan,
+        diff,
+        diff_mode: DiffMode::Worktree,
+        include_untracked: true,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    }
+}
+
+#[test]
+fn backend_for_selects_implementation() {
+    assert_eq!(backend_for(GitBackendKind::Cli).name(), "cli");
+    assert_eq!(backend_for(GitBackendKind::Native).name(), "native");
+}
+
+#[test]
+fn native_diff_matches_cli_for_tracked_changes() {
+    let repo = setup_repo();
+    for mode in [DiffMode::Worktree, DiffMode::Staged, DiffMode::All] {
+        let native = NativeBackend.diff(&repo, &DiffOptions::new(mode, false)).unwrap();
+        let cli = CliBackend.diff(&repo, &DiffOptions::new(mode, false)).unwrap();
+        assert_eq!(native, cli, "diff mode {mode:?}");
+    }
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn native_diff_matches_cli_for_staged_rename() {
+    let repo = setup_repo();
+    run_git(&repo, &["mv", "file.txt", "renamed.txt"]);
+    let native = NativeBackend.diff(&repo, &DiffOptions::new(DiffMode::Staged, false)).unwrap();
+    let cli = CliBackend.diff(&repo, &DiffOptions::new(DiffMode::Staged, false)).unwrap();
+    assert!(native.contains("rename from file.txt"));
+    assert_eq!(native, cli);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn native_diff_lists_untracked_files_relative_to_repo() {
+    let repo = setup_repo();
+    let diff = NativeBackend.diff(&repo, &DiffOptions::new(DiffMode::Worktree, true)).unwrap();
+    assert!(diff.contains("diff --git a/new.txt b/new.txt"));
+    assert!(diff.contains("+new"));
+    assert_eq!(NativeBackend.untracked_files(&repo).unwrap(), vec!["new.txt"]);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn native_backend_stages_and_commits() {
+    let repo = setup_repo();
+    run_git(&repo, &["reset", "-q"]);
+    let diff = NativeBackend.diff(&repo, &DiffOptions::new(DiffMode::Worktree, true)).unwrap();
+    let plan = vec![
+        unit("commit-1", &["file.txt", "gone.txt"]),
+        unit("commit-2", &["staged.txt", "new.txt"]),
+    ];
+
+    let results = apply_plan(request(&repo, &plan, &diff)).unwrap();
+    assert!(results.iter().all(|result| result.status == ApplyStatus::Applied));
+    assert_eq!(results[1].commit_hash.as_deref(), Some(run_git(&repo, &["rev-parse", "HEAD"]).as_str()));
+    assert_eq!(run_git(&repo, &["status", "--porcelain"]), "");
+    let files = run_git(&repo, &["show", "--name-status", "--pretty=", "HEAD~1"]);
+    assert!(files.contains("D\tgone.txt"));
+    let message = run_git(&repo, &["log", "-1", "--pretty=%B"]);
+    assert_eq!(
+        message,
+        "fix[core]: commit changes through the in-process git backend\n\nExercise native staging"
+    );
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn native_backend_falls_back_to_cli_for_hooks() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = setup_repo();
+    run_git(&repo, &["reset", "-q"]);
+    let hook = repo.join(".git/hooks/pre-commit");
+    fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    let diff = NativeBackend.diff(&repo, &DiffOptions::new(DiffMode::Worktree, true)).unwrap();
+    let plan = vec![unit("commit-1", &["file.txt"])];
+
+    let error = apply_plan(request(&repo, &plan, &diff)).unwrap_err();
+    assert!(matches!(error, GitError::HookFailed { .. }));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn native_diff_matches_cli_for_binary_and_large_files() {
+    let repo = setup_repo();
+    fs::write(repo.join("image.bin"), [0u8, 159, 146, 150, 0, 1]).unwrap();
+    fs::write(repo.join("big.txt"), "line\n".repeat(100)).unwrap();
+    run_git(&repo, &["add", "image.bin"]);
+
+    let options = DiffOptions {
+        mode: DiffMode::All,
+        include_untracked: true,
+        max_file_bytes: 256,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        range: None,
+    };
+    let native = NativeBackend.diff(&repo, &options).unwrap();
+    let cli = CliBackend.diff(&repo, &options).unwrap();
+    assert!(native.contains("atomc-omitted: kind=binary size=6"));
+    assert!(native.contains("atomc-omitted: kind=large size=500"));
+    assert_eq!(native, cli);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn native_diff_matches_cli_for_pathspec() {
+    let repo = setup_repo();
+    fs::create_dir_all(repo.join("docs")).unwrap();
+    fs::write(repo.join("docs/guide.md"), "guide\n").unwrap();
+    let options = DiffOptions {
+        pathspec: vec!["docs".to_string(), "file.txt".to_string()],
+        range: None,
+        ..DiffOptions::new(DiffMode::All, true)
+    };
+
+    let native = NativeBackend.diff(&repo, &options).unwrap();
+    let cli = CliBackend.diff(&repo, &options).unwrap();
+    assert!(native.contains("diff --git a/file.txt b/file.txt"));
+    assert!(native.contains("diff --git a/docs/guide.md b/docs/guide.md"));
+    assert!(!native.contains("staged.txt"));
+    assert!(!native.contains("new.txt"));
+    assert_eq!(native, cli);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn native_backend_stages_through_filter_drivers() {
+    let repo = setup_repo();
+    run_git(&repo, &["reset", "-q"]);
+    run_git(&repo, &["config", "filter.upper.clean", "tr a-z A-Z"]);
+    run_git(&repo, &["config", "filter.upper.smudge", "cat"]);
+    fs::write(repo.join(".gitattributes"), "file.txt filter=upper\n").unwrap();
+
+    NativeBackend.stage_files(&repo, &["file.txt".to_string()]).unwrap();
+    assert_eq!(run_git(&repo, &["show", ":file.txt"]), "ONE\nTWO");
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn native_diff_matches_cli_through_filter_drivers() {
+    let repo = setup_repo();
+    run_git(&repo, &["config", "filter.upper.clean", "tr a-z A-Z"]);
+    run_git(&repo, &["config", "filter.upper.smudge", "cat"]);
+    fs::write(repo.join(".gitattributes"), "file.txt filter=upper\n").unwrap();
+    let options = DiffOptions::new(DiffMode::Worktree, false);
+
+    let native = NativeBackend.diff(&repo, &options).unwrap();
+    assert_eq!(native, CliBackend.diff(&repo, &options).unwrap());
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn native_backend_signs_when_gpgsign_is_configured() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = setup_repo();
+    let gpg = repo.join(".git/fake-gpg");
+    fs::write(
+        &gpg,
+        "#!/bin/sh\ncat >/dev/null\necho '[GNUPG:] BEGIN_SIGNING' >&2\necho '[GNUPG:] SIG_CREATED ' >&2\n\
+         echo '-----BEGIN PGP SIGNATURE-----'\necho 'fake'\necho '-----END PGP SIGNATURE-----'\n",
+    )
+    .unwrap();
+    fs::set_permissions(&gpg, fs::Permissions::from_mode(0o755)).unwrap();
+    run_git(&repo, &["config", "gpg.program", gpg.to_str().unwrap()]);
+    run_git(&repo, &["config", "commit.gpgsign", "true"]);
+
+    let options = CommitOptions {
+        assisted_by: None,
+        installed_hooks: Vec::new(),
+        no_verify: true,
+        sign: false,
+        signing_key: None,
+    };
+    let hash = NativeBackend.commit(&repo, &unit("commit-1", &["staged.txt"]), &options).unwrap();
+    assert!(run_git(&repo, &["cat-file", "commit", &hash]).contains("gpgsig "));
+
+    fs::remove_dir_all(&repo).ok();
+}
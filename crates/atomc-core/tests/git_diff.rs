@@ -1,5 +1,10 @@
 use atomc_core::config::DiffMode;
-use atomc_core::git::compute_diff;
+use atomc_core::hash::diff_hash;
+use atomc_core::git::{
+    compute_diff, compute_diff_with, condense_diff, file_hashes, filter_diff, parse_diff_files,
+    DiffOptions, GitError, PathFilter,
+};
+use atomc_core::types::{DiffFile, FileKind, FileStatus};
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
@@ -82,3 +87,215 @@ fn compute_diff_all_includes_all_changes() {
 
     fs::remove_dir_all(&repo).ok();
 }
+
+fn load_fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../tests/fixtures/diffs")
+        .join(name);
+    fs::read_to_string(path).expect("fixture")
+}
+
+#[test]
+fn parse_diff_files_reads_renames() {
+    let files = parse_diff_files(&load_fixture("rename.diff"));
+    assert_eq!(
+        files,
+        vec![DiffFile {
+            path: "new_name.rs".to_string(),
+            old_path: Some("old_name.rs".to_string()),
+            status: FileStatus::Renamed,
+            kind: FileKind::Text,
+            size: None,
+        }]
+    );
+}
+
+#[test]
+fn parse_diff_files_reads_deletions() {
+    let files = parse_diff_files(&load_fixture("deletion.diff"));
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "removed.txt");
+    assert_eq!(files[0].status, FileStatus::Deleted);
+}
+
+#[test]
+fn parse_diff_files_reads_mode_changes() {
+    let files = parse_diff_files(&load_fixture("mode_change.diff"));
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "run.sh");
+    assert_eq!(files[0].status, FileStatus::ModeChanged);
+}
+
+#[test]
+fn parse_diff_files_reads_quoted_and_spaced_paths() {
+    let files = parse_diff_files(&load_fixture("quoted_paths.diff"));
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, vec!["tab\tné.txt", "with space.txt"]);
+    assert!(files.iter().all(|file| file.status == FileStatus::Modified));
+}
+
+#[test]
+fn compute_diff_uses_repo_relative_untracked_paths() {
+    let repo = setup_repo();
+    let diff = compute_diff(&repo, DiffMode::Worktree, true).unwrap();
+    let files = parse_diff_files(&diff);
+    let untracked = files
+        .iter()
+        .find(|file| file.path == "untracked.txt")
+        .expect("untracked file");
+    assert_eq!(untracked.status, FileStatus::Added);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn condense_diff_summarizes_binary_and_lfs_files() {
+    let repo = temp_dir("missing");
+    let diff = condense_diff(&repo, &load_fixture("binary_lfs.diff"), 200_000);
+    assert!(diff.starts_with(
+        "diff --git a/assets/logo.png b/assets/logo.png\nnew file mode 100644\natomc-omitted: kind=binary\n"
+    ));
+    assert!(diff.contains(
+        "diff --git a/assets/video.mp4 b/assets/video.mp4\natomc-omitted: kind=lfs_pointer size=2097152\n"
+    ));
+    assert!(!diff.contains("oid sha256"));
+    assert!(diff.contains("+pub fn stop() {}"));
+
+    let files = parse_diff_files(&diff);
+    let kinds: Vec<(FileKind, Option<u64>)> = files.iter().map(|file| (file.kind, file.size)).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (FileKind::Binary, None),
+            (FileKind::LfsPointer, Some(2_097_152)),
+            (FileKind::Text, None),
+        ]
+    );
+    assert_eq!(files[0].status, FileStatus::Added);
+}
+
+#[test]
+fn parse_diff_files_marks_uncondensed_binaries() {
+    let files = parse_diff_files(&load_fixture("binary_lfs.diff"));
+    assert_eq!(files[0].kind, FileKind::Binary);
+    assert_eq!(files[1].kind, FileKind::Text);
+}
+
+#[test]
+fn compute_diff_summarizes_files_over_threshold() {
+    let repo = setup_repo();
+    fs::write(repo.join("large.log"), "entry\n".repeat(1000)).unwrap();
+    fs::write(repo.join("blob.bin"), [0u8, 1, 2, 3]).unwrap();
+    let options = DiffOptions {
+        mode: DiffMode::Worktree,
+        include_untracked: true,
+        max_file_bytes: 1024,
+        recurse_submodules: false,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        range: None,
+    };
+
+    let diff = compute_diff_with(&repo, &options).unwrap();
+    assert!(diff.contains("diff --git a/large.log b/large.log\nnew file mode 100644\natomc-omitted: kind=large size=6000\n"));
+    assert!(diff.contains("atomc-omitted: kind=binary size=4"));
+    assert!(!diff.contains("+entry"));
+    assert!(diff.contains("+untracked"));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+fn paths(diff: &str) -> Vec<String> {
+    parse_diff_files(diff).into_iter().map(|file| file.path).collect()
+}
+
+#[test]
+fn filter_diff_drops_excluded_paths() {
+    let filter = PathFilter {
+        include: Vec::new(),
+        exclude: vec!["*.lock".to_string(), "vendor/".to_string(), "**/*.pb.rs".to_string()],
+    };
+    let filtered = filter_diff(&load_fixture("path_filters.diff"), &filter).unwrap();
+
+    assert_eq!(paths(&filtered.diff), vec!["src/lib.rs"]);
+    assert_eq!(
+        filtered.excluded,
+        vec!["Cargo.lock", "src/proto/api.pb.rs", "vendor/zlib/zlib.h"]
+    );
+}
+
+#[test]
+fn filter_diff_keeps_only_included_paths() {
+    let filter = PathFilter {
+        include: vec!["src".to_string()],
+        exclude: vec!["proto".to_string()],
+    };
+    let filtered = filter_diff(&load_fixture("path_filters.diff"), &filter).unwrap();
+
+    assert_eq!(paths(&filtered.diff), vec!["src/lib.rs"]);
+    assert_eq!(
+        filtered.excluded,
+        vec!["Cargo.lock", "src/proto/api.pb.rs", "vendor/zlib/zlib.h"]
+    );
+}
+
+#[test]
+fn filter_diff_rejects_invalid_patterns() {
+    let filter = PathFilter {
+        include: vec!["src/[".to_string()],
+        exclude: Vec::new(),
+    };
+    let err = filter_diff(&load_fixture("path_filters.diff"), &filter).unwrap_err();
+    assert!(matches!(err, GitError::InvalidPattern { pattern, .. } if pattern == "src/["));
+}
+
+#[test]
+fn compute_diff_applies_path_filter() {
+    let repo = setup_repo();
+    let options = DiffOptions {
+        filter: PathFilter {
+            include: Vec::new(),
+            exclude: vec!["untracked.txt".to_string()],
+        },
+        ..DiffOptions::new(DiffMode::All, true)
+    };
+
+    let diff = compute_diff_with(&repo, &options).unwrap();
+    assert_eq!(paths(&diff), vec!["tracked.txt", "staged.txt"]);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn file_hashes_cover_each_file_block() {
+    let diff = load_fixture("path_filters.diff");
+    let hashes = file_hashes(&diff);
+    assert_eq!(
+        hashes.keys().collect::<Vec<_>>(),
+        vec!["Cargo.lock", "src/lib.rs", "src/proto/api.pb.rs", "vendor/zlib/zlib.h"]
+    );
+
+    let edited = diff.replace("+pub mod proto;", "+pub mod protos;");
+    let changed = file_hashes(&edited);
+    assert_ne!(hashes["src/lib.rs"], changed["src/lib.rs"]);
+    assert_eq!(hashes["Cargo.lock"], changed["Cargo.lock"]);
+}
+
+#[test]
+fn file_hashes_join_staged_and_unstaged_blocks() {
+    let repo = setup_repo();
+    run_git(&repo, &["add", "tracked.txt"]);
+    fs::write(repo.join("tracked.txt"), "one\ntwo\nthree\n").unwrap();
+    let diff = compute_diff(&repo, DiffMode::All, false).unwrap();
+    assert_eq!(diff.matches("diff --git a/tracked.txt").count(), 2);
+
+    let hashes = file_hashes(&diff);
+    let tracked_only: String = diff
+        .split("diff --git ")
+        .filter(|block| block.starts_with("a/tracked.txt"))
+        .map(|block| format!("diff --git {block}"))
+        .collect();
+    assert_eq!(hashes["tracked.txt"], diff_hash(&tracked_only));
+
+    fs::remove_dir_all(&repo).ok();
+}
@@ -0,0 +1,255 @@
+mod support;
+
+use atomc_core::config::{DiffMode, GitBackendKind};
+use atomc_core::git::{
+    backend_for, carried_unit, compute_diff_with, compute_range_diff, filter_diff, range_authors, resolve_base,
+    resolve_range, split_range, DiffOptions, GitError, PathFilter, SplitRequest,
+};
+use atomc_core::types::{ApplyStatus, CommitType, CommitUnit};
+use std::fs;
+use std::path::PathBuf;
+use support::{init_repo, run_git, temp_dir};
+
+/// Repo with a base commit followed by two WIP commits that mix concerns.
+fn setup_repo() -> PathBuf {
+    let dir = temp_dir("repo");
+    init_repo(&dir);
+
+    fs::write(dir.join("lib.txt"), "one\n").unwrap();
+    fs::write(dir.join("old.txt"), "old\n").unwrap();
+    run_git(&dir, &["add", "."]);
+    run_git(&dir, &["commit", "-qm", "init"]);
+    run_git(&dir, &["tag", "base"]);
+
+    fs::write(dir.join("lib.txt"), "one\ntwo\n").unwrap();
+    fs::write(dir.join("docs.md"), "# docs\n").unwrap();
+    run_git(&dir, &["add", "."]);
+    run_git(&dir, &["commit", "-qm", "wip"]);
+
+    fs::remove_file(dir.join("old.txt")).unwrap();
+    fs::write(dir.join("docs.md"), "# docs\nmore\n").unwrap();
+    run_git(&dir, &["add", "-A"]);
+    run_git(&dir, &["commit", "-qm", "wip again"]);
+
+    dir
+}
+
+fn unit(id: &str, type_: CommitType, files: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_,
+        scope: Some("core".to_string()),
+        summary: "rebuild work in progress history as an atomic commit".to_string(),
+        body: vec!["Split from a WIP range".to_string()],
+        files: files.iter().map(|file| file.to_string()).collect(),
+        hunks: Vec::new(),
+    }
+}
+
+#[test]
+fn resolve_range_defaults_head() {
+    let repo = setup_repo();
+    let range = resolve_range(&repo, "base").unwrap();
+    assert_eq!(range.base, run_git(&repo, &["rev-parse", "base"]));
+    assert_eq!(range.head, run_git(&repo, &["rev-parse", "HEAD"]));
+
+    let error = resolve_range(&repo, "HEAD..base").unwrap_err();
+    assert!(matches!(error, GitError::InvalidRange { .. }));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn split_range_rebuilds_history_on_new_branch() {
+    let repo = setup_repo();
+    let original_head = run_git(&repo, &["rev-parse", "HEAD"]);
+    let range = resolve_range(&repo, "base..HEAD").unwrap();
+    let diff = compute_range_diff(&repo, &range).unwrap();
+    let plan = vec![
+        unit("commit-1", CommitType::Feat, &["lib.txt", "old.txt"]),
+        unit("commit-2", CommitType::Docs, &["docs.md"]),
+    ];
+
+    let results = split_range(SplitRequest {
+        repo: &repo,
+        range: &range,
+        plan: &plan,
+        diff: &diff,
+        excluded: &[],
+        target_ref: "split",
+        assisted_by: Some("qwen2.5-coder:14b"),
+    })
+    .unwrap();
+
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|result| result.status == ApplyStatus::Applied));
+    assert_eq!(run_git(&repo, &["rev-parse", "HEAD"]), original_head);
+    assert_eq!(
+        run_git(&repo, &["rev-parse", "split^{tree}"]),
+        run_git(&repo, &["rev-parse", "HEAD^{tree}"])
+    );
+    assert_eq!(run_git(&repo, &["rev-list", "--count", "base..split"]), "2");
+    let message = run_git(&repo, &["log", "-1", "--pretty=%B", "split"]);
+    assert!(message.starts_with("docs[core]: "));
+    assert!(message.contains("Assisted by: qwen2.5-coder:14b"));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn split_range_commits_excluded_paths_in_their_own_unit() {
+    let repo = setup_repo();
+    let range = resolve_range(&repo, "base..HEAD").unwrap();
+    let diff = compute_range_diff(&repo, &range).unwrap();
+    let excluded = vec!["docs.md".to_string()];
+    let filter = PathFilter {
+        include: Vec::new(),
+        exclude: excluded.clone(),
+    };
+    let diff = filter_diff(&diff, &filter).unwrap().diff;
+    let plan = vec![
+        unit("commit-1", CommitType::Feat, &["lib.txt"]),
+        unit("commit-2", CommitType::Refactor, &["old.txt"]),
+    ];
+
+    let results = split_range(SplitRequest {
+        repo: &repo,
+        range: &range,
+        plan: &plan,
+        diff: &diff,
+        excluded: &excluded,
+        target_ref: "split",
+        assisted_by: None,
+    })
+    .unwrap();
+
+    let ids: Vec<&str> = results.iter().map(|result| result.id.as_str()).collect();
+    assert_eq!(ids, vec!["commit-1", "commit-2", "carried-paths"]);
+    assert_eq!(
+        run_git(&repo, &["rev-parse", "split^{tree}"]),
+        run_git(&repo, &["rev-parse", "HEAD^{tree}"])
+    );
+    let last = run_git(&repo, &["diff-tree", "--no-commit-id", "--name-only", "-r", "split"]);
+    assert_eq!(last, "docs.md");
+    assert!(run_git(&repo, &["log", "-1", "--pretty=%s", "split"]).starts_with("chore: "));
+    assert!(carried_unit(&[]).is_none());
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn range_authors_lists_each_author_once() {
+    let repo = setup_repo();
+    let range = resolve_range(&repo, "base..HEAD").unwrap();
+    let author = format!(
+        "{} <{}>",
+        run_git(&repo, &["config", "user.name"]),
+        run_git(&repo, &["config", "user.email"])
+    );
+
+    assert_eq!(range_authors(&repo, &range).unwrap(), vec![author]);
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn split_range_refuses_incomplete_plan() {
+    let repo = setup_repo();
+    let range = resolve_range(&repo, "base..HEAD").unwrap();
+    let diff = compute_range_diff(&repo, &range).unwrap();
+    let plan = vec![unit("commit-1", CommitType::Feat, &["lib.txt"])];
+
+    let error = split_range(SplitRequest {
+        repo: &repo,
+        range: &range,
+        plan: &plan,
+        diff: &diff,
+        excluded: &[],
+        target_ref: "split",
+        assisted_by: None,
+    })
+    .unwrap_err();
+
+    assert!(matches!(error, GitError::SplitTreeMismatch { .. }));
+    let branches = run_git(&repo, &["branch", "--list", "split"]);
+    assert!(branches.is_empty());
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn split_range_rejects_existing_branch() {
+    let repo = setup_repo();
+    run_git(&repo, &["branch", "split"]);
+    let range = resolve_range(&repo, "base").unwrap();
+    let diff = compute_range_diff(&repo, &range).unwrap();
+    let plan = vec![unit("commit-1", CommitType::Feat, &["lib.txt"])];
+
+    let error = split_range(SplitRequest {
+        repo: &repo,
+        range: &range,
+        plan: &plan,
+        diff: &diff,
+        excluded: &[],
+        target_ref: "split",
+        assisted_by: None,
+    })
+    .unwrap_err();
+
+    assert!(matches!(error, GitError::RefExists { .. }));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn resolve_base_starts_at_merge_base() {
+    let repo = setup_repo();
+    run_git(&repo, &["checkout", "-qb", "side", "base"]);
+    fs::write(repo.join("side.txt"), "side\n").unwrap();
+    run_git(&repo, &["add", "."]);
+    run_git(&repo, &["commit", "-qm", "side"]);
+    run_git(&repo, &["checkout", "-q", "-"]);
+
+    let range = resolve_base(&repo, "side").unwrap();
+    assert_eq!(range.base, run_git(&repo, &["rev-parse", "base"]));
+    assert_eq!(range.head, run_git(&repo, &["rev-parse", "HEAD"]));
+
+    let error = resolve_base(&repo, "HEAD").unwrap_err();
+    assert!(matches!(error, GitError::InvalidRange { .. }));
+    let error = resolve_base(&repo, "missing").unwrap_err();
+    assert!(matches!(error, GitError::InvalidRange { .. }));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn revision_diff_modes_ignore_worktree_and_untracked() {
+    let repo = setup_repo();
+    fs::write(repo.join("lib.txt"), "uncommitted\n").unwrap();
+    fs::write(repo.join("new.txt"), "untracked\n").unwrap();
+    let range = resolve_range(&repo, "base..HEAD").unwrap();
+    let options = DiffOptions {
+        range: Some(range.clone()),
+        ..DiffOptions::new(DiffMode::Range, true)
+    };
+
+    let diff = compute_diff_with(&repo, &options).unwrap();
+    assert_eq!(diff, compute_range_diff(&repo, &range).unwrap());
+    assert!(!diff.contains("uncommitted"));
+    assert!(!diff.contains("new.txt"));
+    assert_eq!(backend_for(GitBackendKind::Native).diff(&repo, &options).unwrap(), diff);
+
+    let scoped = DiffOptions {
+        pathspec: vec!["docs.md".to_string()],
+        ..options.clone()
+    };
+    let diff = compute_diff_with(&repo, &scoped).unwrap();
+    assert!(diff.contains("docs.md"));
+    assert!(!diff.contains("lib.txt"));
+
+    let missing = DiffOptions::new(DiffMode::Base, false);
+    let error = compute_diff_with(&repo, &missing).unwrap_err();
+    assert!(matches!(error, GitError::InvalidRange { .. }));
+
+    fs::remove_dir_all(&repo).ok();
+}
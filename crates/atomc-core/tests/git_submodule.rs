@@ -0,0 +1,332 @@
+mod support;
+
+use atomc_core::config::{DiffMode, GitBackendKind};
+use atomc_core::git::{
+    apply_plan, backend_for, parse_diff_files, plan_submodule_units, submodule_changes, ApplyRequest,
+    DiffOptions, GitError, HookPolicy, PathFilter, SubmoduleChange,
+};
+use atomc_core::hash::diff_hash;
+use atomc_core::semantic::{validate_commit_units, ScopePolicy};
+use atomc_core::types::{ApplyStatus, CommitType, CommitUnit, FileKind, FileStatus};
+use std::fs;
+use std::path::{Path, PathBuf};
+use support::{configure_identity, init_repo, run_git, temp_dir};
+
+fn init_committed_repo(dir: &Path, file: &str) {
+    init_repo(dir);
+    fs::write(dir.join(file), "one\n").unwrap();
+    run_git(dir, &["add", "."]);
+    run_git(dir, &["commit", "-qm", "init"]);
+}
+
+/// Superproject with `vendor/lib` registered as a submodule.
+fn setup_superproject() -> (PathBuf, PathBuf) {
+    let root = temp_dir("root");
+    let upstream = root.join("lib");
+    let repo = root.join("super");
+    init_committed_repo(&upstream, "lib.txt");
+    init_committed_repo(&repo, "app.txt");
+    run_git(&repo, &["submodule", "add", "-q", upstream.to_str().unwrap(), "vendor/lib"]);
+    run_git(&repo, &["commit", "-qm", "add submodule"]);
+    configure_identity(&repo.join("vendor/lib"));
+    (root, repo)
+}
+
+fn load_fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../tests/fixtures/diffs")
+        .join(name);
+    fs::read_to_string(path).expect("fixture")
+}
+
+fn unit(id: &str, files: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_: CommitType::Feat,
+        scope: Some("app".to_string()),
+        summary: "extend the application with a second line of output".to_string(),
+        body: vec!["Print one more line".to_string()],
+        files: files.iter().map(|file| file.to_string()).collect(),
+        hunks: Vec::new(),
+    }
+}
+
+fn options(recurse_submodules: bool) -> DiffOptions {
+    DiffOptions {
+        recurse_submodules,
+        ..DiffOptions::new(DiffMode::All, true)
+    }
+}
+
+fn request<'a>(
+    repo: &'a Path,
+    backend: GitBackendKind,
+    plan: &'a [CommitUnit],
+    diff: &'a str,
+    recurse_submodules: bool,
+) -> ApplyRequest<'a> {
+    ApplyRequest {
+        repo,
+        backend,
+        plan,
+        diff,
+        diff_mode: DiffMode::All,
+        include_untracked: true,
+        max_file_diff_bytes: 200_000,
+        recurse_submodules,
+        filter: PathFilter::default(),
+        pathspec: Vec::new(),
+        expected_diff_hash: Some(diff_hash(diff)),
+        expected_file_hashes: None,
+        strict_diff_hash: false,
+        cleanup_on_error: false,
+        assisted_by: None,
+        hooks: HookPolicy::FailFast,
+        sign: false,
+        signing_key: None,
+        verify_command: None,
+        rollback_on_verify_failure: false,
+    }
+}
+
+#[test]
+fn parse_diff_files_marks_gitlinks() {
+    let files = parse_diff_files(&load_fixture("submodule.diff"));
+    let kinds: Vec<(&str, FileKind, FileStatus)> = files
+        .iter()
+        .map(|file| (file.path.as_str(), file.kind, file.status))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ("vendor/lib", FileKind::Submodule, FileStatus::Modified),
+            (".gitmodules", FileKind::Text, FileStatus::Modified),
+            ("tools/gen", FileKind::Submodule, FileStatus::Added),
+            ("src/lib.rs", FileKind::Text, FileStatus::Modified),
+        ]
+    );
+}
+
+#[test]
+fn submodule_changes_reads_commits() {
+    let changes = submodule_changes(&load_fixture("submodule.diff"));
+    assert_eq!(
+        changes,
+        vec![
+            SubmoduleChange {
+                path: "vendor/lib".to_string(),
+                from: Some("5ab4ee0e10caee46ca3cc7be8097dd3e556434e3".to_string()),
+                to: Some("83ff1d7abaa5f12abe55af6241e16dd9573ba75e".to_string()),
+                dirty: true,
+            },
+            SubmoduleChange {
+                path: "tools/gen".to_string(),
+                from: None,
+                to: Some("1b6a1cc82d91e1420d5b2166fcdf4c18782b4649".to_string()),
+                dirty: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn plan_submodule_units_isolates_gitlinks() {
+    let mut plan = vec![
+        unit("commit-1", &["src/lib.rs", "vendor/lib", ".gitmodules"]),
+        unit("commit-2", &["tools/gen"]),
+    ];
+    plan_submodule_units(&mut plan, &load_fixture("submodule.diff"));
+
+    let ids: Vec<&str> = plan.iter().map(|unit| unit.id.as_str()).collect();
+    assert_eq!(ids, vec!["submodule-1", "submodule-2", "commit-1"]);
+    assert_eq!(plan[0].summary, "bump vendor/lib to 83ff1d7abaa5f12abe55af6241e16dd9573ba75e");
+    assert_eq!(plan[0].files, vec!["vendor/lib"]);
+    assert!(matches!(plan[0].type_, CommitType::Chore));
+    assert_eq!(plan[1].files, vec!["tools/gen", ".gitmodules"]);
+    assert!(plan[1].summary.starts_with("add tools/gen submodule at 1b6a1cc"));
+    assert_eq!(plan[2].files, vec!["src/lib.rs"]);
+    assert!(validate_commit_units(&plan, ScopePolicy::Require).is_ok());
+}
+
+#[test]
+fn plan_submodule_units_shortens_sha_for_long_paths() {
+    let diff = "diff --git a/third_party/very/long/path/to/library b/third_party/very/long/path/to/library\n\
+index 5ab4ee0..83ff1d7 160000\n\
+--- a/third_party/very/long/path/to/library\n\
++++ b/third_party/very/long/path/to/library\n\
+@@ -1 +1 @@\n\
+-Subproject commit 5ab4ee0e10caee46ca3cc7be8097dd3e556434e3\n\
++Subproject commit 83ff1d7abaa5f12abe55af6241e16dd9573ba75e\n";
+    let mut plan = Vec::new();
+    plan_submodule_units(&mut plan, diff);
+    assert_eq!(plan.len(), 1);
+    let summary = &plan[0].summary;
+    assert!(summary.starts_with("bump third_party/very/long/path/to/library to 83ff1d7"));
+    assert!((50..=72).contains(&summary.chars().count()), "{summary}");
+}
+
+#[test]
+fn plan_submodule_units_drops_dirty_only_gitlinks() {
+    let (root, repo) = setup_superproject();
+    fs::write(repo.join("vendor/lib/lib.txt"), "one\ntwo\n").unwrap();
+    fs::write(repo.join("app.txt"), "one\ntwo\n").unwrap();
+    let diff = backend_for(GitBackendKind::Cli).diff(&repo, &options(false)).unwrap();
+    assert!(diff.contains("-dirty"));
+
+    let mut plan = vec![unit("commit-1", &["app.txt", "vendor/lib"])];
+    plan_submodule_units(&mut plan, &diff);
+    assert_eq!(plan.len(), 1);
+    assert_eq!(plan[0].files, vec!["app.txt"]);
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn diff_includes_untracked_nested_repositories() {
+    let (root, repo) = setup_superproject();
+    init_committed_repo(&repo.join("nested"), "nested.txt");
+    let head = run_git(&repo.join("nested"), &["rev-parse", "HEAD"]);
+
+    for kind in [GitBackendKind::Cli, GitBackendKind::Native] {
+        let diff = backend_for(kind).diff(&repo, &options(false)).unwrap();
+        assert!(
+            diff.contains(&format!("diff --git a/nested b/nested\nnew file mode 160000\nindex 0000000..{}", &head[..7])),
+            "{kind:?}: {diff}"
+        );
+        assert!(diff.contains(&format!("+Subproject commit {head}")));
+        let file = parse_diff_files(&diff).into_iter().find(|file| file.path == "nested").unwrap();
+        assert_eq!(file.kind, FileKind::Submodule);
+        assert_eq!(file.status, FileStatus::Added);
+    }
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn apply_commits_submodule_bump() {
+    let (root, repo) = setup_superproject();
+    let submodule = repo.join("vendor/lib");
+    fs::write(submodule.join("lib.txt"), "one\ntwo\n").unwrap();
+    run_git(&submodule, &["commit", "-qam", "two"]);
+    let head = run_git(&submodule, &["rev-parse", "HEAD"]);
+    fs::write(repo.join("app.txt"), "one\ntwo\n").unwrap();
+
+    for kind in [GitBackendKind::Cli, GitBackendKind::Native] {
+        run_git(&repo, &["reset", "-q"]);
+        let diff = backend_for(kind).diff(&repo, &options(false)).unwrap();
+        let mut plan = vec![unit("commit-1", &["app.txt", "vendor/lib"])];
+        plan_submodule_units(&mut plan, &diff);
+        assert_eq!(plan.len(), 2);
+
+        let results = apply_plan(request(&repo, kind, &plan, &diff, false)).unwrap();
+        assert!(results.iter().all(|result| result.status == ApplyStatus::Applied));
+        let tree = run_git(&repo, &["ls-tree", "HEAD~1", "vendor/lib"]);
+        assert!(tree.starts_with(&format!("160000 commit {head}")), "{tree}");
+        let message = run_git(&repo, &["log", "-1", "--pretty=%s", "HEAD~1"]);
+        assert_eq!(message, format!("chore[submodules]: bump vendor/lib to {head}"));
+        assert_eq!(run_git(&repo, &["status", "--porcelain"]), "");
+
+        run_git(&repo, &["reset", "-q", "--soft", "HEAD~2"]);
+    }
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn recursive_diff_prefixes_submodule_paths() {
+    let (root, repo) = setup_superproject();
+    fs::write(repo.join("vendor/lib/lib.txt"), "one\ntwo\n").unwrap();
+    fs::write(repo.join("vendor/lib/new.txt"), "new\n").unwrap();
+
+    let cli = backend_for(GitBackendKind::Cli).diff(&repo, &options(true)).unwrap();
+    let native = backend_for(GitBackendKind::Native).diff(&repo, &options(true)).unwrap();
+    assert!(cli.contains("diff --git a/vendor/lib/lib.txt b/vendor/lib/lib.txt"));
+    assert!(cli.contains("+++ b/vendor/lib/new.txt"));
+    assert_eq!(cli, native);
+
+    let paths: Vec<String> = parse_diff_files(&cli).into_iter().map(|file| file.path).collect();
+    assert_eq!(paths, vec!["vendor/lib", "vendor/lib/lib.txt", "vendor/lib/new.txt"]);
+    assert!(!backend_for(GitBackendKind::Cli)
+        .diff(&repo, &options(false))
+        .unwrap()
+        .contains("vendor/lib/lib.txt"));
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn recursive_apply_commits_inside_submodule() {
+    let (root, repo) = setup_superproject();
+    let submodule = repo.join("vendor/lib");
+    fs::write(submodule.join("lib.txt"), "one\ntwo\n").unwrap();
+    fs::write(repo.join("app.txt"), "one\ntwo\n").unwrap();
+    let before = run_git(&submodule, &["rev-parse", "HEAD"]);
+
+    let diff = backend_for(GitBackendKind::Cli).diff(&repo, &options(true)).unwrap();
+    let mut plan = vec![
+        unit("commit-1", &["vendor/lib/lib.txt"]),
+        unit("commit-2", &["app.txt"]),
+    ];
+    plan_submodule_units(&mut plan, &diff);
+    assert_eq!(plan.len(), 2);
+
+    let results = apply_plan(request(&repo, GitBackendKind::Cli, &plan, &diff, true)).unwrap();
+    assert!(results.iter().all(|result| result.status == ApplyStatus::Applied));
+    assert_eq!(run_git(&submodule, &["rev-parse", "HEAD~1"]), before);
+    assert_eq!(results[0].commit_hash.as_deref(), Some(run_git(&submodule, &["rev-parse", "HEAD"]).as_str()));
+    assert_eq!(run_git(&submodule, &["status", "--porcelain"]), "");
+    let files = run_git(&repo, &["show", "--name-only", "--pretty=", "HEAD"]);
+    assert_eq!(files, "app.txt");
+    // The superproject still points at the old submodule commit.
+    assert_eq!(run_git(&repo, &["status", "--porcelain"]), "M vendor/lib");
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn recursive_apply_rejects_units_spanning_repositories() {
+    let (root, repo) = setup_superproject();
+    fs::write(repo.join("vendor/lib/lib.txt"), "one\ntwo\n").unwrap();
+    fs::write(repo.join("app.txt"), "one\ntwo\n").unwrap();
+
+    let diff = backend_for(GitBackendKind::Cli).diff(&repo, &options(true)).unwrap();
+    let plan = vec![unit("commit-1", &["vendor/lib/lib.txt", "app.txt"])];
+    let error = apply_plan(request(&repo, GitBackendKind::Cli, &plan, &diff, true)).unwrap_err();
+    assert!(matches!(
+        error,
+        GitError::SubmoduleMixed { ref id, ref submodule } if id == "commit-1" && submodule == "vendor/lib"
+    ));
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn recursive_diff_scopes_pathspec_to_submodules() {
+    let (root, repo) = setup_superproject();
+    fs::write(repo.join("app.txt"), "one\ntwo\n").unwrap();
+    fs::write(repo.join("vendor/lib/lib.txt"), "one\ntwo\n").unwrap();
+    fs::write(repo.join("vendor/lib/new.txt"), "new\n").unwrap();
+
+    for backend in [GitBackendKind::Cli, GitBackendKind::Native] {
+        let scoped = |pathspec: &[&str]| {
+            let options = DiffOptions {
+                pathspec: pathspec.iter().map(|spec| spec.to_string()).collect(),
+                range: None,
+                ..options(true)
+            };
+            let diff = backend_for(backend).diff(&repo, &options).unwrap();
+            parse_diff_files(&diff)
+                .into_iter()
+                .map(|file| file.path)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scoped(&["app.txt"]), vec!["app.txt"]);
+        assert_eq!(scoped(&["vendor/lib/new.txt"]), vec!["vendor/lib/new.txt"]);
+        assert_eq!(
+            scoped(&["vendor"]),
+            vec!["vendor/lib", "vendor/lib/lib.txt", "vendor/lib/new.txt"]
+        );
+    }
+
+    fs::remove_dir_all(&root).ok();
+}
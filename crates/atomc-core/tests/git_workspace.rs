@@ -0,0 +1,70 @@
+mod support;
+
+use atomc_core::git::discover_repos;
+use std::fs;
+use support::temp_dir;
+
+#[test]
+fn discover_repos_stops_at_repositories_and_skips_hidden_dirs() {
+    let root = temp_dir("discover");
+    for repo in ["services/api", "services/api/vendor/lib", "web", ".cache/tool"] {
+        fs::create_dir_all(root.join(repo).join(".git")).unwrap();
+    }
+    fs::create_dir_all(root.join("docs")).unwrap();
+
+    let repos = discover_repos(&root).unwrap().repos;
+    assert_eq!(repos, vec![root.join("services/api"), root.join("web")]);
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn discover_repos_returns_root_when_it_is_a_repository() {
+    let root = temp_dir("root");
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("nested/.git")).unwrap();
+
+    assert_eq!(discover_repos(&root).unwrap().repos, vec![root.clone()]);
+    assert!(discover_repos(&root.join("missing")).is_err());
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn discover_repos_skips_build_dirs_and_stops_at_max_depth() {
+    let root = temp_dir("discover-skip");
+    for repo in ["target/debug/build/dep", "node_modules/pkg", "web/vendor/lib", "a/b/c/d", "a/b/c/d/e/deep"] {
+        fs::create_dir_all(root.join(repo).join(".git")).unwrap();
+    }
+    fs::create_dir_all(root.join("x/y/z/w/too-deep/.git")).unwrap();
+
+    let repos = discover_repos(&root).unwrap().repos;
+    assert_eq!(repos, vec![root.join("a/b/c/d")]);
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[cfg(unix)]
+#[test]
+fn discover_repos_lists_unreadable_dirs_instead_of_failing() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = temp_dir("discover-unreadable");
+    fs::create_dir_all(root.join("api/.git")).unwrap();
+    let locked = root.join("locked");
+    fs::create_dir_all(locked.join("inner")).unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+    let discovery = discover_repos(&root);
+    let readable = fs::read_dir(&locked).is_ok();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    let discovery = discovery.unwrap();
+    assert_eq!(discovery.repos, vec![root.join("api")]);
+    // Permissions do not stop root, so there is nothing unreadable then.
+    if !readable {
+        assert_eq!(discovery.unreadable.len(), 1);
+        assert_eq!(discovery.unreadable[0].0, locked);
+    }
+
+    fs::remove_dir_all(&root).ok();
+}
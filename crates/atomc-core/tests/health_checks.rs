@@ -0,0 +1,246 @@
+mod support;
+
+use atomc_core::config::{BackendConfig, ConfigError, ResolvedConfig, Runtime};
+use atomc_core::health::{self, CheckStatus, HealthCheck, HealthReport};
+use atomc_core::schema::{validate_schema, SchemaKind};
+use axum::{routing::get, Json, Router};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use support::{init_repo, run_git, temp_dir};
+
+fn setup_repo() -> PathBuf {
+    let dir = temp_dir("repo");
+    init_repo(&dir);
+    fs::write(dir.join("file.txt"), "one\n").unwrap();
+    run_git(&dir, &["add", "file.txt"]);
+    run_git(&dir, &["commit", "-qm", "init"]);
+    dir
+}
+
+async fn spawn_runtime(tags: Value, version: Value) -> (String, oneshot::Sender<()>) {
+    let app = Router::new()
+        .route("/api/tags", get(move || async move { Json(tags) }))
+        .route("/api/version", get(move || async move { Json(version) }))
+        .route(
+            "/v1/models",
+            get(|| async { Json(json!({ "object": "list", "data": [{ "id": "qwen2.5-coder" }] })) }),
+        );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+    (format!("http://{addr}"), shutdown_tx)
+}
+
+fn config_for(runtime: Runtime, url: &str, model: &str) -> ResolvedConfig {
+    ResolvedConfig {
+        runtime,
+        ollama_url: url.to_string(),
+        model: model.to_string(),
+        ..ResolvedConfig::defaults()
+    }
+}
+
+fn find<'a>(checks: &'a [HealthCheck], name: &str) -> &'a HealthCheck {
+    checks
+        .iter()
+        .find(|check| check.name == name)
+        .unwrap_or_else(|| panic!("missing {name} check"))
+}
+
+#[tokio::test]
+async fn ollama_checks_pass_when_model_is_installed() {
+    let tags = json!({ "models": [{ "name": "deepseek-coder:latest" }, { "name": "llama3:8b" }] });
+    let (url, shutdown) = spawn_runtime(tags, json!({ "version": "0.6.2" })).await;
+
+    let checks = health::check_runtime(&config_for(Runtime::Ollama, &url, "deepseek-coder")).await;
+    for name in ["runtime", "model", "schema_support"] {
+        let check = find(&checks, name);
+        assert_eq!(check.status, CheckStatus::Ok, "{name}: {}", check.message);
+        assert!(check.fix.is_none());
+    }
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn ollama_missing_model_suggests_pull() {
+    let tags = json!({ "models": [{ "name": "llama3:8b" }] });
+    let (url, shutdown) = spawn_runtime(tags, json!({ "version": "0.6.2" })).await;
+
+    let checks = health::check_runtime(&config_for(Runtime::Ollama, &url, "qwen2.5-coder:7b")).await;
+    let model = find(&checks, "model");
+    assert_eq!(model.status, CheckStatus::Fail);
+    assert!(model.fix.as_deref().unwrap().contains("ollama pull qwen2.5-coder:7b"));
+    assert_eq!(model.details.as_ref().unwrap()["installed"], json!(["llama3:8b"]));
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn old_ollama_lacks_schema_support() {
+    let tags = json!({ "models": [{ "name": "deepseek-coder:latest" }] });
+    let (url, shutdown) = spawn_runtime(tags, json!({ "version": "0.4.7" })).await;
+
+    let checks = health::check_runtime(&config_for(Runtime::Ollama, &url, "deepseek-coder")).await;
+    let support = find(&checks, "schema_support");
+    assert_eq!(support.status, CheckStatus::Fail);
+    assert!(support.fix.as_deref().unwrap().contains("0.5.0"));
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn unreachable_runtime_fails_and_skips_model_checks() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let checks = health::check_runtime(&config_for(Runtime::Ollama, &url, "deepseek-coder")).await;
+    let runtime = find(&checks, "runtime");
+    assert_eq!(runtime.status, CheckStatus::Fail);
+    assert!(runtime.fix.as_deref().unwrap().contains("ollama serve"));
+    assert_eq!(find(&checks, "model").status, CheckStatus::Skip);
+    assert_eq!(find(&checks, "schema_support").status, CheckStatus::Skip);
+}
+
+#[tokio::test]
+async fn llama_cpp_lists_served_models() {
+    let (url, shutdown) = spawn_runtime(json!({}), json!({})).await;
+
+    let checks = health::check_runtime(&config_for(Runtime::LlamaCpp, &url, "qwen2.5-coder")).await;
+    assert_eq!(find(&checks, "runtime").status, CheckStatus::Ok);
+    assert_eq!(find(&checks, "model").status, CheckStatus::Ok);
+
+    let checks = health::check_runtime(&config_for(Runtime::LlamaCpp, &url, "other")).await;
+    let model = find(&checks, "model");
+    assert_eq!(model.status, CheckStatus::Warn);
+    assert!(model.fix.is_some());
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn down_fallback_backend_is_only_a_warning() {
+    let tags = json!({ "models": [{ "name": "deepseek-coder:latest" }] });
+    let (url, shutdown) = spawn_runtime(tags, json!({ "version": "0.6.2" })).await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let down = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let mut config = config_for(Runtime::Ollama, &url, "deepseek-coder");
+    config.backends = vec![
+        BackendConfig {
+            name: Some("local".to_string()),
+            ..BackendConfig::default()
+        },
+        BackendConfig {
+            name: Some("lan".to_string()),
+            url: Some(down.clone()),
+            ..BackendConfig::default()
+        },
+    ];
+
+    let checks = health::check_runtime(&config).await;
+    assert_eq!(checks.len(), 6);
+    assert!(checks[0].message.starts_with("local: "));
+    let lan = &checks[3];
+    assert_eq!(lan.name, "runtime");
+    assert_eq!(lan.status, CheckStatus::Warn);
+    assert!(lan.message.starts_with("lan: "));
+
+    config.backends[0].url = Some(down);
+    let checks = health::check_runtime(&config).await;
+    assert_eq!(checks[0].status, CheckStatus::Fail);
+    assert_eq!(checks[3].status, CheckStatus::Fail);
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn replay_runtime_checks_the_cassette() {
+    let mut config = config_for(Runtime::Replay, "http://127.0.0.1:9", "deepseek-coder");
+    let checks = health::check_runtime(&config).await;
+    assert_eq!(find(&checks, "runtime").status, CheckStatus::Fail);
+
+    config.cassette = Some(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/cassettes/plan_retry.cassette.json"),
+    );
+    let checks = health::check_runtime(&config).await;
+    let runtime = find(&checks, "runtime");
+    assert_eq!(runtime.status, CheckStatus::Ok, "{}", runtime.message);
+}
+
+#[test]
+fn repo_check_reports_branch_and_operation() {
+    let repo = setup_repo();
+    let check = health::check_repo(Some(&repo));
+    assert_eq!(check.status, CheckStatus::Ok, "{}", check.message);
+    let details = check.details.as_ref().unwrap();
+    assert_eq!(details["branch"], "main");
+    assert_eq!(details["unborn"], false);
+
+    let head = fs::read_to_string(repo.join(".git/refs/heads/main")).unwrap();
+    fs::write(repo.join(".git/MERGE_HEAD"), head).unwrap();
+    let check = health::check_repo(Some(&repo));
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.fix.as_deref().unwrap().contains("git merge --abort"));
+
+    fs::remove_dir_all(&repo).ok();
+}
+
+#[test]
+fn repo_check_warns_outside_a_repository() {
+    let dir = temp_dir("plain");
+    fs::create_dir_all(&dir).unwrap();
+
+    let check = health::check_repo(Some(&dir));
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.fix.is_some());
+    assert_eq!(health::check_repo(None).status, CheckStatus::Skip);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn config_error_fails_with_fix() {
+    let error = ConfigError::InvalidEnv {
+        key: "LOCAL_COMMIT_RUNTIME".to_string(),
+        value: "gpt".to_string(),
+    };
+    let check = health::check_config(&Err(error));
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert!(check.message.contains("LOCAL_COMMIT_RUNTIME"));
+    assert!(check.fix.is_some());
+}
+
+#[test]
+fn git_and_schema_checks_pass() {
+    assert_eq!(health::check_git().status, CheckStatus::Ok);
+    assert_eq!(health::check_schemas().status, CheckStatus::Ok);
+}
+
+#[tokio::test]
+async fn report_takes_worst_status_and_matches_schema() {
+    let repo = setup_repo();
+    let config = config_for(Runtime::Replay, "http://127.0.0.1:9", "deepseek-coder");
+
+    let report = health::run_checks(Some(&repo), &Ok((config, Vec::new()))).await;
+    assert_eq!(report.status, CheckStatus::Fail);
+    assert_eq!(report.first_failure().unwrap().name, "runtime");
+    validate_schema(SchemaKind::Health, &serde_json::to_value(&report).unwrap()).unwrap();
+
+    let ok = HealthReport::new(vec![]);
+    assert_eq!(ok.status, CheckStatus::Ok);
+
+    fs::remove_dir_all(&repo).ok();
+}
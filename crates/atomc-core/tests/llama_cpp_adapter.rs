@@ -90,12 +90,14 @@ async fn llama_cpp_client_parses_commit_plan() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_secs(2),
+        seed: None,
     };
 
     let plan = client.generate_commit_plan(&prompt, &options).await.unwrap();
@@ -126,12 +128,14 @@ async fn llama_cpp_client_reports_non_success_status() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_secs(2),
+        seed: None,
     };
 
     let error = client.generate_commit_plan(&prompt, &options).await.unwrap_err();
@@ -150,12 +154,14 @@ async fn llama_cpp_client_rejects_invalid_json() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_secs(2),
+        seed: None,
     };
 
     let error = client.generate_commit_plan(&prompt, &options).await.unwrap_err();
@@ -176,12 +182,14 @@ async fn llama_cpp_client_times_out() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_millis(10),
+        seed: None,
     };
 
     let error = client.generate_commit_plan(&prompt, &options).await.unwrap_err();
@@ -214,12 +222,14 @@ async fn llama_cpp_client_accepts_text_fallback() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_secs(2),
+        seed: None,
     };
 
     let plan = client.generate_commit_plan(&prompt, &options).await.unwrap();
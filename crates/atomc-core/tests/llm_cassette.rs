@@ -0,0 +1,234 @@
+mod support;
+
+use atomc_core::config::{ResolvedConfig, Runtime};
+use atomc_core::llm::{
+    generate_commit_plan, redact_diffs, Cassette, Exchange, LlmError, Prompt, RecordedErrorKind,
+    RecordedResponse, CASSETTE_VERSION,
+};
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use support::temp_dir;
+
+fn cassette_path(prefix: &str) -> PathBuf {
+    temp_dir(&format!("cassette-{prefix}")).join("cassette.json")
+}
+
+fn plan_json() -> String {
+    json!({
+        "schema_version": "v1",
+        "plan": [{
+            "id": "commit-1",
+            "type": "docs",
+            "scope": "cli",
+            "summary": "document CLI plan and apply flags for usage examples",
+            "body": ["Add usage examples"],
+            "files": ["docs/02_cli_spec.md"],
+            "hunks": []
+        }]
+    })
+    .to_string()
+}
+
+fn prompt(user: &str) -> Prompt {
+    Prompt {
+        system: "system prompt".to_string(),
+        user: user.to_string(),
+        version: "test".to_string(),
+    }
+}
+
+fn exchange(prompt_hash: &str, response: RecordedResponse) -> Exchange {
+    Exchange {
+        runtime: "ollama".to_string(),
+        model: "qwen2.5-coder:14b".to_string(),
+        temperature: 0.2,
+        seed: None,
+        prompt_hash: prompt_hash.to_string(),
+        prompt_version: "test".to_string(),
+        system: String::new(),
+        user: String::new(),
+        response,
+    }
+}
+
+fn write_cassette(path: &Path, exchanges: Vec<Exchange>) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let cassette = Cassette {
+        version: CASSETTE_VERSION,
+        exchanges,
+    };
+    fs::write(path, serde_json::to_string(&cassette).unwrap()).unwrap();
+}
+
+fn replay_config(path: &Path) -> ResolvedConfig {
+    ResolvedConfig {
+        runtime: Runtime::Replay,
+        cassette: Some(path.to_path_buf()),
+        ..ResolvedConfig::defaults()
+    }
+}
+
+async fn spawn_ollama(response: String) -> (String, oneshot::Sender<()>) {
+    let app = Router::new().route(
+        "/api/generate",
+        post(move |Json(_): Json<Value>| {
+            let response = response.clone();
+            async move { Json(json!({ "response": response })) }
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+    (format!("http://{addr}"), shutdown_tx)
+}
+
+#[tokio::test]
+async fn recorded_exchanges_replay_without_a_model() {
+    let path = cassette_path("roundtrip");
+    let (base_url, shutdown) = spawn_ollama(plan_json()).await;
+    let config = ResolvedConfig {
+        ollama_url: base_url,
+        cassette: Some(path.clone()),
+        ..ResolvedConfig::defaults()
+    };
+
+    let recorded = generate_commit_plan(&config, &prompt("plan this")).await.unwrap();
+    let _ = shutdown.send(());
+
+    let cassette = Cassette::load(&path).unwrap();
+    assert_eq!(cassette.version, CASSETTE_VERSION);
+    assert_eq!(cassette.exchanges.len(), 1);
+    let exchange = &cassette.exchanges[0];
+    assert_eq!(exchange.runtime, "ollama");
+    assert_eq!(exchange.user, "plan this");
+    assert!(matches!(&exchange.response, RecordedResponse::Content(content) if *content == plan_json()));
+
+    let replayed = generate_commit_plan(&replay_config(&path), &prompt("plan this"))
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(&replayed.plan).unwrap(),
+        serde_json::to_value(&recorded.plan).unwrap()
+    );
+    assert_eq!(replayed.backend.unwrap().runtime, "replay");
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[tokio::test]
+async fn replay_reproduces_errors_in_order_then_runs_out() {
+    let path = cassette_path("errors");
+    write_cassette(
+        &path,
+        vec![
+            exchange(
+                "sha256:other",
+                RecordedResponse::Error {
+                    kind: RecordedErrorKind::Runtime,
+                    message: "model not loaded".to_string(),
+                },
+            ),
+            exchange("sha256:other", RecordedResponse::Content("not json".to_string())),
+        ],
+    );
+    let config = ResolvedConfig {
+        cassette_fuzzy: true,
+        ..replay_config(&path)
+    };
+
+    let first = generate_commit_plan(&config, &prompt("a")).await.unwrap_err();
+    assert!(matches!(first, LlmError::Runtime(message) if message == "model not loaded"));
+    let second = generate_commit_plan(&config, &prompt("a")).await.unwrap_err();
+    assert!(matches!(second, LlmError::Parse(_)));
+    let third = generate_commit_plan(&config, &prompt("a")).await.unwrap_err();
+    assert!(matches!(third, LlmError::Runtime(message) if message.contains("no exchange left")));
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[tokio::test]
+async fn replay_prefers_exchanges_recorded_for_the_same_prompt() {
+    let path = cassette_path("match");
+    let recorder = cassette_path("match-recorder");
+    let (base_url, shutdown) = spawn_ollama(plan_json()).await;
+    let record_config = ResolvedConfig {
+        ollama_url: base_url,
+        cassette: Some(recorder.clone()),
+        ..ResolvedConfig::defaults()
+    };
+    generate_commit_plan(&record_config, &prompt("second")).await.unwrap();
+    let _ = shutdown.send(());
+    let matching = Cassette::load(&recorder).unwrap().exchanges.remove(0);
+
+    write_cassette(
+        &path,
+        vec![
+            exchange("sha256:other", RecordedResponse::Content("not json".to_string())),
+            matching,
+        ],
+    );
+    let config = replay_config(&path);
+
+    // Matched by prompt hash, twice, even though it was recorded second.
+    assert!(generate_commit_plan(&config, &prompt("second")).await.is_ok());
+    assert!(generate_commit_plan(&config, &prompt("second")).await.is_ok());
+    // Unmatched prompts fail unless fuzzy replay is on; then they take the
+    // next unused exchange.
+    let unmatched = generate_commit_plan(&config, &prompt("third")).await.unwrap_err();
+    assert!(matches!(unmatched, LlmError::Runtime(message) if message.contains("no exchange recorded for prompt")));
+    let fuzzy = ResolvedConfig {
+        cassette_fuzzy: true,
+        ..replay_config(&path)
+    };
+    assert!(matches!(
+        generate_commit_plan(&fuzzy, &prompt("third")).await,
+        Err(LlmError::Parse(_))
+    ));
+
+    fs::remove_dir_all(path.parent().unwrap()).ok();
+    fs::remove_dir_all(recorder.parent().unwrap()).ok();
+}
+
+#[tokio::test]
+async fn replay_requires_a_readable_cassette() {
+    let config = ResolvedConfig {
+        runtime: Runtime::Replay,
+        ..ResolvedConfig::defaults()
+    };
+    assert!(matches!(
+        generate_commit_plan(&config, &prompt("a")).await,
+        Err(LlmError::Runtime(message)) if message.contains("requires a cassette")
+    ));
+
+    let missing = replay_config(&cassette_path("missing"));
+    assert!(matches!(
+        generate_commit_plan(&missing, &prompt("a")).await,
+        Err(LlmError::Runtime(message)) if message.contains("cassette read error")
+    ));
+}
+
+#[test]
+fn redact_diffs_drops_hunk_content_but_keeps_headers() {
+    let text = "Context:\n- repo_path: /repo\n\nDiff:\ndiff --git a/src/lib.rs b/src/lib.rs\n\
+--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,3 @@\n fn add() {}\n+fn secret() {}\n-fn old() {}\n\
+diff --git a/README.md b/README.md\n@@ -1 +1 @@\n-Old\n+New\n";
+
+    assert_eq!(
+        redact_diffs(text),
+        "Context:\n- repo_path: /repo\n\nDiff:\ndiff --git a/src/lib.rs b/src/lib.rs\n\
+--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,3 @@\n[3 diff lines redacted]\n\
+diff --git a/README.md b/README.md\n@@ -1 +1 @@\n[2 diff lines redacted]\n"
+    );
+}
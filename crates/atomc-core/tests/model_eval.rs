@@ -0,0 +1,154 @@
+use atomc_core::config::{AtomicityPolicy, ResolvedConfig};
+use atomc_core::eval::{self, EvalCase, EvalError};
+use atomc_core::llm::{LlmError, PromptTemplates};
+use atomc_core::schema::{validate_schema, SchemaKind};
+use atomc_core::types::{CommitPlan, CommitType, CommitUnit};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures")
+}
+
+fn unit(id: &str, files: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_: CommitType::Feat,
+        scope: Some("math".to_string()),
+        summary: "add subtraction helper for arithmetic utilities module".to_string(),
+        body: vec!["Mirror the existing add helper".to_string()],
+        files: files.iter().map(|file| file.to_string()).collect(),
+        hunks: Vec::new(),
+    }
+}
+
+fn plan(units: Vec<CommitUnit>) -> CommitPlan {
+    CommitPlan {
+        schema_version: "v1".to_string(),
+        request_id: None,
+        warnings: None,
+        input: None,
+        backend: None,
+        plan: units,
+    }
+}
+
+fn case(expected: Option<CommitPlan>) -> EvalCase {
+    EvalCase {
+        name: "simple_feature".to_string(),
+        diff: "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1,2 @@\n+pub fn sub() {}\n"
+            .to_string(),
+        expected,
+    }
+}
+
+#[test]
+fn load_cases_pairs_diffs_with_expected_plans() {
+    let cases = eval::load_cases(&fixtures_dir()).unwrap();
+    assert_eq!(cases.len(), 10);
+    let with_expected: Vec<&str> = cases
+        .iter()
+        .filter(|case| case.expected.is_some())
+        .map(|case| case.name.as_str())
+        .collect();
+    assert_eq!(
+        with_expected,
+        vec!["mixed_concerns", "refactor_plus_feature", "simple_feature"]
+    );
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let empty = std::env::temp_dir().join(format!("atomc-eval-empty-{nanos}"));
+    fs::create_dir_all(empty.join("diffs")).unwrap();
+    assert!(matches!(eval::load_cases(&empty), Err(EvalError::Empty(_))));
+    assert!(matches!(eval::load_cases(&empty.join("missing")), Err(EvalError::Read { .. })));
+    fs::remove_dir_all(&empty).ok();
+}
+
+fn score(case: &EvalCase, model: &str, response: &Result<CommitPlan, LlmError>, latency: Duration) -> eval::CaseResult {
+    eval::score_case(case, model, response, latency, AtomicityPolicy::Standard)
+}
+
+#[test]
+fn score_case_checks_validity_coverage_and_unit_count() {
+    let expected = plan(vec![unit("commit-1", &["src/lib.rs"])]);
+    let case = case(Some(expected));
+
+    let good = score(&case, "m", &Ok(plan(vec![unit("commit-1", &["src/lib.rs"])])), Duration::from_millis(12));
+    assert!(good.schema_valid && good.semantic_valid);
+    assert_eq!(good.coverage, 1.0);
+    assert_eq!((good.units, good.expected_units, good.units_match), (Some(1), Some(1), Some(true)));
+    assert_eq!(good.latency_ms, 12);
+
+    let mut short = unit("commit-1", &["src/lib.rs"]);
+    short.summary = "add sub".to_string();
+    let bad = score(&case, "m", &Ok(plan(vec![short, unit("commit-2", &["src/lib.rs"])])), Duration::ZERO);
+    assert!(bad.schema_valid && !bad.semantic_valid);
+    assert_eq!(bad.units_match, Some(false));
+
+    let failed = score(&case, "m", &Err(LlmError::Parse("not json".to_string())), Duration::ZERO);
+    assert!(!failed.schema_valid && !failed.semantic_valid);
+    assert_eq!(failed.coverage, 0.0);
+    assert_eq!(failed.units, None);
+    assert_eq!(failed.units_match, Some(false));
+    assert!(failed.error.unwrap().contains("not json"));
+
+    let unlabeled = score(&self::case(None), "m", &Err(LlmError::Timeout), Duration::ZERO);
+    assert_eq!(unlabeled.units_match, None);
+}
+
+#[test]
+fn summarize_totals_each_model() {
+    let case = case(Some(plan(vec![unit("commit-1", &["src/lib.rs"])])));
+    let results = vec![
+        score(&case, "a", &Ok(plan(vec![unit("commit-1", &["src/lib.rs"])])), Duration::from_millis(10)),
+        score(&case, "a", &Err(LlmError::Timeout), Duration::from_millis(30)),
+        score(&case, "b", &Ok(plan(vec![unit("commit-1", &["src/lib.rs"])])), Duration::from_millis(5)),
+    ];
+
+    let summaries = eval::summarize(&results);
+    assert_eq!(summaries.len(), 2);
+    let a = &summaries[0];
+    assert_eq!(a.model, "a");
+    assert_eq!((a.cases, a.schema_valid, a.semantic_valid), (2, 1, 1));
+    assert_eq!(a.coverage, 0.5);
+    assert_eq!(a.unit_agreement, Some(0.5));
+    assert_eq!((a.mean_latency_ms, a.max_latency_ms), (20, 30));
+    assert_eq!(summaries[1].unit_agreement, Some(1.0));
+}
+
+#[tokio::test]
+async fn run_eval_plans_every_case_per_model() {
+    let cases = eval::load_cases(&fixtures_dir()).unwrap();
+    let config = ResolvedConfig {
+        prompt_examples: 3,
+        ..ResolvedConfig::defaults()
+    };
+    let templates = PromptTemplates::load(&config, None).unwrap();
+    let models = vec!["model-a".to_string(), "model-b".to_string()];
+    let calls = Arc::new(Mutex::new(Vec::new()));
+
+    let report = eval::run_eval(&config, &templates, &fixtures_dir(), &cases, &models, |config, prompt| {
+        let calls = Arc::clone(&calls);
+        async move {
+            // A case's own diff is never offered back to it as an example.
+            let (examples, diff) = prompt.user.rsplit_once("\nDiff:\n").unwrap();
+            assert!(examples.contains("Example diff:"));
+            assert!(!examples.contains(diff.trim_end()));
+            calls.lock().unwrap().push(config.model.clone());
+            Ok(plan(vec![unit("commit-1", &["src/lib.rs"])]))
+        }
+    })
+    .await;
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 20);
+    assert!(calls[..10].iter().all(|model| model == "model-a"));
+    assert_eq!(report.cases.len(), 20);
+    assert_eq!(report.models.len(), 2);
+    assert_eq!(report.models[0].schema_valid, 10);
+
+    let value = serde_json::to_value(&report).unwrap();
+    validate_schema(SchemaKind::EvalReport, &value).unwrap();
+}
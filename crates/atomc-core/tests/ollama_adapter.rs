@@ -1,4 +1,5 @@
-use atomc_core::llm::{LlmOptions, LlmError, OllamaClient, Prompt};
+use atomc_core::config::ResolvedConfig;
+use atomc_core::llm::{generate_commit_plan_candidates, LlmOptions, LlmError, OllamaClient, Prompt};
 use axum::{extract::State, routing::post, Json, Router};
 use axum::http::StatusCode;
 use serde_json::{json, Value};
@@ -88,12 +89,14 @@ async fn ollama_client_parses_commit_plan() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_secs(2),
+        seed: None,
     };
 
     let plan = client.generate_commit_plan(&prompt, &options).await.unwrap();
@@ -115,6 +118,47 @@ async fn ollama_client_parses_commit_plan() {
     );
     assert_eq!(payload["options"]["temperature"], json!(0.2));
     assert_eq!(payload["options"]["num_predict"], json!(128));
+    assert!(payload["options"].get("seed").is_none());
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn generate_commit_plan_candidates_samples_with_seeds() {
+    let plan = json!({
+        "schema_version": "v1",
+        "plan": [
+            {
+                "id": "commit-1",
+                "type": "docs",
+                "scope": "cli",
+                "summary": "document CLI plan and apply flags for usage examples",
+                "body": ["Add usage examples"],
+                "files": ["docs/02_cli_spec.md"],
+                "hunks": []
+            }
+        ]
+    });
+    let response = json!({ "response": plan.to_string() });
+    let captured = Arc::new(Mutex::new(None));
+    let (base_url, shutdown) = spawn_server(response, captured.clone()).await;
+
+    let config = ResolvedConfig {
+        ollama_url: base_url,
+        llm_timeout_secs: 2,
+        ..ResolvedConfig::defaults()
+    };
+    let prompt = Prompt {
+        system: "system prompt".to_string(),
+        user: "user prompt".to_string(),
+        version: "test".to_string(),
+    };
+
+    let results = generate_commit_plan_candidates(&config, &prompt, 3).await;
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(Result::is_ok));
+    let payload = captured.lock().unwrap().clone().expect("request captured");
+    assert!(payload["options"]["seed"].is_u64());
 
     let _ = shutdown.send(());
 }
@@ -130,12 +174,14 @@ async fn ollama_client_reports_non_success_status() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_secs(2),
+        seed: None,
     };
 
     let error = client.generate_commit_plan(&prompt, &options).await.unwrap_err();
@@ -154,12 +200,14 @@ async fn ollama_client_rejects_invalid_json() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_secs(2),
+        seed: None,
     };
 
     let error = client.generate_commit_plan(&prompt, &options).await.unwrap_err();
@@ -180,12 +228,14 @@ async fn ollama_client_times_out() {
     let prompt = Prompt {
         system: "system prompt".to_string(),
         user: "user prompt".to_string(),
+        version: "test".to_string(),
     };
     let options = LlmOptions {
         model: "deepseek-coder".to_string(),
         temperature: 0.2,
         max_tokens: 128,
         timeout: Duration::from_millis(10),
+        seed: None,
     };
 
     let error = client.generate_commit_plan(&prompt, &options).await.unwrap_err();
@@ -0,0 +1,227 @@
+use atomc_core::atomicity::{analyze_plan, is_docs_path, is_test_path, AtomicityKind};
+use atomc_core::config::AtomicityPolicy;
+use atomc_core::git::diff_hunks;
+use atomc_core::llm::{build_unit_retry_prompt, Prompt};
+use atomc_core::semantic::{validate_atomicity, SemanticValidationError, SemanticWarning};
+use atomc_core::types::{CommitPlan, CommitType, CommitUnit, Hunk};
+
+fn unit(id: &str, type_: CommitType, files: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_,
+        scope: Some("core".to_string()),
+        summary: "add deterministic JSON output for plan command results".to_string(),
+        body: vec!["Emit JSON by default for automation".to_string()],
+        files: files.iter().map(|file| file.to_string()).collect(),
+        hunks: Vec::new(),
+    }
+}
+
+/// A diff block for `path` with one hunk per `(removed, added)` pair.
+fn file_diff(path: &str, hunks: &[(&[&str], &[&str])]) -> String {
+    let mut diff = format!("diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n");
+    for (index, (removed, added)) in hunks.iter().enumerate() {
+        let start = index * 20 + 1;
+        diff.push_str(&format!(
+            "@@ -{start},{} +{start},{} @@\n",
+            removed.len(),
+            added.len()
+        ));
+        for line in removed.iter() {
+            diff.push_str(&format!("-{line}\n"));
+        }
+        for line in added.iter() {
+            diff.push_str(&format!("+{line}\n"));
+        }
+    }
+    diff
+}
+
+fn kinds(units: &[CommitUnit], diff: &str) -> Vec<(String, AtomicityKind)> {
+    analyze_plan(units, diff)
+        .into_iter()
+        .map(|finding| (finding.unit, finding.kind))
+        .collect()
+}
+
+#[test]
+fn paths_are_classified_as_tests_and_docs() {
+    for path in [
+        "tests/plan.rs",
+        "crates/core/tests/git.rs",
+        "src/__tests__/app.js",
+        "pkg/diff_test.go",
+        "test_parser.py",
+        "web/app.spec.ts",
+        "lib/util.test.js",
+        "spec/models/user_spec.rb",
+    ] {
+        assert!(is_test_path(path), "{path}");
+    }
+    for path in ["src/testing.rs", "src/contest.rs", "latest/main.rs", "docs/02_cli_spec.md"] {
+        assert!(!is_test_path(path), "{path}");
+    }
+    assert!(is_docs_path("docs/guide.html"));
+    assert!(is_docs_path("README.md"));
+    assert!(is_docs_path("crates/core/CHANGELOG.rst"));
+    assert!(is_docs_path("LICENSE"));
+    assert!(is_docs_path("README.txt"));
+    assert!(!is_docs_path("src/doc_comments.rs"));
+    for path in ["requirements.txt", "CMakeLists.txt", "static/robots.txt"] {
+        assert!(!is_docs_path(path), "{path}");
+    }
+}
+
+#[test]
+fn tests_bundled_with_source_need_a_test_type() {
+    let units = [unit("commit-1", CommitType::Feat, &["src/plan.rs", "tests/plan.rs"])];
+    assert_eq!(
+        kinds(&units, ""),
+        vec![("commit-1".to_string(), AtomicityKind::TestsWithSource)]
+    );
+
+    let units = [
+        unit("commit-1", CommitType::Test, &["src/fixtures.rs", "tests/plan.rs"]),
+        unit("commit-2", CommitType::Fix, &["tests/plan.rs"]),
+        unit("commit-3", CommitType::Feat, &["tests/plan.rs", "docs/plan.md"]),
+    ];
+    assert!(kinds(&units, "").is_empty());
+}
+
+#[test]
+fn docs_only_feature_is_flagged() {
+    let units = [
+        unit("commit-1", CommitType::Feat, &["docs/02_cli_spec.md", "README.md"]),
+        unit("commit-2", CommitType::Docs, &["docs/03_schema.md"]),
+        unit("commit-3", CommitType::Feat, &["docs/02_cli_spec.md", "src/cli.rs"]),
+        unit("commit-4", CommitType::Feat, &["requirements.txt"]),
+    ];
+    assert_eq!(
+        kinds(&units, ""),
+        vec![("commit-1".to_string(), AtomicityKind::DocsAsFeature)]
+    );
+}
+
+#[test]
+fn formatting_hunks_bundled_with_logic_are_flagged() {
+    let diff = [
+        file_diff("src/a.rs", &[(&["fn a( x: u32 ) {"], &["fn a(x: u32) {"])]),
+        file_diff("src/b.rs", &[(&["    1"], &["    2"])]),
+        file_diff("src/c.rs", &[(&["let y=1;"], &["let y = 1;"]), (&[], &["call();"])]),
+    ]
+    .concat();
+    let units = [
+        unit("commit-1", CommitType::Fix, &["src/a.rs", "src/b.rs"]),
+        unit("commit-2", CommitType::Style, &["src/a.rs", "src/b.rs"]),
+        unit("commit-3", CommitType::Refactor, &["src/a.rs"]),
+    ];
+    assert_eq!(
+        kinds(&units, &diff),
+        vec![("commit-1".to_string(), AtomicityKind::FormattingWithLogic)]
+    );
+
+    // Only the hunks a unit names count.
+    let mut split = unit("commit-1", CommitType::Fix, &["src/c.rs"]);
+    split.hunks = vec![Hunk {
+        file: "src/c.rs".to_string(),
+        header: "@@ -21,0 +21,1 @@".to_string(),
+        id: None,
+    }];
+    assert!(kinds(&[split], &diff).is_empty());
+    let whole = unit("commit-1", CommitType::Fix, &["src/c.rs"]);
+    assert_eq!(kinds(&[whole], &diff).len(), 1);
+}
+
+#[test]
+fn one_large_unit_among_small_ones_is_flagged() {
+    let big: Vec<String> = (0..250).map(|line| format!("line {line}")).collect();
+    let big: Vec<&str> = big.iter().map(String::as_str).collect();
+    let diff = [
+        file_diff("src/big.rs", &[(&[], &big)]),
+        file_diff("src/small.rs", &[(&["a"], &["b"])]),
+        file_diff("src/other.rs", &[(&["c"], &["d"])]),
+    ]
+    .concat();
+    let units = [
+        unit("commit-1", CommitType::Feat, &["src/small.rs"]),
+        unit("commit-2", CommitType::Feat, &["src/big.rs"]),
+        unit("commit-3", CommitType::Fix, &["src/other.rs"]),
+    ];
+    let findings = analyze_plan(&units, &diff);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].unit, "commit-2");
+    assert_eq!(findings[0].kind, AtomicityKind::OversizedUnit);
+    assert!(findings[0].message.contains("250 lines"));
+
+    // Two units are not enough to call one of them oversized.
+    assert!(analyze_plan(&units[..2], &diff).is_empty());
+}
+
+#[test]
+fn policy_decides_errors_and_warnings() {
+    let units = [
+        unit("commit-1", CommitType::Feat, &["docs/guide.md"]),
+        unit("commit-2", CommitType::Feat, &["src/plan.rs", "tests/plan.rs"]),
+    ];
+
+    let errors = validate_atomicity(&units, "", AtomicityPolicy::Standard).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        SemanticValidationError::NonAtomic { id, kind: AtomicityKind::DocsAsFeature, .. } if id == "commit-1"
+    ));
+
+    let report = validate_atomicity(&units[1..], "", AtomicityPolicy::Standard).unwrap();
+    assert!(matches!(
+        &report.warnings[..],
+        [SemanticWarning::NonAtomic { kind: AtomicityKind::TestsWithSource, .. }]
+    ));
+
+    let errors = validate_atomicity(&units, "", AtomicityPolicy::Strict).unwrap_err();
+    assert_eq!(errors.len(), 2);
+
+    let report = validate_atomicity(&units, "", AtomicityPolicy::Off).unwrap();
+    assert!(report.warnings.is_empty());
+}
+
+#[test]
+fn atomicity_errors_are_fed_back_into_the_retry_prompt() {
+    let units = vec![
+        unit("commit-1", CommitType::Docs, &["docs/guide.md"]),
+        unit("commit-2", CommitType::Feat, &["docs/plan.md"]),
+    ];
+    let errors = validate_atomicity(&units, "", AtomicityPolicy::Standard).unwrap_err();
+    let plan = CommitPlan {
+        schema_version: "v1".to_string(),
+        request_id: None,
+        warnings: None,
+        input: None,
+        backend: None,
+        plan: units,
+    };
+    let base = Prompt {
+        system: "system".to_string(),
+        user: "user".to_string(),
+        version: "1".to_string(),
+    };
+
+    let retry = build_unit_retry_prompt(&base, &plan, &errors);
+    assert!(retry.system.contains("Fix only these commits: commit-2."));
+    assert!(retry.system.contains("commit commit-2 is not atomic: it only changes documentation"));
+}
+
+#[test]
+fn diff_hunks_collect_changed_lines_per_file() {
+    let diff = [
+        file_diff("src/a.rs", &[(&["old"], &["new", "more"])]),
+        file_diff("src/a.rs", &[(&[], &["staged"])]),
+    ]
+    .concat();
+    let hunks = diff_hunks(&diff);
+    let file = &hunks["src/a.rs"];
+    assert_eq!(file.len(), 2);
+    assert_eq!(file[0].header, "@@ -1,1 +1,2 @@");
+    assert_eq!(file[0].removed, vec!["old"]);
+    assert_eq!(file[0].added, vec!["new", "more"]);
+    assert_eq!(file[1].added, vec!["staged"]);
+}
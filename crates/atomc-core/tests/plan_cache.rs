@@ -0,0 +1,237 @@
+mod support;
+
+use atomc_core::cache::{CacheKey, PlanCache};
+use atomc_core::config::{AtomicityPolicy, BackendConfig, DependencyOrder, ResolvedConfig};
+use atomc_core::llm::Prompt;
+use atomc_core::types::{CommitPlan, CommitType, CommitUnit, Warning};
+use std::fs;
+use std::time::{Duration, SystemTime};
+use support::temp_dir;
+
+const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-old\n+new\n";
+
+fn prompt() -> Prompt {
+    Prompt {
+        system: "system".to_string(),
+        user: "user".to_string(),
+        version: "test".to_string(),
+    }
+}
+
+fn plan() -> CommitPlan {
+    CommitPlan {
+        schema_version: "v1".to_string(),
+        request_id: None,
+        warnings: None,
+        input: None,
+        backend: None,
+        plan: vec![CommitUnit {
+            id: "c1".to_string(),
+            type_: CommitType::Fix,
+            scope: None,
+            summary: "replace old value".to_string(),
+            body: vec!["Swap the old value for the new one.".to_string()],
+            files: vec!["src/lib.rs".to_string()],
+            hunks: Vec::new(),
+        }],
+    }
+}
+
+#[test]
+fn plan_cache_round_trips_plans_by_key() {
+    let dir = temp_dir("round-trip");
+    let cache = PlanCache::new(&dir);
+    let key = CacheKey::new(&ResolvedConfig::defaults(), &prompt(), DIFF);
+
+    assert!(cache.get(&key).is_none());
+    cache.put(&key, &plan(), &[]).unwrap();
+    let (cached, warnings) = cache.get(&key).expect("cache hit");
+    assert_eq!(cached.plan[0].summary, "replace old value");
+    assert!(warnings.is_empty());
+
+    let repaired = Warning {
+        code: "plan_repaired".to_string(),
+        message: "summary trimmed".to_string(),
+        details: None,
+    };
+    cache.put(&key, &plan(), std::slice::from_ref(&repaired)).unwrap();
+    let (_, warnings) = cache.get(&key).expect("cache hit");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, "plan_repaired");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn plan_cache_misses_when_any_key_input_changes() {
+    let dir = temp_dir("miss");
+    let cache = PlanCache::new(&dir);
+    let config = ResolvedConfig::defaults();
+    let key = CacheKey::new(&config, &prompt(), DIFF);
+    cache.put(&key, &plan(), &[]).unwrap();
+
+    let other_diff = CacheKey::new(&config, &prompt(), &DIFF.replace("+new", "+newer"));
+    assert!(cache.get(&other_diff).is_none());
+
+    let other_model = ResolvedConfig {
+        model: "other-model".to_string(),
+        ..ResolvedConfig::defaults()
+    };
+    assert!(cache.get(&CacheKey::new(&other_model, &prompt(), DIFF)).is_none());
+
+    let warmer = ResolvedConfig {
+        temperature: config.temperature + 0.5,
+        ..ResolvedConfig::defaults()
+    };
+    assert!(cache.get(&CacheKey::new(&warmer, &prompt(), DIFF)).is_none());
+
+    let other_prompt = Prompt {
+        user: "different repo metadata".to_string(),
+        ..prompt()
+    };
+    assert!(cache.get(&CacheKey::new(&config, &other_prompt, DIFF)).is_none());
+
+    let strict = ResolvedConfig {
+        atomicity: AtomicityPolicy::Strict,
+        ..ResolvedConfig::defaults()
+    };
+    assert!(cache.get(&CacheKey::new(&strict, &prompt(), DIFF)).is_none());
+
+    let unordered = ResolvedConfig {
+        dependency_order: DependencyOrder::Off,
+        ..ResolvedConfig::defaults()
+    };
+    assert!(cache.get(&CacheKey::new(&unordered, &prompt(), DIFF)).is_none());
+
+    let fallback = ResolvedConfig {
+        backends: vec![
+            BackendConfig::default(),
+            BackendConfig {
+                model: Some("fallback-model".to_string()),
+                ..BackendConfig::default()
+            },
+        ],
+        ..ResolvedConfig::defaults()
+    };
+    assert!(cache.get(&CacheKey::new(&fallback, &prompt(), DIFF)).is_none());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn plan_cache_treats_corrupt_entries_as_misses() {
+    let dir = temp_dir("corrupt");
+    let cache = PlanCache::new(&dir);
+    let key = CacheKey::new(&ResolvedConfig::defaults(), &prompt(), DIFF);
+    cache.put(&key, &plan(), &[]).unwrap();
+    for entry in fs::read_dir(dir.join("plans")).unwrap() {
+        fs::write(entry.unwrap().path(), "{not json").unwrap();
+    }
+
+    assert!(cache.get(&key).is_none());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn plan_cache_clear_removes_every_entry() {
+    let dir = temp_dir("clear");
+    let cache = PlanCache::new(&dir);
+    let config = ResolvedConfig::defaults();
+    assert_eq!(cache.clear().unwrap(), 0);
+
+    let first = CacheKey::new(&config, &prompt(), DIFF);
+    let second = CacheKey::new(&config, &prompt(), &DIFF.replace("+new", "+newer"));
+    cache.put(&first, &plan(), &[]).unwrap();
+    cache.put(&second, &plan(), &[]).unwrap();
+
+    assert_eq!(cache.clear().unwrap(), 2);
+    assert!(cache.get(&first).is_none());
+    assert_eq!(cache.clear().unwrap(), 0);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn plan_cache_from_config_respects_switch_and_dir() {
+    let dir = temp_dir("config");
+    let enabled = ResolvedConfig {
+        cache_dir: Some(dir.clone()),
+        ..ResolvedConfig::defaults()
+    };
+    let cache = PlanCache::from_config(&enabled).unwrap().expect("cache enabled");
+    assert_eq!(cache.dir(), dir.as_path());
+
+    let disabled = ResolvedConfig {
+        plan_cache: false,
+        ..enabled
+    };
+    assert!(PlanCache::from_config(&disabled).unwrap().is_none());
+}
+
+#[test]
+fn plan_cache_keeps_only_the_newest_entries() {
+    let dir = temp_dir("max-entries");
+    let cache = PlanCache::new(&dir).with_limits(2, Duration::from_secs(3600));
+    let config = ResolvedConfig::defaults();
+    let keys: Vec<CacheKey> = ["+one", "+two", "+three"]
+        .iter()
+        .map(|line| CacheKey::new(&config, &prompt(), &DIFF.replace("+new", line)))
+        .collect();
+    for key in &keys {
+        // Age the stored entries so modification times order them.
+        for entry in fs::read_dir(dir.join("plans")).into_iter().flatten() {
+            let file = fs::File::options().append(true).open(entry.unwrap().path()).unwrap();
+            let modified = file.metadata().unwrap().modified().unwrap();
+            file.set_modified(modified - Duration::from_secs(60)).unwrap();
+        }
+        cache.put(key, &plan(), &[]).unwrap();
+    }
+
+    assert!(cache.get(&keys[0]).is_none());
+    assert!(cache.get(&keys[1]).is_some());
+    assert!(cache.get(&keys[2]).is_some());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn plan_cache_prunes_expired_entries_on_store() {
+    let dir = temp_dir("max-age");
+    let cache = PlanCache::new(&dir).with_limits(10, Duration::from_secs(3600));
+    let config = ResolvedConfig::defaults();
+    let stale = CacheKey::new(&config, &prompt(), DIFF);
+    cache.put(&stale, &plan(), &[]).unwrap();
+    for entry in fs::read_dir(dir.join("plans")).unwrap() {
+        let file = fs::File::options().append(true).open(entry.unwrap().path()).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(7200)).unwrap();
+    }
+
+    let fresh = CacheKey::new(&config, &prompt(), &DIFF.replace("+new", "+newer"));
+    cache.put(&fresh, &plan(), &[]).unwrap();
+    assert!(cache.get(&stale).is_none());
+    assert!(cache.get(&fresh).is_some());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn plan_cache_put_removes_temp_file_when_store_fails() {
+    let dir = temp_dir("failed-put");
+    let cache = PlanCache::new(&dir);
+    let key = CacheKey::new(&ResolvedConfig::defaults(), &prompt(), DIFF);
+    cache.put(&key, &plan(), &[]).unwrap();
+    // A non-empty directory where the entry goes makes the rename fail.
+    let entry = fs::read_dir(dir.join("plans")).unwrap().next().unwrap().unwrap().path();
+    fs::remove_file(&entry).unwrap();
+    fs::create_dir_all(entry.join("blocker")).unwrap();
+
+    assert!(cache.put(&key, &plan(), &[]).is_err());
+    let names: Vec<_> = fs::read_dir(dir.join("plans"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, vec![entry.file_name().unwrap().to_os_string()]);
+
+    fs::remove_dir_all(&dir).ok();
+}
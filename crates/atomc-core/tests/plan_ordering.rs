@@ -0,0 +1,206 @@
+use atomc_core::ordering::{late_dependencies, order_units, unit_dependencies, Dependency, SymbolKind};
+use atomc_core::types::{CommitType, CommitUnit};
+
+fn unit(id: &str, files: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_: CommitType::Feat,
+        scope: Some("core".to_string()),
+        summary: "add deterministic JSON output for plan command results".to_string(),
+        body: vec!["Emit JSON by default for automation".to_string()],
+        files: files.iter().map(|file| file.to_string()).collect(),
+        hunks: Vec::new(),
+    }
+}
+
+fn new_file(path: &str, lines: &[&str]) -> String {
+    let mut diff = format!(
+        "diff --git a/{path} b/{path}\nnew file mode 100644\n--- /dev/null\n+++ b/{path}\n@@ -0,0 +1,{} @@\n",
+        lines.len()
+    );
+    for line in lines {
+        diff.push_str(&format!("+{line}\n"));
+    }
+    diff
+}
+
+fn changed_file(path: &str, removed: &[&str], added: &[&str]) -> String {
+    let mut diff = format!(
+        "diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n@@ -1,{} +1,{} @@\n",
+        removed.len(),
+        added.len()
+    );
+    for line in removed {
+        diff.push_str(&format!("-{line}\n"));
+    }
+    for line in added {
+        diff.push_str(&format!("+{line}\n"));
+    }
+    diff
+}
+
+fn ids(units: &[CommitUnit]) -> Vec<&str> {
+    units.iter().map(|unit| unit.id.as_str()).collect()
+}
+
+fn dependency(unit: &str, depends_on: &str, kind: SymbolKind, symbol: &str) -> Dependency {
+    Dependency {
+        unit: unit.to_string(),
+        depends_on: depends_on.to_string(),
+        kind,
+        symbol: symbol.to_string(),
+    }
+}
+
+#[test]
+fn new_module_and_its_declaration_are_ordered() {
+    let diff = [
+        changed_file("src/lib.rs", &[], &["pub mod ordering;"]),
+        new_file("src/ordering.rs", &["pub fn order_units() {}"]),
+    ]
+    .concat();
+    let mut units = vec![unit("commit-1", &["src/lib.rs"]), unit("commit-2", &["src/ordering.rs"])];
+
+    let report = order_units(&mut units, &diff);
+    assert!(report.moved);
+    assert_eq!(ids(&units), vec!["commit-2", "commit-1"]);
+    assert_eq!(report.previous, vec!["commit-1", "commit-2"]);
+    assert_eq!(
+        report.dependencies,
+        vec![dependency("commit-1", "commit-2", SymbolKind::Module, "ordering")]
+    );
+    assert!(report.cycle.is_empty());
+}
+
+#[test]
+fn call_sites_follow_new_functions() {
+    let diff = [
+        changed_file("src/main.rs", &["    plan(diff);"], &["    let plan = plan(diff);", "    render_summary(&plan);"]),
+        changed_file("src/docs.md", &[], &["Summaries are rendered."]),
+        changed_file(
+            "src/render.rs",
+            &[],
+            &["pub fn render_summary(plan: &Plan) -> String {", "    String::new()", "}"],
+        ),
+    ]
+    .concat();
+    let mut units = vec![
+        unit("commit-1", &["src/main.rs"]),
+        unit("commit-2", &["src/docs.md"]),
+        unit("commit-3", &["src/render.rs"]),
+    ];
+
+    let report = order_units(&mut units, &diff);
+    assert_eq!(ids(&units), vec!["commit-2", "commit-3", "commit-1"]);
+    assert_eq!(report.dependencies[0].kind, SymbolKind::Function);
+    assert_eq!(report.dependencies[0].symbol, "render_summary");
+}
+
+#[test]
+fn moved_and_common_functions_are_not_dependencies() {
+    let diff = [
+        changed_file("src/old.rs", &["fn helper_value() -> u32 {"], &[]),
+        changed_file("src/new.rs", &[], &["fn helper_value() -> u32 {", "fn new() -> Self {"]),
+        changed_file("src/main.rs", &[], &["let value = helper_value();", "let state = new();"]),
+    ]
+    .concat();
+    let units = vec![
+        unit("commit-1", &["src/main.rs", "src/old.rs"]),
+        unit("commit-2", &["src/new.rs"]),
+    ];
+    assert!(unit_dependencies(&units, &diff).is_empty());
+}
+
+#[test]
+fn method_calls_need_an_import_to_count() {
+    let diff = [
+        changed_file("src/main.rs", &[], &["let plan = input.parse_plan();"]),
+        changed_file("src/cli.rs", &[], &["let plan = args.parse_plan()?;", "use crate::plan::parse_plan;"]),
+        changed_file("src/plan.rs", &[], &["pub fn parse_plan(input: &str) -> Plan {"]),
+        changed_file("src/lib.rs", &[], &["let plan = plan::parse_plan(text);"]),
+    ]
+    .concat();
+    let units = vec![
+        unit("commit-1", &["src/main.rs"]),
+        unit("commit-2", &["src/cli.rs"]),
+        unit("commit-3", &["src/plan.rs"]),
+        unit("commit-4", &["src/lib.rs"]),
+    ];
+
+    assert_eq!(
+        unit_dependencies(&units, &diff),
+        vec![
+            dependency("commit-2", "commit-3", SymbolKind::Function, "parse_plan"),
+            dependency("commit-4", "commit-3", SymbolKind::Function, "parse_plan"),
+        ]
+    );
+}
+
+#[test]
+fn manifest_packages_come_before_their_imports() {
+    let diff = [
+        changed_file("src/cache.rs", &[], &["use serde_json::Value;"]),
+        changed_file("web/app.ts", &[], &["import pad from \"left-pad\";"]),
+        changed_file("tools/run.py", &[], &["import requests"]),
+        changed_file(
+            "Cargo.toml",
+            &[],
+            &["version = \"0.2.0\"", "serde-json = { version = \"1\" }"],
+        ),
+        changed_file("web/package.json", &[], &["    \"left-pad\": \"^1.3.0\","]),
+        changed_file("tools/requirements.txt", &[], &["requests>=2.31"]),
+    ]
+    .concat();
+    let units = vec![
+        unit("commit-1", &["src/cache.rs", "web/app.ts", "tools/run.py"]),
+        unit("commit-2", &["Cargo.toml"]),
+        unit("commit-3", &["web/package.json"]),
+        unit("commit-4", &["tools/requirements.txt"]),
+    ];
+
+    let dependencies = unit_dependencies(&units, &diff);
+    assert_eq!(
+        dependencies,
+        vec![
+            dependency("commit-1", "commit-2", SymbolKind::Package, "serde_json"),
+            dependency("commit-1", "commit-3", SymbolKind::Package, "left-pad"),
+            dependency("commit-1", "commit-4", SymbolKind::Package, "requests"),
+        ]
+    );
+    assert_eq!(late_dependencies(&units, &dependencies).len(), 3);
+}
+
+#[test]
+fn ordered_plans_are_left_alone() {
+    let diff = [
+        new_file("src/ordering.rs", &["pub fn order_units() {}"]),
+        changed_file("src/lib.rs", &[], &["pub mod ordering;"]),
+    ]
+    .concat();
+    let mut units = vec![unit("commit-1", &["src/ordering.rs"]), unit("commit-2", &["src/lib.rs"])];
+
+    let report = order_units(&mut units, &diff);
+    assert!(!report.moved);
+    assert_eq!(report.dependencies.len(), 1);
+    assert!(late_dependencies(&units, &report.dependencies).is_empty());
+    assert_eq!(ids(&units), vec!["commit-1", "commit-2"]);
+}
+
+#[test]
+fn cycles_keep_model_order() {
+    let diff = [
+        changed_file("src/a.rs", &[], &["pub fn alpha_step() {", "    beta_step();", "}"]),
+        changed_file("src/b.rs", &[], &["pub fn beta_step() {", "    alpha_step();", "}"]),
+        changed_file("src/c.rs", &[], &["pub fn gamma_step() {}"]),
+    ]
+    .concat();
+    let mut units = vec![
+        unit("commit-1", &["src/a.rs"]),
+        unit("commit-2", &["src/b.rs"]),
+        unit("commit-3", &["src/c.rs"]),
+    ];
+
+    let report = order_units(&mut units, &diff);
+    assert_eq!(ids(&units), vec!["commit-3", "commit-1", "commit-2"]);
+    assert_eq!(report.cycle, vec!["commit-1", "commit-2"]);
+}
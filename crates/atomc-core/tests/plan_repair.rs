@@ -0,0 +1,105 @@
+use atomc_core::repair::{repair_plan, RepairField};
+use atomc_core::semantic::{self, ScopePolicy};
+use atomc_core::types::{CommitType, CommitUnit};
+
+const SUMMARY: &str = "repair mechanical mistakes in model commit plans early";
+
+fn unit(id: &str, scope: Option<&str>, summary: &str, body: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_: CommitType::Fix,
+        scope: scope.map(str::to_string),
+        summary: summary.to_string(),
+        body: body.iter().map(|line| line.to_string()).collect(),
+        files: vec!["src/repair.rs".to_string()],
+        hunks: Vec::new(),
+    }
+}
+
+#[test]
+fn repair_plan_leaves_valid_units_alone() {
+    let mut units = vec![unit("c1", Some("core"), SUMMARY, &["Explain the change."])];
+    assert!(repair_plan(&mut units).is_empty());
+}
+
+#[test]
+fn repair_plan_fixes_mechanical_mistakes() {
+    let mut units = vec![
+        unit(" c1 ", Some("PlanRepair"), &format!("fix(core): {SUMMARY}  "), &["- Strip bullets.", "  "]),
+        unit("", Some("git_adapter"), &format!("Feat[git]!: {SUMMARY}"), &["* Renumber ids."]),
+        unit("c1", Some("  "), SUMMARY, &["Trim whitespace.  "]),
+    ];
+
+    let repairs = repair_plan(&mut units);
+
+    let ids: Vec<&str> = units.iter().map(|unit| unit.id.as_str()).collect();
+    assert_eq!(ids, vec!["c1", "commit-1", "commit-2"]);
+    assert_eq!(units[0].scope.as_deref(), Some("plan-repair"));
+    assert_eq!(units[1].scope.as_deref(), Some("git-adapter"));
+    assert_eq!(units[2].scope, None);
+    assert!(units.iter().all(|unit| unit.summary == SUMMARY));
+    assert_eq!(units[0].body, vec!["Strip bullets."]);
+    assert_eq!(units[1].body, vec!["Renumber ids."]);
+    assert_eq!(units[2].body, vec!["Trim whitespace."]);
+    assert!(semantic::validate_commit_units(&units, ScopePolicy::Warn).is_ok());
+
+    let unit_one: Vec<RepairField> = repairs
+        .iter()
+        .filter(|repair| repair.unit == "commit-1")
+        .map(|repair| repair.field)
+        .collect();
+    assert_eq!(
+        unit_one,
+        vec![RepairField::Id, RepairField::Scope, RepairField::Summary, RepairField::Body]
+    );
+    let scope = repairs
+        .iter()
+        .find(|repair| repair.unit == "c1" && repair.field == RepairField::Scope)
+        .unwrap();
+    assert_eq!((scope.before.as_str(), scope.after.as_str()), ("PlanRepair", "plan-repair"));
+}
+
+#[test]
+fn repair_plan_keeps_prefixes_that_are_not_commit_types() {
+    let summary = "Note: keep colons in summaries that merely start with a label";
+    let mut units = vec![unit("c1", Some("core"), summary, &["Explain the change."])];
+
+    assert!(repair_plan(&mut units).is_empty());
+    assert_eq!(units[0].summary, summary);
+}
+
+#[test]
+fn repair_plan_records_dropped_blank_body_lines() {
+    let mut units = vec![unit("c1", Some("core"), SUMMARY, &["Explain the change.", ""])];
+
+    let repairs = repair_plan(&mut units);
+    assert_eq!(units[0].body, vec!["Explain the change."]);
+    assert_eq!(repairs.len(), 1);
+    assert_eq!(repairs[0].field, RepairField::Body);
+    assert_eq!((repairs[0].before.as_str(), repairs[0].after.as_str()), ("", ""));
+}
+
+#[test]
+fn repair_plan_leaves_non_ascii_scopes_to_validation() {
+    let mut units = vec![unit("c1", Some("café"), SUMMARY, &["Explain the change."])];
+
+    assert!(repair_plan(&mut units).is_empty());
+    assert_eq!(units[0].scope.as_deref(), Some("café"));
+    assert!(semantic::validate_commit_units(&units, ScopePolicy::Warn).is_err());
+}
+
+#[test]
+fn repair_plan_leaves_summary_length_to_validation() {
+    let short = "repair mechanical mistakes in model plans early";
+    let long = format!("{SUMMARY}, including over-long summaries");
+    let mut units = vec![
+        unit("c1", Some("core"), short, &["Explain the change."]),
+        unit("c2", Some("core"), &long, &["Explain the change."]),
+    ];
+
+    assert!(repair_plan(&mut units).is_empty());
+    assert_eq!(units[0].summary, short);
+    assert_eq!(units[1].summary, long);
+    let errors = semantic::repair_and_validate_commit_units(&mut units, ScopePolicy::Warn).unwrap_err();
+    assert_eq!(errors.len(), 2);
+}
@@ -0,0 +1,203 @@
+use atomc_core::config::{BackendConfig, ResolvedConfig};
+use atomc_core::llm::{LlmError, Prompt};
+use atomc_core::retry::{plan_with_retry, AttemptOutcome, RetryError, RetryPolicy};
+use atomc_core::semantic::{self, ScopePolicy, SemanticValidationErrors};
+use atomc_core::types::{BackendMeta, CommitPlan, CommitType, CommitUnit};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const SUMMARY: &str = "retry commit plans with per-error recovery strategies";
+
+fn prompt() -> Prompt {
+    Prompt {
+        system: "system".to_string(),
+        user: "user".to_string(),
+        version: "test".to_string(),
+    }
+}
+
+fn plan(summary: &str) -> CommitPlan {
+    CommitPlan {
+        schema_version: "v1".to_string(),
+        request_id: None,
+        warnings: None,
+        input: None,
+        backend: None,
+        plan: vec![CommitUnit {
+            id: "c1".to_string(),
+            type_: CommitType::Feat,
+            scope: Some("core".to_string()),
+            summary: summary.to_string(),
+            body: vec!["Explain the change.".to_string()],
+            files: vec!["src/retry.rs".to_string()],
+            hunks: Vec::new(),
+        }],
+    }
+}
+
+fn policy(max_attempts: u32, backoff_ms: u64) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        backoff: Duration::from_millis(backoff_ms),
+    }
+}
+
+fn validate(plan: &mut CommitPlan) -> Result<usize, SemanticValidationErrors> {
+    semantic::validate_commit_units(&plan.plan, ScopePolicy::Require).map(|report| report.warnings.len())
+}
+
+/// Replays `responses` in order and records each call's temperature and
+/// system prompt.
+struct Script {
+    responses: RefCell<VecDeque<Result<CommitPlan, LlmError>>>,
+    calls: RefCell<Vec<(f32, String)>>,
+}
+
+impl Script {
+    fn new(responses: Vec<Result<CommitPlan, LlmError>>) -> Self {
+        Self {
+            responses: RefCell::new(responses.into()),
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+
+    async fn request(&self, config: ResolvedConfig, prompt: Prompt) -> Result<CommitPlan, LlmError> {
+        self.calls.borrow_mut().push((config.temperature, prompt.system));
+        self.responses.borrow_mut().pop_front().expect("unexpected request")
+    }
+}
+
+#[tokio::test]
+async fn plan_with_retry_lowers_temperature_after_parse_errors() {
+    let config = ResolvedConfig::defaults();
+    let script = Script::new(vec![Err(LlmError::Parse("not json".to_string())), Ok(plan(SUMMARY))]);
+
+    let run = plan_with_retry(&config, &prompt(), &policy(3, 0), |config, prompt, _| script.request(config, prompt), validate).await;
+
+    assert!(run.result.is_ok());
+    assert_eq!(run.attempts.len(), 2);
+    assert_eq!(run.attempts[0].outcome, AttemptOutcome::ParseError);
+    assert_eq!(run.attempts[1].outcome, AttemptOutcome::Accepted);
+    let calls = script.calls.borrow();
+    assert_eq!(calls[0].0, config.temperature);
+    assert_eq!(calls[1].0, config.temperature / 2.0);
+    assert!(calls[1].1.contains("Your previous response was not valid JSON for the schema:"));
+    assert!(calls[1].1.contains("not json"));
+    assert!(!calls[1].1.contains("semantic validation"));
+}
+
+#[tokio::test]
+async fn plan_with_retry_feeds_back_failing_units() {
+    let config = ResolvedConfig::defaults();
+    let script = Script::new(vec![Ok(plan("too short")), Ok(plan(SUMMARY))]);
+
+    let run = plan_with_retry(&config, &prompt(), &policy(3, 0), |config, prompt, _| script.request(config, prompt), validate).await;
+
+    assert!(run.result.is_ok());
+    assert_eq!(run.attempts[0].outcome, AttemptOutcome::SemanticError);
+    assert_eq!(run.attempts[0].failing_units, vec!["c1"]);
+    let calls = script.calls.borrow();
+    assert_eq!(calls[1].0, config.temperature);
+    assert!(calls[1].1.contains("Fix only these commits: c1."));
+    assert!(calls[1].1.contains("\"summary\":\"too short\""));
+}
+
+#[tokio::test]
+async fn plan_with_retry_backs_off_on_runtime_errors_until_budget_is_spent() {
+    let config = ResolvedConfig::defaults();
+    let script = Script::new(vec![
+        Err(LlmError::Runtime("connection refused".to_string())),
+        Err(LlmError::Runtime("connection refused".to_string())),
+        Err(LlmError::Runtime("connection refused".to_string())),
+    ]);
+
+    let run = plan_with_retry(&config, &prompt(), &policy(3, 1), |config, prompt, _| script.request(config, prompt), validate).await;
+
+    assert!(matches!(run.result, Err(RetryError::Llm(LlmError::Runtime(_)))));
+    let backoffs: Vec<Option<u64>> = run.attempts.iter().map(|attempt| attempt.backoff_ms).collect();
+    assert_eq!(backoffs, vec![Some(1), Some(2), None]);
+    assert!(script.calls.borrow().iter().all(|(_, system)| system == "system"));
+}
+
+#[tokio::test]
+async fn plan_with_retry_stops_on_timeouts_and_reports_semantic_failures() {
+    let config = ResolvedConfig::defaults();
+    let script = Script::new(vec![Err(LlmError::Timeout)]);
+    let run = plan_with_retry(&config, &prompt(), &policy(3, 0), |config, prompt, _| script.request(config, prompt), validate).await;
+    assert!(matches!(run.result, Err(RetryError::Llm(LlmError::Timeout))));
+    assert_eq!(run.attempts.len(), 1);
+
+    let script = Script::new(vec![Ok(plan("too short")), Ok(plan("still short"))]);
+    let run = plan_with_retry(&config, &prompt(), &policy(2, 0), |config, prompt, _| script.request(config, prompt), validate).await;
+    match run.result {
+        Err(RetryError::Semantic(errors)) => assert_eq!(errors.len(), 1),
+        other => panic!("expected semantic failure, got {other:?}"),
+    }
+    assert_eq!(run.attempts.len(), 2);
+}
+
+#[test]
+fn retry_policy_caps_backoff_and_reads_config() {
+    let config = ResolvedConfig {
+        llm_max_attempts: 0,
+        llm_retry_backoff_ms: 1_000,
+        ..ResolvedConfig::defaults()
+    };
+    let policy = RetryPolicy::from_config(&config);
+    assert_eq!(policy.max_attempts, 1);
+    assert_eq!(policy.backoff_for(1), Duration::from_secs(1));
+    assert_eq!(policy.backoff_for(3), Duration::from_secs(4));
+    assert_eq!(policy.backoff_for(40), Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn plan_with_retry_escalates_to_next_backend_after_semantic_errors() {
+    let config = ResolvedConfig {
+        backends: vec![
+            BackendConfig {
+                name: Some("local".to_string()),
+                model: Some("small".to_string()),
+                ..BackendConfig::default()
+            },
+            BackendConfig {
+                name: Some("lan".to_string()),
+                url: Some("http://gpu-box:11434".to_string()),
+                model: Some("large".to_string()),
+                ..BackendConfig::default()
+            },
+        ],
+        ..ResolvedConfig::defaults()
+    };
+    let models = RefCell::new(Vec::new());
+
+    let run = plan_with_retry(
+        &config,
+        &prompt(),
+        &policy(3, 0),
+        |config, _, _| {
+            let backend = config.backend_chain().remove(0);
+            models.borrow_mut().push(backend.model.clone());
+            let summary = if backend.name == "local" { "too short" } else { SUMMARY };
+            let plan = CommitPlan {
+                backend: Some(BackendMeta {
+                    name: backend.name,
+                    runtime: backend.runtime.as_str().to_string(),
+                    url: backend.url,
+                    model: backend.model,
+                }),
+                ..plan(summary)
+            };
+            async move { Ok(plan) }
+        },
+        validate,
+    )
+    .await;
+
+    let (plan, _) = run.result.unwrap();
+    assert_eq!(plan.backend.unwrap().name, "lan");
+    assert_eq!(*models.borrow(), ["small", "large"]);
+    assert_eq!(run.attempts[0].outcome, AttemptOutcome::SemanticError);
+    assert_eq!(run.attempts[0].backend.as_deref(), Some("local"));
+    assert_eq!(run.attempts[1].backend.as_deref(), Some("lan"));
+}
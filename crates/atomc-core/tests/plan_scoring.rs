@@ -0,0 +1,165 @@
+use atomc_core::config::{AtomicityPolicy, ResolvedConfig};
+use atomc_core::llm::LlmOptions;
+use atomc_core::scoring::{best_score, score_plan, PlanScore};
+use atomc_core::semantic::ScopePolicy;
+use atomc_core::types::{CommitType, CommitUnit, Hunk};
+
+fn unit(id: &str, summary: &str, files: &[&str]) -> CommitUnit {
+    CommitUnit {
+        id: id.to_string(),
+        type_: CommitType::Feat,
+        scope: Some("core".to_string()),
+        summary: summary.to_string(),
+        body: vec!["Explain the change.".to_string()],
+        files: files.iter().map(|file| file.to_string()).collect(),
+        hunks: Vec::new(),
+    }
+}
+
+fn paths(files: &[&str]) -> Vec<String> {
+    files.iter().map(|file| file.to_string()).collect()
+}
+
+/// A diff adding one line to each of `files`.
+fn diff(files: &[&str]) -> String {
+    files
+        .iter()
+        .map(|file| format!("diff --git a/{file} b/{file}\n--- a/{file}\n+++ b/{file}\n@@ -1 +1,2 @@\n+line\n"))
+        .collect()
+}
+
+fn score(units: &[CommitUnit], diff: &str) -> PlanScore {
+    score_plan(units, diff, &[], ScopePolicy::Warn, AtomicityPolicy::Standard)
+}
+
+const SUMMARY: &str = "add plan scoring for sampled candidate commit plans";
+
+#[test]
+fn score_plan_rewards_valid_complete_plans() {
+    let diff = diff(&["src/a.rs", "src/b.rs"]);
+    let complete = vec![
+        unit("c1", SUMMARY, &["src/a.rs"]),
+        unit("c2", SUMMARY, &["src/b.rs"]),
+    ];
+    let partial = vec![unit("c1", SUMMARY, &["src/a.rs"])];
+    let invalid = vec![
+        unit("c1", "too short", &["src/a.rs"]),
+        unit("c2", SUMMARY, &["src/b.rs"]),
+    ];
+
+    let complete = score(&complete, &diff);
+    let partial = score(&partial, &diff);
+    let invalid = score(&invalid, &diff);
+
+    assert!(complete.is_valid());
+    assert_eq!(complete.coverage, 1.0);
+    assert_eq!(partial.coverage, 0.5);
+    assert!(!invalid.is_valid());
+    assert!(complete.total > partial.total);
+    assert!(partial.total > invalid.total);
+}
+
+#[test]
+fn score_plan_counts_required_files_and_warnings() {
+    let diff = diff(&["src/a.rs", "assets/logo.png"]);
+    let mut units = vec![unit("c1", SUMMARY, &["src/a.rs"])];
+    units[0].scope = None;
+
+    let required = paths(&["assets/logo.png"]);
+    let score = score_plan(&units, &diff, &required, ScopePolicy::Warn, AtomicityPolicy::Standard);
+    assert_eq!(score.errors, 1);
+    assert_eq!(score.warnings, 1);
+}
+
+#[test]
+fn score_plan_penalizes_non_atomic_units() {
+    let diff = diff(&["src/a.rs", "tests/a.rs"]);
+    let atomic = vec![
+        unit("c1", SUMMARY, &["src/a.rs"]),
+        unit("c2", SUMMARY, &["tests/a.rs"]),
+    ];
+    let mixed = vec![unit("c1", SUMMARY, &["src/a.rs", "tests/a.rs"])];
+    let shared = vec![
+        unit("c1", SUMMARY, &["src/a.rs", "tests/a.rs"]),
+        unit("c2", SUMMARY, &["tests/a.rs"]),
+    ];
+
+    let atomic = score(&atomic, &diff);
+    let mixed = score(&mixed, &diff);
+    let shared = score(&shared, &diff);
+
+    assert_eq!(atomic.non_atomic, 0);
+    assert_eq!(mixed.non_atomic, 1);
+    assert_eq!(shared.shared_files, 1);
+    assert!(atomic.total > mixed.total);
+    assert!(mixed.total > shared.total);
+}
+
+#[test]
+fn score_plan_applies_atomicity_policy() {
+    let diff = diff(&["docs/a.md", "Cargo.toml", "src/a.rs"]);
+    let units = vec![
+        unit("c1", SUMMARY, &["docs/a.md"]),
+        unit("c2", SUMMARY, &["Cargo.toml", "src/a.rs"]),
+    ];
+
+    let standard = score(&units, &diff);
+    assert_eq!((standard.errors, standard.non_atomic), (1, 0));
+    assert!(!standard.is_valid());
+
+    let off = score_plan(&units, &diff, &[], ScopePolicy::Warn, AtomicityPolicy::Off);
+    assert!(off.is_valid());
+}
+
+#[test]
+fn score_plan_allows_files_split_by_hunks() {
+    let diff = diff(&["src/a.rs"]);
+    let mut units = vec![
+        unit("c1", SUMMARY, &["src/a.rs"]),
+        unit("c2", SUMMARY, &["src/a.rs"]),
+    ];
+    for (unit, header) in units.iter_mut().zip(["@@ -1,2 +1,2 @@", "@@ -10,2 +10,2 @@"]) {
+        unit.hunks.push(Hunk {
+            file: "src/a.rs".to_string(),
+            header: header.to_string(),
+            id: None,
+        });
+    }
+
+    assert_eq!(score(&units, &diff).shared_files, 0);
+}
+
+#[test]
+fn best_score_prefers_earliest_on_ties() {
+    let diff = diff(&["src/a.rs"]);
+    let valid = score(&[unit("c1", SUMMARY, &["src/a.rs"])], &diff);
+    let invalid = score(&[unit("c1", "short", &["src/a.rs"])], &diff);
+
+    assert_eq!(best_score(&[invalid.clone(), valid.clone(), valid.clone()]), Some(1));
+    assert_eq!(best_score(&[valid, invalid]), Some(0));
+    assert_eq!(best_score(&[]), None);
+}
+
+#[test]
+fn candidate_options_vary_temperature_and_seed() {
+    let config = ResolvedConfig::defaults();
+    let first = LlmOptions::candidate(&config, 0);
+    let second = LlmOptions::candidate(&config, 1);
+    let last = LlmOptions::candidate(&config, 20);
+
+    assert_eq!(first.temperature, config.temperature);
+    assert_eq!(first.seed, Some(0));
+    assert!(second.temperature > first.temperature);
+    assert_eq!(second.seed, Some(1));
+    assert_eq!(last.temperature, 1.0);
+}
+
+#[test]
+fn score_plan_scores_repaired_units() {
+    let diff = diff(&["src/a.rs"]);
+    let units = vec![unit("", &format!("feat(core): {SUMMARY}"), &["src/a.rs"])];
+
+    let score = score(&units, &diff);
+    assert!(score.is_valid());
+    assert_eq!(units[0].id, "");
+}
@@ -0,0 +1,109 @@
+mod support;
+
+use atomc_core::config::ResolvedConfig;
+use atomc_core::llm::{ExampleError, ExampleSet, PromptContext, PromptTemplates};
+use std::fs;
+use std::path::{Path, PathBuf};
+use support::create_temp_dir;
+
+const MULTIPLY_DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n\
+--- a/src/lib.rs\n\
++++ b/src/lib.rs\n\
+@@ -1,3 +1,7 @@\n\
+ pub fn add(a: i32, b: i32) -> i32 {\n\
++pub fn mul(a: i32, b: i32) -> i32 {\n\
++    a * b\n\
++}\n";
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures")
+}
+
+fn names(examples: &[&atomc_core::llm::Example]) -> Vec<String> {
+    examples.iter().map(|example| example.name.clone()).collect()
+}
+
+#[test]
+fn builtin_examples_are_the_fixture_gold_pairs() {
+    let builtin = ExampleSet::builtin();
+    let from_fixtures = ExampleSet::from_dir(&fixtures_dir()).unwrap();
+
+    let mut builtin_names: Vec<_> = builtin.examples.iter().map(|example| example.name.clone()).collect();
+    builtin_names.sort();
+    let fixture_names: Vec<_> = from_fixtures.examples.iter().map(|example| example.name.clone()).collect();
+    assert_eq!(builtin_names, fixture_names);
+    assert_eq!(
+        fixture_names,
+        vec!["mixed_concerns", "refactor_plus_feature", "simple_feature"]
+    );
+}
+
+#[test]
+fn select_prefers_similar_examples() {
+    let set = ExampleSet::builtin();
+    let selected = set.select(MULTIPLY_DIFF, 1, 10_000);
+    assert_eq!(names(&selected), vec!["simple_feature"]);
+
+    let all = set.select(MULTIPLY_DIFF, 5, 10_000);
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[0].name, "simple_feature");
+    assert!(all[0].similarity(MULTIPLY_DIFF) >= all[1].similarity(MULTIPLY_DIFF));
+    assert!(set.select(MULTIPLY_DIFF, 0, 10_000).is_empty());
+}
+
+#[test]
+fn select_stays_within_token_budget() {
+    let set = ExampleSet::builtin();
+    let smallest = set.examples.iter().map(|example| example.tokens()).min().unwrap();
+
+    let selected = set.select(MULTIPLY_DIFF, 3, smallest);
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].tokens(), smallest);
+    assert!(set.select(MULTIPLY_DIFF, 3, smallest - 1).is_empty());
+}
+
+#[test]
+fn from_dir_reports_invalid_plans() {
+    let dir = create_temp_dir("invalid");
+    fs::create_dir_all(dir.join("diffs")).unwrap();
+    fs::create_dir_all(dir.join("plans")).unwrap();
+    fs::write(dir.join("diffs/broken.diff"), MULTIPLY_DIFF).unwrap();
+    fs::write(dir.join("plans/broken.plan.json"), "{\"plan\": 1}").unwrap();
+
+    match ExampleSet::from_dir(&dir) {
+        Err(ExampleError::Parse { path, .. }) => assert!(path.ends_with("plans/broken.plan.json")),
+        other => panic!("expected parse error, got {other:?}"),
+    }
+    assert!(matches!(
+        ExampleSet::from_dir(&dir.join("missing")),
+        Err(ExampleError::Read { .. })
+    ));
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn prompt_includes_selected_examples_when_enabled() {
+    let context = || PromptContext {
+        repo_path: None,
+        diff_mode: None,
+        include_untracked: None,
+        git_status: None,
+        workspace: None,
+        diff: MULTIPLY_DIFF,
+    };
+
+    let disabled = PromptTemplates::load(&ResolvedConfig::defaults(), None).unwrap();
+    assert!(!disabled.build_prompt(context()).user.contains("Examples:"));
+
+    let config = ResolvedConfig {
+        prompt_examples: 1,
+        examples_dir: Some(fixtures_dir()),
+        ..ResolvedConfig::defaults()
+    };
+    let prompt = PromptTemplates::load(&config, None).unwrap().build_prompt(context());
+    assert!(prompt.user.contains("Examples:\nExample diff:\ndiff --git a/src/lib.rs"));
+    assert!(prompt.user.contains("Example plan:\n{\"plan\":[{"));
+    assert_eq!(prompt.user.matches("Example diff:").count(), 1);
+    assert!(prompt.user.ends_with(MULTIPLY_DIFF));
+}
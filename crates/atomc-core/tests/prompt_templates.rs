@@ -0,0 +1,122 @@
+mod support;
+
+use atomc_core::config::{DiffMode, ResolvedConfig};
+use atomc_core::llm::{self, PromptContext, PromptTemplates, TemplateError, PROMPT_VERSION, REPO_TEMPLATE_PATH};
+use std::fs;
+use std::path::Path;
+use support::create_temp_dir;
+
+fn context<'a>(repo: &'a Path, diff: &'a str) -> PromptContext<'a> {
+    PromptContext {
+        repo_path: Some(repo),
+        diff_mode: Some(DiffMode::Staged),
+        include_untracked: Some(false),
+        git_status: Some("M src/lib.rs"),
+        workspace: None,
+        diff,
+    }
+}
+
+#[test]
+fn builtin_templates_render_context_and_diff() {
+    let repo = Path::new("/repo");
+    let prompt = llm::build_prompt(context(repo, "diff --git a/x b/x\n"));
+
+    assert_eq!(prompt.version, PROMPT_VERSION);
+    assert!(prompt.system.contains("\"schema_version\": \"v1\""));
+    assert!(prompt.user.contains(
+        "- repo_path: /repo\n- diff_mode: staged\n- include_untracked: false\n- git_status: M src/lib.rs\n\nDiff:\ndiff --git a/x b/x\n"
+    ));
+    assert!(!prompt.user.contains("{{"));
+}
+
+#[test]
+fn template_file_overrides_templates_and_fills_sections() {
+    let dir = create_temp_dir("file");
+    let path = dir.join("prompt.toml");
+    fs::write(
+        &path,
+        r#"
+version = "qwen-tuned-2"
+user = """
+{{conventions}}{{scopes}}{{examples}}Status: {{ status }}
+Diff:
+{{diff}}"""
+scopes = ["cli", "core"]
+conventions = "Reference the issue number in the body."
+"#,
+    )
+    .unwrap();
+
+    let templates = PromptTemplates::from_file(&path).unwrap();
+    let prompt = templates.build_prompt(context(&dir, "DIFF"));
+
+    assert_eq!(prompt.version, "qwen-tuned-2");
+    assert_eq!(prompt.system, PromptTemplates::builtin().system);
+    assert_eq!(
+        prompt.user,
+        "Conventions:\nReference the issue number in the body.\n\nScopes:\nPrefer one of: cli, core\n\nStatus: M src/lib.rs\nDiff:\nDIFF"
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn template_file_without_version_gets_content_version() {
+    let dir = create_temp_dir("version");
+    let first = dir.join("first.toml");
+    let second = dir.join("second.toml");
+    fs::write(&first, "user = \"Plan this: {{diff}}\"\n").unwrap();
+    fs::write(&second, "user = \"Plan these changes: {{diff}}\"\n").unwrap();
+
+    let first = PromptTemplates::from_file(&first).unwrap().version;
+    let second = PromptTemplates::from_file(&second).unwrap().version;
+    assert!(first.starts_with("custom-"));
+    assert_eq!(first.len(), "custom-".len() + 12);
+    assert_ne!(first, second);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn template_file_rejects_unknown_placeholders_and_fields() {
+    let dir = create_temp_dir("invalid");
+    let path = dir.join("prompt.toml");
+    fs::write(&path, "user = \"{{diff}} {{branch}}\"\n").unwrap();
+    match PromptTemplates::from_file(&path) {
+        Err(TemplateError::UnknownPlaceholder { name, .. }) => assert_eq!(name, "branch"),
+        other => panic!("expected unknown placeholder, got {other:?}"),
+    }
+
+    fs::write(&path, "sytem = \"typo\"\n").unwrap();
+    assert!(matches!(PromptTemplates::from_file(&path), Err(TemplateError::Parse { .. })));
+    assert!(matches!(
+        PromptTemplates::from_file(&dir.join("missing.toml")),
+        Err(TemplateError::Read { .. })
+    ));
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn load_prefers_config_then_repo_template() {
+    let repo = create_temp_dir("repo");
+    let config = ResolvedConfig::defaults();
+    assert_eq!(PromptTemplates::load(&config, Some(&repo)).unwrap().version, PROMPT_VERSION);
+
+    let repo_template = repo.join(REPO_TEMPLATE_PATH);
+    fs::create_dir_all(repo_template.parent().unwrap()).unwrap();
+    fs::write(&repo_template, "version = \"repo\"\n").unwrap();
+    assert_eq!(PromptTemplates::load(&config, Some(&repo)).unwrap().version, "repo");
+    assert_eq!(PromptTemplates::load(&config, None).unwrap().version, PROMPT_VERSION);
+
+    let explicit = repo.join("explicit.toml");
+    fs::write(&explicit, "version = \"explicit\"\n").unwrap();
+    let config = ResolvedConfig {
+        prompt_template: Some(explicit),
+        ..ResolvedConfig::defaults()
+    };
+    assert_eq!(PromptTemplates::load(&config, Some(&repo)).unwrap().version, "explicit");
+
+    fs::remove_dir_all(&repo).ok();
+}
@@ -91,3 +91,33 @@ fn error_schema_rejects_unknown_code() {
     let result = validate_schema(SchemaKind::ErrorResponse, &payload);
     assert!(result.is_err());
 }
+
+#[test]
+fn workspace_apply_schema_accepts_valid_payload() {
+    let payload = json!({
+        "schema_version": "v1",
+        "repos": [{
+            "repo": "services/api",
+            "response": {
+                "schema_version": "v1",
+                "plan": [base_commit_unit()],
+                "results": [{"id": "commit-1", "status": "planned", "error": null}]
+            },
+            "error": null
+        }]
+    });
+
+    let result = validate_schema(SchemaKind::WorkspaceApply, &payload);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn workspace_apply_schema_rejects_missing_response() {
+    let payload = json!({
+        "schema_version": "v1",
+        "repos": [{"repo": "services/api"}]
+    });
+
+    let result = validate_schema(SchemaKind::WorkspaceApply, &payload);
+    assert!(result.is_err());
+}
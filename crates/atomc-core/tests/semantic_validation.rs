@@ -1,5 +1,6 @@
 use atomc_core::semantic::{
-    validate_commit_units, ScopePolicy, SemanticValidationError, SemanticWarning,
+    validate_commit_units, validate_file_coverage, ScopePolicy, SemanticValidationError,
+    SemanticWarning,
 };
 use atomc_core::types::{CommitType, CommitUnit, Hunk};
 
@@ -140,3 +141,16 @@ fn scope_none_is_error_when_required() {
     let errors = validate_commit_units(&[unit], ScopePolicy::Require).unwrap_err();
     assert!(errors.iter().any(|err| matches!(err, SemanticValidationError::ScopeMissing { .. })));
 }
+
+#[test]
+fn unplanned_required_files_are_reported() {
+    let unit = base_unit();
+    assert!(validate_file_coverage(std::slice::from_ref(&unit), ["src/main.rs"]).is_ok());
+
+    let errors = validate_file_coverage(&[unit], ["src/main.rs", "assets/logo.png"]).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        SemanticValidationError::FileUnplanned { path } if path == "assets/logo.png"
+    ));
+}
@@ -0,0 +1,59 @@
+//! Fixtures shared by the integration tests; each test crate uses only
+//! some of them.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A fresh path under the system temp dir. Nothing is created.
+pub fn temp_dir(prefix: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let count = COUNTER.fetch_add(1, Ordering::SeqCst);
+    std::env::temp_dir().join(format!("atomc-{prefix}-{nanos}-{count}"))
+}
+
+/// [`temp_dir`], created.
+pub fn create_temp_dir(prefix: &str) -> PathBuf {
+    let dir = temp_dir(prefix);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run `git` in `repo` and return its trimmed stdout. Submodules may be
+/// cloned from local paths.
+pub fn run_git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .current_dir(repo)
+        .args(["-c", "protocol.file.allow=always"])
+        .args(args)
+        .output()
+        .expect("git command failed to start");
+    assert!(
+        output.status.success(),
+        "git command failed: git {}: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Create `dir` as an empty repository on `main` with a commit identity.
+pub fn init_repo(dir: &Path) {
+    fs::create_dir_all(dir).unwrap();
+    run_git(dir, &["init", "-q", "-b", "main"]);
+    configure_identity(dir);
+}
+
+/// Set the commit identity of the repository at `dir`.
+pub fn configure_identity(dir: &Path) {
+    run_git(dir, &["config", "user.email", "atomc@example.com"]);
+    run_git(dir, &["config", "user.name", "atomc"]);
+}
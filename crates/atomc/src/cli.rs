@@ -1,3 +1,4 @@
+use atomc_core::config::MAX_PLAN_CANDIDATES;
 use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
@@ -22,6 +23,15 @@ pub struct Cli {
 pub enum Commands {
     Plan(PlanArgs),
     Apply(ApplyArgs),
+    Split(SplitArgs),
+    /// Plan, and optionally apply, changes across several repositories.
+    Workspace(WorkspaceArgs),
+    /// Manage the local plan cache.
+    Cache(CacheArgs),
+    /// Score one or more models on a corpus of diff fixtures.
+    Eval(EvalArgs),
+    /// Check git, the repository, config, and the model runtime.
+    Doctor(DoctorArgs),
     Serve(ServeArgs),
 }
 
@@ -33,22 +43,59 @@ pub struct PlanArgs {
     pub diff_file: Option<PathBuf>,
     #[arg(long, value_enum)]
     pub diff_mode: Option<DiffMode>,
+    /// Plan the commits on HEAD since its merge base with this revision.
+    #[arg(
+        long,
+        value_name = "REV",
+        requires = "repo",
+        conflicts_with_all = ["diff_file", "diff_mode", "range"]
+    )]
+    pub base: Option<String>,
+    /// Plan the committed changes between two revisions.
+    #[arg(
+        long,
+        value_name = "BASE..HEAD",
+        requires = "repo",
+        conflicts_with_all = ["diff_file", "diff_mode"]
+    )]
+    pub range: Option<String>,
     #[arg(long, action = ArgAction::SetTrue)]
     pub include_untracked: bool,
     #[arg(long = "no-include-untracked", action = ArgAction::SetTrue, conflicts_with = "include_untracked")]
     pub no_include_untracked: bool,
+    /// Include changes inside initialized submodules.
+    #[arg(long)]
+    pub recurse_submodules: bool,
+    /// Plan only changed paths matching this glob (repeatable).
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Leave changed paths matching this glob out of the plan (repeatable).
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
     #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
     pub format: OutputFormat,
     #[arg(long, action = ArgAction::SetTrue)]
     pub log_diff: bool,
     #[arg(long = "no-log-diff", action = ArgAction::SetTrue, conflicts_with = "log_diff")]
     pub no_log_diff: bool,
+    /// Ask the model even when a cached plan matches.
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Sample this many plans and keep the best-scoring one (at most 8).
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_PLAN_CANDIDATES as i64))]
+    pub candidates: Option<u32>,
+    /// Show this many similar example plans to the model (0 disables).
+    #[arg(long)]
+    pub examples: Option<u32>,
     #[arg(long)]
     pub model: Option<String>,
     #[arg(long)]
     pub dry_run: bool,
     #[arg(long)]
     pub timeout: Option<u64>,
+    /// Limit the repo diff to these pathspecs, given after `--`.
+    #[arg(last = true, value_name = "PATHSPEC")]
+    pub pathspec: Vec<String>,
 }
 
 impl PlanArgs {
@@ -85,12 +132,30 @@ pub struct ApplyArgs {
     pub include_untracked: bool,
     #[arg(long = "no-include-untracked", action = ArgAction::SetTrue, conflicts_with = "include_untracked")]
     pub no_include_untracked: bool,
+    /// Include changes inside initialized submodules.
+    #[arg(long)]
+    pub recurse_submodules: bool,
+    /// Plan only changed paths matching this glob (repeatable).
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Leave changed paths matching this glob out of the plan (repeatable).
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
     #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
     pub format: OutputFormat,
     #[arg(long, action = ArgAction::SetTrue)]
     pub log_diff: bool,
     #[arg(long = "no-log-diff", action = ArgAction::SetTrue, conflicts_with = "log_diff")]
     pub no_log_diff: bool,
+    /// Ask the model even when a cached plan matches.
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Sample this many plans and keep the best-scoring one (at most 8).
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_PLAN_CANDIDATES as i64))]
+    pub candidates: Option<u32>,
+    /// Show this many similar example plans to the model (0 disables).
+    #[arg(long)]
+    pub examples: Option<u32>,
     #[arg(long)]
     pub model: Option<String>,
     #[arg(long)]
@@ -99,8 +164,31 @@ pub struct ApplyArgs {
     pub execute: bool,
     #[arg(long)]
     pub cleanup_on_error: bool,
+    /// How to treat repository commit hooks.
+    #[arg(long, value_enum, default_value_t = HookMode::FailFast)]
+    pub hooks: HookMode,
+    /// Sign each commit (`git commit -S`).
+    #[arg(long)]
+    pub sign: bool,
+    /// Key id to sign with; implies `--sign`.
+    #[arg(long)]
+    pub signing_key: Option<String>,
+    /// Command run in a clean checkout of each new commit (e.g. `cargo check`).
+    #[arg(long)]
+    pub verify_command: Option<String>,
+    /// Undo the applied commits when the verify command fails. Requires a
+    /// verify command, from `--verify-command` or config.
+    #[arg(long)]
+    pub rollback_on_verify_failure: bool,
+    /// Abort if anything in the diff changed since planning, not only the
+    /// planned files.
+    #[arg(long)]
+    pub strict_diff_hash: bool,
     #[arg(long)]
     pub timeout: Option<u64>,
+    /// Limit the repo diff to these pathspecs, given after `--`.
+    #[arg(last = true, value_name = "PATHSPEC")]
+    pub pathspec: Vec<String>,
 }
 
 impl ApplyArgs {
@@ -125,6 +213,180 @@ impl ApplyArgs {
     }
 }
 
+#[derive(Args, Debug)]
+pub struct SplitArgs {
+    /// Commit range to rewrite: `<base>..<head>`, or `<base>` for `<base>..HEAD`.
+    pub range: String,
+    #[arg(long)]
+    pub repo: PathBuf,
+    /// Branch to create for the rebuilt history.
+    #[arg(long)]
+    pub branch: Option<String>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub log_diff: bool,
+    #[arg(long = "no-log-diff", action = ArgAction::SetTrue, conflicts_with = "log_diff")]
+    pub no_log_diff: bool,
+    #[arg(long)]
+    pub model: Option<String>,
+    #[arg(long)]
+    pub assisted_by: Option<String>,
+    #[arg(long)]
+    pub execute: bool,
+    #[arg(long)]
+    pub timeout: Option<u64>,
+}
+
+impl SplitArgs {
+    pub fn log_diff_override(&self) -> Option<bool> {
+        if self.no_log_diff {
+            Some(false)
+        } else if self.log_diff {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct WorkspaceArgs {
+    /// Repository to include (repeatable).
+    #[arg(long = "repo", value_name = "PATH")]
+    pub repos: Vec<PathBuf>,
+    /// Include every repository found under this directory.
+    #[arg(long, value_name = "DIR")]
+    pub discover: Option<PathBuf>,
+    #[arg(long, value_enum)]
+    pub diff_mode: Option<DiffMode>,
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub include_untracked: bool,
+    #[arg(long = "no-include-untracked", action = ArgAction::SetTrue, conflicts_with = "include_untracked")]
+    pub no_include_untracked: bool,
+    /// Plan only changed paths matching this glob (repeatable).
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Leave changed paths matching this glob out of the plan (repeatable).
+    #[arg(long = "exclude", value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub log_diff: bool,
+    #[arg(long = "no-log-diff", action = ArgAction::SetTrue, conflicts_with = "log_diff")]
+    pub no_log_diff: bool,
+    /// Ask the model even when a cached plan matches.
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Sample this many plans and keep the best-scoring one (at most 8).
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_PLAN_CANDIDATES as i64))]
+    pub candidates: Option<u32>,
+    /// Show this many similar example plans to the model (0 disables).
+    #[arg(long)]
+    pub examples: Option<u32>,
+    #[arg(long)]
+    pub model: Option<String>,
+    #[arg(long)]
+    pub assisted_by: Option<String>,
+    #[arg(long)]
+    pub execute: bool,
+    #[arg(long)]
+    pub cleanup_on_error: bool,
+    /// How to treat repository commit hooks.
+    #[arg(long, value_enum, default_value_t = HookMode::FailFast)]
+    pub hooks: HookMode,
+    /// Sign each commit (`git commit -S`).
+    #[arg(long)]
+    pub sign: bool,
+    /// Key id to sign with; implies `--sign`.
+    #[arg(long)]
+    pub signing_key: Option<String>,
+    /// Command run in a clean checkout of each new commit (e.g. `cargo check`).
+    #[arg(long)]
+    pub verify_command: Option<String>,
+    /// Undo the commits in every repository when one fails to apply or
+    /// verify.
+    #[arg(long)]
+    pub rollback_on_verify_failure: bool,
+    /// Abort if anything in a repository's diff changed since planning, not
+    /// only the planned files.
+    #[arg(long)]
+    pub strict_diff_hash: bool,
+    #[arg(long)]
+    pub timeout: Option<u64>,
+}
+
+impl WorkspaceArgs {
+    pub fn include_untracked_override(&self) -> Option<bool> {
+        if self.no_include_untracked {
+            Some(false)
+        } else if self.include_untracked {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    pub fn log_diff_override(&self) -> Option<bool> {
+        if self.no_log_diff {
+            Some(false)
+        } else if self.log_diff {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Remove every cached plan.
+    Clear(CacheClearArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct CacheClearArgs {
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct EvalArgs {
+    /// Directory with `diffs/*.diff` and optional expected
+    /// `plans/<name>.plan.json` files.
+    #[arg(long, value_name = "DIR", default_value = "tests/fixtures")]
+    pub fixtures: PathBuf,
+    /// Model to evaluate (repeatable); the configured model when omitted.
+    #[arg(long = "model", value_name = "MODEL")]
+    pub models: Vec<String>,
+    /// Also write the JSON report to this file.
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+    /// Show this many similar example plans to the model (0 disables).
+    #[arg(long)]
+    pub examples: Option<u32>,
+    #[arg(long)]
+    pub timeout: Option<u64>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Repository to inspect; the current directory when omitted.
+    #[arg(long)]
+    pub repo: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+}
+
 #[derive(Args, Debug)]
 pub struct ServeArgs {
     #[arg(long, default_value = "127.0.0.1")]
@@ -162,6 +424,13 @@ pub enum DiffMode {
     All,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum HookMode {
+    Run,
+    Skip,
+    FailFast,
+}
+
 #[derive(ValueEnum, Debug, Clone, Copy)]
 pub enum OutputFormat {
     Json,
@@ -1,24 +1,40 @@
 mod cli;
 
-use atomc_core::config::{self, ConfigError, PartialConfig, ResolvedConfig};
+use atomc_core::cache::{self, CacheError, CacheKey, PlanCache};
+use atomc_core::config::{
+    self, AtomicityPolicy, ConfigError, ConfigLayer, DependencyOrder, PartialConfig, ResolvedConfig,
+};
+use atomc_core::eval::{self, EvalReport};
 use atomc_core::git::{self, GitError};
 use atomc_core::hash;
-use atomc_core::llm::{self, LlmError, Prompt, PromptContext};
+use atomc_core::health::{self, CheckStatus, HealthReport};
+use atomc_core::llm::{self, LlmError, Prompt, PromptContext, PromptTemplates};
+use atomc_core::ordering;
+use atomc_core::repair::Repair;
+use atomc_core::retry::{self, PlanAttempt, PlanRun, RetryError, RetryPolicy};
 use atomc_core::schema::{self, SchemaKind};
-use atomc_core::semantic::{self, ScopePolicy, SemanticWarning};
+use atomc_core::scoring;
+use atomc_core::semantic::{
+    self, ScopePolicy, SemanticValidationError, SemanticValidationErrors, SemanticValidationReport,
+    SemanticWarning,
+};
 use atomc_core::types::{
     ApplyResult, ApplyStatus, CommitApplyResponse, CommitPlan, CommitUnit, DiffMode as OutputDiffMode,
-    ErrorDetail, ErrorResponse, InputMeta, InputSource, Warning,
+    ErrorDetail, ErrorResponse, FileKind, InputMeta, InputRevisions, InputSource, RepoApplyResponse, Warning,
+    WorkspaceApplyResponse,
 };
 use atomc_core::SCHEMA_VERSION;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::{HeaderMap, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
-use axum::routing::post;
+use axum::routing::{get, post};
 use axum::Json;
 use axum::Router;
 use clap::Parser;
-use cli::{ApplyArgs, Cli, Commands, LogFormat, OutputFormat, PlanArgs, ServeArgs};
+use cli::{
+    ApplyArgs, CacheArgs, CacheCommand, Cli, Commands, DoctorArgs, EvalArgs, LogFormat, OutputFormat, PlanArgs,
+    ServeArgs, SplitArgs, WorkspaceArgs,
+};
 use serde::Deserialize;
 use serde_json::Value;
 use std::io::{self, IsTerminal, Read};
@@ -52,29 +68,62 @@ fn run() -> Result<(), ExitCode> {
     match cli.command {
         Commands::Plan(ref args) => handle_plan(&cli, args),
         Commands::Apply(ref args) => handle_apply(&cli, args),
+        Commands::Split(ref args) => handle_split(&cli, args),
+        Commands::Workspace(ref args) => handle_workspace(&cli, args),
+        Commands::Cache(ref args) => handle_cache(&cli, args),
+        Commands::Eval(ref args) => handle_eval(&cli, args),
+        Commands::Doctor(ref args) => handle_doctor(&cli, args),
         Commands::Serve(ref args) => handle_serve(&cli, args),
     }
 }
 
 fn handle_plan(cli: &Cli, args: &PlanArgs) -> Result<(), ExitCode> {
-    let overrides = command_overrides(
-        args.model.clone(),
-        args.diff_mode,
-        args.include_untracked_override(),
-        args.log_diff_override(),
-        args.timeout,
-    );
-    let config = resolve_config(cli, overrides, args.format)?;
+    let overrides = PartialConfig {
+        recurse_submodules: args.recurse_submodules.then_some(true),
+        plan_cache: args.no_cache.then_some(false),
+        plan_candidates: args.candidates,
+        prompt_examples: args.examples,
+        include: (!args.include.is_empty()).then(|| args.include.clone()),
+        ..command_overrides(
+            args.model.clone(),
+            args.diff_mode,
+            args.include_untracked_override(),
+            args.log_diff_override(),
+            args.timeout,
+        )
+    };
+    let mut config = resolve_config(cli, overrides, args.format)?;
+    config.ignore.extend(args.exclude.iter().cloned());
+    let mut revisions = None;
     if let Some(repo) = &args.repo {
         validate_repo_path(repo, args.format)?;
+        if let Some((mode, range)) =
+            resolve_plan_revisions(repo, args.base.as_deref(), args.range.as_deref()).map_err(|err| {
+                emit_error(
+                    args.format,
+                    ErrorCode::InputInvalid,
+                    "invalid revision",
+                    Some(git_error_details(err)),
+                )
+            })?
+        {
+            config.diff_mode = mode;
+            revisions = Some(range);
+        }
     }
 
     let mut diff = resolve_diff_input(args.diff_file.clone(), config.max_diff_bytes, args.format)?;
     let mut source = InputSource::Diff;
     if diff.is_none() {
         if let Some(repo) = args.repo.as_deref() {
-            diff = Some(compute_repo_diff(repo, &config, args.format)?);
-            source = InputSource::Repo;
+            diff = Some(compute_repo_diff(
+                repo,
+                &config,
+                &args.pathspec,
+                revisions.as_ref(),
+                args.format,
+            )?);
+            source = repo_source(revisions.as_ref());
         }
     }
     validate_diff_requirements(&diff, args.repo.as_deref(), &config, args.format)?;
@@ -87,6 +136,7 @@ fn handle_plan(cli: &Cli, args: &PlanArgs) -> Result<(), ExitCode> {
             None,
         )
     })?;
+    let (diff, filter_warnings) = filter_input_diff(diff, &config, args.format)?;
 
     let request_id = request_id();
     info!(
@@ -98,23 +148,35 @@ fn handle_plan(cli: &Cli, args: &PlanArgs) -> Result<(), ExitCode> {
 
     log_diff_preview(&request_id, &diff, config.log_diff);
 
-    let prompt = llm::build_prompt(PromptContext {
+    let templates = prompt_templates(&config, args.repo.as_deref(), args.format)?;
+    let prompt = templates.build_prompt(PromptContext {
         repo_path: args.repo.as_deref(),
         diff_mode: input_diff_mode(&source, config.diff_mode),
         include_untracked: input_include_untracked(&source, config.include_untracked),
         git_status: None,
+        workspace: None,
         diff: &diff,
     });
 
-    let (mut plan, warnings) = request_commit_plan_with_retry(&config, &prompt, args.format)?;
+    let (mut plan, warnings) = request_commit_plan_with_retry(&config, &prompt, &diff, args.format)?;
     plan.schema_version = SCHEMA_VERSION.to_string();
     plan.request_id = Some(request_id.clone());
-    plan.input = Some(build_input_meta(source.clone(), &config, &diff));
-    plan.warnings = merge_warnings(plan.warnings.take(), warnings);
+    plan.input = Some(InputMeta {
+        prompt_version: Some(prompt.version.clone()),
+        ..build_input_meta(
+            source.clone(),
+            &config,
+            &diff,
+            &args.pathspec,
+            revisions.as_ref(),
+        )
+    });
+    plan.warnings = merge_warnings(plan.warnings.take(), [filter_warnings, warnings].concat());
 
     info!(
         request_id = %request_id,
         commits = plan.plan.len(),
+        prompt_version = %prompt.version,
         "plan request complete"
     );
 
@@ -122,20 +184,36 @@ fn handle_plan(cli: &Cli, args: &PlanArgs) -> Result<(), ExitCode> {
 }
 
 fn handle_apply(cli: &Cli, args: &ApplyArgs) -> Result<(), ExitCode> {
-    let overrides = command_overrides(
-        args.model.clone(),
-        args.diff_mode,
-        args.include_untracked_override(),
-        args.log_diff_override(),
-        args.timeout,
-    );
-    let config = resolve_config(cli, overrides, args.format)?;
+    let overrides = PartialConfig {
+        verify_command: args.verify_command.clone(),
+        recurse_submodules: args.recurse_submodules.then_some(true),
+        strict_diff_hash: args.strict_diff_hash.then_some(true),
+        plan_cache: args.no_cache.then_some(false),
+        plan_candidates: args.candidates,
+        prompt_examples: args.examples,
+        include: (!args.include.is_empty()).then(|| args.include.clone()),
+        ..command_overrides(
+            args.model.clone(),
+            args.diff_mode,
+            args.include_untracked_override(),
+            args.log_diff_override(),
+            args.timeout,
+        )
+    };
+    let mut config = resolve_config(cli, overrides, args.format)?;
+    config.ignore.extend(args.exclude.iter().cloned());
     validate_repo_path(&args.repo, args.format)?;
 
     let mut diff = resolve_diff_input(args.diff_file.clone(), config.max_diff_bytes, args.format)?;
     let mut source = InputSource::Diff;
     if diff.is_none() {
-        diff = Some(compute_repo_diff(args.repo.as_path(), &config, args.format)?);
+        diff = Some(compute_repo_diff(
+            args.repo.as_path(),
+            &config,
+            &args.pathspec,
+            None,
+            args.format,
+        )?);
         source = InputSource::Repo;
     }
     validate_diff_requirements(&diff, Some(args.repo.as_path()), &config, args.format)?;
@@ -148,6 +226,7 @@ fn handle_apply(cli: &Cli, args: &ApplyArgs) -> Result<(), ExitCode> {
             None,
         )
     })?;
+    let (diff, filter_warnings) = filter_input_diff(diff, &config, args.format)?;
 
     let request_id = request_id();
     info!(
@@ -160,30 +239,47 @@ fn handle_apply(cli: &Cli, args: &ApplyArgs) -> Result<(), ExitCode> {
 
     log_diff_preview(&request_id, &diff, config.log_diff);
 
-    let prompt = llm::build_prompt(PromptContext {
+    let templates = prompt_templates(&config, Some(args.repo.as_path()), args.format)?;
+    let prompt = templates.build_prompt(PromptContext {
         repo_path: Some(args.repo.as_path()),
         diff_mode: input_diff_mode(&source, config.diff_mode),
         include_untracked: input_include_untracked(&source, config.include_untracked),
         git_status: None,
+        workspace: None,
         diff: &diff,
     });
 
-    let (mut plan, warnings) = request_commit_plan_with_retry(&config, &prompt, args.format)?;
+    let (mut plan, warnings) = request_commit_plan_with_retry(&config, &prompt, &diff, args.format)?;
     plan.schema_version = SCHEMA_VERSION.to_string();
     plan.request_id = Some(request_id.clone());
-    plan.input = Some(build_input_meta(source.clone(), &config, &diff));
-    plan.warnings = merge_warnings(plan.warnings.take(), warnings.clone());
+    plan.input = Some(InputMeta {
+        prompt_version: Some(prompt.version.clone()),
+        ..build_input_meta(source.clone(), &config, &diff, &args.pathspec, None)
+    });
+    plan.warnings = merge_warnings(plan.warnings.take(), [filter_warnings, warnings.clone()].concat());
 
     let results = if args.execute {
         let request = git::ApplyRequest {
             repo: args.repo.as_path(),
+            backend: config.git_backend,
             plan: &plan.plan,
             diff: &diff,
             diff_mode: config.diff_mode,
             include_untracked: config.include_untracked,
+            max_file_diff_bytes: config.max_file_diff_bytes,
+            recurse_submodules: config.recurse_submodules,
+            filter: git::PathFilter::from_config(&config),
+            pathspec: args.pathspec.clone(),
             expected_diff_hash: plan.input.as_ref().and_then(|input| input.diff_hash.clone()),
+            expected_file_hashes: plan.input.as_ref().and_then(|input| input.file_hashes.clone()),
+            strict_diff_hash: config.strict_diff_hash,
             cleanup_on_error: args.cleanup_on_error,
             assisted_by: args.assisted_by.as_deref(),
+            hooks: map_hook_mode(args.hooks),
+            sign: args.sign,
+            signing_key: args.signing_key.as_deref(),
+            verify_command: config.verify_command.as_deref(),
+            rollback_on_verify_failure: args.rollback_on_verify_failure,
         };
         execute_apply_plan(request).map_err(|err| {
             emit_error(
@@ -197,7 +293,7 @@ fn handle_apply(cli: &Cli, args: &ApplyArgs) -> Result<(), ExitCode> {
         planned_results(&plan)
     };
 
-    let response = build_apply_response(plan, results, source, &config, &diff);
+    let response = build_apply_response(plan, results, source, &config, &diff, &args.pathspec);
 
     info!(
         request_id = %request_id,
@@ -205,9 +301,626 @@ fn handle_apply(cli: &Cli, args: &ApplyArgs) -> Result<(), ExitCode> {
         "apply request complete"
     );
 
+    emit_apply(args.format, &response)?;
+    // Units rejected by hooks under `--hooks run` are reported in the results;
+    // the exit code still flags that the plan was not fully applied.
+    if response
+        .results
+        .iter()
+        .any(|result| result.status == ApplyStatus::Failed)
+    {
+        return Err(ErrorCode::GitError.exit_code());
+    }
+    Ok(())
+}
+
+fn handle_split(cli: &Cli, args: &SplitArgs) -> Result<(), ExitCode> {
+    let overrides = command_overrides(
+        args.model.clone(),
+        None,
+        None,
+        args.log_diff_override(),
+        args.timeout,
+    );
+    let mut config = resolve_config(cli, overrides, args.format)?;
+    config.diff_mode = config::DiffMode::Range;
+    validate_repo_path(&args.repo, args.format)?;
+
+    let range = git::resolve_range(&args.repo, &args.range).map_err(|err| {
+        emit_error(
+            args.format,
+            ErrorCode::InputInvalid,
+            "invalid revision range",
+            Some(git_error_details(err)),
+        )
+    })?;
+    // Condensed like `plan --range`; filtered paths are not planned but still
+    // have to land in the rebuilt history.
+    let diff = compute_repo_diff(&args.repo, &config, &[], Some(&range), args.format)?;
+    validate_diff_requirements(&Some(diff.clone()), Some(args.repo.as_path()), &config, args.format)?;
+    let filtered = filter_nonempty_diff(&diff, &config, args.format)?;
+    let diff = filtered.diff;
+    let mut filter_warnings = carried_paths_warnings(&filtered.excluded);
+    filter_warnings.extend(split_authorship_warning(&args.repo, &range));
+
+    let request_id = request_id();
+    info!(
+        request_id = %request_id,
+        base = %range.base,
+        head = %range.head,
+        diff_bytes = diff.len(),
+        execute = args.execute,
+        "split request start"
+    );
+
+    log_diff_preview(&request_id, &diff, config.log_diff);
+
+    let templates = prompt_templates(&config, Some(args.repo.as_path()), args.format)?;
+    let prompt = templates.build_prompt(PromptContext {
+        repo_path: Some(args.repo.as_path()),
+        diff_mode: None,
+        include_untracked: None,
+        git_status: None,
+        workspace: None,
+        diff: &diff,
+    });
+
+    let (mut plan, warnings) = request_commit_plan_with_retry(&config, &prompt, &diff, args.format)?;
+    plan.schema_version = SCHEMA_VERSION.to_string();
+    plan.request_id = Some(request_id.clone());
+    plan.warnings = merge_warnings(plan.warnings.take(), [filter_warnings, warnings].concat());
+
+    let carried = git::carried_unit(&filtered.excluded);
+    let results = if args.execute {
+        let target_ref = args
+            .branch
+            .clone()
+            .unwrap_or_else(|| default_split_branch(&range));
+        let request = git::SplitRequest {
+            repo: args.repo.as_path(),
+            range: &range,
+            plan: &plan.plan,
+            diff: &diff,
+            excluded: &filtered.excluded,
+            target_ref: &target_ref,
+            assisted_by: args.assisted_by.as_deref(),
+        };
+        let results = git::split_range(request).map_err(|err| {
+            emit_error(
+                args.format,
+                ErrorCode::GitError,
+                "split execution failed",
+                Some(git_error_details(err)),
+            )
+        })?;
+        info!(request_id = %request_id, branch = %target_ref, "split branch created");
+        plan.plan.extend(carried);
+        results
+    } else {
+        plan.plan.extend(carried);
+        planned_results(&plan)
+    };
+
+    let response = CommitApplyResponse {
+        schema_version: SCHEMA_VERSION.to_string(),
+        request_id: plan.request_id.clone(),
+        warnings: plan.warnings,
+        input: Some(InputMeta {
+            prompt_version: Some(prompt.version.clone()),
+            ..build_input_meta(InputSource::Range, &config, &diff, &[], Some(&range))
+        }),
+        backend: plan.backend,
+        plan: plan.plan,
+        results,
+    };
+
+    info!(
+        request_id = %request_id,
+        results = response.results.len(),
+        "split request complete"
+    );
+
     emit_apply(args.format, &response)
 }
 
+/// A `split_authorship_rewritten` warning naming the authors of the range,
+/// since split commits are authored and dated by the current identity.
+fn split_authorship_warning(repo: &Path, range: &git::RevisionRange) -> Option<Warning> {
+    let authors = git::range_authors(repo, range).ok()?;
+    Some(Warning {
+        code: "split_authorship_rewritten".to_string(),
+        message: "split commits are authored and dated by the current git identity".to_string(),
+        details: Some(serde_json::json!({ "authors": authors })),
+    })
+}
+
+fn default_split_branch(range: &git::RevisionRange) -> String {
+    let short: String = range.head.chars().take(12).collect();
+    format!("atomc/split-{short}")
+}
+
+/// A workspace repository with changes to plan.
+struct WorkspaceEntry {
+    path: PathBuf,
+    label: String,
+    diff: String,
+    warnings: Vec<Warning>,
+}
+
+fn handle_workspace(cli: &Cli, args: &WorkspaceArgs) -> Result<(), ExitCode> {
+    let overrides = PartialConfig {
+        verify_command: args.verify_command.clone(),
+        strict_diff_hash: args.strict_diff_hash.then_some(true),
+        plan_cache: args.no_cache.then_some(false),
+        plan_candidates: args.candidates,
+        prompt_examples: args.examples,
+        include: (!args.include.is_empty()).then(|| args.include.clone()),
+        ..command_overrides(
+            args.model.clone(),
+            args.diff_mode,
+            args.include_untracked_override(),
+            args.log_diff_override(),
+            args.timeout,
+        )
+    };
+    let mut config = resolve_config(cli, overrides, args.format)?;
+    config.ignore.extend(args.exclude.iter().cloned());
+
+    let (repos, mut warnings) = workspace_repos(args)?;
+    let mut entries = Vec::new();
+    for path in repos {
+        validate_repo_path(&path, args.format)?;
+        let label = path.display().to_string();
+        let diff = compute_repo_diff(&path, &config, &[], None, args.format)?;
+        let mut excluded = Vec::new();
+        let diff = if diff.is_empty() {
+            diff
+        } else {
+            check_diff_size(&diff, &config, args.format)?;
+            let filtered = filter_diff_paths(&diff, &config, args.format)?;
+            excluded = filtered.excluded;
+            filtered.diff
+        };
+        // A repository whose changes are all filtered out has nothing to plan.
+        if diff.is_empty() {
+            warnings.push(Warning {
+                code: "repo_unchanged".to_string(),
+                message: format!("{label} has no changes to plan"),
+                details: Some(serde_json::json!({ "repo": label, "excluded": excluded })),
+            });
+            continue;
+        }
+        entries.push(WorkspaceEntry {
+            path,
+            label,
+            diff,
+            warnings: excluded_paths_warnings(&excluded),
+        });
+    }
+    if entries.is_empty() {
+        return Err(emit_error(
+            args.format,
+            ErrorCode::InputInvalid,
+            "no workspace repository has changes",
+            None,
+        ));
+    }
+
+    let request_id = request_id();
+    info!(
+        request_id = %request_id,
+        repos = entries.len(),
+        execute = args.execute,
+        "workspace request start"
+    );
+
+    // Repositories are planned in order; each prompt lists the whole
+    // workspace and the commits planned so far.
+    let mut context = llm::WorkspaceContext {
+        repos: entries
+            .iter()
+            .map(|entry| llm::WorkspaceRepo {
+                label: entry.label.clone(),
+                files: git::diff_paths(&git::parse_diff_files(&entry.diff))
+                    .map(str::to_string)
+                    .collect(),
+            })
+            .collect(),
+        ..llm::WorkspaceContext::default()
+    };
+    let mut plans = Vec::new();
+    for entry in &entries {
+        log_diff_preview(&request_id, &entry.diff, config.log_diff);
+        context.current = entry.label.clone();
+        let templates = prompt_templates(&config, Some(entry.path.as_path()), args.format)?;
+        let prompt = templates.build_prompt(PromptContext {
+            repo_path: Some(entry.path.as_path()),
+            diff_mode: Some(config.diff_mode),
+            include_untracked: Some(config.include_untracked),
+            git_status: None,
+            workspace: Some(&context),
+            diff: &entry.diff,
+        });
+        let (mut plan, plan_warnings) =
+            request_commit_plan_with_retry(&config, &prompt, &entry.diff, args.format)?;
+        plan.schema_version = SCHEMA_VERSION.to_string();
+        plan.request_id = Some(request_id.clone());
+        plan.input = Some(InputMeta {
+            prompt_version: Some(prompt.version.clone()),
+            ..build_input_meta(InputSource::Repo, &config, &entry.diff, &[], None)
+        });
+        plan.warnings = merge_warnings(
+            plan.warnings.take(),
+            [entry.warnings.clone(), plan_warnings].concat(),
+        );
+        context
+            .planned
+            .extend(plan.plan.iter().map(|unit| format!("{}: {}", entry.label, unit_header(unit))));
+        plans.push(plan);
+    }
+
+    let mut outcomes: Vec<WorkspaceOutcome> = Vec::new();
+    if args.execute {
+        // Check every repository before committing to any of them, so drift in
+        // one repository leaves the whole workspace untouched.
+        for (entry, plan) in entries.iter().zip(&plans) {
+            check_apply_request(&workspace_apply_request(entry, plan, &config, args)).map_err(|err| {
+                let mut details = git_error_details(err);
+                details["repo"] = Value::String(entry.label.clone());
+                emit_error(
+                    args.format,
+                    ErrorCode::GitError,
+                    "workspace apply check failed",
+                    Some(details),
+                )
+            })?;
+        }
+        let rollback_error = |err: GitError| {
+            emit_error(
+                args.format,
+                ErrorCode::GitError,
+                "workspace rollback failed",
+                Some(git_error_details(err)),
+            )
+        };
+        let rollback = if args.rollback_on_verify_failure {
+            Some(
+                entries
+                    .iter()
+                    .map(|entry| git::RollbackPoint::capture(&entry.path))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(rollback_error)?,
+            )
+        } else {
+            None
+        };
+        let mut failed = false;
+        for (entry, plan) in entries.iter().zip(&plans) {
+            if failed {
+                outcomes.push(WorkspaceOutcome::new(skipped_results(plan), None));
+                continue;
+            }
+            match execute_apply_plan(workspace_apply_request(entry, plan, &config, args)) {
+                Ok(results) => {
+                    // A unit that does not verify stops the workspace like an
+                    // error; hook failures under `--hooks run` do not.
+                    failed = results.iter().any(|result| {
+                        result.error.as_ref().is_some_and(|error| error.code == "verify_failed")
+                    });
+                    outcomes.push(WorkspaceOutcome::new(results, None));
+                }
+                Err(err) => {
+                    failed = true;
+                    let error = ErrorDetail {
+                        code: ErrorCode::GitError.as_str().to_string(),
+                        message: "apply execution failed".to_string(),
+                        details: Some(git_error_details(err)),
+                    };
+                    outcomes.push(WorkspaceOutcome::new(skipped_results(plan), Some(error)));
+                }
+            }
+        }
+        if let (true, Some(points)) = (failed, &rollback) {
+            rollback_workspace(&entries, points, &mut outcomes).map_err(rollback_error)?;
+        }
+        if failed {
+            warnings.extend(partial_apply_warning(&entries, &outcomes));
+        }
+    } else {
+        outcomes.extend(plans.iter().map(|plan| WorkspaceOutcome::new(planned_results(plan), None)));
+    }
+
+    let repos = entries
+        .iter()
+        .zip(plans)
+        .zip(outcomes)
+        .map(|((entry, plan), outcome)| RepoApplyResponse {
+            repo: entry.label.clone(),
+            response: build_apply_response(plan, outcome.results, InputSource::Repo, &config, &entry.diff, &[]),
+            error: outcome.error,
+            rolled_back: outcome.rolled_back,
+        })
+        .collect::<Vec<_>>();
+    let response = WorkspaceApplyResponse {
+        schema_version: SCHEMA_VERSION.to_string(),
+        request_id: Some(request_id.clone()),
+        warnings: merge_warnings(None, warnings),
+        repos,
+    };
+
+    info!(
+        request_id = %request_id,
+        repos = response.repos.len(),
+        "workspace request complete"
+    );
+
+    emit_workspace(args.format, &response)?;
+    let failed = response.repos.iter().any(|repo| {
+        repo.error.is_some()
+            || repo
+                .response
+                .results
+                .iter()
+                .any(|result| result.status == ApplyStatus::Failed)
+    });
+    if failed {
+        return Err(ErrorCode::GitError.exit_code());
+    }
+    Ok(())
+}
+
+/// What applying one workspace repository did.
+struct WorkspaceOutcome {
+    results: Vec<ApplyResult>,
+    error: Option<ErrorDetail>,
+    rolled_back: bool,
+}
+
+impl WorkspaceOutcome {
+    fn new(results: Vec<ApplyResult>, error: Option<ErrorDetail>) -> Self {
+        Self {
+            results,
+            error,
+            rolled_back: false,
+        }
+    }
+}
+
+/// Warn about the repositories that kept commits when the workspace stopped
+/// part-way, since the workspace is then only partly applied.
+fn partial_apply_warning(entries: &[WorkspaceEntry], outcomes: &[WorkspaceOutcome]) -> Option<Warning> {
+    let committed: Vec<&str> = entries
+        .iter()
+        .zip(outcomes)
+        .filter(|(_, outcome)| {
+            outcome
+                .results
+                .iter()
+                .any(|result| result.status == ApplyStatus::Applied && result.commit_hash.is_some())
+        })
+        .map(|(entry, _)| entry.label.as_str())
+        .collect();
+    if committed.is_empty() {
+        return None;
+    }
+    Some(Warning {
+        code: "workspace_partial_apply".to_string(),
+        message: format!(
+            "workspace apply stopped part-way; {} repository(ies) kept their commits",
+            committed.len()
+        ),
+        details: Some(serde_json::json!({ "repos": committed })),
+    })
+}
+
+/// Return every repository applied so far to its rollback point, so one
+/// repository failing verification leaves the whole workspace uncommitted.
+fn rollback_workspace(
+    entries: &[WorkspaceEntry],
+    points: &[git::RollbackPoint],
+    outcomes: &mut [WorkspaceOutcome],
+) -> Result<(), GitError> {
+    for ((entry, point), outcome) in entries.iter().zip(points).zip(outcomes.iter_mut()) {
+        // The failing repository may have committed units before its error.
+        let attempted = outcome.error.is_some() || outcome.results.iter().any(|result| result.commit_hash.is_some());
+        if !attempted {
+            continue;
+        }
+        point.restore(&entry.path)?;
+        for result in &mut outcome.results {
+            if result.status == ApplyStatus::Applied {
+                result.status = ApplyStatus::Skipped;
+                result.commit_hash = None;
+            }
+        }
+        outcome.rolled_back = true;
+    }
+    Ok(())
+}
+
+fn workspace_apply_request<'a>(
+    entry: &'a WorkspaceEntry,
+    plan: &'a CommitPlan,
+    config: &'a ResolvedConfig,
+    args: &'a WorkspaceArgs,
+) -> git::ApplyRequest<'a> {
+    let input = plan.input.as_ref();
+    git::ApplyRequest {
+        repo: entry.path.as_path(),
+        backend: config.git_backend,
+        plan: &plan.plan,
+        diff: &entry.diff,
+        diff_mode: config.diff_mode,
+        include_untracked: config.include_untracked,
+        max_file_diff_bytes: config.max_file_diff_bytes,
+        recurse_submodules: config.recurse_submodules,
+        filter: git::PathFilter::from_config(config),
+        pathspec: Vec::new(),
+        expected_diff_hash: input.and_then(|input| input.diff_hash.clone()),
+        expected_file_hashes: input.and_then(|input| input.file_hashes.clone()),
+        strict_diff_hash: config.strict_diff_hash,
+        cleanup_on_error: args.cleanup_on_error,
+        assisted_by: args.assisted_by.as_deref(),
+        hooks: map_hook_mode(args.hooks),
+        sign: args.sign,
+        signing_key: args.signing_key.as_deref(),
+        verify_command: config.verify_command.as_deref(),
+        // The workspace rolls back on apply errors too, so the flag does not
+        // need a verify command here; see `handle_workspace`.
+        rollback_on_verify_failure: args.rollback_on_verify_failure && config.verify_command.is_some(),
+    }
+}
+
+/// Repositories named with `--repo` followed by those found under
+/// `--discover`, without duplicates, and a `discover_unreadable` warning for
+/// each directory discovery could not read.
+fn workspace_repos(args: &WorkspaceArgs) -> Result<(Vec<PathBuf>, Vec<Warning>), ExitCode> {
+    let mut repos = args.repos.clone();
+    let mut warnings = Vec::new();
+    if let Some(root) = &args.discover {
+        validate_repo_path(root, args.format)?;
+        let found = git::discover_repos(root).map_err(|err| {
+            emit_error(
+                args.format,
+                ErrorCode::InputInvalid,
+                "failed to discover repositories",
+                Some(git_error_details(err)),
+            )
+        })?;
+        repos.extend(found.repos);
+        warnings.extend(found.unreadable.into_iter().map(|(path, error)| Warning {
+            code: "discover_unreadable".to_string(),
+            message: format!("cannot read {} while discovering repositories", path.display()),
+            details: Some(serde_json::json!({ "path": path.display().to_string(), "error": error })),
+        }));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    repos.retain(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())));
+    if repos.is_empty() {
+        return Err(emit_error(
+            args.format,
+            ErrorCode::UsageError,
+            "no workspace repositories given; use --repo or --discover",
+            None,
+        ));
+    }
+    Ok((repos, warnings))
+}
+
+fn handle_cache(cli: &Cli, args: &CacheArgs) -> Result<(), ExitCode> {
+    match &args.command {
+        CacheCommand::Clear(args) => {
+            let config = resolve_config(cli, PartialConfig::default(), args.format)?;
+            let cache_error = |err: CacheError| {
+                emit_error(
+                    args.format,
+                    ErrorCode::ConfigError,
+                    "failed to clear plan cache",
+                    Some(serde_json::json!({ "error": err.to_string() })),
+                )
+            };
+            let cache = match &config.cache_dir {
+                Some(dir) => PlanCache::new(dir),
+                None => PlanCache::new(cache::default_cache_dir().map_err(cache_error)?),
+            };
+            let removed = cache.clear().map_err(cache_error)?;
+            info!(removed, dir = %cache.dir().display(), "plan cache cleared");
+            match args.format {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::json!({
+                        "schema_version": SCHEMA_VERSION,
+                        "dir": cache.dir().display().to_string(),
+                        "removed": removed
+                    })
+                ),
+                OutputFormat::Human => {
+                    println!("Removed {removed} cached plans from {}", cache.dir().display());
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn handle_eval(cli: &Cli, args: &EvalArgs) -> Result<(), ExitCode> {
+    let overrides = PartialConfig {
+        prompt_examples: args.examples,
+        llm_timeout_secs: args.timeout,
+        ..PartialConfig::default()
+    };
+    let config = resolve_config(cli, overrides, args.format)?;
+    let cases = eval::load_cases(&args.fixtures).map_err(|err| {
+        emit_error(
+            args.format,
+            ErrorCode::InputInvalid,
+            "failed to load eval fixtures",
+            Some(serde_json::json!({ "error": err.to_string() })),
+        )
+    })?;
+    let templates = prompt_templates(&config, None, args.format)?;
+    let models = if args.models.is_empty() {
+        vec![config.model.clone()]
+    } else {
+        args.models.clone()
+    };
+    info!(cases = cases.len(), models = ?models, "eval start");
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| map_llm_error(args.format, LlmError::Runtime(err.to_string())))?;
+    let report = runtime.block_on(eval::run_eval(
+        &config,
+        &templates,
+        &args.fixtures,
+        &cases,
+        &models,
+        |config, prompt| async move { request_commit_plan_impl(&config, &prompt).await },
+    ));
+
+    if let Some(path) = &args.report {
+        let payload = serde_json::to_string_pretty(&report).unwrap_or_default();
+        std::fs::write(path, payload).map_err(|err| {
+            emit_error(
+                args.format,
+                ErrorCode::InputInvalid,
+                "failed to write eval report",
+                Some(serde_json::json!({ "path": path.display().to_string(), "error": err.to_string() })),
+            )
+        })?;
+    }
+    emit_eval(args.format, &report)
+}
+
+fn handle_doctor(cli: &Cli, args: &DoctorArgs) -> Result<(), ExitCode> {
+    let repo = args.repo.clone().unwrap_or_else(|| PathBuf::from("."));
+    let config = config::resolve_config_layers(cli.config.clone(), PartialConfig::default());
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| map_llm_error(args.format, LlmError::Runtime(err.to_string())))?;
+    let report = runtime.block_on(health::run_checks(Some(&repo), &config));
+    info!(status = report.status.as_str(), checks = report.checks.len(), "doctor complete");
+    emit_health(args.format, &report);
+    match report.first_failure() {
+        Some(check) => Err(health_error_code(check.name).exit_code()),
+        None => Ok(()),
+    }
+}
+
+/// The error code a failing check maps to, so `doctor` exits the way the
+/// command hitting the same problem would.
+fn health_error_code(check: &str) -> ErrorCode {
+    match check {
+        "git" | "repo" => ErrorCode::GitError,
+        "config" => ErrorCode::ConfigError,
+        "schema" | "schema_support" => ErrorCode::LlmParseError,
+        _ => ErrorCode::LlmRuntimeError,
+    }
+}
+
 fn handle_serve(cli: &Cli, args: &ServeArgs) -> Result<(), ExitCode> {
     let overrides = PartialConfig {
         model: args.model.clone(),
@@ -215,8 +928,8 @@ fn handle_serve(cli: &Cli, args: &ServeArgs) -> Result<(), ExitCode> {
         log_diff: args.log_diff_override(),
         ..PartialConfig::default()
     };
-    let config = resolve_config(cli, overrides, OutputFormat::Human)?;
-    let state = ServerState { config };
+    let (config, config_layers) = resolve_config_layers(cli, overrides, OutputFormat::Human)?;
+    let state = ServerState { config, config_layers };
 
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -237,6 +950,14 @@ fn handle_serve(cli: &Cli, args: &ServeArgs) -> Result<(), ExitCode> {
 #[derive(Clone)]
 struct ServerState {
     config: ResolvedConfig,
+    /// Where the server's config came from, for `/v1/health`.
+    config_layers: Vec<ConfigLayer>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+struct HealthQuery {
+    repo_path: Option<PathBuf>,
 }
 
 #[derive(Deserialize)]
@@ -246,9 +967,18 @@ struct PlanRequest {
     diff: Option<String>,
     diff_mode: Option<config::DiffMode>,
     include_untracked: Option<bool>,
+    recurse_submodules: Option<bool>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    pathspec: Option<Vec<String>>,
+    base: Option<String>,
+    range: Option<String>,
     git_status: Option<String>,
     model: Option<String>,
     log_diff: Option<bool>,
+    cache: Option<bool>,
+    candidates: Option<u32>,
+    examples: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -258,6 +988,10 @@ struct ApplyRequestBody {
     diff: Option<String>,
     diff_mode: Option<config::DiffMode>,
     include_untracked: Option<bool>,
+    recurse_submodules: Option<bool>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    pathspec: Option<Vec<String>>,
     git_status: Option<String>,
     model: Option<String>,
     plan: Option<Vec<CommitUnit>>,
@@ -266,12 +1000,21 @@ struct ApplyRequestBody {
     dry_run: Option<bool>,
     log_diff: Option<bool>,
     assisted_by: Option<String>,
+    hooks: Option<git::HookPolicy>,
+    sign: Option<bool>,
+    cache: Option<bool>,
+    candidates: Option<u32>,
+    examples: Option<u32>,
+    signing_key: Option<String>,
+    rollback_on_verify_failure: Option<bool>,
+    strict_diff_hash: Option<bool>,
 }
 
 fn build_app(state: ServerState) -> Router {
     Router::new()
         .route("/v1/commit-plan", post(plan_handler))
         .route("/v1/commit-apply", post(apply_handler))
+        .route("/v1/health", get(health_handler))
         .with_state(state)
 }
 
@@ -294,6 +1037,18 @@ async fn shutdown_signal() {
     let _ = tokio::signal::ctrl_c().await;
 }
 
+async fn health_handler(State(state): State<ServerState>, Query(query): Query<HealthQuery>) -> Response {
+    let config = Ok((state.config.clone(), state.config_layers.clone()));
+    let report = health::run_checks(query.repo_path.as_deref(), &config).await;
+    info!(status = report.status.as_str(), "health check complete");
+    let status = if report.status == CheckStatus::Fail {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(report)).into_response()
+}
+
 async fn plan_handler(
     State(state): State<ServerState>,
     headers: HeaderMap,
@@ -301,13 +1056,33 @@ async fn plan_handler(
 ) -> Response {
     let request_id = extract_request_id(&headers);
     info!(request_id = %request_id, "plan request received");
-    let config = config_with_request_overrides(
+    let mut config = config_with_request_overrides(
         &state.config,
         payload.model.clone(),
         payload.diff_mode,
         payload.include_untracked,
         payload.log_diff,
     );
+    if let Some(recurse_submodules) = payload.recurse_submodules {
+        config.recurse_submodules = recurse_submodules;
+    }
+    if let Some(cache) = payload.cache {
+        config.plan_cache = cache;
+    }
+    if let Some(candidates) = payload.candidates {
+        if let Err(response) = validate_candidates_http(candidates, &request_id) {
+            return response;
+        }
+        config.plan_candidates = candidates;
+    }
+    if let Some(examples) = payload.examples {
+        config.prompt_examples = examples;
+    }
+    if let Some(include) = payload.include.clone() {
+        config.include = include;
+    }
+    config.ignore.extend(payload.exclude.iter().flatten().cloned());
+    let pathspec = payload.pathspec.clone().unwrap_or_default();
 
     let repo_path = payload.repo_path.as_deref();
     if let Some(path) = repo_path {
@@ -316,10 +1091,39 @@ async fn plan_handler(
         }
     }
 
-    let (diff, source) = match resolve_request_diff(
+    let mut revisions = None;
+    if payload.base.is_some() || payload.range.is_some() {
+        let Some(repo) = repo_path.filter(|_| payload.diff.is_none()) else {
+            return error_response(
+                ErrorCode::InputInvalid,
+                "base and range require repo_path and no diff",
+                None,
+                &request_id,
+            );
+        };
+        match resolve_plan_revisions(repo, payload.base.as_deref(), payload.range.as_deref()) {
+            Ok(Some((mode, range))) => {
+                config.diff_mode = mode;
+                revisions = Some(range);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                return error_response(
+                    ErrorCode::InputInvalid,
+                    "invalid revision",
+                    Some(git_error_details(err)),
+                    &request_id,
+                );
+            }
+        }
+    }
+
+    let (diff, source, filter_warnings) = match resolve_request_diff(
         repo_path,
         payload.diff,
         &config,
+        &pathspec,
+        revisions.as_ref(),
         &request_id,
     ) {
         Ok(result) => result,
@@ -337,27 +1141,36 @@ async fn plan_handler(
         return response;
     }
 
-    let prompt = llm::build_prompt(PromptContext {
+    let templates = match prompt_templates_request(&config, repo_path, &request_id) {
+        Ok(templates) => templates,
+        Err(response) => return response,
+    };
+    let prompt = templates.build_prompt(PromptContext {
         repo_path,
         diff_mode: input_diff_mode(&source, config.diff_mode),
         include_untracked: input_include_untracked(&source, config.include_untracked),
         git_status: payload.git_status.as_deref(),
+        workspace: None,
         diff: &diff,
     });
 
-    let (mut plan, warnings) = match request_commit_plan_http_with_retry(&config, &prompt, &request_id).await {
+    let (mut plan, warnings) = match request_commit_plan_http_with_retry(&config, &prompt, &diff, &request_id).await {
         Ok(result) => result,
         Err(response) => return response,
     };
 
     plan.schema_version = SCHEMA_VERSION.to_string();
     plan.request_id = Some(request_id.clone());
-    plan.input = Some(build_input_meta(source, &config, &diff));
-    plan.warnings = merge_warnings(plan.warnings.take(), warnings);
+    plan.input = Some(InputMeta {
+        prompt_version: Some(prompt.version.clone()),
+        ..build_input_meta(source, &config, &diff, &pathspec, revisions.as_ref())
+    });
+    plan.warnings = merge_warnings(plan.warnings.take(), [filter_warnings, warnings].concat());
 
     info!(
         request_id = %request_id,
         commits = plan.plan.len(),
+        prompt_version = %prompt.version,
         "plan request complete"
     );
     json_response(StatusCode::OK, &request_id, plan)
@@ -370,22 +1183,47 @@ async fn apply_handler(
 ) -> Response {
     let request_id = extract_request_id(&headers);
     info!(request_id = %request_id, "apply request received");
-    let config = config_with_request_overrides(
+    let mut config = config_with_request_overrides(
         &state.config,
         payload.model.clone(),
         payload.diff_mode,
         payload.include_untracked,
         payload.log_diff,
     );
+    if let Some(recurse_submodules) = payload.recurse_submodules {
+        config.recurse_submodules = recurse_submodules;
+    }
+    if let Some(cache) = payload.cache {
+        config.plan_cache = cache;
+    }
+    if let Some(candidates) = payload.candidates {
+        if let Err(response) = validate_candidates_http(candidates, &request_id) {
+            return response;
+        }
+        config.plan_candidates = candidates;
+    }
+    if let Some(examples) = payload.examples {
+        config.prompt_examples = examples;
+    }
+    if let Some(strict_diff_hash) = payload.strict_diff_hash {
+        config.strict_diff_hash = strict_diff_hash;
+    }
+    if let Some(include) = payload.include.clone() {
+        config.include = include;
+    }
+    config.ignore.extend(payload.exclude.iter().flatten().cloned());
+    let pathspec = payload.pathspec.clone().unwrap_or_default();
 
     if let Err(response) = validate_repo_path_http(&payload.repo_path, &request_id) {
         return response;
     }
 
-    let (diff, source) = match resolve_request_diff(
+    let (diff, source, filter_warnings) = match resolve_request_diff(
         Some(payload.repo_path.as_path()),
         payload.diff,
         &config,
+        &pathspec,
+        None,
         &request_id,
     ) {
         Ok(result) => result,
@@ -403,6 +1241,7 @@ async fn apply_handler(
         return response;
     }
 
+    let mut prompt_version = None;
     let (mut plan, warnings) = if let Some(plan_units) = payload.plan {
         let plan = match build_request_plan(plan_units, &request_id) {
             Ok(plan) => plan,
@@ -414,15 +1253,21 @@ async fn apply_handler(
         };
         (plan, warnings)
     } else {
-        let prompt = llm::build_prompt(PromptContext {
+        let templates = match prompt_templates_request(&config, Some(payload.repo_path.as_path()), &request_id) {
+            Ok(templates) => templates,
+            Err(response) => return response,
+        };
+        let prompt = templates.build_prompt(PromptContext {
             repo_path: Some(payload.repo_path.as_path()),
             diff_mode: input_diff_mode(&source, config.diff_mode),
             include_untracked: input_include_untracked(&source, config.include_untracked),
             git_status: payload.git_status.as_deref(),
+            workspace: None,
             diff: &diff,
         });
+        prompt_version = Some(prompt.version.clone());
 
-        match request_commit_plan_http_with_retry(&config, &prompt, &request_id).await {
+        match request_commit_plan_http_with_retry(&config, &prompt, &diff, &request_id).await {
             Ok(result) => result,
             Err(response) => return response,
         }
@@ -430,8 +1275,11 @@ async fn apply_handler(
 
     plan.schema_version = SCHEMA_VERSION.to_string();
     plan.request_id = Some(request_id.clone());
-    plan.input = Some(build_input_meta(source.clone(), &config, &diff));
-    plan.warnings = merge_warnings(plan.warnings.take(), warnings);
+    plan.input = Some(InputMeta {
+        prompt_version,
+        ..build_input_meta(source.clone(), &config, &diff, &pathspec, None)
+    });
+    plan.warnings = merge_warnings(plan.warnings.take(), [filter_warnings, warnings].concat());
 
     let execute = payload.execute.unwrap_or(false);
     let dry_run = payload.dry_run.unwrap_or(false);
@@ -439,19 +1287,49 @@ async fn apply_handler(
     let cleanup_on_error = payload.cleanup_on_error.unwrap_or(false);
 
     let results = if should_execute {
-        let request = git::ApplyRequest {
-            repo: payload.repo_path.as_path(),
-            plan: &plan.plan,
-            diff: &diff,
-            diff_mode: config.diff_mode,
-            include_untracked: config.include_untracked,
-            expected_diff_hash: plan.input.as_ref().and_then(|input| input.diff_hash.clone()),
-            cleanup_on_error,
-            assisted_by: payload.assisted_by.as_deref(),
-        };
-        match execute_apply_plan(request) {
-            Ok(results) => results,
-            Err(err) => {
+        let repo = payload.repo_path.clone();
+        let units = plan.plan.clone();
+        let apply_diff = diff.clone();
+        let apply_config = config.clone();
+        let apply_pathspec = pathspec.clone();
+        let expected_diff_hash = plan.input.as_ref().and_then(|input| input.diff_hash.clone());
+        let expected_file_hashes = plan.input.as_ref().and_then(|input| input.file_hashes.clone());
+        let assisted_by = payload.assisted_by.clone();
+        let signing_key = payload.signing_key.clone();
+        let hooks = payload.hooks.unwrap_or_default();
+        let sign = payload.sign.unwrap_or(false);
+        let rollback_on_verify_failure = payload.rollback_on_verify_failure.unwrap_or(false);
+        // Staging, committing, and the verify command all block, so they run
+        // off the async workers.
+        let applied = tokio::task::spawn_blocking(move || {
+            execute_apply_plan(git::ApplyRequest {
+                repo: repo.as_path(),
+                backend: apply_config.git_backend,
+                plan: &units,
+                diff: &apply_diff,
+                diff_mode: apply_config.diff_mode,
+                include_untracked: apply_config.include_untracked,
+                max_file_diff_bytes: apply_config.max_file_diff_bytes,
+                recurse_submodules: apply_config.recurse_submodules,
+                filter: git::PathFilter::from_config(&apply_config),
+                pathspec: apply_pathspec,
+                expected_diff_hash,
+                expected_file_hashes,
+                strict_diff_hash: apply_config.strict_diff_hash,
+                cleanup_on_error,
+                assisted_by: assisted_by.as_deref(),
+                hooks,
+                sign,
+                signing_key: signing_key.as_deref(),
+                // Runs via `sh -c`, so it only ever comes from server config.
+                verify_command: apply_config.verify_command.as_deref(),
+                rollback_on_verify_failure,
+            })
+        })
+        .await;
+        match applied {
+            Ok(Ok(results)) => results,
+            Ok(Err(err)) => {
                 return error_response(
                     ErrorCode::GitError,
                     "apply execution failed",
@@ -459,12 +1337,20 @@ async fn apply_handler(
                     &request_id,
                 )
             }
+            Err(err) => {
+                return error_response(
+                    ErrorCode::GitError,
+                    "apply execution failed",
+                    Some(serde_json::json!({ "message": err.to_string() })),
+                    &request_id,
+                )
+            }
         }
     } else {
         planned_results(&plan)
     };
 
-    let response = build_apply_response(plan, results, source, &config, &diff);
+    let response = build_apply_response(plan, results, source, &config, &diff, &pathspec);
     info!(
         request_id = %request_id,
         results = response.results.len(),
@@ -495,10 +1381,44 @@ fn config_with_request_overrides(
     config
 }
 
+#[allow(clippy::result_large_err)]
 fn resolve_request_diff(
     repo_path: Option<&Path>,
     diff: Option<String>,
     config: &ResolvedConfig,
+    pathspec: &[String],
+    revisions: Option<&git::RevisionRange>,
+    request_id: &str,
+) -> Result<(String, InputSource, Vec<Warning>), Response> {
+    let (diff, source) =
+        resolve_unfiltered_request_diff(repo_path, diff, config, pathspec, revisions, request_id)?;
+    let filtered = git::filter_diff(&diff, &git::PathFilter::from_config(config)).map_err(|err| {
+        error_response(
+            ErrorCode::InputInvalid,
+            "invalid path filter",
+            Some(git_error_details(err)),
+            request_id,
+        )
+    })?;
+    if filtered.diff.is_empty() {
+        return Err(error_response(
+            ErrorCode::InputInvalid,
+            "no changes left after path filters",
+            Some(serde_json::json!({ "excluded": filtered.excluded })),
+            request_id,
+        ));
+    }
+    let warnings = excluded_paths_warnings(&filtered.excluded);
+    Ok((filtered.diff, source, warnings))
+}
+
+#[allow(clippy::result_large_err)]
+fn resolve_unfiltered_request_diff(
+    repo_path: Option<&Path>,
+    diff: Option<String>,
+    config: &ResolvedConfig,
+    pathspec: &[String],
+    revisions: Option<&git::RevisionRange>,
     request_id: &str,
 ) -> Result<(String, InputSource), Response> {
     if let Some(diff) = diff {
@@ -522,7 +1442,9 @@ fn resolve_request_diff(
         )
     })?;
 
-    let diff = git::compute_diff(repo, config.diff_mode, config.include_untracked).map_err(|err| {
+    let backend = git::backend_for(config.git_backend);
+    let options = unfiltered_diff_options(config, pathspec, revisions);
+    let diff = backend.diff(repo, &options).map_err(|err| {
         error_response(
             ErrorCode::GitError,
             "failed to compute git diff",
@@ -540,9 +1462,10 @@ fn resolve_request_diff(
         ));
     }
 
-    Ok((diff, InputSource::Repo))
+    Ok((diff, repo_source(revisions)))
 }
 
+#[allow(clippy::result_large_err)]
 fn validate_repo_path_http(path: &Path, request_id: &str) -> Result<(), Response> {
     if !path.exists() {
         return Err(error_response(
@@ -552,101 +1475,397 @@ fn validate_repo_path_http(path: &Path, request_id: &str) -> Result<(), Response
             request_id,
         ));
     }
-    if !path.is_dir() {
-        return Err(error_response(
-            ErrorCode::InputInvalid,
-            "repo path is not a directory",
-            Some(serde_json::json!({ "path": path.display().to_string() })),
-            request_id,
-        ));
+    if !path.is_dir() {
+        return Err(error_response(
+            ErrorCode::InputInvalid,
+            "repo path is not a directory",
+            Some(serde_json::json!({ "path": path.display().to_string() })),
+            request_id,
+        ));
+    }
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn validate_candidates_http(candidates: u32, request_id: &str) -> Result<(), Response> {
+    if candidates > config::MAX_PLAN_CANDIDATES {
+        return Err(error_response(
+            ErrorCode::InputInvalid,
+            "candidates exceeds the maximum",
+            Some(serde_json::json!({
+                "candidates": candidates,
+                "max": config::MAX_PLAN_CANDIDATES,
+            })),
+            request_id,
+        ));
+    }
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn validate_diff_size(diff: &str, max_bytes: u64, request_id: &str) -> Result<(), Response> {
+    let max_bytes_usize = usize::try_from(max_bytes).unwrap_or(usize::MAX);
+    if diff.len() > max_bytes_usize {
+        return Err(error_response(
+            ErrorCode::InputInvalid,
+            "diff exceeds max_diff_bytes",
+            Some(serde_json::json!({ "max_diff_bytes": max_bytes })),
+            request_id,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(test))]
+async fn request_commit_plan_http_impl(
+    config: &ResolvedConfig,
+    prompt: &llm::Prompt,
+) -> Result<CommitPlan, LlmError> {
+    llm::generate_commit_plan(config, prompt).await
+}
+
+#[cfg(test)]
+async fn request_commit_plan_http_impl(
+    _config: &ResolvedConfig,
+    _prompt: &llm::Prompt,
+) -> Result<CommitPlan, LlmError> {
+    use std::sync::atomic::Ordering;
+    match LLM_HTTP_MODE.load(Ordering::SeqCst) {
+        1 => Err(LlmError::Runtime("simulated runtime error".to_string())),
+        2 => Err(LlmError::Parse("simulated parse error".to_string())),
+        3 => Err(LlmError::Timeout),
+        _ => Ok(test_commit_plan()),
+    }
+}
+
+#[cfg(not(test))]
+async fn request_commit_plan_candidates_impl(
+    config: &ResolvedConfig,
+    prompt: &llm::Prompt,
+) -> Vec<Result<CommitPlan, LlmError>> {
+    llm::generate_commit_plan_candidates(config, prompt, config.plan_candidates).await
+}
+
+#[cfg(test)]
+async fn request_commit_plan_candidates_impl(
+    config: &ResolvedConfig,
+    _prompt: &llm::Prompt,
+) -> Vec<Result<CommitPlan, LlmError>> {
+    test_plan_candidates(config.plan_candidates)
+}
+
+async fn request_commit_plan_http_with_retry(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    diff: &str,
+    request_id: &str,
+) -> Result<(CommitPlan, Vec<Warning>), Response> {
+    let cache = plan_cache(config);
+    let key = CacheKey::new(config, prompt, diff);
+    if let Some(hit) = cached_plan(cache.as_ref(), &key, diff, config.atomicity) {
+        return Ok(hit);
+    }
+    let (plan, warnings) = request_commit_plan_http_uncached(config, prompt, diff, request_id).await?;
+    store_plan(cache.as_ref(), &key, &plan, &warnings);
+    Ok((plan, warnings))
+}
+
+async fn request_commit_plan_http_uncached(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    diff: &str,
+    request_id: &str,
+) -> Result<(CommitPlan, Vec<Warning>), Response> {
+    let run = run_plan_attempts(config, prompt, diff, |config, prompt| async move {
+        request_commit_plan_http_impl(&config, &prompt).await
+    })
+    .await;
+    match run.result {
+        Ok((plan, mut warnings)) => {
+            warnings.extend(retry_warning(&run.attempts));
+            Ok((plan, warnings))
+        }
+        Err(RetryError::Llm(err)) => Err(llm_error_response(err, request_id)),
+        Err(RetryError::Semantic(errors)) => Err(error_response(
+            ErrorCode::LlmParseError,
+            "semantic validation failed",
+            Some(semantic_error_details(&errors)),
+            request_id,
+        )),
+    }
+}
+
+/// Run the retry engine with `single` making each LLM call. With
+/// `plan_candidates` above 1 the first attempt samples several plans and
+/// keeps the best-scoring one.
+async fn run_plan_attempts<F, Fut>(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    diff: &str,
+    single: F,
+) -> PlanRun<Vec<Warning>>
+where
+    F: Fn(ResolvedConfig, Prompt) -> Fut,
+    Fut: std::future::Future<Output = Result<CommitPlan, LlmError>>,
+{
+    let omitted = &omitted_files(diff);
+    let single = &single;
+    let policy = RetryPolicy::from_config(config);
+    let run = retry::plan_with_retry(
+        config,
+        prompt,
+        &policy,
+        |config, prompt, attempt| async move {
+            if attempt == 1 && config.plan_candidates > 1 {
+                let candidates = request_commit_plan_candidates_impl(&config, &prompt).await;
+                select_candidate(candidates, diff, omitted, config.atomicity)
+            } else {
+                single(config, prompt).await
+            }
+        },
+        |plan| {
+            git::plan_submodule_units(&mut plan.plan, diff);
+            let mut warnings = repaired_validation_report(plan, omitted, diff, config.atomicity)?;
+            warnings.extend(order_plan(&mut plan.plan, diff, config.dependency_order));
+            Ok(warnings)
+        },
+    )
+    .await;
+    if run.result.is_err() {
+        debug!(attempts = ?run.attempts, "plan attempts exhausted");
+    }
+    run
+}
+
+/// One `plan_repaired` warning per repair made to the model's plan.
+fn repair_warnings(repairs: &[Repair]) -> Vec<Warning> {
+    repairs
+        .iter()
+        .map(|repair| Warning {
+            code: "plan_repaired".to_string(),
+            message: format!("commit {} {} repaired", repair.unit, repair.field.as_str()),
+            details: serde_json::to_value(repair).ok(),
+        })
+        .collect()
+}
+
+/// Order `units` by the dependencies between them under `policy`: a
+/// `plan_reordered` warning when units moved, one `unit_out_of_order`
+/// warning per late dependency with `warn`, and a `dependency_cycle`
+/// warning for units that could not be ordered.
+fn order_plan(units: &mut Vec<CommitUnit>, diff: &str, policy: DependencyOrder) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    match policy {
+        DependencyOrder::Off => {}
+        DependencyOrder::Warn => {
+            let dependencies = ordering::unit_dependencies(units, diff);
+            for dependency in ordering::late_dependencies(units, &dependencies) {
+                warnings.push(Warning {
+                    code: "unit_out_of_order".to_string(),
+                    message: format!(
+                        "commit {} uses {} from later commit {}",
+                        dependency.unit, dependency.symbol, dependency.depends_on
+                    ),
+                    details: serde_json::to_value(dependency).ok(),
+                });
+            }
+        }
+        DependencyOrder::Reorder => {
+            let report = ordering::order_units(units, diff);
+            if report.moved {
+                let order: Vec<&str> = units.iter().map(|unit| unit.id.as_str()).collect();
+                warnings.push(Warning {
+                    code: "plan_reordered".to_string(),
+                    message: format!("commits reordered so dependencies come first: {}", order.join(", ")),
+                    details: Some(serde_json::json!({
+                        "previous": report.previous,
+                        "order": order,
+                        "dependencies": report.dependencies,
+                    })),
+                });
+            }
+            if !report.cycle.is_empty() {
+                warnings.push(Warning {
+                    code: "dependency_cycle".to_string(),
+                    message: format!("commits {} depend on each other", report.cycle.join(", ")),
+                    details: Some(serde_json::json!({ "units": report.cycle })),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+/// An `llm_retried` warning carrying the attempt history, when the plan
+/// took more than one attempt.
+fn retry_warning(attempts: &[PlanAttempt]) -> Option<Warning> {
+    (attempts.len() > 1).then(|| Warning {
+        code: "llm_retried".to_string(),
+        message: format!("plan accepted after {} attempts", attempts.len()),
+        details: Some(serde_json::json!({ "attempts": attempts })),
+    })
+}
+
+/// The best-scoring of several sampled plans, or the first error when no
+/// sample parsed. Every candidate's score is logged at debug level.
+fn select_candidate(
+    candidates: Vec<Result<CommitPlan, LlmError>>,
+    diff: &str,
+    omitted: &[String],
+    atomicity: AtomicityPolicy,
+) -> Result<CommitPlan, LlmError> {
+    let mut plans = Vec::new();
+    let mut scores = Vec::new();
+    let mut first_error = None;
+    for (index, candidate) in candidates.into_iter().enumerate() {
+        match candidate {
+            Ok(plan) => {
+                let mut units = plan.plan.clone();
+                git::plan_submodule_units(&mut units, diff);
+                let score = scoring::score_plan(&units, diff, omitted, ScopePolicy::Warn, atomicity);
+                debug!(
+                    candidate = index,
+                    total = score.total,
+                    errors = score.errors,
+                    warnings = score.warnings,
+                    coverage = score.coverage,
+                    shared_files = score.shared_files,
+                    non_atomic = score.non_atomic,
+                    "scored plan candidate"
+                );
+                plans.push((index, plan));
+                scores.push(score);
+            }
+            Err(err) => {
+                debug!(candidate = index, error = %err, "plan candidate failed");
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+
+    match scoring::best_score(&scores) {
+        Some(best) => {
+            let (index, plan) = plans.swap_remove(best);
+            info!(candidate = index, total = scores[best].total, "selected plan candidate");
+            Ok(plan)
+        }
+        None => Err(first_error.unwrap_or_else(|| LlmError::Runtime("no plan candidates".to_string()))),
     }
-    Ok(())
 }
 
-fn validate_diff_size(diff: &str, max_bytes: u64, request_id: &str) -> Result<(), Response> {
-    let max_bytes_usize = usize::try_from(max_bytes).unwrap_or(usize::MAX);
-    if diff.as_bytes().len() > max_bytes_usize {
-        return Err(error_response(
-            ErrorCode::InputInvalid,
-            "diff exceeds max_diff_bytes",
-            Some(serde_json::json!({ "max_diff_bytes": max_bytes })),
-            request_id,
-        ));
-    }
-    Ok(())
+fn plan_cache(config: &ResolvedConfig) -> Option<PlanCache> {
+    plan_cache_impl(config)
 }
 
 #[cfg(not(test))]
-async fn request_commit_plan_http_impl(
-    config: &ResolvedConfig,
-    prompt: &llm::Prompt,
-) -> Result<CommitPlan, LlmError> {
-    llm::generate_commit_plan(config, prompt).await
+fn plan_cache_impl(config: &ResolvedConfig) -> Option<PlanCache> {
+    PlanCache::from_config(config).unwrap_or_else(|err| {
+        warn!(error = %err, "plan cache disabled");
+        None
+    })
 }
 
+/// Tests only cache into an explicit `cache_dir`, never the user's cache.
 #[cfg(test)]
-async fn request_commit_plan_http_impl(
-    _config: &ResolvedConfig,
-    _prompt: &llm::Prompt,
-) -> Result<CommitPlan, LlmError> {
-    use std::sync::atomic::Ordering;
-    match LLM_HTTP_MODE.load(Ordering::SeqCst) {
-        1 => Err(LlmError::Runtime("simulated runtime error".to_string())),
-        2 => Err(LlmError::Parse("simulated parse error".to_string())),
-        3 => Err(LlmError::Timeout),
-        _ => Ok(test_commit_plan()),
-    }
+fn plan_cache_impl(config: &ResolvedConfig) -> Option<PlanCache> {
+    config.cache_dir.as_ref().filter(|_| config.plan_cache).map(PlanCache::new)
 }
 
-async fn request_commit_plan_http_with_retry(
-    config: &ResolvedConfig,
-    prompt: &Prompt,
-    request_id: &str,
-) -> Result<(CommitPlan, Vec<Warning>), Response> {
-    let mut plan = match request_commit_plan_http_impl(config, prompt).await {
-        Ok(plan) => plan,
-        Err(err) => {
-            if matches!(err, LlmError::Parse(_)) {
-                let retry_prompt = llm::build_retry_prompt(prompt, &[err.to_string()]);
-                request_commit_plan_http_impl(config, &retry_prompt)
-                    .await
-                    .map_err(|err| llm_error_response(err, request_id))?
-            } else {
-                return Err(llm_error_response(err, request_id));
-            }
-        }
-    };
-    match semantic_validation_report(&plan) {
-        Ok(warnings) => Ok((plan, warnings)),
-        Err(errors) => {
-            let retry_prompt = llm::build_retry_prompt(prompt, &errors);
-            plan = request_commit_plan_http_impl(config, &retry_prompt)
-                .await
-                .map_err(|err| llm_error_response(err, request_id))?;
-            match semantic_validation_report(&plan) {
-                Ok(warnings) => Ok((plan, warnings)),
-                Err(errors) => Err(error_response(
-                    ErrorCode::LlmParseError,
-                    "semantic validation failed",
-                    Some(semantic_error_details(&errors)),
-                    request_id,
-                )),
-            }
+/// A cached plan for `key` that still passes semantic validation, with the
+/// warnings raised when it was accepted and a `plan_cached` warning.
+fn cached_plan(
+    cache: Option<&PlanCache>,
+    key: &CacheKey,
+    diff: &str,
+    atomicity: AtomicityPolicy,
+) -> Option<(CommitPlan, Vec<Warning>)> {
+    let (plan, mut warnings) = cache?.get(key)?;
+    semantic_validation_report(&plan, &omitted_files(diff), diff, atomicity).ok()?;
+    // The model that answered, which is not the primary one after a fallback.
+    let model = plan.backend.as_ref().map_or(&key.model, |backend| &backend.model);
+    info!(diff_hash = %key.diff_hash, model = %model, "plan cache hit");
+    warnings.push(Warning {
+        code: "plan_cached".to_string(),
+        message: "plan reused from the local cache".to_string(),
+        details: Some(serde_json::json!({ "diff_hash": key.diff_hash, "model": model })),
+    });
+    Some((plan, warnings))
+}
+
+fn store_plan(cache: Option<&PlanCache>, key: &CacheKey, plan: &CommitPlan, warnings: &[Warning]) {
+    if let Some(cache) = cache {
+        if let Err(err) = cache.put(key, plan, warnings) {
+            warn!(error = %err, "failed to cache plan");
         }
     }
 }
 
-fn semantic_validation_report(plan: &CommitPlan) -> Result<Vec<Warning>, Vec<String>> {
-    match semantic::validate_commit_units(&plan.plan, ScopePolicy::Warn) {
-        Ok(report) => Ok(semantic_warnings_to_warnings(&report.warnings)),
-        Err(errors) => Err(errors.iter().map(|err| err.to_string()).collect()),
+/// Semantic checks, coverage of `required` paths the model only saw as
+/// metadata, and atomicity checks against `diff`.
+fn semantic_validation_report(
+    plan: &CommitPlan,
+    required: &[String],
+    diff: &str,
+    atomicity: AtomicityPolicy,
+) -> Result<Vec<Warning>, SemanticValidationErrors> {
+    let units = semantic::validate_commit_units(&plan.plan, ScopePolicy::Warn);
+    plan_validation_report(plan, units, required, diff, atomicity)
+}
+
+/// [`semantic_validation_report`] for a model's plan, which is repaired
+/// first. Each repair made becomes a `plan_repaired` warning.
+fn repaired_validation_report(
+    plan: &mut CommitPlan,
+    required: &[String],
+    diff: &str,
+    atomicity: AtomicityPolicy,
+) -> Result<Vec<Warning>, SemanticValidationErrors> {
+    let units = semantic::repair_and_validate_commit_units(&mut plan.plan, ScopePolicy::Warn);
+    plan_validation_report(plan, units, required, diff, atomicity)
+}
+
+fn plan_validation_report(
+    plan: &CommitPlan,
+    units: Result<SemanticValidationReport, SemanticValidationErrors>,
+    required: &[String],
+    diff: &str,
+    atomicity: AtomicityPolicy,
+) -> Result<Vec<Warning>, SemanticValidationErrors> {
+    let coverage = semantic::validate_file_coverage(&plan.plan, required.iter().map(String::as_str));
+    let grouping = semantic::validate_atomicity(&plan.plan, diff, atomicity);
+    match (units, coverage, grouping) {
+        (Ok(report), Ok(()), Ok(grouping)) => {
+            let mut warnings = repair_warnings(&report.repairs);
+            warnings.extend(semantic_warnings_to_warnings(&report.warnings));
+            warnings.extend(semantic_warnings_to_warnings(&grouping.warnings));
+            Ok(warnings)
+        }
+        (units, coverage, grouping) => Err(units
+            .err()
+            .into_iter()
+            .flatten()
+            .chain(coverage.err().into_iter().flatten())
+            .chain(grouping.err().into_iter().flatten())
+            .collect()),
     }
 }
 
-fn semantic_error_details(errors: &[String]) -> Value {
+/// Files whose content was replaced by metadata in `diff`. Gitlinks are
+/// planned separately by `git::plan_submodule_units`.
+fn omitted_files(diff: &str) -> Vec<String> {
+    git::parse_diff_files(diff)
+        .into_iter()
+        .filter(|file| !matches!(file.kind, FileKind::Text | FileKind::Submodule))
+        .map(|file| file.path)
+        .collect()
+}
+
+fn semantic_error_details(errors: &[SemanticValidationError]) -> Value {
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
     serde_json::json!({ "errors": errors })
 }
 
+#[allow(clippy::result_large_err)]
 fn build_request_plan(plan: Vec<CommitUnit>, request_id: &str) -> Result<CommitPlan, Response> {
     let plan_units = plan;
     let value = serde_json::json!({
@@ -666,12 +1885,24 @@ fn build_request_plan(plan: Vec<CommitUnit>, request_id: &str) -> Result<CommitP
         request_id: None,
         warnings: None,
         input: None,
+        backend: None,
         plan: plan_units,
     })
 }
 
+#[allow(clippy::result_large_err)]
+fn prompt_templates_request(
+    config: &ResolvedConfig,
+    repo: Option<&Path>,
+    request_id: &str,
+) -> Result<PromptTemplates, Response> {
+    PromptTemplates::load(config, repo)
+        .map_err(|err| error_response(ErrorCode::ConfigError, &err.to_string(), None, request_id))
+}
+
+#[allow(clippy::result_large_err)]
 fn semantic_warnings_request(plan: &CommitPlan, request_id: &str) -> Result<Vec<Warning>, Response> {
-    match semantic_validation_report(plan) {
+    match semantic_validation_report(plan, &[], "", AtomicityPolicy::Off) {
         Ok(warnings) => Ok(warnings),
         Err(errors) => Err(error_response(
             ErrorCode::InputInvalid,
@@ -765,6 +1996,7 @@ fn test_commit_plan() -> CommitPlan {
         request_id: None,
         warnings: None,
         input: None,
+        backend: None,
         plan: vec![atomc_core::types::CommitUnit {
             id: "commit-1".to_string(),
             type_: atomc_core::types::CommitType::Docs,
@@ -780,12 +2012,29 @@ fn test_commit_plan() -> CommitPlan {
     }
 }
 
+fn prompt_templates(
+    config: &ResolvedConfig,
+    repo: Option<&Path>,
+    format: OutputFormat,
+) -> Result<PromptTemplates, ExitCode> {
+    PromptTemplates::load(config, repo)
+        .map_err(|err| emit_error(format, ErrorCode::ConfigError, &err.to_string(), None))
+}
+
 fn resolve_config(
     cli: &Cli,
     overrides: PartialConfig,
     format: OutputFormat,
 ) -> Result<ResolvedConfig, ExitCode> {
-    config::resolve_config(cli.config.clone(), overrides).map_err(|err| {
+    resolve_config_layers(cli, overrides, format).map(|(config, _)| config)
+}
+
+fn resolve_config_layers(
+    cli: &Cli,
+    overrides: PartialConfig,
+    format: OutputFormat,
+) -> Result<(ResolvedConfig, Vec<ConfigLayer>), ExitCode> {
+    config::resolve_config_layers(cli.config.clone(), overrides).map_err(|err| {
         emit_error(
             format,
             ErrorCode::ConfigError,
@@ -826,9 +2075,17 @@ fn map_diff_mode(value: cli::DiffMode) -> config::DiffMode {
     }
 }
 
+fn map_hook_mode(value: cli::HookMode) -> git::HookPolicy {
+    match value {
+        cli::HookMode::Run => git::HookPolicy::Run,
+        cli::HookMode::Skip => git::HookPolicy::Skip,
+        cli::HookMode::FailFast => git::HookPolicy::FailFast,
+    }
+}
+
 fn input_diff_mode(source: &InputSource, mode: config::DiffMode) -> Option<config::DiffMode> {
     match source {
-        InputSource::Repo => Some(mode),
+        InputSource::Repo | InputSource::Range => Some(mode),
         InputSource::Diff => None,
     }
 }
@@ -836,7 +2093,7 @@ fn input_diff_mode(source: &InputSource, mode: config::DiffMode) -> Option<confi
 fn input_include_untracked(source: &InputSource, include_untracked: bool) -> Option<bool> {
     match source {
         InputSource::Repo => Some(include_untracked),
-        InputSource::Diff => None,
+        InputSource::Diff | InputSource::Range => None,
     }
 }
 
@@ -845,6 +2102,37 @@ fn output_diff_mode(mode: config::DiffMode) -> OutputDiffMode {
         config::DiffMode::Worktree => OutputDiffMode::Worktree,
         config::DiffMode::Staged => OutputDiffMode::Staged,
         config::DiffMode::All => OutputDiffMode::All,
+        config::DiffMode::Base => OutputDiffMode::Base,
+        config::DiffMode::Range => OutputDiffMode::Range,
+    }
+}
+
+/// Resolve `--base` or `--range` to commits, with the diff mode that plans
+/// them. `None` when neither is given.
+fn resolve_plan_revisions(
+    repo: &Path,
+    base: Option<&str>,
+    range: Option<&str>,
+) -> Result<Option<(config::DiffMode, git::RevisionRange)>, GitError> {
+    match (base, range) {
+        (Some(base), _) => Ok(Some((config::DiffMode::Base, git::resolve_base(repo, base)?))),
+        (None, Some(range)) => Ok(Some((config::DiffMode::Range, git::resolve_range(repo, range)?))),
+        (None, None) => Ok(None),
+    }
+}
+
+fn repo_source(revisions: Option<&git::RevisionRange>) -> InputSource {
+    if revisions.is_some() {
+        InputSource::Range
+    } else {
+        InputSource::Repo
+    }
+}
+
+fn input_revisions(range: &git::RevisionRange) -> InputRevisions {
+    InputRevisions {
+        base: range.base.clone(),
+        head: range.head.clone(),
     }
 }
 
@@ -892,27 +2180,47 @@ fn validate_diff_requirements(
                 None,
             ));
         }
-        let max_bytes = usize::try_from(config.max_diff_bytes).unwrap_or(usize::MAX);
-        if diff.as_bytes().len() > max_bytes {
-            return Err(emit_error(
-                format,
-                ErrorCode::InputInvalid,
-                "diff exceeds max_diff_bytes",
-                Some(serde_json::json!({ "max_diff_bytes": config.max_diff_bytes })),
-            ));
-        }
+        check_diff_size(diff, config, format)?;
     }
 
     Ok(())
 }
 
-fn build_input_meta(source: InputSource, config: &ResolvedConfig, diff: &str) -> InputMeta {
-    let (diff_mode, include_untracked) = match source {
+fn check_diff_size(diff: &str, config: &ResolvedConfig, format: OutputFormat) -> Result<(), ExitCode> {
+    let max_bytes = usize::try_from(config.max_diff_bytes).unwrap_or(usize::MAX);
+    if diff.len() > max_bytes {
+        return Err(emit_error(
+            format,
+            ErrorCode::InputInvalid,
+            "diff exceeds max_diff_bytes",
+            Some(serde_json::json!({ "max_diff_bytes": config.max_diff_bytes })),
+        ));
+    }
+    Ok(())
+}
+
+fn build_input_meta(
+    source: InputSource,
+    config: &ResolvedConfig,
+    diff: &str,
+    pathspec: &[String],
+    revisions: Option<&git::RevisionRange>,
+) -> InputMeta {
+    let pathspec_meta = (!pathspec.is_empty()).then(|| pathspec.to_vec());
+    let (diff_mode, include_untracked, pathspec, revisions) = match source {
         InputSource::Repo => (
             Some(output_diff_mode(config.diff_mode)),
             Some(config.include_untracked),
+            pathspec_meta,
+            None,
+        ),
+        InputSource::Range => (
+            Some(output_diff_mode(config.diff_mode)),
+            None,
+            pathspec_meta,
+            revisions.map(input_revisions),
         ),
-        InputSource::Diff => (None, None),
+        InputSource::Diff => (None, None, None, None),
     };
 
     InputMeta {
@@ -920,6 +2228,11 @@ fn build_input_meta(source: InputSource, config: &ResolvedConfig, diff: &str) ->
         diff_mode,
         include_untracked,
         diff_hash: Some(hash::diff_hash(diff)),
+        files: Some(git::parse_diff_files(diff)),
+        pathspec,
+        file_hashes: Some(git::file_hashes(diff)),
+        revisions,
+        prompt_version: None,
     }
 }
 
@@ -929,14 +2242,20 @@ fn build_apply_response(
     source: InputSource,
     config: &ResolvedConfig,
     diff: &str,
+    pathspec: &[String],
 ) -> CommitApplyResponse {
     let request_id = plan.request_id.clone().or_else(|| Some(request_id()));
+    let prompt_version = plan.input.and_then(|input| input.prompt_version);
 
     CommitApplyResponse {
         schema_version: SCHEMA_VERSION.to_string(),
         request_id,
         warnings: plan.warnings,
-        input: Some(build_input_meta(source, config, diff)),
+        input: Some(InputMeta {
+            prompt_version,
+            ..build_input_meta(source, config, diff, pathspec, None)
+        }),
+        backend: plan.backend,
         plan: plan.plan,
         results,
     }
@@ -950,6 +2269,24 @@ fn planned_results(plan: &CommitPlan) -> Vec<ApplyResult> {
             status: ApplyStatus::Planned,
             commit_hash: None,
             error: None,
+            hooks: None,
+            signed: None,
+            verify: None,
+        })
+        .collect()
+}
+
+fn skipped_results(plan: &CommitPlan) -> Vec<ApplyResult> {
+    plan.plan
+        .iter()
+        .map(|unit| ApplyResult {
+            id: unit.id.clone(),
+            status: ApplyStatus::Skipped,
+            commit_hash: None,
+            error: None,
+            hooks: None,
+            signed: None,
+            verify: None,
         })
         .collect()
 }
@@ -962,6 +2299,9 @@ fn applied_results(plan: &[atomc_core::types::CommitUnit]) -> Vec<ApplyResult> {
             status: ApplyStatus::Applied,
             commit_hash: Some("test-hash".to_string()),
             error: None,
+            hooks: Some(atomc_core::types::HookStatus::NotInstalled),
+            signed: Some(false),
+            verify: None,
         })
         .collect()
 }
@@ -975,6 +2315,11 @@ fn semantic_warnings_to_warnings(warnings: &[SemanticWarning]) -> Vec<Warning> {
                 message: format!("commit {id} scope is missing"),
                 details: None,
             },
+            SemanticWarning::NonAtomic { id, kind, message } => Warning {
+                code: "non_atomic".to_string(),
+                message: format!("commit {id} may not be atomic: {message}"),
+                details: Some(serde_json::json!({ "commit_id": id, "kind": kind })),
+            },
         })
         .collect()
 }
@@ -989,6 +2334,96 @@ fn merge_warnings(existing: Option<Vec<Warning>>, new: Vec<Warning>) -> Option<V
     }
 }
 
+/// Diff options without the path filter, so the paths it drops can be
+/// reported by [`filter_input_diff`].
+fn unfiltered_diff_options(
+    config: &ResolvedConfig,
+    pathspec: &[String],
+    revisions: Option<&git::RevisionRange>,
+) -> git::DiffOptions {
+    git::DiffOptions {
+        filter: git::PathFilter::default(),
+        pathspec: pathspec.to_vec(),
+        range: revisions.cloned(),
+        ..git::DiffOptions::from_config(config)
+    }
+}
+
+/// Apply the configured include and ignore globs to `diff`, with a warning
+/// listing the paths left out.
+fn filter_input_diff(
+    diff: String,
+    config: &ResolvedConfig,
+    format: OutputFormat,
+) -> Result<(String, Vec<Warning>), ExitCode> {
+    let filtered = filter_nonempty_diff(&diff, config, format)?;
+    let warnings = excluded_paths_warnings(&filtered.excluded);
+    Ok((filtered.diff, warnings))
+}
+
+/// [`filter_diff_paths`], failing when the filters leave nothing to plan.
+fn filter_nonempty_diff(
+    diff: &str,
+    config: &ResolvedConfig,
+    format: OutputFormat,
+) -> Result<git::FilteredDiff, ExitCode> {
+    let filtered = filter_diff_paths(diff, config, format)?;
+    if filtered.diff.is_empty() {
+        return Err(emit_error(
+            format,
+            ErrorCode::InputInvalid,
+            "no changes left after path filters",
+            Some(serde_json::json!({ "excluded": filtered.excluded })),
+        ));
+    }
+    Ok(filtered)
+}
+
+/// Like [`excluded_paths_warnings`], for `split`, which has to commit the
+/// excluded paths to keep the rebuilt tree identical.
+fn carried_paths_warnings(excluded: &[String]) -> Vec<Warning> {
+    if excluded.is_empty() {
+        return Vec::new();
+    }
+    vec![Warning {
+        code: "paths_excluded".to_string(),
+        message: format!(
+            "{} changed path(s) excluded by path filters and committed in a separate chore commit",
+            excluded.len()
+        ),
+        details: Some(serde_json::json!({ "paths": excluded })),
+    }]
+}
+
+fn filter_diff_paths(
+    diff: &str,
+    config: &ResolvedConfig,
+    format: OutputFormat,
+) -> Result<git::FilteredDiff, ExitCode> {
+    git::filter_diff(diff, &git::PathFilter::from_config(config)).map_err(|err| {
+        emit_error(
+            format,
+            ErrorCode::UsageError,
+            "invalid path filter",
+            Some(git_error_details(err)),
+        )
+    })
+}
+
+fn excluded_paths_warnings(excluded: &[String]) -> Vec<Warning> {
+    if excluded.is_empty() {
+        return Vec::new();
+    }
+    vec![Warning {
+        code: "paths_excluded".to_string(),
+        message: format!(
+            "{} changed path(s) excluded by path filters and left uncommitted",
+            excluded.len()
+        ),
+        details: Some(serde_json::json!({ "paths": excluded })),
+    }]
+}
+
 fn resolve_diff_input(
     diff_file: Option<PathBuf>,
     max_bytes: u64,
@@ -1089,7 +2524,7 @@ fn read_limited<R: Read>(
     })?;
 
     let max_bytes_usize = usize::try_from(max_bytes).unwrap_or(usize::MAX);
-    if buffer.as_bytes().len() > max_bytes_usize {
+    if buffer.len() > max_bytes_usize {
         let mut payload = base_details;
         if let Some(obj) = payload.as_object_mut() {
             obj.insert("max_diff_bytes".to_string(), serde_json::json!(max_bytes));
@@ -1108,53 +2543,57 @@ fn read_limited<R: Read>(
 fn request_commit_plan_with_retry(
     config: &ResolvedConfig,
     prompt: &Prompt,
+    diff: &str,
     format: OutputFormat,
 ) -> Result<(CommitPlan, Vec<Warning>), ExitCode> {
-    let mut plan = match request_commit_plan_impl(config, prompt) {
-        Ok(plan) => plan,
-        Err(err) => {
-            if matches!(err, LlmError::Parse(_)) {
-                let retry_prompt = llm::build_retry_prompt(prompt, &[err.to_string()]);
-                request_commit_plan_impl(config, &retry_prompt)
-                    .map_err(|err| map_llm_error(format, err))?
-            } else {
-                return Err(map_llm_error(format, err));
-            }
-        }
-    };
-    match semantic_validation_report(&plan) {
-        Ok(warnings) => Ok((plan, warnings)),
-        Err(errors) => {
-            let retry_prompt = llm::build_retry_prompt(prompt, &errors);
-            plan = request_commit_plan_impl(config, &retry_prompt)
-                .map_err(|err| map_llm_error(format, err))?;
-            match semantic_validation_report(&plan) {
-                Ok(warnings) => Ok((plan, warnings)),
-                Err(errors) => Err(emit_error(
-                    format,
-                    ErrorCode::LlmParseError,
-                    "semantic validation failed",
-                    Some(semantic_error_details(&errors)),
-                )),
-            }
+    let cache = plan_cache(config);
+    let key = CacheKey::new(config, prompt, diff);
+    if let Some(hit) = cached_plan(cache.as_ref(), &key, diff, config.atomicity) {
+        return Ok(hit);
+    }
+    let (plan, warnings) = request_commit_plan_uncached(config, prompt, diff, format)?;
+    store_plan(cache.as_ref(), &key, &plan, &warnings);
+    Ok((plan, warnings))
+}
+
+fn request_commit_plan_uncached(
+    config: &ResolvedConfig,
+    prompt: &Prompt,
+    diff: &str,
+    format: OutputFormat,
+) -> Result<(CommitPlan, Vec<Warning>), ExitCode> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| map_llm_error(format, LlmError::Runtime(err.to_string())))?;
+    let run = runtime.block_on(run_plan_attempts(config, prompt, diff, |config, prompt| async move {
+        request_commit_plan_impl(&config, &prompt).await
+    }));
+    match run.result {
+        Ok((plan, mut warnings)) => {
+            warnings.extend(retry_warning(&run.attempts));
+            Ok((plan, warnings))
         }
+        Err(RetryError::Llm(err)) => Err(map_llm_error(format, err)),
+        Err(RetryError::Semantic(errors)) => Err(emit_error(
+            format,
+            ErrorCode::LlmParseError,
+            "semantic validation failed",
+            Some(semantic_error_details(&errors)),
+        )),
     }
 }
 
 #[cfg(not(test))]
-fn request_commit_plan_impl(
+async fn request_commit_plan_impl(
     config: &ResolvedConfig,
     prompt: &llm::Prompt,
 ) -> Result<CommitPlan, LlmError> {
-    let runtime = tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()
-        .map_err(|err| LlmError::Runtime(err.to_string()))?;
-    runtime.block_on(llm::generate_commit_plan(config, prompt))
+    llm::generate_commit_plan(config, prompt).await
 }
 
 #[cfg(test)]
-fn request_commit_plan_impl(
+async fn request_commit_plan_impl(
     _config: &ResolvedConfig,
     _prompt: &llm::Prompt,
 ) -> Result<CommitPlan, LlmError> {
@@ -1163,6 +2602,7 @@ fn request_commit_plan_impl(
         request_id: None,
         warnings: None,
         input: None,
+        backend: None,
         plan: vec![atomc_core::types::CommitUnit {
             id: "commit-1".to_string(),
             type_: atomc_core::types::CommitType::Docs,
@@ -1178,6 +2618,23 @@ fn request_commit_plan_impl(
     })
 }
 
+/// A parse failure, a plan that fails semantic validation, then valid
+/// plans, repeated up to `count`.
+#[cfg(test)]
+fn test_plan_candidates(count: u32) -> Vec<Result<CommitPlan, LlmError>> {
+    (0..count)
+        .map(|index| match index {
+            0 => Err(LlmError::Parse("simulated parse error".to_string())),
+            1 => {
+                let mut plan = test_commit_plan();
+                plan.plan[0].summary = "too short".to_string();
+                Ok(plan)
+            }
+            _ => Ok(test_commit_plan()),
+        })
+        .collect()
+}
+
 fn execute_apply_plan(request: git::ApplyRequest<'_>) -> Result<Vec<ApplyResult>, GitError> {
     execute_apply_plan_impl(request)
 }
@@ -1195,10 +2652,25 @@ fn execute_apply_plan_impl(request: git::ApplyRequest<'_>) -> Result<Vec<ApplyRe
             stderr: "simulated failure".to_string(),
         });
     }
+    crate::tests::record_verify_command(request.verify_command);
 
     Ok(applied_results(request.plan))
 }
 
+fn check_apply_request(request: &git::ApplyRequest<'_>) -> Result<(), GitError> {
+    check_apply_request_impl(request)
+}
+
+#[cfg(not(test))]
+fn check_apply_request_impl(request: &git::ApplyRequest<'_>) -> Result<(), GitError> {
+    git::check_apply_request(request)
+}
+
+#[cfg(test)]
+fn check_apply_request_impl(_request: &git::ApplyRequest<'_>) -> Result<(), GitError> {
+    Ok(())
+}
+
 fn map_llm_error(format: OutputFormat, error: LlmError) -> ExitCode {
     match error {
         LlmError::Runtime(message) => emit_error(
@@ -1335,6 +2807,7 @@ fn input_source_str(source: &InputSource) -> &'static str {
     match source {
         InputSource::Repo => "repo",
         InputSource::Diff => "diff",
+        InputSource::Range => "range",
     }
 }
 
@@ -1389,21 +2862,143 @@ fn emit_apply(format: OutputFormat, response: &CommitApplyResponse) -> Result<()
             println!("{payload}");
             Ok(())
         }
-        OutputFormat::Human => {
-            print_apply_human(response);
-            Ok(())
+        OutputFormat::Human => {
+            print_apply_human(response);
+            Ok(())
+        }
+    }
+}
+
+fn emit_workspace(format: OutputFormat, response: &WorkspaceApplyResponse) -> Result<(), ExitCode> {
+    match format {
+        OutputFormat::Json => {
+            let payload = serde_json::to_string(response).unwrap_or_else(|_| {
+                format!(
+                    "{{\"schema_version\":\"{}\",\"error\":\"failed to serialize workspace response\"}}",
+                    SCHEMA_VERSION
+                )
+            });
+            println!("{payload}");
+            Ok(())
+        }
+        OutputFormat::Human => {
+            for repo in &response.repos {
+                println!("Repository {}:", repo.repo);
+                print_apply_human(&repo.response);
+                if let Some(error) = &repo.error {
+                    println!("   error: {}", error.message);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn emit_eval(format: OutputFormat, report: &EvalReport) -> Result<(), ExitCode> {
+    match format {
+        OutputFormat::Json => {
+            let payload = serde_json::to_string(report).unwrap_or_else(|_| {
+                format!(
+                    "{{\"schema_version\":\"{}\",\"error\":\"failed to serialize eval report\"}}",
+                    SCHEMA_VERSION
+                )
+            });
+            println!("{payload}");
+        }
+        OutputFormat::Human => print_eval_human(report),
+    }
+    Ok(())
+}
+
+fn print_eval_human(report: &EvalReport) {
+    let width = report
+        .models
+        .iter()
+        .map(|summary| summary.model.len())
+        .max()
+        .unwrap_or(0)
+        .max("model".len());
+    println!(
+        "{:<width$}  {:>7}  {:>8}  {:>8}  {:>5}  {:>8}  {:>7}",
+        "model", "schema", "semantic", "coverage", "units", "mean_ms", "max_ms"
+    );
+    for summary in &report.models {
+        let units = summary
+            .unit_agreement
+            .map(|agreement| format!("{:.0}%", agreement * 100.0))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<width$}  {:>7}  {:>8}  {:>7.0}%  {:>5}  {:>8}  {:>7}",
+            summary.model,
+            format!("{}/{}", summary.schema_valid, summary.cases),
+            format!("{}/{}", summary.semantic_valid, summary.cases),
+            summary.coverage * 100.0,
+            units,
+            summary.mean_latency_ms,
+            summary.max_latency_ms,
+        );
+    }
+    let failures: Vec<_> = report.cases.iter().filter(|result| !result.semantic_valid).collect();
+    if !failures.is_empty() {
+        println!("Failed cases:");
+        for result in failures {
+            let reason = result.error.as_deref().unwrap_or("semantic validation failed");
+            println!("- {} / {}: {reason}", result.model, result.case);
+        }
+    }
+}
+
+fn emit_health(format: OutputFormat, report: &HealthReport) {
+    match format {
+        OutputFormat::Json => {
+            let payload = serde_json::to_string(report).unwrap_or_else(|_| {
+                format!(
+                    "{{\"schema_version\":\"{}\",\"error\":\"failed to serialize health report\"}}",
+                    SCHEMA_VERSION
+                )
+            });
+            println!("{payload}");
+        }
+        OutputFormat::Human => print_health_human(report),
+    }
+}
+
+fn print_health_human(report: &HealthReport) {
+    for check in &report.checks {
+        println!("[{}] {}: {}", check.status.as_str(), check.name, check.message);
+        if let Some(fix) = &check.fix {
+            println!("   fix: {fix}");
+        }
+        if check.name == "config" {
+            let layers = check
+                .details
+                .as_ref()
+                .and_then(|details| details.get("layers"))
+                .and_then(Value::as_array);
+            for layer in layers.into_iter().flatten() {
+                let name = layer.get("name").and_then(Value::as_str).unwrap_or_default();
+                let keys: Vec<&str> = layer
+                    .get("keys")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .collect();
+                let keys = if keys.is_empty() { "-".to_string() } else { keys.join(", ") };
+                match layer.get("source").and_then(Value::as_str) {
+                    Some(source) => println!("   {name} ({source}): {keys}"),
+                    None => println!("   {name}: {keys}"),
+                }
+            }
         }
     }
+    println!("Overall: {}", report.status.as_str());
 }
 
 fn print_plan_human(plan: &CommitPlan) {
     println!("Commit plan ({} commits):", plan.plan.len());
     for (idx, unit) in plan.plan.iter().enumerate() {
-        let header = match unit.scope.as_deref() {
-            Some(scope) => format!("{}[{}]: {}", commit_type_str(&unit.type_), scope, unit.summary),
-            None => format!("{}: {}", commit_type_str(&unit.type_), unit.summary),
-        };
-        println!("{}. {}", idx + 1, header);
+        println!("{}. {}", idx + 1, unit_header(unit));
         for line in &unit.body {
             println!("   {}", line);
         }
@@ -1416,11 +3011,7 @@ fn print_plan_human(plan: &CommitPlan) {
 fn print_apply_human(response: &CommitApplyResponse) {
     println!("Apply plan ({} commits):", response.plan.len());
     for (idx, unit) in response.plan.iter().enumerate() {
-        let header = match unit.scope.as_deref() {
-            Some(scope) => format!("{}[{}]: {}", commit_type_str(&unit.type_), scope, unit.summary),
-            None => format!("{}: {}", commit_type_str(&unit.type_), unit.summary),
-        };
-        println!("{}. {}", idx + 1, header);
+        println!("{}. {}", idx + 1, unit_header(unit));
         for line in &unit.body {
             println!("   {}", line);
         }
@@ -1433,6 +3024,13 @@ fn print_apply_human(response: &CommitApplyResponse) {
     }
 }
 
+fn unit_header(unit: &CommitUnit) -> String {
+    match unit.scope.as_deref() {
+        Some(scope) => format!("{}[{}]: {}", commit_type_str(&unit.type_), scope, unit.summary),
+        None => format!("{}: {}", commit_type_str(&unit.type_), unit.summary),
+    }
+}
+
 fn commit_type_str(commit_type: &atomc_core::types::CommitType) -> &'static str {
     match commit_type {
         atomc_core::types::CommitType::Feat => "feat",
@@ -1461,17 +3059,27 @@ fn request_id() -> String {
     Ulid::new().to_string()
 }
 
-fn compute_repo_diff(repo: &Path, config: &ResolvedConfig, format: OutputFormat) -> Result<String, ExitCode> {
-    compute_repo_diff_impl(repo, config, format)
+fn compute_repo_diff(
+    repo: &Path,
+    config: &ResolvedConfig,
+    pathspec: &[String],
+    revisions: Option<&git::RevisionRange>,
+    format: OutputFormat,
+) -> Result<String, ExitCode> {
+    compute_repo_diff_impl(repo, config, pathspec, revisions, format)
 }
 
 #[cfg(not(test))]
 fn compute_repo_diff_impl(
     repo: &Path,
     config: &ResolvedConfig,
+    pathspec: &[String],
+    revisions: Option<&git::RevisionRange>,
     format: OutputFormat,
 ) -> Result<String, ExitCode> {
-    atomc_core::git::compute_diff(repo, config.diff_mode, config.include_untracked).map_err(|err| {
+    let backend = atomc_core::git::backend_for(config.git_backend);
+    let options = unfiltered_diff_options(config, pathspec, revisions);
+    backend.diff(repo, &options).map_err(|err| {
         emit_error(
             format,
             ErrorCode::GitError,
@@ -1485,6 +3093,8 @@ fn compute_repo_diff_impl(
 fn compute_repo_diff_impl(
     repo: &Path,
     config: &ResolvedConfig,
+    _pathspec: &[String],
+    _revisions: Option<&git::RevisionRange>,
     _format: OutputFormat,
 ) -> Result<String, ExitCode> {
     if config.max_diff_bytes == 0 {
@@ -1503,8 +3113,8 @@ fn git_error_details(error: GitError) -> Value {
             serde_json::json!({ "cmd": cmd, "error": source.to_string() })
         }
         GitError::OutputNotUtf8 => serde_json::json!({ "error": "git output was not utf-8" }),
-        GitError::DiffHashMismatch { expected, actual } => {
-            serde_json::json!({ "expected": expected, "actual": actual })
+        GitError::DiffHashMismatch { expected, actual, files } => {
+            serde_json::json!({ "expected": expected, "actual": actual, "files": files })
         }
         GitError::HunksNotSupported { id } => serde_json::json!({ "id": id }),
         GitError::PlanFileMissing { id, file } => {
@@ -1514,10 +3124,62 @@ fn git_error_details(error: GitError) -> Value {
             serde_json::json!({ "id": id, "expected": expected, "actual": actual })
         }
         GitError::StagedDiffEmpty { id } => serde_json::json!({ "id": id }),
+        GitError::InvalidRange { spec, reason } => {
+            serde_json::json!({ "range": spec, "error": reason })
+        }
+        GitError::RefExists { name } => serde_json::json!({ "ref": name }),
+        GitError::SplitTreeMismatch { expected, actual } => {
+            serde_json::json!({ "expected_tree": expected, "actual_tree": actual })
+        }
+        GitError::TreeDrift { expected, actual, files } => serde_json::json!({
+            "expected_tree": expected,
+            "actual_tree": actual,
+            "files": files
+        }),
+        GitError::RenameSplit { id, from, to } => serde_json::json!({
+            "id": id,
+            "from": from,
+            "to": to
+        }),
+        GitError::SubmoduleMixed { id, submodule } => serde_json::json!({
+            "id": id,
+            "submodule": submodule
+        }),
+        GitError::InvalidPattern { pattern, message } => serde_json::json!({
+            "pattern": pattern,
+            "error": message
+        }),
+        GitError::ReadOnlyDiffMode { mode } => serde_json::json!({ "diff_mode": mode }),
+        GitError::RollbackWithoutVerify => serde_json::json!({
+            "error": "rollback_on_verify_failure is set but no verify command is configured"
+        }),
+        GitError::Io { path, source } => serde_json::json!({
+            "path": path,
+            "error": source.to_string()
+        }),
+        GitError::Native { op, message } => serde_json::json!({
+            "op": op,
+            "error": message
+        }),
+        GitError::HookFailed {
+            id,
+            hooks,
+            exit_code,
+            stdout,
+            stderr,
+        } => serde_json::json!({
+            "id": id,
+            "hooks": hooks,
+            "exit_code": exit_code,
+            "stdout": stdout,
+            "stderr": stderr
+        }),
     }
 }
 
 #[cfg(test)]
+// Tests serialize shared global state with a std mutex across awaits.
+#[allow(clippy::await_holding_lock)]
 mod tests {
     use super::*;
     use atomc_core::config::ResolvedConfig;
@@ -1551,6 +3213,7 @@ mod tests {
 
     static TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
     static LOG_DIFF_PREVIEW: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+    static VERIFY_COMMAND: OnceLock<Mutex<Option<String>>> = OnceLock::new();
 
     fn lock_test_state() -> std::sync::MutexGuard<'static, ()> {
         TEST_LOCK
@@ -1575,6 +3238,22 @@ mod tests {
         *guard = Some(preview.to_string());
     }
 
+    pub(super) fn record_verify_command(command: Option<&str>) {
+        let mut guard = VERIFY_COMMAND
+            .get_or_init(|| Mutex::new(None))
+            .lock()
+            .unwrap();
+        *guard = command.map(str::to_string);
+    }
+
+    fn take_verify_command() -> Option<String> {
+        VERIFY_COMMAND
+            .get_or_init(|| Mutex::new(None))
+            .lock()
+            .unwrap()
+            .take()
+    }
+
     fn take_log_diff_preview() -> Option<String> {
         LOG_DIFF_PREVIEW
             .get_or_init(|| Mutex::new(None))
@@ -1618,6 +3297,79 @@ mod tests {
         std::env::temp_dir().join(format!("atomc-{prefix}-{nanos}"))
     }
 
+    #[test]
+    fn plan_args_accept_trailing_pathspec() {
+        let cli = Cli::try_parse_from(["atomc", "plan", "--repo", ".", "--", "crates/atomc-core", "docs"])
+            .unwrap();
+        let Commands::Plan(args) = cli.command else {
+            panic!("expected plan command");
+        };
+        assert_eq!(args.pathspec, vec!["crates/atomc-core", "docs"]);
+    }
+
+    #[test]
+    fn build_input_meta_records_pathspec_for_repo_diffs() {
+        let config = ResolvedConfig::defaults();
+        let pathspec = vec!["crates/atomc-core".to_string()];
+        let repo = build_input_meta(InputSource::Repo, &config, "diff", &pathspec, None);
+        assert_eq!(repo.pathspec, Some(pathspec.clone()));
+        let supplied = build_input_meta(InputSource::Diff, &config, "diff", &pathspec, None);
+        assert_eq!(supplied.pathspec, None);
+        let whole = build_input_meta(InputSource::Repo, &config, "diff", &[], None);
+        assert_eq!(whole.pathspec, None);
+    }
+
+    #[test]
+    fn plan_args_accept_base_and_range() {
+        let cli = Cli::try_parse_from(["atomc", "plan", "--repo", ".", "--base", "origin/main"]).unwrap();
+        let Commands::Plan(args) = cli.command else {
+            panic!("expected plan command");
+        };
+        assert_eq!(args.base.as_deref(), Some("origin/main"));
+
+        let cli = Cli::try_parse_from(["atomc", "plan", "--repo", ".", "--range", "v1.0..v1.1"]).unwrap();
+        let Commands::Plan(args) = cli.command else {
+            panic!("expected plan command");
+        };
+        assert_eq!(args.range.as_deref(), Some("v1.0..v1.1"));
+    }
+
+    #[test]
+    fn plan_args_reject_conflicting_revision_sources() {
+        for args in [
+            vec!["atomc", "plan", "--base", "main"],
+            vec!["atomc", "plan", "--repo", ".", "--base", "main", "--range", "a..b"],
+            vec!["atomc", "plan", "--repo", ".", "--range", "a..b", "--diff-mode", "staged"],
+            vec!["atomc", "plan", "--repo", ".", "--base", "main", "--diff-file", "x.diff"],
+        ] {
+            assert!(Cli::try_parse_from(&args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn build_input_meta_records_revisions_for_range_diffs() {
+        let config = ResolvedConfig {
+            diff_mode: config::DiffMode::Base,
+            ..ResolvedConfig::defaults()
+        };
+        let range = git::RevisionRange {
+            base: "a".repeat(40),
+            head: "b".repeat(40),
+        };
+        let meta = build_input_meta(InputSource::Range, &config, "diff", &[], Some(&range));
+        assert!(matches!(meta.diff_mode, Some(OutputDiffMode::Base)));
+        assert_eq!(meta.include_untracked, None);
+        assert_eq!(
+            meta.revisions,
+            Some(InputRevisions {
+                base: range.base.clone(),
+                head: range.head.clone(),
+            })
+        );
+        let repo = build_input_meta(InputSource::Repo, &ResolvedConfig::defaults(), "diff", &[], Some(&range));
+        assert_eq!(repo.revisions, None);
+    }
+
     #[test]
     fn validate_repo_path_rejects_missing_path() {
         let path = temp_dir("missing");
@@ -1706,11 +3458,20 @@ mod tests {
                 repo: Some(dir.clone()),
                 diff_file: None,
                 diff_mode: None,
+                base: None,
+                range: None,
                 include_untracked: false,
                 no_include_untracked: false,
+                recurse_submodules: false,
+                include: Vec::new(),
+                exclude: Vec::new(),
+                pathspec: Vec::new(),
                 format: OutputFormat::Json,
                 log_diff: false,
                 no_log_diff: false,
+                no_cache: false,
+                candidates: None,
+                examples: None,
                 model: None,
                 dry_run: true,
                 timeout: None,
@@ -1742,13 +3503,26 @@ mod tests {
                 diff_mode: None,
                 include_untracked: false,
                 no_include_untracked: false,
+                recurse_submodules: false,
+                include: Vec::new(),
+                exclude: Vec::new(),
+                pathspec: Vec::new(),
                 format: OutputFormat::Json,
                 log_diff: false,
                 no_log_diff: false,
+                no_cache: false,
+                candidates: None,
+                examples: None,
                 model: None,
                 assisted_by: None,
                 execute: false,
                 cleanup_on_error: false,
+                hooks: cli::HookMode::FailFast,
+                sign: false,
+                signing_key: None,
+                verify_command: None,
+                rollback_on_verify_failure: false,
+                strict_diff_hash: false,
                 timeout: None,
             }),
         };
@@ -1779,13 +3553,26 @@ mod tests {
                 diff_mode: None,
                 include_untracked: false,
                 no_include_untracked: false,
+                recurse_submodules: false,
+                include: Vec::new(),
+                exclude: Vec::new(),
+                pathspec: Vec::new(),
                 format: OutputFormat::Json,
                 log_diff: false,
                 no_log_diff: false,
+                no_cache: false,
+                candidates: None,
+                examples: None,
                 model: None,
                 assisted_by: None,
                 execute: true,
                 cleanup_on_error: true,
+                hooks: cli::HookMode::FailFast,
+                sign: false,
+                signing_key: None,
+                verify_command: None,
+                rollback_on_verify_failure: false,
+                strict_diff_hash: false,
                 timeout: None,
             }),
         };
@@ -1798,6 +3585,457 @@ mod tests {
         fs::remove_dir_all(&dir).ok();
     }
 
+    fn parse_workspace(args: &[&str]) -> Cli {
+        Cli::try_parse_from([&["atomc", "workspace"], args].concat()).unwrap()
+    }
+
+    #[test]
+    fn handle_workspace_plans_each_repo() {
+        let _lock = lock_env();
+        let api = temp_dir("workspace-api");
+        let web = temp_dir("workspace-web");
+        fs::create_dir_all(&api).unwrap();
+        fs::create_dir_all(&web).unwrap();
+
+        let cli = parse_workspace(&["--repo", api.to_str().unwrap(), "--repo", web.to_str().unwrap()]);
+        if let Commands::Workspace(ref args) = cli.command {
+            assert!(handle_workspace(&cli, args).is_ok());
+        }
+
+        fs::remove_dir_all(&api).ok();
+        fs::remove_dir_all(&web).ok();
+    }
+
+    #[test]
+    fn handle_eval_writes_report_for_each_model() {
+        let _lock = lock_env();
+        let dir = temp_dir("eval");
+        fs::create_dir_all(dir.join("diffs")).unwrap();
+        fs::write(
+            dir.join("diffs/docs.diff"),
+            "diff --git a/docs/02_cli_spec.md b/docs/02_cli_spec.md\n--- a/docs/02_cli_spec.md\n+++ b/docs/02_cli_spec.md\n@@ -1 +1,2 @@\n+More docs\n",
+        )
+        .unwrap();
+        let report = dir.join("report.json");
+
+        let cli = Cli::try_parse_from([
+            "atomc",
+            "eval",
+            "--fixtures",
+            dir.to_str().unwrap(),
+            "--model",
+            "model-a",
+            "--model",
+            "model-b",
+            "--report",
+            report.to_str().unwrap(),
+        ])
+        .unwrap();
+        let Commands::Eval(ref args) = cli.command else {
+            panic!("expected eval command");
+        };
+        assert!(handle_eval(&cli, args).is_ok());
+
+        let value: Value = serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+        schema::validate_schema(SchemaKind::EvalReport, &value).unwrap();
+        let models: Vec<&str> = value["models"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|summary| summary["model"].as_str().unwrap())
+            .collect();
+        assert_eq!(models, vec!["model-a", "model-b"]);
+        assert_eq!(value["models"][0]["semantic_valid"], 1);
+        assert_eq!(value["cases"][0]["units_match"], Value::Null);
+
+        let missing = Cli::try_parse_from(["atomc", "eval", "--fixtures", dir.join("missing").to_str().unwrap()]).unwrap();
+        let Commands::Eval(ref args) = missing.command else {
+            panic!("expected eval command");
+        };
+        assert!(handle_eval(&missing, args).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn handle_workspace_execute_reports_git_error() {
+        let _lock = lock_env();
+        set_apply_failure(true);
+        let api = temp_dir("workspace-exec-api");
+        let web = temp_dir("workspace-exec-web");
+        fs::create_dir_all(&api).unwrap();
+        fs::create_dir_all(&web).unwrap();
+
+        let cli = parse_workspace(&[
+            "--repo",
+            api.to_str().unwrap(),
+            "--repo",
+            web.to_str().unwrap(),
+            "--execute",
+        ]);
+        if let Commands::Workspace(ref args) = cli.command {
+            assert_eq!(handle_workspace(&cli, args).unwrap_err(), ExitCode::from(6));
+        }
+
+        fs::remove_dir_all(&api).ok();
+        fs::remove_dir_all(&web).ok();
+    }
+
+    #[test]
+    fn workspace_flags_reach_each_apply_request() {
+        let cli = parse_workspace(&[
+            "--repo",
+            ".",
+            "--signing-key",
+            "ABC123",
+            "--verify-command",
+            "cargo check",
+            "--rollback-on-verify-failure",
+        ]);
+        let Commands::Workspace(args) = cli.command else {
+            panic!("expected workspace command");
+        };
+        let entry = WorkspaceEntry {
+            path: PathBuf::from("."),
+            label: ".".to_string(),
+            diff: String::new(),
+            warnings: Vec::new(),
+        };
+        let plan = CommitPlan {
+            schema_version: SCHEMA_VERSION.to_string(),
+            request_id: None,
+            input: None,
+            backend: None,
+            plan: Vec::new(),
+            warnings: None,
+        };
+        let config = ResolvedConfig {
+            verify_command: args.verify_command.clone(),
+            ..ResolvedConfig::defaults()
+        };
+        let request = workspace_apply_request(&entry, &plan, &config, &args);
+        assert_eq!(request.signing_key, Some("ABC123"));
+        assert!(request.rollback_on_verify_failure);
+        assert_eq!(args.verify_command.as_deref(), Some("cargo check"));
+
+        // Without a verify command the workspace still rolls back on apply
+        // errors, but each repository's apply must not ask for it.
+        let config = ResolvedConfig::defaults();
+        assert!(!workspace_apply_request(&entry, &plan, &config, &args).rollback_on_verify_failure);
+    }
+
+    #[test]
+    fn rollback_workspace_undoes_commits_in_earlier_repos() {
+        let git = |dir: &Path, args: &[&str]| {
+            let output = std::process::Command::new("git").current_dir(dir).args(args).output().unwrap();
+            assert!(output.status.success(), "git {}", args.join(" "));
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        let entries: Vec<WorkspaceEntry> = ["rollback-api", "rollback-web"]
+            .iter()
+            .map(|name| {
+                let path = temp_dir(name);
+                fs::create_dir_all(&path).unwrap();
+                git(&path, &["init", "-q"]);
+                git(&path, &["config", "user.email", "atomc@example.com"]);
+                git(&path, &["config", "user.name", "atomc"]);
+                git(&path, &["commit", "-q", "--allow-empty", "-m", "init"]);
+                WorkspaceEntry {
+                    label: name.to_string(),
+                    path,
+                    diff: String::new(),
+                    warnings: Vec::new(),
+                }
+            })
+            .collect();
+        let heads: Vec<String> = entries.iter().map(|entry| git(&entry.path, &["rev-parse", "HEAD"])).collect();
+        let points: Vec<git::RollbackPoint> = entries
+            .iter()
+            .map(|entry| git::RollbackPoint::capture(&entry.path).unwrap())
+            .collect();
+
+        fs::write(entries[0].path.join("api.txt"), "api\n").unwrap();
+        git(&entries[0].path, &["add", "api.txt"]);
+        git(&entries[0].path, &["commit", "-qm", "feat: add api"]);
+        let unit = |status, commit_hash: Option<&str>| ApplyResult {
+            id: "commit-1".to_string(),
+            status,
+            commit_hash: commit_hash.map(str::to_string),
+            error: None,
+            hooks: None,
+            signed: None,
+            verify: None,
+        };
+        let error = ErrorDetail {
+            code: "git_error".to_string(),
+            message: "apply execution failed".to_string(),
+            details: None,
+        };
+        let mut outcomes = vec![
+            WorkspaceOutcome::new(vec![unit(ApplyStatus::Applied, Some("api-hash"))], None),
+            WorkspaceOutcome::new(vec![unit(ApplyStatus::Skipped, None)], Some(error)),
+        ];
+
+        rollback_workspace(&entries, &points, &mut outcomes).unwrap();
+        assert_eq!(git(&entries[0].path, &["rev-parse", "HEAD"]), heads[0]);
+        assert_eq!(git(&entries[0].path, &["diff", "--cached", "--name-only"]), "");
+        assert_eq!(git(&entries[1].path, &["rev-parse", "HEAD"]), heads[1]);
+        assert!(outcomes.iter().all(|outcome| outcome.rolled_back));
+        assert_eq!(outcomes[0].results[0].status, ApplyStatus::Skipped);
+        assert_eq!(outcomes[0].results[0].commit_hash, None);
+        assert!(partial_apply_warning(&entries, &outcomes).is_none());
+
+        for entry in &entries {
+            fs::remove_dir_all(&entry.path).ok();
+        }
+    }
+
+    #[test]
+    fn partial_apply_warning_lists_repos_that_kept_commits() {
+        let entries: Vec<WorkspaceEntry> = ["api", "web"]
+            .iter()
+            .map(|name| WorkspaceEntry {
+                path: PathBuf::from(name),
+                label: name.to_string(),
+                diff: String::new(),
+                warnings: Vec::new(),
+            })
+            .collect();
+        let unit = |status, commit_hash: Option<&str>| ApplyResult {
+            id: "commit-1".to_string(),
+            status,
+            commit_hash: commit_hash.map(str::to_string),
+            error: None,
+            hooks: None,
+            signed: None,
+            verify: None,
+        };
+        let outcomes = vec![
+            WorkspaceOutcome::new(vec![unit(ApplyStatus::Applied, Some("api-hash"))], None),
+            WorkspaceOutcome::new(vec![unit(ApplyStatus::Skipped, None)], None),
+        ];
+
+        let warning = partial_apply_warning(&entries, &outcomes).unwrap();
+        assert_eq!(warning.code, "workspace_partial_apply");
+        assert_eq!(warning.details.unwrap()["repos"], serde_json::json!(["api"]));
+    }
+
+    #[test]
+    fn workspace_repos_merges_discovered_repos_without_duplicates() {
+        let root = temp_dir("workspace-discover");
+        for name in ["api", "web", ".cache"] {
+            fs::create_dir_all(root.join(name).join(".git")).unwrap();
+        }
+
+        let api = root.join("api");
+        let cli = parse_workspace(&["--repo", api.to_str().unwrap(), "--discover", root.to_str().unwrap()]);
+        let Commands::Workspace(args) = cli.command else {
+            panic!("expected workspace command");
+        };
+        assert_eq!(workspace_repos(&args).unwrap().0, vec![api, root.join("web")]);
+
+        let cli = parse_workspace(&[]);
+        let Commands::Workspace(args) = cli.command else {
+            panic!("expected workspace command");
+        };
+        assert_eq!(workspace_repos(&args).unwrap_err(), ExitCode::from(2));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn request_commit_plan_reuses_cached_plans() {
+        let _lock = lock_test_state();
+        let dir = temp_dir("plan-cache");
+        let config = ResolvedConfig {
+            cache_dir: Some(dir.clone()),
+            ..ResolvedConfig::defaults()
+        };
+        let prompt = Prompt {
+            system: "system".to_string(),
+            user: "user".to_string(),
+            version: "test".to_string(),
+        };
+        let diff = "diff --git a/docs/02_cli_spec.md b/docs/02_cli_spec.md\n";
+        let cached = |warnings: &[Warning]| warnings.iter().any(|warning| warning.code == "plan_cached");
+
+        let (_, first) = request_commit_plan_with_retry(&config, &prompt, diff, OutputFormat::Json).unwrap();
+        assert!(!cached(&first));
+        let (plan, warnings) = request_commit_plan_with_retry(&config, &prompt, diff, OutputFormat::Json).unwrap();
+        assert!(cached(&warnings));
+        assert_eq!(plan.plan[0].files, vec!["docs/02_cli_spec.md"]);
+        let codes = |warnings: &[Warning]| {
+            warnings
+                .iter()
+                .map(|warning| warning.code.clone())
+                .filter(|code| code != "plan_cached")
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes(&warnings), codes(&first));
+
+        let key = CacheKey::new(&config, &prompt, diff);
+        let reordered = Warning {
+            code: "plan_reordered".to_string(),
+            message: "units reordered".to_string(),
+            details: None,
+        };
+        store_plan(Some(&PlanCache::new(&dir)), &key, &plan, &[reordered]);
+        let (_, warnings) = cached_plan(Some(&PlanCache::new(&dir)), &key, diff, config.atomicity).unwrap();
+        let codes: Vec<&str> = warnings.iter().map(|warning| warning.code.as_str()).collect();
+        assert_eq!(codes, vec!["plan_reordered", "plan_cached"]);
+
+        let disabled = ResolvedConfig {
+            plan_cache: false,
+            ..config
+        };
+        let (_, warnings) = request_commit_plan_with_retry(&disabled, &prompt, diff, OutputFormat::Json).unwrap();
+        assert!(!cached(&warnings));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn request_commit_plan_selects_best_candidate() {
+        let _lock = lock_test_state();
+        let config = ResolvedConfig {
+            plan_candidates: 3,
+            plan_cache: false,
+            ..ResolvedConfig::defaults()
+        };
+        let prompt = Prompt {
+            system: "system".to_string(),
+            user: "user".to_string(),
+            version: "test".to_string(),
+        };
+        let diff = "diff --git a/docs/02_cli_spec.md b/docs/02_cli_spec.md\n";
+
+        let (plan, _) = request_commit_plan_with_retry(&config, &prompt, diff, OutputFormat::Json).unwrap();
+        assert_eq!(plan.plan[0].summary, test_commit_plan().plan[0].summary);
+
+        let candidates = test_plan_candidates(2);
+        let selected = select_candidate(candidates, diff, &[], AtomicityPolicy::Standard).unwrap();
+        assert_eq!(selected.plan[0].summary, "too short");
+        assert!(matches!(
+            select_candidate(test_plan_candidates(1), diff, &[], AtomicityPolicy::Standard),
+            Err(LlmError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn semantic_validation_report_applies_atomicity_policy() {
+        let mut plan = test_commit_plan();
+        plan.plan[0].type_ = atomc_core::types::CommitType::Feat;
+        plan.plan[0].files.push("src/cli.rs".to_string());
+        plan.plan[0].files.push("tests/cli.rs".to_string());
+
+        let warnings = semantic_validation_report(&plan, &[], "", AtomicityPolicy::Standard).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "non_atomic");
+        assert_eq!(warnings[0].details.as_ref().unwrap()["kind"], "tests_with_source");
+
+        let errors = semantic_validation_report(&plan, &[], "", AtomicityPolicy::Strict).unwrap_err();
+        assert!(matches!(&errors[..], [SemanticValidationError::NonAtomic { .. }]));
+        assert!(semantic_validation_report(&plan, &[], "", AtomicityPolicy::Off).unwrap().is_empty());
+    }
+
+    #[test]
+    fn order_plan_reorders_or_warns_by_policy() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,0 +1,1 @@\n+mod ordering;\ndiff --git a/src/ordering.rs b/src/ordering.rs\nnew file mode 100644\n--- /dev/null\n+++ b/src/ordering.rs\n@@ -0,0 +1,1 @@\n+pub fn order_units() {}\n";
+        let mut units = test_commit_plan().plan;
+        units[0].files = vec!["src/lib.rs".to_string()];
+        let mut foundation = units[0].clone();
+        foundation.id = "commit-2".to_string();
+        foundation.files = vec!["src/ordering.rs".to_string()];
+        units.push(foundation);
+
+        let mut warned = units.clone();
+        let warnings = order_plan(&mut warned, diff, DependencyOrder::Warn);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "unit_out_of_order");
+        assert_eq!(warnings[0].message, "commit commit-1 uses ordering from later commit commit-2");
+        assert_eq!(warned[0].id, "commit-1");
+
+        assert!(order_plan(&mut units, diff, DependencyOrder::Off).is_empty());
+        let warnings = order_plan(&mut units, diff, DependencyOrder::Reorder);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "plan_reordered");
+        assert_eq!(warnings[0].details.as_ref().unwrap()["order"], serde_json::json!(["commit-2", "commit-1"]));
+        assert_eq!(units[0].id, "commit-2");
+        assert!(order_plan(&mut units, diff, DependencyOrder::Reorder).is_empty());
+    }
+
+    #[tokio::test]
+    async fn run_plan_attempts_repairs_plans_before_validation() {
+        let config = ResolvedConfig {
+            plan_cache: false,
+            ..ResolvedConfig::defaults()
+        };
+        let prompt = Prompt {
+            system: "system".to_string(),
+            user: "user".to_string(),
+            version: "test".to_string(),
+        };
+        let diff = "diff --git a/docs/02_cli_spec.md b/docs/02_cli_spec.md\n";
+
+        let run = run_plan_attempts(&config, &prompt, diff, |_, _| async {
+            let mut plan = test_commit_plan();
+            let unit = &mut plan.plan[0];
+            unit.summary = format!("docs(server): {}", unit.summary);
+            unit.body = vec!["- Describe request and response payloads".to_string()];
+            Ok(plan)
+        })
+        .await;
+
+        let (plan, warnings) = run.result.unwrap();
+        assert_eq!(run.attempts.len(), 1);
+        assert_eq!(plan.plan[0].summary, test_commit_plan().plan[0].summary);
+        let fields: Vec<&JsonValue> = warnings
+            .iter()
+            .filter(|warning| warning.code == "plan_repaired")
+            .filter_map(|warning| warning.details.as_ref())
+            .map(|details| &details["field"])
+            .collect();
+        assert_eq!(fields, vec!["summary", "body"]);
+    }
+
+    #[test]
+    fn candidates_flag_rejects_zero() {
+        assert!(Cli::try_parse_from(["atomc", "plan", "--repo", ".", "--candidates", "0"]).is_err());
+        assert!(Cli::try_parse_from(["atomc", "plan", "--repo", ".", "--candidates", "9"]).is_err());
+        let cli = Cli::try_parse_from(["atomc", "plan", "--repo", ".", "--candidates", "3"]).unwrap();
+        let Commands::Plan(args) = cli.command else {
+            panic!("expected plan command");
+        };
+        assert_eq!(args.candidates, Some(3));
+    }
+
+    #[test]
+    fn examples_flag_sets_prompt_examples() {
+        let cli = Cli::try_parse_from(["atomc", "apply", "--repo", ".", "--examples", "2"]).unwrap();
+        let Commands::Apply(args) = cli.command else {
+            panic!("expected apply command");
+        };
+        assert_eq!(args.examples, Some(2));
+    }
+
+    #[test]
+    fn cache_flags_parse() {
+        let cli = Cli::try_parse_from(["atomc", "plan", "--repo", ".", "--no-cache"]).unwrap();
+        let Commands::Plan(args) = cli.command else {
+            panic!("expected plan command");
+        };
+        assert!(args.no_cache);
+
+        let cli = Cli::try_parse_from(["atomc", "cache", "clear", "--format", "json"]).unwrap();
+        let Commands::Cache(CacheArgs {
+            command: CacheCommand::Clear(args),
+        }) = cli.command
+        else {
+            panic!("expected cache clear command");
+        };
+        assert!(matches!(args.format, OutputFormat::Json));
+    }
+
     #[tokio::test]
     async fn plan_endpoint_returns_plan_with_metadata() {
         let _lock = lock_server();
@@ -1805,7 +4043,10 @@ mod tests {
         let _ = take_log_diff_preview();
         let mut config = ResolvedConfig::defaults();
         config.log_diff = true;
-        let app = super::build_app(ServerState { config });
+        let app = super::build_app(ServerState {
+            config,
+            config_layers: Vec::new(),
+        });
         let payload = serde_json::json!({
             "diff": "diff --git a/file.txt b/file.txt\n",
             "log_diff": true
@@ -1834,7 +4075,10 @@ mod tests {
         let _ = take_log_diff_preview();
         let mut config = ResolvedConfig::defaults();
         config.log_diff = true;
-        let app = super::build_app(ServerState { config });
+        let app = super::build_app(ServerState {
+            config,
+            config_layers: Vec::new(),
+        });
         let payload = serde_json::json!({
             "diff": "diff --git a/file.txt b/file.txt\n",
             "log_diff": false
@@ -1866,7 +4110,11 @@ mod tests {
         for (mode, status, code) in cases {
             set_llm_mode(mode);
             let app = super::build_app(ServerState {
-                config: ResolvedConfig::defaults(),
+                config: ResolvedConfig {
+                    llm_retry_backoff_ms: 0,
+                    ..ResolvedConfig::defaults()
+                },
+                config_layers: Vec::new(),
             });
             let payload = serde_json::json!({
                 "diff": "diff --git a/file.txt b/file.txt\n"
@@ -1884,6 +4132,39 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn endpoints_reject_too_many_candidates() {
+        let _lock = lock_server();
+        set_llm_mode(0);
+        let dir = temp_dir("server-candidates");
+        fs::create_dir_all(&dir).unwrap();
+
+        for uri in ["/v1/commit-plan", "/v1/commit-apply"] {
+            let app = super::build_app(ServerState {
+                config: ResolvedConfig::defaults(),
+                config_layers: Vec::new(),
+            });
+            let payload = serde_json::json!({
+                "repo_path": dir,
+                "diff": "diff --git a/file.txt b/file.txt\n",
+                "candidates": 1000
+            });
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap();
+
+            let (status, _headers, json) = send_request(app, request).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(json["error"]["code"], "input_invalid");
+            assert_eq!(json["error"]["details"]["max"], 8);
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[tokio::test]
     async fn apply_endpoint_maps_git_error_on_execute() {
         let _lock = lock_server();
@@ -1894,6 +4175,7 @@ mod tests {
 
         let app = super::build_app(ServerState {
             config: ResolvedConfig::defaults(),
+            config_layers: Vec::new(),
         });
         let payload = serde_json::json!({
             "repo_path": dir,
@@ -1914,6 +4196,40 @@ mod tests {
         fs::remove_dir_all(&dir).ok();
     }
 
+    #[tokio::test]
+    async fn apply_endpoint_ignores_verify_command_in_body() {
+        let _lock = lock_server();
+        set_llm_mode(0);
+        let _ = take_verify_command();
+        let dir = temp_dir("server-apply-verify");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut config = ResolvedConfig::defaults();
+        config.verify_command = Some("cargo check".to_string());
+        let app = super::build_app(ServerState {
+            config,
+            config_layers: Vec::new(),
+        });
+        let payload = serde_json::json!({
+            "repo_path": dir,
+            "diff": "diff --git a/file.txt b/file.txt\n",
+            "execute": true,
+            "verify_command": "echo from-request"
+        });
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/commit-apply")
+            .header("content-type", "application/json")
+            .body(Body::from(payload.to_string()))
+            .unwrap();
+
+        let (status, _headers, _json) = send_request(app, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(take_verify_command().as_deref(), Some("cargo check"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[tokio::test]
     async fn apply_endpoint_log_diff_enabled_emits_preview() {
         let _lock = lock_server();
@@ -1924,7 +4240,10 @@ mod tests {
 
         let mut config = ResolvedConfig::defaults();
         config.log_diff = true;
-        let app = super::build_app(ServerState { config });
+        let app = super::build_app(ServerState {
+            config,
+            config_layers: Vec::new(),
+        });
         let payload = serde_json::json!({
             "repo_path": dir,
             "diff": "diff --git a/file.txt b/file.txt\n",
@@ -1956,7 +4275,10 @@ mod tests {
 
         let mut config = ResolvedConfig::defaults();
         config.log_diff = true;
-        let app = super::build_app(ServerState { config });
+        let app = super::build_app(ServerState {
+            config,
+            config_layers: Vec::new(),
+        });
         let payload = serde_json::json!({
             "repo_path": dir,
             "diff": "diff --git a/file.txt b/file.txt\n",
@@ -1992,11 +4314,20 @@ mod tests {
                 repo: Some(dir.clone()),
                 diff_file: None,
                 diff_mode: None,
+                base: None,
+                range: None,
                 include_untracked: false,
                 no_include_untracked: false,
+                recurse_submodules: false,
+                include: Vec::new(),
+                exclude: Vec::new(),
+                pathspec: Vec::new(),
                 format: OutputFormat::Json,
                 log_diff: false,
                 no_log_diff: false,
+                no_cache: false,
+                candidates: None,
+                examples: None,
                 model: None,
                 dry_run: true,
                 timeout: None,
@@ -2012,4 +4343,95 @@ mod tests {
 
         fs::remove_dir_all(&dir).ok();
     }
+
+    fn replay_cassette() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures/cassettes/plan_retry.cassette.json")
+    }
+
+    #[tokio::test]
+    async fn health_endpoint_reports_checks() {
+        let _lock = lock_server();
+        let config = ResolvedConfig {
+            runtime: config::Runtime::Replay,
+            cassette: Some(replay_cassette()),
+            ..ResolvedConfig::defaults()
+        };
+        let app = super::build_app(ServerState {
+            config,
+            config_layers: Vec::new(),
+        });
+        let request = Request::builder().uri("/v1/health").body(Body::empty()).unwrap();
+
+        let (status, _headers, json) = send_request(app, request).await;
+        assert_eq!(status, StatusCode::OK);
+        schema::validate_schema(SchemaKind::Health, &json).unwrap();
+        let checks = json["checks"].as_array().unwrap();
+        let status_of = |name: &str| {
+            checks
+                .iter()
+                .find(|check| check["name"] == name)
+                .map(|check| check["status"].clone())
+        };
+        assert_eq!(status_of("git"), Some("ok".into()));
+        assert_eq!(status_of("repo"), Some("skip".into()));
+        assert_eq!(status_of("config"), Some("ok".into()));
+        assert_eq!(status_of("runtime"), Some("ok".into()));
+    }
+
+    #[tokio::test]
+    async fn health_endpoint_returns_unavailable_on_failure() {
+        let _lock = lock_server();
+        let dir = temp_dir("health-repo");
+        fs::create_dir_all(&dir).unwrap();
+        let config = ResolvedConfig {
+            runtime: config::Runtime::Replay,
+            ..ResolvedConfig::defaults()
+        };
+        let app = super::build_app(ServerState {
+            config,
+            config_layers: Vec::new(),
+        });
+        let uri = format!("/v1/health?repo_path={}", dir.display());
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let (status, _headers, json) = send_request(app, request).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["status"], "fail");
+        let runtime = json["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|check| check["name"] == "runtime")
+            .unwrap();
+        assert_eq!(runtime["status"], "fail");
+        assert!(runtime["fix"].as_str().unwrap().contains("LOCAL_COMMIT_CASSETTE"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn handle_doctor_exits_with_code_of_first_failure() {
+        let _lock = lock_env();
+        let dir = temp_dir("doctor-repo");
+        fs::create_dir_all(&dir).unwrap();
+        let cli = Cli::try_parse_from(["atomc", "doctor", "--repo", dir.to_str().unwrap(), "--format", "json"])
+            .unwrap();
+        let Commands::Doctor(ref args) = cli.command else {
+            panic!("expected doctor command");
+        };
+
+        let _runtime = EnvVarGuard::set("LOCAL_COMMIT_RUNTIME", "replay");
+        let cassette = replay_cassette();
+        {
+            let _cassette = EnvVarGuard::set("LOCAL_COMMIT_CASSETTE", cassette.to_str().unwrap());
+            assert!(handle_doctor(&cli, args).is_ok());
+        }
+        assert_eq!(handle_doctor(&cli, args).unwrap_err(), ExitCode::from(4));
+        {
+            let _invalid = EnvVarGuard::set("LOCAL_COMMIT_PLAN_CACHE", "maybe");
+            assert_eq!(handle_doctor(&cli, args).unwrap_err(), ExitCode::from(7));
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
 }
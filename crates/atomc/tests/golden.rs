@@ -4,7 +4,7 @@ use serde_json::Value;
 use std::io::Write;
 use std::path::Path;
 use std::process::Stdio;
-use support::{atomc_bin, run_atomc, start_mock_ollama};
+use support::{atomc_bin, isolated_cache_dir, run_atomc, start_mock_ollama};
 use tempfile::TempDir;
 
 struct GoldenCase {
@@ -53,7 +53,7 @@ async fn golden_plan_fixtures_match_cli_output() {
 
         assert_eq!(output["schema_version"], "v1");
         assert_eq!(output["input"]["source"], "diff");
-        assert!(output.get("warnings").map_or(true, |value| value.is_null()));
+        assert!(output.get("warnings").is_none_or(|value| value.is_null()));
         assert_eq!(output["plan"], expected["plan"]);
     }
 }
@@ -139,6 +139,115 @@ async fn golden_plan_rejects_semantic_violation() {
     assert_eq!(output["error"]["code"], "llm_parse_error");
 }
 
+#[test]
+fn golden_plan_replays_cassette_through_retry() {
+    let diff = load_fixture("diffs/simple_feature.diff");
+    let expected: Value =
+        serde_json::from_str(&load_fixture("plans/simple_feature.plan.json")).expect("fixture json");
+    let cassette = fixtures_root().join("cassettes/plan_retry.cassette.json");
+    let cwd = TempDir::new().expect("temp dir");
+
+    let output = run_atomc_replay(&["plan", "--format", "json"], cwd.path(), &cassette, &diff, false);
+    assert!(output.status.success(), "atomc failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let plan: Value = serde_json::from_slice(&output.stdout).expect("plan json");
+    assert_eq!(plan["plan"], expected["plan"]);
+    assert_eq!(plan["backend"]["name"], "default");
+    assert_eq!(plan["backend"]["runtime"], "replay");
+    let warnings = plan["warnings"].as_array().expect("warnings");
+    let retried = warnings
+        .iter()
+        .find(|warning| warning["code"] == "llm_retried")
+        .expect("llm_retried warning");
+    assert_eq!(retried["details"]["attempts"][0]["outcome"], "parse_error");
+}
+
+#[test]
+fn golden_plan_replay_fails_on_unrecorded_prompts() {
+    let diff = load_fixture("diffs/mixed_concerns.diff");
+    let cassette = fixtures_root().join("cassettes/plan_retry.cassette.json");
+    let cwd = TempDir::new().expect("temp dir");
+
+    let output = run_atomc_replay(&["plan", "--format", "json"], cwd.path(), &cassette, &diff, false);
+    assert_eq!(output.status.code(), Some(4));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("no exchange recorded for prompt"), "unexpected output: {stdout}");
+}
+
+#[tokio::test]
+async fn golden_plan_records_cassette_that_replays_identically() {
+    let diff = load_fixture("diffs/mixed_concerns.diff");
+    let expected_json = load_fixture("plans/mixed_concerns.plan.json");
+    let mock = start_mock_ollama(expected_json).await;
+    let cwd = TempDir::new().expect("temp dir");
+    let cassette = cwd.path().join("recorded.cassette.json");
+
+    let recorded = {
+        let args = ["plan", "--format", "json"].map(String::from).to_vec();
+        let dir = cwd.path().to_path_buf();
+        let base_url = mock.base_url.clone();
+        let cassette = cassette.clone();
+        let diff = diff.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut cmd = atomc_command(&args, &dir);
+            cmd.env("LOCAL_COMMIT_OLLAMA_URL", base_url)
+                .env("LOCAL_COMMIT_CASSETTE", cassette)
+                .env("LOCAL_COMMIT_CASSETTE_REDACT", "true");
+            run_with_stdin(cmd, &diff)
+        })
+        .await
+        .expect("spawn blocking")
+    };
+    assert!(recorded.status.success(), "atomc failed: {}", String::from_utf8_lossy(&recorded.stderr));
+    drop(mock);
+
+    let contents = std::fs::read_to_string(&cassette).expect("cassette written");
+    assert!(contents.contains("diff lines redacted"));
+    assert!(!contents.contains("Run the CLI with --help"));
+
+    let replayed = run_atomc_replay(&["plan", "--format", "json"], cwd.path(), &cassette, &diff, false);
+    assert!(replayed.status.success(), "atomc failed: {}", String::from_utf8_lossy(&replayed.stderr));
+    let recorded: Value = serde_json::from_slice(&recorded.stdout).expect("plan json");
+    let replayed: Value = serde_json::from_slice(&replayed.stdout).expect("plan json");
+    assert_eq!(replayed["plan"], recorded["plan"]);
+}
+
+fn run_atomc_replay(args: &[&str], dir: &Path, cassette: &Path, input: &str, fuzzy: bool) -> std::process::Output {
+    let args = args.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+    let mut cmd = atomc_command(&args, dir);
+    cmd.env("LOCAL_COMMIT_RUNTIME", "replay")
+        .env("LOCAL_COMMIT_CASSETTE", cassette)
+        .env("LOCAL_COMMIT_CASSETTE_FUZZY", fuzzy.to_string());
+    run_with_stdin(cmd, input)
+}
+
+fn atomc_command(args: &[String], dir: &Path) -> std::process::Command {
+    let mut cmd = std::process::Command::new(atomc_bin());
+    cmd.args(args)
+        .current_dir(dir)
+        .env("LOCAL_COMMIT_RUNTIME", "ollama")
+        .env("LOCAL_COMMIT_LLM_TIMEOUT_SECS", "5")
+        .env("LOCAL_COMMIT_LLM_RETRY_BACKOFF_MS", "0")
+        .env("LOCAL_COMMIT_CACHE_DIR", isolated_cache_dir())
+        .env_remove("LOCAL_COMMIT_AGENT_CONFIG")
+        .env_remove("LOCAL_COMMIT_CASSETTE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
+fn run_with_stdin(mut cmd: std::process::Command, input: &str) -> std::process::Output {
+    let mut child = cmd.spawn().expect("spawn atomc");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(input.as_bytes())
+        .expect("write stdin");
+    child.wait_with_output().expect("atomc output")
+}
+
 fn load_fixture(relative: &str) -> String {
     let path = fixtures_root().join(relative);
     std::fs::read_to_string(&path)
@@ -173,6 +282,7 @@ fn run_atomc_expect_failure_sync(
         .env("LOCAL_COMMIT_RUNTIME", "ollama")
         .env("LOCAL_COMMIT_OLLAMA_URL", ollama_url)
         .env("LOCAL_COMMIT_LLM_TIMEOUT_SECS", "5")
+        .env("LOCAL_COMMIT_CACHE_DIR", isolated_cache_dir())
         .env_remove("LOCAL_COMMIT_AGENT_CONFIG")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -261,6 +371,7 @@ fn run_atomc_stderr_sync(
         .env("LOCAL_COMMIT_RUNTIME", "ollama")
         .env("LOCAL_COMMIT_OLLAMA_URL", ollama_url)
         .env("LOCAL_COMMIT_LLM_TIMEOUT_SECS", "5")
+        .env("LOCAL_COMMIT_CACHE_DIR", isolated_cache_dir())
         .env_remove("LOCAL_COMMIT_AGENT_CONFIG")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -270,3 +270,45 @@ async fn http_apply_accepts_explicit_plan() {
     assert_eq!(payload["results"][0]["status"], "planned");
     assert_eq!(payload["plan"][0]["files"][0], "file.txt");
 }
+
+#[tokio::test]
+async fn cli_split_execute_creates_branch() {
+    let repo = init_repo_with_change();
+    run_git(repo.path(), &["commit", "-am", "wip"]);
+    let plan_json = plan_payload(&["file.txt"]);
+    let mock = start_mock_ollama(plan_json).await;
+
+    let stdout = run_atomc(
+        &[
+            "split",
+            "HEAD~1..HEAD",
+            "--repo",
+            repo.path().to_str().expect("repo path"),
+            "--branch",
+            "rebuilt",
+            "--execute",
+            "--format",
+            "json",
+        ],
+        repo.path(),
+        &mock.base_url,
+        None,
+    )
+    .await;
+    let value: Value = serde_json::from_str(&stdout).expect("split json");
+    assert_eq!(value["results"][0]["status"], "applied");
+    assert!(value["input"]["file_hashes"]["file.txt"].is_string());
+    let authorship = value["warnings"]
+        .as_array()
+        .expect("warnings")
+        .iter()
+        .find(|warning| warning["code"] == "split_authorship_rewritten")
+        .expect("authorship warning");
+    assert_eq!(authorship["details"]["authors"].as_array().map(Vec::len), Some(1));
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s", "rebuilt"]);
+    assert_eq!(subject.trim(), format!("test[{SCOPE}]: {SUMMARY}"));
+    assert_eq!(
+        run_git(repo.path(), &["rev-parse", "rebuilt^{tree}"]),
+        run_git(repo.path(), &["rev-parse", "HEAD^{tree}"])
+    );
+}
@@ -65,6 +65,7 @@ pub fn start_atomc_server(port: u16, ollama_url: &str) -> AtomcServer {
         .env("LOCAL_COMMIT_RUNTIME", "ollama")
         .env("LOCAL_COMMIT_OLLAMA_URL", ollama_url)
         .env("LOCAL_COMMIT_LLM_TIMEOUT_SECS", "5")
+        .env("LOCAL_COMMIT_CACHE_DIR", crate::support::isolated_cache_dir())
         .env_remove("LOCAL_COMMIT_AGENT_CONFIG")
         .stdout(Stdio::null())
         .stderr(Stdio::null());
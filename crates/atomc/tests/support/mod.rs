@@ -5,6 +5,7 @@ use serde_json::{json, Value};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::task::JoinHandle;
@@ -24,6 +25,14 @@ pub fn atomc_bin() -> PathBuf {
     PathBuf::from(env!("CARGO_BIN_EXE_atomc"))
 }
 
+/// A plan cache directory of its own for one atomc run, so cached plans
+/// never carry over between runs or land in the user's cache.
+pub fn isolated_cache_dir() -> PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let run = NEXT.fetch_add(1, Ordering::SeqCst);
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("plan-cache-{}-{run}", std::process::id()))
+}
+
 pub async fn start_mock_ollama(plan_json: String) -> MockOllama {
     let state = Arc::new(plan_json);
     let app = Router::new()
@@ -70,6 +79,7 @@ fn run_atomc_sync(
         .env("LOCAL_COMMIT_RUNTIME", "ollama")
         .env("LOCAL_COMMIT_OLLAMA_URL", ollama_url)
         .env("LOCAL_COMMIT_LLM_TIMEOUT_SECS", "5")
+        .env("LOCAL_COMMIT_CACHE_DIR", isolated_cache_dir())
         .env_remove("LOCAL_COMMIT_AGENT_CONFIG")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());